@@ -0,0 +1,205 @@
+//! # Policy Analysis
+//!
+//! Implements the `analyze` subcommand: reports conflicting, shadowed and
+//! redundant rules in the `--config` policy, each with a request that
+//! demonstrates it.
+//!
+//! ```bash
+//! # Everything that looks suspicious
+//! nacm-validator --config config.xml analyze
+//!
+//! # Fail a CI job on cross-list conflicts only
+//! nacm-validator --config config.xml --format exit-code analyze --kind conflict
+//! ```
+//!
+//! ## Findings
+//!
+//! See `nacm_validator::analysis` for the exact definitions:
+//!
+//! - **conflict**: rules with opposite effects in different rule lists that
+//!   both match a request of a user in both rule lists' groups
+//! - **shadowed**: a rule that an earlier rule with the opposite effect
+//!   always preempts
+//! - **redundant**: a rule that an earlier rule with the same effect always
+//!   preempts
+//!
+//! ## Exit Codes
+//!
+//! - 0: no findings (of the selected kinds)
+//! - 1: at least one finding
+//! - 2: the output could not be written
+
+use crate::simulate::{decision_label, describe, effect_name, rule_label};
+use crate::OutputFormat;
+use clap::{Args, ValueEnum};
+use nacm_validator::analysis::{Finding, FindingKind};
+use nacm_validator::{NacmConfig, OwnedAccessRequest, RuleEffect};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io;
+
+/// Arguments for the `analyze` subcommand
+#[derive(Args)]
+pub struct AnalyzeArgs {
+    /// Only report findings of this kind (repeatable; default: all kinds)
+    #[arg(long)]
+    pub kind: Vec<KindArg>,
+}
+
+/// Command-line wrapper for `FindingKind`
+#[derive(Clone, Copy, ValueEnum)]
+pub enum KindArg {
+    /// Opposite effects in different rule lists for a user in both
+    Conflict,
+    /// A rule preempted by an earlier rule with the opposite effect
+    Shadowed,
+    /// A rule preempted by an earlier rule with the same effect
+    Redundant,
+}
+
+impl From<KindArg> for FindingKind {
+    fn from(kind: KindArg) -> Self {
+        match kind {
+            KindArg::Conflict => FindingKind::Conflict,
+            KindArg::Shadowed => FindingKind::Shadowed,
+            KindArg::Redundant => FindingKind::Redundant,
+        }
+    }
+}
+
+/// A finding, as written in JSON mode
+#[derive(Serialize)]
+struct JsonFinding<'a> {
+    /// "conflict", "shadowed" or "redundant"
+    kind: String,
+    /// The rule with higher precedence, as "list/rule"
+    first: String,
+    /// Effect of the first rule
+    first_effect: RuleEffect,
+    /// The rule with lower precedence, as "list/rule"
+    second: String,
+    /// Effect of the second rule
+    second_effect: RuleEffect,
+    /// A request matched by both rules
+    counterexample: &'a OwnedAccessRequest,
+    /// Decision for the counterexample
+    decision: RuleEffect,
+    /// The rule that decides the counterexample
+    decided_by: String,
+}
+
+/// Trailer record of an analysis in JSON mode
+#[derive(Serialize)]
+struct JsonAnalysisSummary {
+    /// Number of findings reported
+    total: usize,
+    /// Findings per kind
+    by_kind: BTreeMap<String, usize>,
+}
+
+/// Analyze the configuration and print the findings
+///
+/// ## Returns
+///
+/// * `i32` - Exit code (see the module documentation)
+pub fn run(config: &NacmConfig, args: &AnalyzeArgs, format: &OutputFormat) -> i32 {
+    let kinds: Vec<FindingKind> = args.kind.iter().map(|&kind| kind.into()).collect();
+    let findings: Vec<Finding> = config
+        .analyze()
+        .into_iter()
+        .filter(|finding| kinds.is_empty() || kinds.contains(&finding.kind))
+        .collect();
+
+    match format {
+        OutputFormat::Text => {
+            for finding in &findings {
+                println!(
+                    "{} {} ({}) over {} ({})",
+                    finding.kind.to_string().to_uppercase(),
+                    finding.first,
+                    effect_name(finding.first_effect),
+                    finding.second,
+                    effect_name(finding.second_effect)
+                );
+                println!(
+                    "    user={} {} -> {} by {}",
+                    finding.counterexample.user,
+                    describe(&finding.counterexample),
+                    decision_label(&finding.decision),
+                    rule_label(finding.decision.matched_rule)
+                );
+            }
+            println!("findings: {}", findings.len());
+        }
+        OutputFormat::Json => {
+            for finding in &findings {
+                let json = JsonFinding {
+                    kind: finding.kind.to_string(),
+                    first: finding.first.to_string(),
+                    first_effect: finding.first_effect,
+                    second: finding.second.to_string(),
+                    second_effect: finding.second_effect,
+                    counterexample: &finding.counterexample,
+                    decision: finding.decision.result.effect,
+                    decided_by: rule_label(finding.decision.matched_rule),
+                };
+                println!("{}", serde_json::to_string(&json).unwrap());
+            }
+            let mut by_kind = BTreeMap::new();
+            for finding in &findings {
+                *by_kind.entry(finding.kind.to_string()).or_insert(0) += 1;
+            }
+            let summary = JsonAnalysisSummary {
+                total: findings.len(),
+                by_kind,
+            };
+            println!("{{\"summary\":{}}}", serde_json::to_string(&summary).unwrap());
+        }
+        OutputFormat::Csv => {
+            if let Err(e) = write_csv(&findings) {
+                eprintln!("Error writing CSV: {}", e);
+                return 2;
+            }
+        }
+        OutputFormat::ExitCode => {
+            // Silent mode: the exit code says whether anything was found
+        }
+    }
+
+    if findings.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+/// Write one CSV row per finding
+fn write_csv(findings: &[Finding]) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    writer.write_record([
+        "kind", "first", "first_effect", "second", "second_effect", "user", "module", "rpc", "operation",
+        "path", "context", "command", "decision", "decided_by",
+    ])?;
+
+    for finding in findings {
+        let request = &finding.counterexample;
+        writer.write_record([
+            finding.kind.to_string(),
+            finding.first.to_string(),
+            effect_name(finding.first_effect).to_string(),
+            finding.second.to_string(),
+            effect_name(finding.second_effect).to_string(),
+            request.user.clone(),
+            request.module_name.clone().unwrap_or_default(),
+            request.rpc_name.clone().unwrap_or_default(),
+            request.operation.to_string(),
+            request.path.clone().unwrap_or_default(),
+            request.context.as_ref().map(|ctx| ctx.to_string()).unwrap_or_default(),
+            request.command.clone().unwrap_or_default(),
+            effect_name(finding.decision.result.effect).to_string(),
+            rule_label(finding.decision.matched_rule),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,514 @@
+//! # File-Based Batch Input
+//!
+//! Implements `--input <file>`: reads a whole batch of requests from a file
+//! (or `-` for stdin) in one of several formats and reports the decisions in
+//! the chosen `--format`.
+//!
+//! ## Input Formats
+//!
+//! All formats map onto the same fields as `--json-input` (`user`,
+//! `operation`, `module`, `rpc`, `path`, `context`, `command`, `id`):
+//!
+//! - **NDJSON**: one JSON object per line (`.ndjson`, `.jsonl`)
+//! - **JSON array**: a single array of request objects (`.json`)
+//! - **YAML**: a sequence of request mappings (`.yaml`, `.yml`)
+//! - **CSV**: a header row naming the fields, then one request per row
+//!   (`.csv`); empty cells are treated as absent
+//!
+//! The format is chosen from the file extension, falling back to sniffing
+//! the content, and can be forced with `--input-format`. `.ndjson`/`.jsonl`,
+//! `.yaml`/`.yml` and `.csv` name their format. For `.json`, stdin and any
+//! other extension, content starting with `[` is a JSON array and content
+//! starting with `{` is NDJSON; a first line with commas that names a
+//! `user` column is CSV, and anything else is read as YAML.
+//!
+//! ## Output Formats
+//!
+//! Decisions are written in `--format` text, json or csv, or only reported
+//! through the exit code. There is no JUnit output: a batch carries no
+//! expected decisions, so a deny is not a test failure. Use the `test`
+//! subcommand, which writes JUnit XML, to check decisions against
+//! expectations.
+//!
+//! ## Record Numbers
+//!
+//! Each output record carries a `line` number pointing back at its source:
+//! the physical line for NDJSON and CSV, and the 1-based element position
+//! for JSON arrays and YAML sequences.
+
+use crate::{process_json_value, Evaluation, JsonRecord, JsonSummary, OutputFormat};
+use clap::ValueEnum;
+use nacm_validator::{MappedAccess, NacmConfig, RequestContext};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// Supported batch input formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// Newline-delimited JSON objects
+    Ndjson,
+    /// A single JSON array of objects
+    Json,
+    /// A YAML sequence of mappings
+    Yaml,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+/// One request read from the input, before evaluation
+#[derive(Debug)]
+pub struct InputRecord {
+    /// Line or element number used to correlate output with input
+    pub line: usize,
+    /// The request as a JSON value, or why it could not be read
+    pub value: Result<serde_json::Value, String>,
+}
+
+/// Read and parse a batch input file
+///
+/// ## Parameters
+///
+/// * `path` - File to read, or `-` for standard input
+/// * `format` - Explicit format, or `None` to auto-detect
+///
+/// ## Returns
+///
+/// * `Ok(Vec<InputRecord>)` - One record per request; malformed individual
+///   requests are kept as errors so that they show up in the output
+/// * `Err(String)` - The file could not be read or is malformed as a whole
+pub fn read_input(path: &Path, format: Option<InputFormat>) -> Result<Vec<InputRecord>, String> {
+    let content = read_text(path)?;
+
+    let format = format.unwrap_or_else(|| detect_format(path, &content));
+    match format {
+        InputFormat::Ndjson => Ok(parse_ndjson(&content)),
+        InputFormat::Json => parse_json_array(&content),
+        InputFormat::Yaml => parse_yaml(&content),
+        InputFormat::Csv => parse_csv(&content),
+    }
+}
+
+/// Read a whole file, or stdin for "-"
+pub fn read_text(path: &Path) -> Result<String, String> {
+    if path == Path::new("-") {
+        let mut buffer = String::new();
+        io::stdin()
+            .read_to_string(&mut buffer)
+            .map_err(|e| format!("Error reading stdin: {}", e))?;
+        Ok(buffer)
+    } else {
+        std::fs::read_to_string(path).map_err(|e| format!("Error reading {:?}: {}", path, e))
+    }
+}
+
+/// Pick an input format from the file extension, then from the content
+fn detect_format(path: &Path, content: &str) -> InputFormat {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+
+    match extension.as_deref() {
+        Some("ndjson") | Some("jsonl") => return InputFormat::Ndjson,
+        Some("yaml") | Some("yml") => return InputFormat::Yaml,
+        Some("csv") => return InputFormat::Csv,
+        _ => {}
+    }
+
+    // ".json" files and unknown extensions: look at the content itself
+    let trimmed = content.trim_start();
+    let first_line = trimmed.lines().next().unwrap_or("");
+    if trimmed.starts_with('[') {
+        InputFormat::Json
+    } else if trimmed.starts_with('{') {
+        InputFormat::Ndjson
+    } else if first_line.contains(',') && first_line.split(',').any(|h| h.trim() == "user") {
+        InputFormat::Csv
+    } else {
+        InputFormat::Yaml
+    }
+}
+
+/// Parse newline-delimited JSON, keeping physical line numbers
+fn parse_ndjson(content: &str) -> Vec<InputRecord> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| InputRecord {
+            line: index + 1,
+            value: serde_json::from_str(line).map_err(|e| format!("Invalid JSON: {}", e)),
+        })
+        .collect()
+}
+
+/// Parse a JSON array of request objects
+fn parse_json_array(content: &str) -> Result<Vec<InputRecord>, String> {
+    let items: Vec<serde_json::Value> =
+        serde_json::from_str(content).map_err(|e| format!("Expected a JSON array of requests: {}", e))?;
+    Ok(numbered(items))
+}
+
+/// Parse a YAML sequence of request mappings
+fn parse_yaml(content: &str) -> Result<Vec<InputRecord>, String> {
+    let items: Vec<serde_json::Value> =
+        serde_yaml::from_str(content).map_err(|e| format!("Expected a YAML sequence of requests: {}", e))?;
+    Ok(numbered(items))
+}
+
+/// Parse CSV with a header row into request objects
+///
+/// Every cell is a string; empty cells are left out so that optional fields
+/// become absent rather than empty strings.
+fn parse_csv(content: &str) -> Result<Vec<InputRecord>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Invalid CSV header: {}", e))?
+        .clone();
+
+    let mut records = Vec::new();
+    for (index, row) in reader.records().enumerate() {
+        // Prefer the position reported by the parser, which accounts for quoted newlines
+        let line = row
+            .as_ref()
+            .ok()
+            .and_then(|r| r.position())
+            .map(|p| p.line() as usize)
+            .unwrap_or(index + 2);
+
+        let value = row
+            .map_err(|e| format!("Invalid CSV row: {}", e))
+            .map(|row| {
+                let object = headers
+                    .iter()
+                    .zip(row.iter())
+                    .filter(|(_, cell)| !cell.is_empty())
+                    .map(|(header, cell)| (header.to_string(), serde_json::Value::String(cell.to_string())))
+                    .collect();
+                serde_json::Value::Object(object)
+            });
+
+        records.push(InputRecord { line, value });
+    }
+    Ok(records)
+}
+
+/// Number array elements from 1
+fn numbered(items: Vec<serde_json::Value>) -> Vec<InputRecord> {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| InputRecord { line: index + 1, value: Ok(value) })
+        .collect()
+}
+
+/// Evaluate every input record against the configuration
+///
+/// Records that could not be read become error records with their line
+/// number, exactly like in `--json-input` mode.
+pub fn evaluate(config: &NacmConfig, evaluation: &Evaluation, records: Vec<InputRecord>) -> Vec<JsonRecord> {
+    records
+        .into_iter()
+        .map(|record| match record.value {
+            Ok(value) => process_json_value(config, evaluation, value, Some(record.line)),
+            Err(error) => JsonRecord::Error(crate::JsonError {
+                line: Some(record.line),
+                id: None,
+                error,
+            }),
+        })
+        .collect()
+}
+
+/// Evaluate access requests mapped from a protocol message for `user`
+///
+/// Records are numbered from 1 in mapping order. A decision that cannot be
+/// written to the audit log becomes an error record.
+pub fn evaluate_mapped(
+    config: &NacmConfig,
+    evaluation: &Evaluation,
+    user: &str,
+    context: Option<&RequestContext>,
+    accesses: &[MappedAccess],
+) -> Vec<JsonRecord> {
+    accesses
+        .iter()
+        .enumerate()
+        .map(|(index, access)| {
+            let request = access.to_request(user, context);
+            match evaluation.decide(config, &request) {
+                Ok(decision) => {
+                    let mut result = crate::decision_to_json_result(&decision, &request);
+                    result.line = Some(index + 1);
+                    JsonRecord::Result(Box::new(result))
+                }
+                Err(error) => JsonRecord::Error(crate::JsonError {
+                    line: Some(index + 1),
+                    id: None,
+                    error,
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Count the decisions and errors in a set of records
+pub fn summarize(records: &[JsonRecord]) -> JsonSummary {
+    let mut summary = JsonSummary::default();
+    for record in records {
+        match record {
+            JsonRecord::Result(result) if result.decision == "permit" => summary.permit += 1,
+            JsonRecord::Result(_) => summary.deny += 1,
+            JsonRecord::Error(_) => summary.error += 1,
+        }
+    }
+    summary
+}
+
+/// Write batch results in the requested format
+///
+/// ## Exit Codes
+///
+/// Returns the process exit code for the batch:
+/// - 0: every request was permitted
+/// - 1: at least one request was denied
+/// - 2: at least one request could not be evaluated
+pub fn output_records(records: &[JsonRecord], format: &OutputFormat) -> i32 {
+    let summary = summarize(records);
+
+    match format {
+        OutputFormat::Text => {
+            for record in records {
+                match record {
+                    JsonRecord::Result(result) => {
+                        let log_indicator = if result.should_log { " [LOGGED]" } else { "" };
+                        let rule = match (&result.rule_list, &result.rule) {
+                            (Some(list), Some(rule)) => format!("{}/{}", list, rule),
+                            _ => "default policy".to_string(),
+                        };
+                        let path = result.path.as_ref().map(|p| format!(" path={}", p)).unwrap_or_default();
+                        println!(
+                            "{}: {}{} user={} operation={}{} ({})",
+                            result.line.unwrap_or(0),
+                            result.decision.to_uppercase(),
+                            log_indicator,
+                            result.user,
+                            result.operation,
+                            path,
+                            rule
+                        );
+                    }
+                    JsonRecord::Error(error) => {
+                        println!("{}: ERROR {}", error.line.unwrap_or(0), error.error);
+                    }
+                }
+            }
+            println!("permit: {}, deny: {}, error: {}", summary.permit, summary.deny, summary.error);
+        }
+        OutputFormat::Json => {
+            for record in records {
+                println!("{}", serde_json::to_string(record).unwrap());
+            }
+            println!("{{\"summary\":{}}}", serde_json::to_string(&summary).unwrap());
+        }
+        OutputFormat::Csv => {
+            if let Err(e) = write_csv(records, io::stdout()) {
+                eprintln!("Error writing CSV: {}", e);
+                return 2;
+            }
+        }
+        OutputFormat::ExitCode => {
+            // Silent mode: the exit code carries the overall outcome
+        }
+    }
+
+    if summary.error > 0 {
+        2
+    } else if summary.deny > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Write one CSV row per record, with an `error` column for failures
+pub fn write_csv<W: Write>(records: &[JsonRecord], output: W) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_writer(output);
+    writer.write_record([
+        "line", "id", "decision", "user", "module", "rpc", "operation", "path", "context", "command",
+        "should_log", "rule_list", "rule", "error",
+    ])?;
+
+    for record in records {
+        let row: Vec<String> = match record {
+            JsonRecord::Result(r) => vec![
+                r.line.map(|l| l.to_string()).unwrap_or_default(),
+                r.id.as_ref().map(id_to_cell).unwrap_or_default(),
+                r.decision.clone(),
+                r.user.clone(),
+                r.module.clone().unwrap_or_default(),
+                r.rpc.clone().unwrap_or_default(),
+                r.operation.clone(),
+                r.path.clone().unwrap_or_default(),
+                r.context.clone().unwrap_or_default(),
+                r.command.clone().unwrap_or_default(),
+                r.should_log.to_string(),
+                r.rule_list.clone().unwrap_or_default(),
+                r.rule.clone().unwrap_or_default(),
+                String::new(),
+            ],
+            JsonRecord::Error(e) => {
+                let mut row = vec![String::new(); 14];
+                row[0] = e.line.map(|l| l.to_string()).unwrap_or_default();
+                row[1] = e.id.as_ref().map(id_to_cell).unwrap_or_default();
+                row[13] = e.error.clone();
+                row
+            }
+        };
+        writer.write_record(&row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Render a request id for a CSV cell, without quotes around plain strings
+fn id_to_cell(id: &serde_json::Value) -> String {
+    match id {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nacm_validator::{Operation, RuleEffect};
+    use serde_json::json;
+
+    fn values(records: &[InputRecord]) -> Vec<(usize, serde_json::Value)> {
+        records
+            .iter()
+            .map(|r| (r.line, r.value.clone().unwrap_or_else(|e| json!({ "error": e }))))
+            .collect()
+    }
+
+    #[test]
+    fn format_is_taken_from_the_extension() {
+        let array = "[{\"user\":\"alice\"}]";
+        assert_eq!(detect_format(Path::new("r.ndjson"), array), InputFormat::Ndjson);
+        assert_eq!(detect_format(Path::new("r.JSONL"), array), InputFormat::Ndjson);
+        assert_eq!(detect_format(Path::new("r.yml"), array), InputFormat::Yaml);
+        assert_eq!(detect_format(Path::new("r.yaml"), array), InputFormat::Yaml);
+        assert_eq!(detect_format(Path::new("r.csv"), array), InputFormat::Csv);
+    }
+
+    #[test]
+    fn format_is_sniffed_for_json_stdin_and_unknown_extensions() {
+        for path in ["r.json", "-", "requests.txt", "requests"] {
+            let path = Path::new(path);
+            assert_eq!(detect_format(path, "  \n[{\"user\":\"alice\"}]"), InputFormat::Json);
+            assert_eq!(detect_format(path, "{\"user\":\"alice\"}\n{}"), InputFormat::Ndjson);
+            assert_eq!(detect_format(path, "id, user ,operation\n1,alice,read"), InputFormat::Csv);
+            assert_eq!(detect_format(path, "- user: alice\n  operation: read"), InputFormat::Yaml);
+            // Commas alone do not make CSV without a user column
+            assert_eq!(detect_format(path, "- {user: alice, operation: read}"), InputFormat::Yaml);
+        }
+    }
+
+    #[test]
+    fn ndjson_keeps_physical_line_numbers() {
+        let records = parse_ndjson("{\"user\":\"a\"}\n\n  \n{\"user\":\"b\"}\n{oops\n");
+        assert_eq!(records.iter().map(|r| r.line).collect::<Vec<_>>(), [1, 4, 5]);
+        assert_eq!(records[1].value.as_ref().unwrap(), &json!({"user": "b"}));
+        assert!(records[2].value.as_ref().unwrap_err().starts_with("Invalid JSON"));
+    }
+
+    #[test]
+    fn json_arrays_and_yaml_sequences_are_numbered_by_element() {
+        let records = parse_json_array("[\n{\"user\":\"a\"},\n\n{\"user\":\"b\"}]").unwrap();
+        assert_eq!(values(&records), [(1, json!({"user": "a"})), (2, json!({"user": "b"}))]);
+        assert!(parse_json_array("{\"user\":\"a\"}").unwrap_err().starts_with("Expected a JSON array"));
+
+        let records = parse_yaml("- user: a\n  id: 7\n\n- {user: b, operation: read}\n").unwrap();
+        assert_eq!(
+            values(&records),
+            [(1, json!({"user": "a", "id": 7})), (2, json!({"user": "b", "operation": "read"}))]
+        );
+        assert!(parse_yaml("user: a").unwrap_err().starts_with("Expected a YAML sequence"));
+    }
+
+    #[test]
+    fn csv_rows_become_objects_without_empty_cells() {
+        let content = "id,user,operation,command\nr1, alice ,read,\"show\nstatus\"\nr2,bob,exec,\n";
+        let records = parse_csv(content).unwrap();
+        assert_eq!(
+            values(&records),
+            [
+                (2, json!({"id": "r1", "user": "alice", "operation": "read", "command": "show\nstatus"})),
+                // The quoted newline above puts this row on line 4
+                (4, json!({"id": "r2", "user": "bob", "operation": "exec"})),
+            ]
+        );
+
+        // Short rows are allowed; missing trailing cells are absent
+        let records = parse_csv("user,operation,module\nalice,read\n").unwrap();
+        assert_eq!(values(&records), [(2, json!({"user": "alice", "operation": "read"}))]);
+    }
+
+    #[test]
+    fn explicit_format_overrides_detection() {
+        let path = std::env::temp_dir().join(format!("nacm-batch-{}.csv", std::process::id()));
+        std::fs::write(&path, "{\"user\":\"alice\",\"operation\":\"read\"}\n").unwrap();
+        let detected = read_input(&path, None);
+        let forced = read_input(&path, Some(InputFormat::Ndjson));
+        let missing = read_input(Path::new("/nonexistent/requests.csv"), None);
+        std::fs::remove_file(&path).unwrap();
+
+        // As CSV the line is only a header
+        assert!(detected.unwrap().is_empty());
+        assert_eq!(values(&forced.unwrap()), [(1, json!({"user": "alice", "operation": "read"}))]);
+        assert!(missing.unwrap_err().starts_with("Error reading"));
+    }
+
+    #[test]
+    fn evaluated_records_are_summarized_and_written_as_csv() {
+        let config = NacmConfig::builder()
+            .exec_default(RuleEffect::Deny)
+            .group("admin", ["alice"])
+            .rule_list("admin-acl", ["admin"], |rl| {
+                rl.permit().name("edit").rpc("edit-config").ops([Operation::Exec])
+            })
+            .build()
+            .unwrap();
+        let mut records = parse_ndjson(concat!(
+            "{\"id\":\"a\",\"user\":\"alice\",\"operation\":\"exec\",\"rpc\":\"edit-config\"}\n",
+            "{\"id\":[1,2],\"user\":\"bob\",\"operation\":\"exec\",\"rpc\":\"edit-config\"}\n",
+            "{\"user\":\"bob\",\"operation\":\"fly\"}\n",
+        ));
+        records.push(InputRecord { line: 9, value: Err("Invalid CSV row: oops".to_string()) });
+        let results = evaluate(&config, &Evaluation::default(), records);
+
+        let summary = summarize(&results);
+        assert_eq!((summary.permit, summary.deny, summary.error), (1, 1, 2));
+
+        let mut csv = Vec::new();
+        write_csv(&results, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "line,id,decision,user,module,rpc,operation,path,context,command,should_log,rule_list,rule,error"
+        );
+        assert_eq!(lines[1], "1,a,permit,alice,,edit-config,exec,,,,false,admin-acl,edit,");
+        assert_eq!(lines[2], "2,\"[1,2]\",deny,bob,,edit-config,exec,,,,false,,,");
+        assert!(lines[3].starts_with("3,,,,,,,,,,,,,"));
+        assert!(lines[3].contains("fly"));
+        assert_eq!(lines[4], "9,,,,,,,,,,,,,Invalid CSV row: oops");
+        assert_eq!(lines.len(), 5);
+    }
+}
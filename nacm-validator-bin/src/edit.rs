@@ -0,0 +1,130 @@
+//! # Edit Pre-flight
+//!
+//! Implements the `edit` subcommand: derives the node-level operations of a
+//! NETCONF `<config>` payload or a RESTCONF YANG Patch and checks each of
+//! them for the `--user`, optionally against the current datastore.
+//!
+//! ```bash
+//! nacm-validator --config config.xml --user oper edit change.xml --datastore running.xml
+//! nacm-validator --config config.xml --user oper --format json edit patch.json
+//! ```
+//!
+//! Every node check is reported like a batch result, with the instance path
+//! of the node, so the output formats and exit codes are those of `--input`.
+
+use crate::batch;
+use crate::{JsonRecord, JsonResult, OutputFormat};
+use clap::Args;
+use nacm_validator::condition::Environment;
+use nacm_validator::edit::{EditAuthorization, EditOperation, EditPayload, EditRequest, NodeAccess};
+use nacm_validator::tree::{InstanceTree, ModuleResolver};
+use nacm_validator::xmltree::Element;
+use nacm_validator::{NacmConfig, RequestContext, RuleEffect};
+use std::path::PathBuf;
+
+/// Arguments for the `edit` subcommand
+#[derive(Args)]
+pub struct EditArgs {
+    /// Edit payload: NETCONF <config> XML or YANG Patch JSON (use "-" for stdin)
+    pub payload: PathBuf,
+
+    /// Current datastore contents, XML or JSON, to tell creates from updates
+    ///
+    /// Without it, every node in the payload is assumed to exist already.
+    #[arg(long)]
+    pub datastore: Option<PathBuf>,
+
+    /// NETCONF default-operation for nodes without an operation attribute
+    #[arg(long, default_value = "merge", value_parser = ["merge", "replace", "none"])]
+    pub default_operation: String,
+
+    /// Map an XML namespace to a YANG module name (NAMESPACE=MODULE, repeatable)
+    #[arg(long, value_name = "NAMESPACE=MODULE", value_parser = crate::filter::parse_namespace)]
+    pub namespace: Vec<(String, String)>,
+}
+
+/// Check the edit and print one result per touched node
+///
+/// ## Returns
+///
+/// * `Ok(i32)` - Exit code: 0 if every node check is permitted, 1 otherwise
+/// * `Err(String)` - The payload or datastore could not be read or parsed
+pub fn run(
+    config: &NacmConfig,
+    user: &str,
+    context: Option<&RequestContext>,
+    args: &EditArgs,
+    env: &Environment,
+    format: &OutputFormat,
+) -> Result<i32, String> {
+    let payload: EditPayload = batch::read_text(&args.payload)?.parse()?;
+    let datastore = match &args.datastore {
+        Some(path) => Some(parse_datastore(&batch::read_text(path)?)?),
+        None => None,
+    };
+
+    let mut resolver = ModuleResolver::new();
+    for (namespace, module) in &args.namespace {
+        resolver.insert(namespace.clone(), module.clone());
+    }
+
+    let request = EditRequest {
+        user,
+        context,
+        payload: &payload,
+        datastore: datastore.as_ref(),
+        default_operation: args.default_operation.parse::<EditOperation>()?,
+    };
+    let authorization = config.authorize_edit_with(&request, &resolver, env)?;
+    let records = node_records(&authorization, user, context, 1);
+    Ok(batch::output_records(&records, format))
+}
+
+/// Report every node check of an edit, numbering the records from `first_line`
+pub fn node_records(
+    authorization: &EditAuthorization,
+    user: &str,
+    context: Option<&RequestContext>,
+    first_line: usize,
+) -> Vec<JsonRecord> {
+    authorization
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| node_record(first_line + index, user, context, node))
+        .collect()
+}
+
+/// Parse a datastore document; JSON documents start with an object
+fn parse_datastore(content: &str) -> Result<InstanceTree, String> {
+    if content.trim_start().starts_with('{') {
+        let value = serde_json::from_str(content).map_err(|e| format!("Invalid JSON datastore: {}", e))?;
+        Ok(InstanceTree::Json(value))
+    } else {
+        let root = Element::parse(content.as_bytes()).map_err(|e| format!("Invalid XML datastore: {}", e))?;
+        Ok(InstanceTree::Xml(root))
+    }
+}
+
+/// Report a node check in the shape of a batch result
+fn node_record(line: usize, user: &str, context: Option<&RequestContext>, node: &NodeAccess) -> JsonRecord {
+    JsonRecord::Result(Box::new(JsonResult {
+        line: Some(line),
+        id: None,
+        decision: match node.effect {
+            RuleEffect::Permit => "permit".to_string(),
+            RuleEffect::Deny => "deny".to_string(),
+        },
+        user: user.to_string(),
+        module: node.module.clone(),
+        rpc: None,
+        operation: node.operation.to_string(),
+        path: Some(node.path.clone()),
+        context: context.map(|ctx| ctx.to_string()),
+        command: None,
+        config_loaded: true,
+        should_log: node.should_log,
+        rule_list: node.rule_list.clone(),
+        rule: node.rule.clone(),
+    }))
+}
@@ -0,0 +1,92 @@
+//! # Offline Data Tree Filtering
+//!
+//! Implements the `filter` subcommand: prunes an XML or JSON instance
+//! document to the nodes the `--user` may read, as a NETCONF `<get>` would.
+//!
+//! ```bash
+//! nacm-validator --config config.xml --user dash filter running.xml
+//! nacm-validator --config config.xml --user dash --context netconf \
+//!     filter --namespace http://tail-f.com/ns/ncs=tailf-ncs running.json
+//! ```
+
+use clap::Args;
+use nacm_validator::condition::Environment;
+use nacm_validator::tree::{InstanceTree, ModuleResolver};
+use nacm_validator::xmltree::{Element, EmitterConfig};
+use nacm_validator::{NacmConfig, RequestContext};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Arguments for the `filter` subcommand
+#[derive(Args)]
+pub struct FilterArgs {
+    /// Instance document to filter, XML or JSON (use "-" for stdin)
+    pub document: PathBuf,
+
+    /// Map an XML namespace to a YANG module name (NAMESPACE=MODULE, repeatable)
+    ///
+    /// IETF namespaces (urn:ietf:params:xml:ns:yang:<module>) are known already.
+    #[arg(long, value_name = "NAMESPACE=MODULE", value_parser = parse_namespace)]
+    pub namespace: Vec<(String, String)>,
+}
+
+/// Parse a `NAMESPACE=MODULE` pair, splitting at the last `=`
+pub fn parse_namespace(s: &str) -> Result<(String, String), String> {
+    match s.rsplit_once('=') {
+        Some((namespace, module)) if !namespace.is_empty() && !module.is_empty() => {
+            Ok((namespace.to_string(), module.to_string()))
+        }
+        _ => Err(format!("expected NAMESPACE=MODULE, got '{}'", s)),
+    }
+}
+
+/// Filter the document and print the result in the input's encoding
+///
+/// ## Returns
+///
+/// * `Ok(())` - The filtered document was written to stdout
+/// * `Err(String)` - The document could not be read or parsed
+pub fn run(
+    config: &NacmConfig,
+    user: &str,
+    context: Option<&RequestContext>,
+    args: &FilterArgs,
+    env: &Environment,
+) -> Result<(), String> {
+    let content = if args.document.as_path() == Path::new("-") {
+        let mut buffer = String::new();
+        io::stdin()
+            .read_to_string(&mut buffer)
+            .map_err(|e| format!("Error reading stdin: {}", e))?;
+        buffer
+    } else {
+        std::fs::read_to_string(&args.document).map_err(|e| format!("Error reading {:?}: {}", args.document, e))?
+    };
+
+    // JSON documents start with an object; everything else is taken as XML
+    let document = if content.trim_start().starts_with('{') {
+        let value = serde_json::from_str(&content).map_err(|e| format!("Invalid JSON document: {}", e))?;
+        InstanceTree::Json(value)
+    } else {
+        let root = Element::parse(content.as_bytes()).map_err(|e| format!("Invalid XML document: {}", e))?;
+        InstanceTree::Xml(root)
+    };
+
+    let mut resolver = ModuleResolver::new();
+    for (namespace, module) in &args.namespace {
+        resolver.insert(namespace.clone(), module.clone());
+    }
+
+    match config.filter_tree_with(user, context, &document, &resolver, env) {
+        InstanceTree::Json(value) => {
+            println!("{}", serde_json::to_string_pretty(&value).unwrap());
+        }
+        InstanceTree::Xml(root) => {
+            let emitter = EmitterConfig::new().perform_indent(true);
+            root.write_with_config(io::stdout(), emitter)
+                .map_err(|e| format!("Error writing XML: {}", e))?;
+            println!();
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,415 @@
+//! # HTTP/JSON Decision API
+//!
+//! Optional HTTP front end for the `serve` subcommand, enabled with the
+//! `http` cargo feature. It follows the external-authorizer pattern used by
+//! API gateways: the gateway POSTs the request attributes and acts on the
+//! returned decision.
+//!
+//! ## Endpoints
+//!
+//! - `POST /v1/decide` - body is one JSON request (same fields as
+//!   `--json-input`); responds with the JSON result including `decision`,
+//!   `should_log`, `rule_list` and `rule`
+//! - `POST /v1/decide/batch` - body is a JSON array of requests; responds
+//!   with `{"results": [...]}`, one entry per request in the same order.
+//!   Entries that could not be evaluated carry an `error` field instead of
+//!   a `decision`; any request `id` is echoed in both cases
+//! - `GET /health` - liveness probe with a short configuration summary
+//! - `GET /metrics` and `GET /v1/nacm` - denial statistics; see the
+//!   `metrics` module
+//!
+//! Malformed requests get status 400 with an `{"error": "..."}` body.
+//!
+//! Conditional rules see the client's address as the `source-ip` attribute;
+//! a request's own `attributes` take precedence.
+//!
+//! ```bash
+//! curl -s -X POST http://127.0.0.1:8181/v1/decide \
+//!   -d '{"user":"alice","operation":"read","module":"ietf-interfaces"}'
+//! ```
+
+use crate::serve::SharedConfig;
+use crate::{process_json_value, Evaluation, JsonError, JsonRecord};
+use serde::Serialize;
+use std::io::{self, Read};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Number of worker threads answering HTTP requests
+const WORKER_THREADS: usize = 4;
+
+/// Largest request body accepted, in bytes
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+/// Body of a batch response
+#[derive(Serialize)]
+struct BatchResponse {
+    /// One entry per request, in request order
+    results: Vec<JsonRecord>,
+}
+
+/// Body of the health endpoint
+#[derive(Serialize)]
+struct HealthResponse {
+    /// Always "ok" while the server is answering
+    status: &'static str,
+    /// Whether NACM enforcement is enabled in the loaded configuration
+    nacm_enabled: bool,
+    /// Number of groups in the loaded configuration
+    groups: usize,
+    /// Number of rule lists in the loaded configuration
+    rule_lists: usize,
+}
+
+/// Serve the HTTP/JSON decision API until the process is terminated
+///
+/// ## Parameters
+///
+/// * `addr` - Loopback address to listen on
+/// * `shared` - Configuration shared with the SIGHUP reload handler
+/// * `evaluation` - Environment and audit log to decide in
+/// * `verbose` - Log each request to stderr
+pub fn serve_http(addr: SocketAddr, shared: &SharedConfig, evaluation: &Evaluation, verbose: bool) -> io::Result<()> {
+    let server = Server::http(addr).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    eprintln!("Listening on http://{}", server.server_addr());
+    run_workers(Arc::new(server), shared, evaluation, verbose);
+    Ok(())
+}
+
+/// Answer requests on `server` from a pool of worker threads until it shuts down
+fn run_workers(server: Arc<Server>, shared: &SharedConfig, evaluation: &Evaluation, verbose: bool) {
+    // tiny_http lets several threads receive from the same server
+    let workers: Vec<_> = (0..WORKER_THREADS)
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let shared = Arc::clone(shared);
+            let evaluation = evaluation.clone();
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    if verbose {
+                        eprintln!("{} {}", request.method(), request.url());
+                    }
+                    if let Err(e) = handle_request(request, &shared, &evaluation) {
+                        eprintln!("Error writing HTTP response: {}", e);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+}
+
+/// Route a single HTTP request and send the response
+fn handle_request(mut request: Request, shared: &SharedConfig, evaluation: &Evaluation) -> io::Result<()> {
+    // Ignore any query string when routing
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+
+    if *request.method() == Method::Get {
+        let accept = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Accept"))
+            .map(|h| h.value.as_str().to_string());
+        if let Some(stats) = crate::metrics::render(&path, accept.as_deref(), shared) {
+            let content_type = Header::from_bytes(&b"Content-Type"[..], stats.content_type.as_bytes()).unwrap();
+            let response = Response::from_string(stats.body)
+                .with_status_code(stats.status)
+                .with_header(content_type);
+            return request.respond(response);
+        }
+    }
+
+    let evaluation = evaluation.for_peer(request.remote_addr().map(|addr| addr.ip()));
+    let (status, body) = match (request.method(), path.as_str()) {
+        (Method::Post, "/v1/decide") => match read_body(&mut request) {
+            Ok(body) => decide_one(&body, shared, &evaluation),
+            Err(e) => error_response(400, e),
+        },
+        (Method::Post, "/v1/decide/batch") => match read_body(&mut request) {
+            Ok(body) => decide_batch(&body, shared, &evaluation),
+            Err(e) => error_response(400, e),
+        },
+        (Method::Get, "/health") => {
+            let config = shared.read().unwrap_or_else(|e| e.into_inner());
+            let health = HealthResponse {
+                status: "ok",
+                nacm_enabled: config.enable_nacm,
+                groups: config.groups.len(),
+                rule_lists: config.rule_lists.len(),
+            };
+            (200, serde_json::to_string(&health).unwrap())
+        }
+        (_, "/v1/decide") | (_, "/v1/decide/batch") | (_, "/health") | (_, "/metrics") | (_, "/v1/nacm") => {
+            error_response(405, format!("Method {} not allowed", request.method()))
+        }
+        _ => error_response(404, format!("No such endpoint: {}", path)),
+    };
+
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    request.respond(response)
+}
+
+/// Read the request body as UTF-8, enforcing the size limit
+fn read_body(request: &mut Request) -> Result<String, String> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| format!("Error reading request body: {}", e))?;
+
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return Err(format!("Request body exceeds {} bytes", MAX_BODY_BYTES));
+    }
+    Ok(body)
+}
+
+/// Handle `POST /v1/decide`
+fn decide_one(body: &str, shared: &SharedConfig, evaluation: &Evaluation) -> (u16, String) {
+    let value = match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(value) => value,
+        Err(e) => return error_response(400, format!("Invalid JSON: {}", e)),
+    };
+
+    let config = shared.read().unwrap_or_else(|e| e.into_inner());
+    let record = process_json_value(&config, evaluation, value, None);
+    let status = match record {
+        JsonRecord::Result(_) => 200,
+        JsonRecord::Error(_) => 400,
+    };
+    (status, serde_json::to_string(&record).unwrap())
+}
+
+/// Handle `POST /v1/decide/batch`
+///
+/// The body must be a JSON array. Individual requests that fail to parse or
+/// evaluate produce an error entry rather than failing the whole batch.
+fn decide_batch(body: &str, shared: &SharedConfig, evaluation: &Evaluation) -> (u16, String) {
+    let items = match serde_json::from_str::<Vec<serde_json::Value>>(body) {
+        Ok(items) => items,
+        Err(e) => return error_response(400, format!("Expected a JSON array of requests: {}", e)),
+    };
+
+    // Hold the read lock once so the whole batch sees the same configuration
+    let config = shared.read().unwrap_or_else(|e| e.into_inner());
+    let results = items
+        .into_iter()
+        .map(|item| process_json_value(&config, evaluation, item, None))
+        .collect();
+
+    (200, serde_json::to_string(&BatchResponse { results }).unwrap())
+}
+
+/// Build an error status and JSON body
+fn error_response(status: u16, error: String) -> (u16, String) {
+    (status, serde_json::to_string(&JsonError { line: None, id: None, error }).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nacm_validator::condition::{AttributeMatch, RuleCondition};
+    use nacm_validator::stats::NacmStats;
+    use nacm_validator::{NacmConfig, Operation, RuleEffect};
+    use serde_json::{json, Value};
+    use std::io::Write;
+    use std::net::{Shutdown, TcpStream};
+    use std::sync::RwLock;
+
+    /// Serve `config` on an ephemeral loopback port
+    fn start(config: NacmConfig) -> SocketAddr {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+        let shared: SharedConfig = Arc::new(RwLock::new(config));
+        thread::spawn(move || run_workers(Arc::new(server), &shared, &Evaluation::default(), false));
+        addr
+    }
+
+    fn policy() -> NacmConfig {
+        NacmConfig::builder()
+            .exec_default(RuleEffect::Deny)
+            .group("admin", ["alice"])
+            .rule_list("admin-acl", ["admin"], |rl| {
+                rl.permit().name("edit").rpc("edit-config").ops([Operation::Exec])
+            })
+            .build()
+            .unwrap()
+    }
+
+    /// Send one HTTP/1.1 request and return the status and body
+    fn call(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split_once("\r\n\r\n").map_or("", |(_, body)| body).to_string();
+        (status, body)
+    }
+
+    fn json(body: &str) -> Value {
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[test]
+    fn decide_returns_decision_and_rule() {
+        let addr = start(policy());
+        let (status, body) = call(
+            addr,
+            "POST",
+            "/v1/decide",
+            r#"{"id":"r1","user":"alice","operation":"exec","rpc":"edit-config"}"#,
+        );
+        assert_eq!(status, 200);
+        let result = json(&body);
+        assert_eq!(result["id"], "r1");
+        assert_eq!(result["decision"], "permit");
+        assert_eq!(result["rule_list"], "admin-acl");
+        assert_eq!(result["rule"], "edit");
+        assert!(result.get("line").is_none());
+
+        let bob = r#"{"user":"bob","operation":"exec","rpc":"edit-config"}"#;
+        let (status, body) = call(addr, "POST", "/v1/decide?trace=1", bob);
+        assert_eq!(status, 200);
+        assert_eq!(json(&body)["decision"], "deny");
+        assert_eq!(json(&body)["rule"], Value::Null);
+    }
+
+    #[test]
+    fn peer_address_is_the_source_ip() {
+        let loopback = RuleCondition {
+            attributes: vec![AttributeMatch::new("source-ip", "127.0.0.0/8").unwrap()],
+            ..Default::default()
+        };
+        let config = NacmConfig::builder()
+            .exec_default(RuleEffect::Deny)
+            .group("admin", ["alice"])
+            .rule_list("admin-acl", ["admin"], |rl| {
+                rl.permit().name("local").rpc("edit-config").ops([Operation::Exec]).condition(loopback)
+            })
+            .build()
+            .unwrap();
+        let addr = start(config);
+
+        let local = r#"{"user":"alice","operation":"exec","rpc":"edit-config"}"#;
+        let (_, body) = call(addr, "POST", "/v1/decide", local);
+        assert_eq!(json(&body)["decision"], "permit");
+
+        let remote = json!({
+            "user": "alice", "operation": "exec", "rpc": "edit-config",
+            "attributes": {"source-ip": "192.0.2.1"}
+        });
+        let (_, body) = call(addr, "POST", "/v1/decide", &remote.to_string());
+        assert_eq!(json(&body)["decision"], "deny");
+    }
+
+    #[test]
+    fn decide_rejects_bad_requests() {
+        let addr = start(policy());
+
+        let (status, body) = call(addr, "POST", "/v1/decide", "{not json");
+        assert_eq!(status, 400);
+        assert!(json(&body)["error"].as_str().unwrap().starts_with("Invalid JSON"));
+
+        let (status, body) = call(addr, "POST", "/v1/decide", r#"{"id":5,"user":"alice","operation":"fly"}"#);
+        assert_eq!(status, 400);
+        assert_eq!(json(&body)["id"], 5);
+        assert!(json(&body)["error"].as_str().unwrap().contains("fly"));
+
+        let (status, body) = call(addr, "POST", "/v1/decide", r#"{"operation":"read"}"#);
+        assert_eq!(status, 400);
+        assert!(json(&body)["error"].as_str().unwrap().contains("user"));
+    }
+
+    #[test]
+    fn batch_answers_in_order_with_inline_errors() {
+        let addr = start(policy());
+        let (status, body) = call(
+            addr,
+            "POST",
+            "/v1/decide/batch",
+            r#"[{"id":1,"user":"alice","operation":"exec","rpc":"edit-config"},
+                {"id":2,"user":"alice","operation":"fly"},
+                {"id":3,"user":"bob","operation":"exec","rpc":"edit-config"}]"#,
+        );
+        assert_eq!(status, 200);
+        let results = json(&body)["results"].as_array().unwrap().clone();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["id"], 1);
+        assert_eq!(results[0]["decision"], "permit");
+        assert_eq!(results[1]["id"], 2);
+        assert!(results[1].get("decision").is_none());
+        assert!(results[1]["error"].is_string());
+        assert_eq!(results[2]["decision"], "deny");
+
+        let (status, body) = call(addr, "POST", "/v1/decide/batch", r#"{"user":"alice"}"#);
+        assert_eq!(status, 400);
+        assert!(json(&body)["error"].as_str().unwrap().starts_with("Expected a JSON array"));
+
+        let (status, body) = call(addr, "POST", "/v1/decide/batch", "[]");
+        assert_eq!((status, json(&body)["results"].as_array().unwrap().len()), (200, 0));
+    }
+
+    #[test]
+    fn health_summarizes_the_configuration() {
+        let addr = start(policy());
+        let (status, body) = call(addr, "GET", "/health", "");
+        assert_eq!(status, 200);
+        let health = json(&body);
+        assert_eq!(health["status"], "ok");
+        assert_eq!(health["nacm_enabled"], true);
+        assert_eq!(health["groups"], 1);
+        assert_eq!(health["rule_lists"], 1);
+    }
+
+    #[test]
+    fn body_size_is_capped() {
+        let addr = start(policy());
+        let at_limit = " ".repeat(MAX_BODY_BYTES as usize - 2) + "[]";
+        let (status, _) = call(addr, "POST", "/v1/decide/batch", &at_limit);
+        assert_eq!(status, 200);
+
+        let over_limit = " ".repeat(MAX_BODY_BYTES as usize - 1) + "[]";
+        let (status, body) = call(addr, "POST", "/v1/decide/batch", &over_limit);
+        assert_eq!(status, 400);
+        assert!(json(&body)["error"].as_str().unwrap().contains("exceeds"));
+    }
+
+    #[test]
+    fn unknown_paths_and_methods_are_rejected() {
+        let mut config = policy();
+        config.stats = Some(Arc::new(NacmStats::new()));
+        let addr = start(config);
+
+        assert_eq!(call(addr, "GET", "/v1/decide", "").0, 405);
+        assert_eq!(call(addr, "POST", "/health", "").0, 405);
+        assert_eq!(call(addr, "DELETE", "/metrics", "").0, 405);
+        assert_eq!(call(addr, "GET", "/v2/decide", "").0, 404);
+
+        let (status, body) = call(addr, "GET", "/metrics", "");
+        assert_eq!(status, 200);
+        assert!(body.contains("nacm_denied_operations_total 0"));
+
+        // Without statistics the metrics endpoints report that instead
+        let addr = start(policy());
+        assert_eq!(call(addr, "GET", "/v1/nacm", "").0, 404);
+    }
+}
@@ -29,6 +29,81 @@
 //! # Process multiple requests from JSON
 //! echo '{"user":"alice","operation":"read","module":"ietf-interfaces"}' | \
 //!   nacm-validator --config config.xml --json-input
+//! 
+//! # Process a file of requests (NDJSON, JSON array, YAML or CSV) as a CSV report
+//! nacm-validator --config config.xml --input requests.csv --format csv
+//! ```
+//! 
+//! ### Audit Logging
+//! ```bash
+//! # Append decisions flagged by log-if-* settings to a JSON-lines file
+//! nacm-validator --config config.xml --audit-log audit.jsonl --user bob --operation exec --context cli --command reboot
+//! ```
+//! 
+//! ### Validation Server
+//! ```bash
+//! # Load the config once and answer newline-delimited JSON requests on a socket
+//! nacm-validator --config config.xml serve --socket /run/nacm.sock
+//! 
+//! # Or on localhost TCP; send SIGHUP to reload the configuration
+//! nacm-validator --config config.xml serve --listen 127.0.0.1:7878
+//! 
+//! # HTTP/JSON decision API (requires the `http` cargo feature)
+//! nacm-validator --config config.xml serve --http 127.0.0.1:8181
+//! 
+//! # Expose RFC 8341 denial counters for Prometheus on /metrics
+//! nacm-validator --config config.xml serve --socket /run/nacm.sock --metrics 127.0.0.1:9100
+//! ```
+//! 
+//! ### Data Tree Filtering
+//! ```bash
+//! # Show what a user would get back from a <get>: unreadable nodes are pruned
+//! nacm-validator --config config.xml --user dash filter running.xml
+//! ```
+//! 
+//! ### Edit Pre-flight
+//! ```bash
+//! # Check every node an edit-config would create, update or delete
+//! nacm-validator --config config.xml --user oper edit change.xml --datastore running.xml
+//! ```
+//! 
+//! ### NETCONF Messages
+//! ```bash
+//! # Check the protocol operation and data nodes of an <rpc> message
+//! nacm-validator --config config.xml --user alice --netconf-rpc edit-config.xml
+//! ```
+//! 
+//! ### RESTCONF Requests
+//! ```bash
+//! # Check the access requests behind a RESTCONF call
+//! nacm-validator --config config.xml --user alice restconf GET /restconf/data/ietf-interfaces:interfaces
+//! ```
+//! 
+//! ### Policy Simulation
+//! ```bash
+//! # Which logged requests would change under a proposed /nacm edit?
+//! nacm-validator --config config.xml simulate audit.jsonl --patch add-deny.xml
+//! ```
+//! 
+//! ### Policy Synthesis
+//! ```bash
+//! # Propose permit rules covering what the oper group was seen doing
+//! nacm-validator synthesize audit.jsonl --group oper --user bob --standalone > oper.xml
+//! ```
+//! 
+//! ### Policy Analysis
+//! ```bash
+//! # Conflicting, shadowed and redundant rules, each with a counterexample
+//! nacm-validator --config config.xml analyze
+//! ```
+//! 
+//! ### Policy Test Suites
+//! ```bash
+//! # Check a catalogue of expected decisions; the suite may name its own config
+//! nacm-validator test policy-tests.yaml
+//! 
+//! # JUnit XML for CI, against an explicitly chosen config
+//! nacm-validator --config config.xml test policy-tests.yaml --format junit
 //! ```
 //! 
 //! ## Exit Codes
@@ -37,11 +112,83 @@
 //! - **1**: Access denied  
 //! - **2**: Error (invalid config, missing file, etc.)
 
-use clap::{Parser, ValueEnum};
-use nacm_validator::{AccessRequest, NacmConfig, Operation, RuleEffect, RequestContext};
-use serde::{Deserialize, Serialize};
+mod analyze;
+mod batch;
+mod edit;
+mod filter;
+#[cfg(feature = "http")]
+mod http;
+mod metrics;
+mod restconf;
+mod serve;
+mod simulate;
+mod synthesize;
+mod suite;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use nacm_validator::audit::{parse_rfc3339, AuditSink, JsonLinesSink};
+use nacm_validator::condition::Environment;
+use nacm_validator::edit::EditRequest;
+use nacm_validator::merge::{DefaultsMerge, MergeStrategy, RuleListMerge};
+use nacm_validator::netconf;
+use nacm_validator::tree::ModuleResolver;
+use nacm_validator::{AccessRequest, Decision, NacmConfig, Operation, OwnedAccessRequest, RuleEffect, RequestContext};
+use serde::Serialize;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::process;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Where and how decisions are made, apart from the configuration
+/// 
+/// Built once in `main()` from the global options and passed down to every
+/// evaluation path along with the configuration. Cloning is cheap, so
+/// servers make one per connection or request.
+#[derive(Clone, Default)]
+struct Evaluation {
+    /// Time and request attributes for conditional rules: `--at` and
+    /// `--attribute`, plus whatever a connection or request adds
+    env: Environment,
+    /// Audit log opened from `--audit-log`
+    audit_log: Option<Arc<dyn AuditSink + Send + Sync>>,
+}
+
+impl Evaluation {
+    /// The evaluation for requests from `peer`, whose address becomes the
+    /// `source-ip` attribute
+    fn for_peer(&self, peer: Option<IpAddr>) -> Evaluation {
+        match peer {
+            Some(ip) => Evaluation {
+                env: self.env.clone().with_attribute("source-ip", ip.to_string()),
+                ..self.clone()
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// The evaluation for one JSON request, with its `at` and `attributes`
+    /// fields applied (see [`request_environment`])
+    fn for_request(&self, value: &serde_json::Value) -> Result<Evaluation, String> {
+        Ok(Evaluation {
+            env: request_environment(value, &self.env)?,
+            ..self.clone()
+        })
+    }
+
+    /// Decide a request, auditing the decision if there is an audit log
+    /// 
+    /// A decision that should be logged but cannot be written to the audit
+    /// log is reported as an error rather than returned unaudited.
+    fn decide<'a>(&self, config: &'a NacmConfig, request: &AccessRequest) -> Result<Decision<'a>, String> {
+        match &self.audit_log {
+            Some(sink) => config
+                .decide_and_audit_with(request, &self.env, sink.as_ref())
+                .map_err(|e| format!("Error writing audit log: {}", e)),
+            None => Ok(config.decide_with(request, &self.env)),
+        }
+    }
+}
 
 /// Command-line interface structure for the NACM validator
 /// 
@@ -57,12 +204,33 @@ use std::process;
 #[derive(Parser)]
 #[command(author, version, about = "NACM Access Control Validator", long_about = None)]
 struct Cli {
-    /// Path to the NACM XML configuration file
+    /// Path to a NACM XML configuration file, or a directory of them
     /// 
-    /// This is the only truly required argument in all modes.
+    /// Required in all modes except `test`, where the suite file may name
+    /// the configuration instead (this option overrides it).
     /// The configuration file contains the NACM groups, rules, and policies.
-    #[arg(short, long)]
-    config: PathBuf,
+    /// 
+    /// Repeat the option to split the policy across files: they are merged
+    /// in the order given, each into the result of the ones before. A
+    /// directory stands for the `.xml` files in it, in file name order.
+    #[arg(short, long, value_name = "PATH")]
+    config: Vec<PathBuf>,
+
+    /// How later configuration files change the global settings
+    /// 
+    /// - `strictest`: `deny` beats `permit`, enabled beats disabled (default)
+    /// - `keep`: the first file's settings stay
+    /// - `override`: each file's settings replace the earlier ones
+    #[arg(long, value_name = "STRATEGY", default_value = "strictest")]
+    merge_defaults: DefaultsMerge,
+
+    /// Where the rule lists of later configuration files go
+    /// 
+    /// - `append`: after the earlier ones; duplicate names are an error (default)
+    /// - `prepend`: before the earlier ones; duplicate names are an error
+    /// - `replace`: a rule list replaces an earlier one of the same name
+    #[arg(long, value_name = "STRATEGY", default_value = "append")]
+    merge_rule_lists: RuleListMerge,
 
     /// Username making the request
     /// 
@@ -96,15 +264,17 @@ struct Cli {
     /// 
     /// XPath or data path for the access request.
     /// Supports simple wildcard patterns like "/interfaces/*".
+    /// With `--operation exec`, the path is that of a YANG 1.1 action.
     #[arg(short, long)]
     path: Option<String>,
 
     /// Request context (optional)
     /// 
-    /// The management interface or context from which the request originates.
+    /// The management interface or context from which the request originates,
+    /// e.g. "netconf", "cli", "webui", or any custom name such as "restconf".
     /// Used for Tail-f ACM context-aware access control.
     #[arg(short = 'x', long)]
-    context: Option<ContextArg>,
+    context: Option<RequestContext>,
 
     /// Command (optional)
     /// 
@@ -118,6 +288,7 @@ struct Cli {
     /// Controls how results are displayed:
     /// - `text`: Human-readable output (default)
     /// - `json`: Structured JSON for programmatic processing
+    /// - `csv`: Comma-separated values with a header row
     /// - `exit-code`: No output, only exit codes (for shell scripting)
     #[arg(long, default_value = "text")]
     format: OutputFormat,
@@ -126,15 +297,126 @@ struct Cli {
     /// 
     /// Shows additional information like configuration summary,
     /// rule matching details, and group membership.
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     verbose: bool,
 
     /// JSON input mode - read request from stdin
     /// 
     /// When enabled, the tool reads JSON-formatted requests from standard input
     /// instead of using command-line arguments. Useful for batch processing.
-    #[arg(long)]
+    #[arg(long, conflicts_with = "input")]
     json_input: bool,
+
+    /// Batch input file (use "-" for stdin)
+    /// 
+    /// Reads a whole batch of requests from NDJSON, a JSON array, YAML or CSV
+    /// and reports the results in the chosen `--format`. The input format is
+    /// detected from the file extension or content.
+    #[arg(short, long, value_name = "FILE")]
+    input: Option<PathBuf>,
+
+    /// Force the format of the `--input` file instead of auto-detecting it
+    #[arg(long, requires = "input")]
+    input_format: Option<batch::InputFormat>,
+
+    /// NETCONF <rpc> message to check for `--user` (use "-" for stdin)
+    /// 
+    /// Checks the protocol operation and the data nodes it touches (filter
+    /// selections, edit-config nodes, action nodes), with the modules
+    /// resolved from the XML namespaces. Edit nodes are checked as by the
+    /// `edit` subcommand. The context defaults to netconf.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["input", "json_input"])]
+    netconf_rpc: Option<PathBuf>,
+
+    /// Append an audit record for every decision that should be logged
+    /// 
+    /// Decisions whose `should_log` flag is set (Tail-f ACM `log-if-*`
+    /// settings) are written as JSON lines with the request fields, the
+    /// decision, the matching rule and a timestamp. Applies to all modes.
+    #[arg(long, value_name = "FILE", global = true)]
+    audit_log: Option<PathBuf>,
+
+    /// Evaluate conditional rules at this time instead of now (RFC 3339)
+    /// 
+    /// E.g. `2024-06-01T02:30:00Z`. A JSON request's own `at` field takes
+    /// precedence. Applies to all modes.
+    #[arg(long, value_name = "TIME", value_parser = parse_rfc3339, global = true)]
+    at: Option<SystemTime>,
+
+    /// Request attribute for conditional rules, as NAME=VALUE (repeatable)
+    /// 
+    /// E.g. `source-ip=10.1.2.3`. The peer address `serve` sets as
+    /// `source-ip` and the `attributes` of a JSON request take precedence.
+    /// Applies to all modes.
+    #[arg(long, value_name = "NAME=VALUE", value_parser = parse_attribute, global = true)]
+    attribute: Vec<(String, String)>,
+
+    /// Optional subcommand
+    /// 
+    /// Without a subcommand the tool validates a single request (or a batch
+    /// with `--json-input`) and exits.
+    #[command(subcommand)]
+    subcommand: Option<Commands>,
+}
+
+/// Subcommands for modes beyond one-shot validation
+#[derive(Subcommand)]
+enum Commands {
+    /// Run a long-lived validation server
+    /// 
+    /// Loads the configuration once and answers newline-delimited JSON
+    /// requests (the same format as `--json-input`) with one JSON result per
+    /// line. Sending SIGHUP reloads the configuration file.
+    Serve(serve::ServeArgs),
+
+    /// Run a policy test suite
+    /// 
+    /// Evaluates each request in the suite and compares the decision, logging
+    /// flag and deciding rule with the expected values. Exits with 1 if any
+    /// test fails.
+    Test(suite::TestArgs),
+
+    /// Prune an XML or JSON instance document to what `--user` may read
+    /// 
+    /// Evaluates read access for every data node, as NACM does for `<get>`
+    /// and `<get-config>`, and prints the filtered document.
+    Filter(filter::FilterArgs),
+
+    /// Pre-flight an edit-config or YANG Patch payload for `--user`
+    /// 
+    /// Derives the create, update and delete operations the edit implies
+    /// for every data node and prints one decision per node. Exits with 1 if
+    /// any node check is denied.
+    Edit(edit::EditArgs),
+
+    /// Check a RESTCONF request for `--user`
+    /// 
+    /// Maps the HTTP method and URI to the access requests NACM checks for
+    /// it (read, create, update, delete or exec, with module and path) and
+    /// prints one decision per access request.
+    Restconf(restconf::RestconfArgs),
+
+    /// Replay requests against a proposed policy and show what changes
+    /// 
+    /// Evaluates a corpus of requests (such as an audit log) against the
+    /// current and the proposed configuration and prints the requests whose
+    /// decision changes, grouped by user and by the rule responsible. Exits
+    /// with 1 if any request changes.
+    Simulate(simulate::SimulateArgs),
+
+    /// Propose least-privilege permit rules from observed requests
+    /// 
+    /// Learns a rule list that permits the requests in a corpus (such as an
+    /// audit log) and nothing else, and prints it as XML. `--config` is
+    /// optional; when given, only requests it permits are learned.
+    Synthesize(synthesize::SynthesizeArgs),
+
+    /// Find conflicting, shadowed and redundant rules
+    /// 
+    /// Compares every pair of rules and prints each suspicious pair with a
+    /// request that both rules match and the decision for it. Exits with 1
+    /// if anything is found.
+    Analyze(analyze::AnalyzeArgs),
 }
 
 /// Command-line operation argument wrapper
@@ -176,35 +458,6 @@ impl From<OperationArg> for Operation {
     }
 }
 
-/// Command-line context argument wrapper
-/// 
-/// This enum wraps our library's `RequestContext` enum to work with clap's
-/// argument parsing. Similar to `OperationArg`, this allows CLI parsing
-/// without making the library depend on clap.
-#[derive(Clone, ValueEnum)]
-enum ContextArg {
-    /// NETCONF protocol access
-    Netconf,
-    /// Command-line interface access
-    Cli,
-    /// Web-based user interface access
-    Webui,
-}
-
-/// Convert CLI context argument to library context type
-/// 
-/// This `From` trait implementation allows automatic conversion between
-/// the CLI enum and the library enum.
-impl From<ContextArg> for nacm_validator::RequestContext {
-    fn from(ctx: ContextArg) -> Self {
-        match ctx {
-            ContextArg::Netconf => nacm_validator::RequestContext::NETCONF,
-            ContextArg::Cli => nacm_validator::RequestContext::CLI,
-            ContextArg::Webui => nacm_validator::RequestContext::WebUI,
-        }
-    }
-}
-
 /// Output format options for results
 /// 
 /// Controls how validation results are displayed to the user.
@@ -217,51 +470,26 @@ enum OutputFormat {
     /// Structured JSON output for programmatic processing
     /// Includes all request details and decision information  
     Json,
+    /// Comma-separated values with a header row
+    /// Convenient for spreadsheets and batch reports
+    Csv,
     /// Exit code only, no text output
     /// Perfect for shell scripting where you only care about success/failure
     ExitCode,
 }
 
-/// JSON request structure for batch processing
-/// 
-/// When using `--json-input` mode, requests are provided as JSON objects
-/// with this structure. All fields are deserialized from the JSON payload.
-/// 
-/// Example JSON:
-/// ```json
-/// {
-///   "user": "alice",
-///   "module": "ietf-interfaces", 
-///   "operation": "read",
-///   "path": "/interfaces/interface[name='eth0']",
-///   "context": "netconf",
-///   "command": "show status"
-/// }
-/// ```
-#[derive(Serialize, Deserialize)]
-struct JsonRequest {
-    /// Username making the request
-    user: String,
-    /// YANG module name (optional)
-    module: Option<String>,
-    /// RPC name (optional) 
-    rpc: Option<String>,
-    /// Operation type as string ("read", "create", etc.)
-    operation: String,
-    /// XPath or data path (optional)
-    path: Option<String>,
-    /// Request context as string ("netconf", "cli", "webui") (optional)
-    context: Option<String>,
-    /// Command being executed (optional)
-    command: Option<String>,
-}
-
 /// JSON response structure for results
 /// 
 /// Used when outputting results in JSON format. Includes both the
 /// access decision and all the request details for complete traceability.
 #[derive(Serialize)]
 struct JsonResult {
+    /// Input line number (1-based) in streaming modes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    /// Correlation id copied from the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
     /// Access decision: "permit" or "deny"
     decision: String,
     /// Original request details echoed back
@@ -270,7 +498,7 @@ struct JsonResult {
     rpc: Option<String>,
     operation: String,
     path: Option<String>,
-    /// Request context ("netconf", "cli", "webui")
+    /// Request context ("netconf", "cli", "webui", or a custom name)
     context: Option<String>,
     /// Command being executed
     command: Option<String>,
@@ -278,6 +506,50 @@ struct JsonResult {
     config_loaded: bool,
     /// Whether this decision should be logged (Tail-f ACM extension)
     should_log: bool,
+    /// Rule list containing the rule that decided the request (None = default policy)
+    rule_list: Option<String>,
+    /// Name of the rule that decided the request (None = default policy)
+    rule: Option<String>,
+}
+
+/// JSON error record for input that could not be evaluated
+/// 
+/// Emitted in place of a `JsonResult` so that streaming output keeps one
+/// record per input line and callers can correlate failures.
+#[derive(Serialize)]
+struct JsonError {
+    /// Input line number (1-based) in streaming modes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    /// Correlation id copied from the request, if it could be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
+    /// Human-readable description of the problem
+    error: String,
+}
+
+/// One output record: either a decision or an error
+/// 
+/// Serialized without a tag, so consumers distinguish the two by the
+/// presence of the `decision` or `error` field.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum JsonRecord {
+    /// The request was evaluated
+    Result(Box<JsonResult>),
+    /// The request could not be evaluated
+    Error(JsonError),
+}
+
+/// Trailer record summarising a batch run
+#[derive(Serialize, Default)]
+struct JsonSummary {
+    /// Number of permitted requests
+    permit: usize,
+    /// Number of denied requests
+    deny: usize,
+    /// Number of input lines that produced an error record
+    error: usize,
 }
 
 /// Main entry point for the NACM validator CLI tool
@@ -304,8 +576,65 @@ fn main() {
     // If parsing fails (invalid args), clap automatically shows help and exits
     let cli = Cli::parse();
 
-    // Load NACM configuration from the specified file
-    let config = match load_config(&cli.config) {
+    // Conditional rules are evaluated at --at, with the --attribute values
+    let mut evaluation = Evaluation::default();
+    if let Some(at) = cli.at {
+        evaluation.env = evaluation.env.at(at);
+    }
+    for (name, value) in &cli.attribute {
+        evaluation.env = evaluation.env.with_attribute(name, value);
+    }
+
+    // Open the audit log before anything is evaluated
+    if let Some(path) = &cli.audit_log {
+        match JsonLinesSink::open(path) {
+            Ok(sink) => evaluation.audit_log = Some(Arc::new(sink)),
+            Err(e) => {
+                eprintln!("Error opening audit log {:?}: {}", path, e);
+                process::exit(2);
+            }
+        }
+    }
+
+    let strategy = MergeStrategy {
+        defaults: cli.merge_defaults,
+        rule_lists: cli.merge_rule_lists,
+    };
+
+    // Test suites may name their own configuration, so they load it themselves
+    if let Some(Commands::Test(args)) = &cli.subcommand {
+        process::exit(suite::run(&cli.config, &strategy, args, &evaluation, cli.verbose));
+    }
+
+    // Synthesis can start from nothing but a corpus
+    if let Some(Commands::Synthesize(args)) = &cli.subcommand {
+        let config = if cli.config.is_empty() {
+            None
+        } else {
+            match load_config(&cli.config, &strategy) {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    eprintln!("Error loading config: {}", e);
+                    process::exit(2);
+                }
+            }
+        };
+        match synthesize::run(config.as_ref(), args, cli.verbose) {
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        }
+    }
+
+    if cli.config.is_empty() {
+        eprintln!("Error: --config is required");
+        process::exit(2);
+    }
+
+    // Load NACM configuration from the specified files, merged in order
+    let config = match load_config(&cli.config, &strategy) {
         Ok(config) => config,
         Err(e) => {
             eprintln!("Error loading config: {}", e);
@@ -322,9 +651,84 @@ fn main() {
     }
 
     // Route to appropriate handler based on input mode
-    if cli.json_input {
+    if let Some(Commands::Filter(args)) = &cli.subcommand {
+        let Some(user) = &cli.user else {
+            eprintln!("Error: --user is required for filter");
+            process::exit(2);
+        };
+        if let Err(e) = filter::run(&config, user, cli.context.as_ref(), args, &evaluation.env) {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    } else if let Some(Commands::Edit(args)) = &cli.subcommand {
+        let Some(user) = &cli.user else {
+            eprintln!("Error: --user is required for edit");
+            process::exit(2);
+        };
+        match edit::run(&config, user, cli.context.as_ref(), args, &evaluation.env, &cli.format) {
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        }
+    } else if let Some(Commands::Restconf(args)) = &cli.subcommand {
+        let Some(user) = &cli.user else {
+            eprintln!("Error: --user is required for restconf");
+            process::exit(2);
+        };
+        let context = cli.context.clone().unwrap_or_else(|| RequestContext::Other("restconf".to_string()));
+        match restconf::run(&config, user, &context, args, &evaluation, &cli.format) {
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        }
+    } else if let Some(Commands::Simulate(args)) = &cli.subcommand {
+        match simulate::run(&config, &strategy, args, &evaluation.env, &cli.format) {
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        }
+    } else if let Some(Commands::Analyze(args)) = &cli.subcommand {
+        process::exit(analyze::run(&config, args, &cli.format));
+    } else if let Some(Commands::Serve(args)) = &cli.subcommand {
+        // Server mode: runs until the process is terminated
+        if let Err(e) = serve::run(&cli.config, strategy, config, args, evaluation, cli.verbose) {
+            eprintln!("Server error: {}", e);
+            process::exit(2);
+        }
+    } else if let Some(message) = &cli.netconf_rpc {
+        // NETCONF mode: check everything one <rpc> message needs
+        let Some(user) = &cli.user else {
+            eprintln!("Error: --user is required for --netconf-rpc");
+            process::exit(2);
+        };
+        let context = cli.context.clone().unwrap_or(RequestContext::NETCONF);
+        match batch::read_text(message).and_then(|rpc| netconf_records(&config, &evaluation, user, &context, &rpc)) {
+            Ok(records) => process::exit(batch::output_records(&records, &cli.format)),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        }
+    } else if cli.json_input {
         // Batch processing mode: read JSON requests from stdin
-        handle_json_input(&config, &cli);
+        handle_json_input(&config, &evaluation);
+    } else if let Some(input) = &cli.input {
+        // File batch mode: read all requests, then report in the chosen format
+        let records = match batch::read_input(input, cli.input_format) {
+            Ok(records) => records,
+            Err(e) => {
+                eprintln!("Error reading input: {}", e);
+                process::exit(2);
+            }
+        };
+        let results = batch::evaluate(&config, &evaluation, records);
+        process::exit(batch::output_records(&results, &cli.format));
     } else {
         // Single request mode: use command-line arguments
         
@@ -346,38 +750,164 @@ fn main() {
         };
         
         // Process the single request and exit with appropriate code
-        handle_single_request(&config, &cli, user, operation);
+        handle_single_request(&config, &evaluation, &cli, user, operation);
     }
 }
 
-/// Load and parse NACM configuration from file
+/// Load, parse and merge NACM configuration files
 /// 
 /// This helper function encapsulates the file loading and XML parsing logic.
 /// It provides a clean error boundary and consistent error handling.
 /// 
 /// ## Parameters
 /// 
-/// * `config_path` - Path to the NACM XML configuration file
+/// * `config_paths` - NACM XML configuration files or directories, in merge order
+/// * `strategy` - How each file is merged into the ones before it
 /// 
 /// ## Returns
 /// 
-/// * `Ok(NacmConfig)` - Successfully loaded and parsed configuration
+/// * `Ok(NacmConfig)` - Successfully loaded and merged configuration
 /// * `Err(Box<dyn Error>)` - File not found, permission denied, invalid XML, etc.
 /// 
 /// ## Error Types
 /// 
 /// This function can return various error types:
-/// - I/O errors (file not found, permission denied)
+/// - I/O errors (file not found, permission denied, empty directory)
 /// - XML parsing errors (malformed XML, unknown elements)
 /// - NACM validation errors (invalid rule effects, unknown operations)
-fn load_config(config_path: &PathBuf) -> Result<NacmConfig, Box<dyn std::error::Error>> {
-    // Read the entire file into memory as a UTF-8 string
-    // This will fail if the file doesn't exist or isn't readable
-    let xml_content = std::fs::read_to_string(config_path)?;
-    
-    // Parse the XML content using our library's parsing function
-    // This can fail for malformed XML or invalid NACM content
-    NacmConfig::from_xml(&xml_content)
+/// - Merge conflicts (the same rule list name in two files when appending)
+/// 
+/// With more than one file, errors name the file they come from.
+fn load_config(config_paths: &[PathBuf], strategy: &MergeStrategy) -> Result<NacmConfig, Box<dyn std::error::Error>> {
+    let files = config_files(config_paths)?;
+    let single = files.len() == 1;
+
+    let mut merged: Option<NacmConfig> = None;
+    for file in &files {
+        let in_file = |e: Box<dyn std::error::Error>| -> Box<dyn std::error::Error> {
+            if single {
+                e
+            } else {
+                format!("{}: {}", file.display(), e).into()
+            }
+        };
+
+        // Read the entire file into memory as a UTF-8 string
+        // This will fail if the file doesn't exist or isn't readable
+        let xml_content = std::fs::read_to_string(file).map_err(|e| in_file(e.into()))?;
+
+        // Parse the XML content using our library's parsing function
+        // This can fail for malformed XML or invalid NACM content
+        let (config, settings) = NacmConfig::from_xml_partial(&xml_content).map_err(in_file)?;
+
+        // Later files only merge the global settings they actually set
+        match merged.as_mut() {
+            Some(base) => base.merge_partial(config, &settings, strategy).map_err(|e| in_file(e.into()))?,
+            None => merged = Some(config),
+        }
+    }
+    merged.ok_or_else(|| "no configuration files given".into())
+}
+
+/// Parse a `--attribute` value of the form NAME=VALUE
+fn parse_attribute(text: &str) -> Result<(String, String), String> {
+    match text.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, got '{}'", text)),
+    }
+}
+
+/// Apply the `at` and `attributes` fields of a JSON request to `base`
+/// 
+/// `at` is an RFC 3339 time and `attributes` an object of strings, e.g.
+/// `{"source-ip": "10.1.2.3"}`. Both override the values in `base`.
+fn request_environment(value: &serde_json::Value, base: &Environment) -> Result<Environment, String> {
+    let mut env = base.clone();
+    if let Some(at) = value.get("at") {
+        let at = at.as_str().ok_or("Invalid request: at must be an RFC 3339 string")?;
+        env = env.at(parse_rfc3339(at).map_err(|e| format!("Invalid request: {}", e))?);
+    }
+    if let Some(attributes) = value.get("attributes") {
+        let attributes = attributes
+            .as_object()
+            .ok_or("Invalid request: attributes must be an object")?;
+        for (name, value) in attributes {
+            let value = value
+                .as_str()
+                .ok_or_else(|| format!("Invalid request: attribute {} must be a string", name))?;
+            env = env.with_attribute(name, value);
+        }
+    }
+    Ok(env)
+}
+
+/// Expand configuration paths: directories become their `.xml` files
+/// 
+/// Files inside a directory are taken in file name order, so numbered
+/// names such as `00-base.xml` and `10-product.xml` control the merge order.
+/// Subdirectories are not searched.
+fn config_files(config_paths: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    for path in config_paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            let entry_path = entry?.path();
+            let is_xml = entry_path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"));
+            if is_xml && entry_path.is_file() {
+                entries.push(entry_path);
+            }
+        }
+        if entries.is_empty() {
+            return Err(format!("no .xml files in directory {}", path.display()).into());
+        }
+        entries.sort();
+        files.extend(entries);
+    }
+    Ok(files)
+}
+
+/// Check a NETCONF `<rpc>` message for `user`
+/// 
+/// The protocol operation and any reads are checked one by one. The data
+/// nodes of an edit (`<edit-config>`, `<edit-data>`, inline `<copy-config>`)
+/// go through `authorize_edit`, so that a node without a matching rule falls
+/// back to its ancestors' rules, as in the `edit` subcommand.
+/// 
+/// ## Returns
+/// 
+/// * `Ok(Vec<JsonRecord>)` - One record per check, numbered from 1
+/// * `Err(String)` - The message could not be parsed
+fn netconf_records(
+    config: &NacmConfig,
+    evaluation: &Evaluation,
+    user: &str,
+    context: &RequestContext,
+    rpc: &str,
+) -> Result<Vec<JsonRecord>, String> {
+    let accesses = netconf::map_rpc(rpc)?;
+    let Some(rpc_edit) = netconf::rpc_edit(rpc)? else {
+        return Ok(batch::evaluate_mapped(config, evaluation, user, Some(context), &accesses));
+    };
+
+    // Only the protocol operation precedes the node checks of an edit
+    let mut records = batch::evaluate_mapped(config, evaluation, user, Some(context), &accesses[..1]);
+    let request = EditRequest {
+        user,
+        context: Some(context),
+        payload: &rpc_edit.payload,
+        datastore: None,
+        default_operation: rpc_edit.default_operation,
+    };
+    let authorization = config.authorize_edit_with(&request, &ModuleResolver::new(), &evaluation.env)?;
+    records.extend(edit::node_records(&authorization, user, Some(context), records.len() + 1));
+    Ok(records)
 }
 
 /// Handle single access request validation
@@ -389,6 +919,7 @@ fn load_config(config_path: &PathBuf) -> Result<NacmConfig, Box<dyn std::error::
 /// ## Parameters
 /// 
 /// * `config` - Loaded NACM configuration
+/// * `evaluation` - Environment and audit log to decide in
 /// * `cli` - Parsed command-line arguments
 /// * `user` - Username making the request (validated to be present)
 /// * `operation` - Operation type (validated to be present)
@@ -398,14 +929,17 @@ fn load_config(config_path: &PathBuf) -> Result<NacmConfig, Box<dyn std::error::
 /// This function always calls `process::exit()`:
 /// - Code 0: Access permitted
 /// - Code 1: Access denied
-fn handle_single_request(config: &NacmConfig, cli: &Cli, user: &str, operation: &OperationArg) {
+fn handle_single_request(
+    config: &NacmConfig,
+    evaluation: &Evaluation,
+    cli: &Cli,
+    user: &str,
+    operation: &OperationArg,
+) {
     // Convert CLI operation argument to library operation type
     // This conversion is infallible (never panics) due to the From impl
     let operation = operation.clone().into();
     
-    // Convert CLI context argument to library context type (if provided)
-    let context = cli.context.as_ref().map(|ctx| ctx.clone().into());
-    
     // Build the access request from command-line arguments
     // Uses borrowed string slices for efficiency (no copying)
     let request = AccessRequest {
@@ -414,15 +948,22 @@ fn handle_single_request(config: &NacmConfig, cli: &Cli, user: &str, operation:
         rpc_name: cli.rpc.as_deref(),
         operation,
         path: cli.path.as_deref(),
-        context: context.as_ref(), // Convert Option<RequestContext> to Option<&RequestContext>
+        context: cli.context.as_ref(), // Convert Option<RequestContext> to Option<&RequestContext>
         command: cli.command.as_deref(), // Convert Option<String> to Option<&str>
     };
 
     // Perform the actual NACM validation using our library
-    let result = config.validate(&request);
+    let decision = match evaluation.decide(config, &request) {
+        Ok(decision) => decision,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+    let result = decision.result;
     
     // Output results in the requested format
-    output_result(&result, &request, config, &cli.format, cli.verbose);
+    output_result(&decision, &request, config, &cli.format, cli.verbose);
     
     // Set exit code based on access decision
     // This is crucial for shell script integration
@@ -440,105 +981,72 @@ fn handle_single_request(config: &NacmConfig, cli: &Cli, user: &str, operation:
 /// 
 /// ## Input Format
 /// 
-/// Each line of stdin should be a complete JSON object:
+/// Each line of stdin should be a complete JSON object, optionally with an
+/// `id` field that is echoed back:
 /// ```json
-/// {"user": "admin", "operation": "read", "module": "example"}
+/// {"user": "admin", "operation": "read", "module": "example", "id": 1}
 /// {"user": "operator", "operation": "execute", "rpc": "restart"}
 /// ```
 /// 
 /// ## Output Format
 /// 
-/// For each valid input line, outputs a JSON result:
+/// Every non-blank input line produces exactly one output record carrying
+/// its line number, followed by a summary trailer:
 /// ```json
-/// {"decision": "permit", "user": "admin", "operation": "read", ...}
-/// {"decision": "deny", "user": "operator", "operation": "execute", ...}
+/// {"line": 1, "id": 1, "decision": "permit", "user": "admin", "operation": "read", ...}
+/// {"line": 2, "error": "Invalid operation 'execute': Unknown operation: execute"}
+/// {"summary": {"permit": 1, "deny": 0, "error": 1}}
 /// ```
 /// 
 /// ## Error Handling
 /// 
-/// - Invalid JSON lines are logged to stderr but don't stop processing
-/// - Invalid operations are logged and skipped
+/// - Invalid JSON, operations or contexts produce an error record and processing continues
+/// - Blank lines are skipped but still counted for line numbering
 /// - I/O errors terminate the processing loop
 /// 
 /// ## Parameters
 /// 
 /// * `config` - Loaded NACM configuration for validation
-/// * `cli` - Command-line arguments (mainly for format settings)
-fn handle_json_input(config: &NacmConfig, _cli: &Cli) {
-    use std::io::{self, BufRead};
-    
-    // Create a buffered reader from stdin for line-by-line processing
+/// * `evaluation` - Environment and audit log to decide in
+fn handle_json_input(config: &NacmConfig, evaluation: &Evaluation) {
+    use std::io;
+
     let stdin = io::stdin();
-    for line in stdin.lock().lines() {
+    let stdout = io::stdout();
+    if let Err(e) = process_json_stream(config, evaluation, stdin.lock(), &mut stdout.lock()) {
+        eprintln!("Error writing output: {}", e);
+    }
+}
+
+/// Answer newline-delimited JSON requests from `input` on `output`
+///
+/// Writes one record per non-blank input line and the summary trailer, as
+/// described for `handle_json_input`. A read error ends the input early;
+/// only write errors are returned.
+fn process_json_stream<R: std::io::BufRead, W: std::io::Write>(
+    config: &NacmConfig,
+    evaluation: &Evaluation,
+    input: R,
+    output: &mut W,
+) -> std::io::Result<()> {
+    let mut summary = JsonSummary::default();
+    
+    for (index, line) in input.lines().enumerate() {
         match line {
             Ok(json_str) => {
-                // Try to parse each line as a JSON request
-                match serde_json::from_str::<JsonRequest>(&json_str) {
-                    Ok(json_req) => {
-                        // Parse the operation string into our Operation enum
-                        let operation = match json_req.operation.parse::<Operation>() {
-                            Ok(op) => op,
-                            Err(e) => {
-                                eprintln!("Invalid operation '{}': {}", json_req.operation, e);
-                                continue; // Skip this request and continue with next
-                            }
-                        };
-                        
-                        // Parse the context string into our RequestContext enum (if provided)
-                        let context = match &json_req.context {
-                            Some(ctx_str) => {
-                                match ctx_str.to_lowercase().as_str() {
-                                    "netconf" => Some(RequestContext::NETCONF),
-                                    "cli" => Some(RequestContext::CLI),
-                                    "webui" => Some(RequestContext::WebUI),
-                                    _ => {
-                                        eprintln!("Invalid context '{}': must be 'netconf', 'cli', or 'webui'", ctx_str);
-                                        continue; // Skip this request and continue with next
-                                    }
-                                }
-                            }
-                            None => None,
-                        };
-                        
-                        // Build the access request from JSON data
-                        let request = AccessRequest {
-                            user: &json_req.user,
-                            module_name: json_req.module.as_deref(),
-                            rpc_name: json_req.rpc.as_deref(),
-                            operation,
-                            path: json_req.path.as_deref(),
-                            context: context.as_ref(), // Convert Option<RequestContext> to Option<&RequestContext>
-                            command: json_req.command.as_deref(), // Convert Option<String> to Option<&str>
-                        };
-
-                        // Validate the request using NACM
-                        let result = config.validate(&request);
-                        
-                        // Build JSON response with complete traceability
-                        let json_result = JsonResult {
-                            decision: match result.effect {
-                                RuleEffect::Permit => "permit".to_string(),
-                                RuleEffect::Deny => "deny".to_string(),
-                            },
-                            user: json_req.user,
-                            module: json_req.module,
-                            rpc: json_req.rpc,
-                            operation: json_req.operation,
-                            path: json_req.path,
-                            context: json_req.context,
-                            command: json_req.command,
-                            config_loaded: true,
-                            should_log: result.should_log,
-                        };
-                        
-                        // Output result as compact JSON (one per line)
-                        println!("{}", serde_json::to_string(&json_result).unwrap());
-                    }
-                    Err(e) => {
-                        // Log JSON parsing errors but continue processing
-                        eprintln!("Invalid JSON: {}", e);
-                    }
+                if json_str.trim().is_empty() {
+                    continue;
+                }
+                
+                let record = process_json_line(config, evaluation, &json_str, Some(index + 1));
+                match &record {
+                    JsonRecord::Result(result) if result.decision == "permit" => summary.permit += 1,
+                    JsonRecord::Result(_) => summary.deny += 1,
+                    JsonRecord::Error(_) => summary.error += 1,
                 }
+                
+                // Output record as compact JSON (one per line)
+                writeln!(output, "{}", serde_json::to_string(&record).unwrap())?;
             }
             Err(e) => {
                 // I/O errors are more serious - terminate processing
@@ -547,6 +1055,114 @@ fn handle_json_input(config: &NacmConfig, _cli: &Cli) {
             }
         }
     }
+    
+    // Trailer record so pipelines can check totals without counting lines
+    writeln!(output, "{{\"summary\":{}}}", serde_json::to_string(&summary).unwrap())
+}
+
+/// Process one line of newline-delimited JSON input
+/// 
+/// Shared by the stdin batch mode and the `serve` subcommand. Never fails:
+/// problems are reported as a `JsonRecord::Error` carrying the line number
+/// and, when the line is valid JSON, the request's `id`.
+fn process_json_line(config: &NacmConfig, evaluation: &Evaluation, json_str: &str, line: Option<usize>) -> JsonRecord {
+    match serde_json::from_str::<serde_json::Value>(json_str) {
+        Ok(value) => process_json_value(config, evaluation, value, line),
+        Err(e) => JsonRecord::Error(JsonError {
+            line,
+            id: None,
+            error: format!("Invalid JSON: {}", e),
+        }),
+    }
+}
+
+/// Process one already-parsed JSON request value
+/// 
+/// Requests use the serde form of `OwnedAccessRequest`, plus an optional
+/// caller-supplied `id` of any JSON type that is echoed back unchanged:
+/// 
+/// ```json
+/// {
+///   "user": "alice",
+///   "module": "ietf-interfaces", 
+///   "operation": "read",
+///   "path": "/interfaces/interface[name='eth0']",
+///   "context": "netconf",
+///   "command": "show status",
+///   "at": "2024-06-01T02:30:00Z",
+///   "attributes": {"source-ip": "10.1.2.3"},
+///   "id": "req-42"
+/// }
+/// ```
+/// 
+/// Conditional rules are checked in the `evaluation` environment, with the
+/// time and attributes of the optional `at` and `attributes` fields taking
+/// precedence.
+/// 
+/// The `id` is extracted before the request is deserialized so that it can
+/// be echoed even when the rest of the request is malformed.
+fn process_json_value(
+    config: &NacmConfig,
+    evaluation: &Evaluation,
+    value: serde_json::Value,
+    line: Option<usize>,
+) -> JsonRecord {
+    let id = value.get("id").cloned();
+    
+    let outcome = evaluation.for_request(&value).and_then(|evaluation| {
+        serde_json::from_value::<OwnedAccessRequest>(value)
+            .map_err(|e| format!("Invalid request: {}", e))
+            .and_then(|request| evaluate_json_request(config, &evaluation, id.clone(), request))
+    });
+    
+    match outcome {
+        Ok(mut json_result) => {
+            json_result.line = line;
+            JsonRecord::Result(Box::new(json_result))
+        }
+        Err(error) => JsonRecord::Error(JsonError { line, id, error }),
+    }
+}
+
+/// Validate a single JSON request against the configuration
+/// 
+/// Used for every JSON input path (batch, server, HTTP). Runs the
+/// validation and builds the JSON result echoing the request back.
+/// 
+/// ## Returns
+/// 
+/// * `Ok(JsonResult)` - The decision for this request
+/// * `Err(String)` - The decision could not be audited
+fn evaluate_json_request(
+    config: &NacmConfig,
+    evaluation: &Evaluation,
+    id: Option<serde_json::Value>,
+    request: OwnedAccessRequest,
+) -> Result<JsonResult, String> {
+    // Validate the request using NACM, keeping track of the deciding rule
+    let decision = evaluation.decide(config, &request.as_request())?;
+    let result = decision.result;
+    
+    // Build JSON response with complete traceability
+    Ok(JsonResult {
+        line: None,
+        id,
+        decision: match result.effect {
+            RuleEffect::Permit => "permit".to_string(),
+            RuleEffect::Deny => "deny".to_string(),
+        },
+        user: request.user,
+        module: request.module_name,
+        rpc: request.rpc_name,
+        operation: request.operation.to_string(),
+        path: request.path,
+        context: request.context.map(|ctx| ctx.to_string()),
+        command: request.command,
+        config_loaded: true,
+        should_log: result.should_log,
+        rule_list: decision.matched_rule.map(|m| m.rule_list.to_string()),
+        rule: decision.matched_rule.map(|m| m.rule.to_string()),
+    })
 }
 
 /// Output validation results in the requested format
@@ -576,7 +1192,9 @@ fn handle_json_input(config: &NacmConfig, _cli: &Cli) {
 ///   "module": "example-module",
 ///   "context": "cli",
 ///   "command": "show status",
-///   "should_log": true
+///   "should_log": true,
+///   "rule_list": "operators",
+///   "rule": "cli-show-status"
 /// }
 /// ```
 /// 
@@ -589,22 +1207,23 @@ fn handle_json_input(config: &NacmConfig, _cli: &Cli) {
 /// 
 /// ## Parameters
 /// 
-/// * `result` - The validation result with access decision and logging flag
+/// * `decision` - The validation result with access decision, logging flag and matching rule
 /// * `request` - Original access request details
 /// * `config` - NACM configuration (for verbose output)
 /// * `format` - Output format selection
 /// * `verbose` - Whether to include additional details
 fn output_result(
-    result: &nacm_validator::ValidationResult,
+    decision: &Decision,
     request: &AccessRequest,
     _config: &NacmConfig,
     format: &OutputFormat,
     verbose: bool,
 ) {
+    let result = &decision.result;
     match format {
         OutputFormat::Text => {
             // Human-readable text output
-            let decision = match result.effect {
+            let decision_text = match result.effect {
                 RuleEffect::Permit => "PERMIT",
                 RuleEffect::Deny => "DENY",
             };
@@ -625,41 +1244,299 @@ fn output_result(
                     println!("Path: {}", path);
                 }
                 if let Some(context) = request.context {
-                    println!("Context: {:?}", context);
+                    println!("Context: {}", context);
                 }
                 if let Some(command) = request.command {
                     println!("Command: {}", command);
                 }
-                println!("Decision: {}{}", decision, log_indicator);
+                match &decision.matched_rule {
+                    Some(rule) => println!("Matched rule: {}", rule),
+                    None => println!("Matched rule: none (default policy)"),
+                }
+                println!("Decision: {}{}", decision_text, log_indicator);
             } else {
                 // Simple mode: show decision with log indicator
-                println!("{}{}", decision, log_indicator);
+                println!("{}{}", decision_text, log_indicator);
             }
         }
         OutputFormat::Json => {
             // Structured JSON output for programmatic consumption
-            let json_result = JsonResult {
-                decision: match result.effect {
-                    RuleEffect::Permit => "permit".to_string(),
-                    RuleEffect::Deny => "deny".to_string(),
-                },
-                user: request.user.to_string(),
-                module: request.module_name.map(|s| s.to_string()),
-                rpc: request.rpc_name.map(|s| s.to_string()),
-                operation: format!("{:?}", request.operation).to_lowercase(),
-                path: request.path.map(|s| s.to_string()),
-                context: request.context.map(|ctx| format!("{:?}", ctx).to_lowercase()),
-                command: request.command.map(|s| s.to_string()),
-                config_loaded: true,
-                should_log: result.should_log,
-            };
+            let json_result = decision_to_json_result(decision, request);
             
             // Pretty-print JSON for readability
             println!("{}", serde_json::to_string_pretty(&json_result).unwrap());
         }
+        OutputFormat::Csv => {
+            // Header plus a single row, same columns as batch CSV output
+            let record = JsonRecord::Result(Box::new(decision_to_json_result(decision, request)));
+            if let Err(e) = batch::write_csv(&[record], std::io::stdout()) {
+                eprintln!("Error writing CSV: {}", e);
+            }
+        }
         OutputFormat::ExitCode => {
             // Silent mode: only use exit codes, no text output
             // This is useful for shell scripts that only care about success/failure
         }
     }
 }
+
+/// Build the JSON result structure for a request decided from CLI arguments
+fn decision_to_json_result(decision: &Decision, request: &AccessRequest) -> JsonResult {
+    JsonResult {
+        line: None,
+        id: None,
+        decision: match decision.result.effect {
+            RuleEffect::Permit => "permit".to_string(),
+            RuleEffect::Deny => "deny".to_string(),
+        },
+        user: request.user.to_string(),
+        module: request.module_name.map(|s| s.to_string()),
+        rpc: request.rpc_name.map(|s| s.to_string()),
+        operation: request.operation.to_string(),
+        path: request.path.map(|s| s.to_string()),
+        context: request.context.map(|ctx| ctx.to_string()),
+        command: request.command.map(|s| s.to_string()),
+        config_loaded: true,
+        should_log: decision.result.should_log,
+        rule_list: decision.matched_rule.map(|m| m.rule_list.to_string()),
+        rule: decision.matched_rule.map(|m| m.rule.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nacm_validator::audit::MemorySink;
+    use nacm_validator::condition::{RuleCondition, Weekday};
+    use serde_json::{json, Value};
+
+    fn policy() -> NacmConfig {
+        NacmConfig::builder()
+            .exec_default(RuleEffect::Deny)
+            .group("admin", ["alice"])
+            .rule_list("admin-acl", ["admin"], |rl| {
+                rl.permit().name("edit").rpc("edit-config").ops([Operation::Exec])
+            })
+            .build()
+            .unwrap()
+    }
+
+    /// A record as the JSON object written to the output
+    fn record(config: &NacmConfig, line: &str) -> Value {
+        serde_json::to_value(process_json_line(config, &Evaluation::default(), line, Some(3))).unwrap()
+    }
+
+    #[test]
+    fn netconf_edit_nodes_fall_back_to_ancestor_rules() {
+        // The ipv4 container is in an augmenting module no rule names; on its
+        // own it would be permitted by the write default
+        let config = NacmConfig::builder()
+            .write_default(RuleEffect::Permit)
+            .group("admin", ["alice"])
+            .rule_list("admin-acl", ["admin"], |rl| {
+                rl.permit().name("edit").rpc("edit-config").ops([Operation::Exec])
+            })
+            .rule_list("freeze", ["*"], |rl| {
+                rl.deny().name("interfaces").module("ietf-interfaces").path("/interfaces").ops([Operation::Update])
+            })
+            .build()
+            .unwrap();
+        let rpc = r#"<rpc message-id="1" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+            <edit-config>
+                <target><running/></target>
+                <config>
+                    <interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces">
+                        <interface><name>eth0</name><ipv4 xmlns="urn:ietf:params:xml:ns:yang:ietf-ip"/></interface>
+                    </interfaces>
+                </config>
+            </edit-config>
+        </rpc>"#;
+
+        let records = netconf_records(&config, &Evaluation::default(), "alice", &RequestContext::NETCONF, rpc).unwrap();
+        let records: Vec<Value> = records.iter().map(|r| serde_json::to_value(r).unwrap()).collect();
+        assert_eq!(records[0]["line"], 1);
+        assert_eq!(records[0]["rpc"], "edit-config");
+        assert_eq!(records[0]["decision"], "permit");
+
+        let ipv4 = records.last().unwrap();
+        assert_eq!(ipv4["line"], records.len());
+        assert_eq!(ipv4["path"], "/interfaces/interface/ipv4");
+        assert_eq!(ipv4["module"], "ietf-ip");
+        assert_eq!(ipv4["decision"], "deny");
+        assert_eq!(ipv4["rule"], "interfaces");
+    }
+
+    #[test]
+    fn netconf_reads_are_checked_one_by_one() {
+        let rpc = r#"<get xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+            <filter><system xmlns="urn:ietf:params:xml:ns:yang:ietf-system"/></filter>
+        </get>"#;
+        let evaluation = Evaluation::default();
+        let records = netconf_records(&policy(), &evaluation, "alice", &RequestContext::NETCONF, rpc).unwrap();
+        let records: Vec<Value> = records.iter().map(|r| serde_json::to_value(r).unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1]["path"], "/system");
+        assert_eq!(records[1]["operation"], "read");
+    }
+
+    #[test]
+    fn json_request_is_evaluated_with_line_and_id() {
+        let config = policy();
+        let result = record(&config, r#"{"id":{"n":1},"user":"alice","operation":"exec","rpc":"edit-config"}"#);
+        assert_eq!(result["line"], 3);
+        assert_eq!(result["id"], json!({"n": 1}));
+        assert_eq!(result["decision"], "permit");
+        assert_eq!(result["rule_list"], "admin-acl");
+        assert_eq!(result["rule"], "edit");
+
+        // Without an id or a line number neither field is written
+        let value = json!({"user": "bob", "operation": "exec", "rpc": "edit-config"});
+        let result = serde_json::to_value(process_json_value(&config, &Evaluation::default(), value, None)).unwrap();
+        assert!(result.get("line").is_none() && result.get("id").is_none());
+        assert_eq!(result["decision"], "deny");
+    }
+
+    #[test]
+    fn json_request_errors_keep_line_and_id() {
+        let config = policy();
+
+        let invalid = record(&config, "{\"id\":1,");
+        assert_eq!(invalid["line"], 3);
+        assert!(invalid.get("id").is_none());
+        assert!(invalid["error"].as_str().unwrap().starts_with("Invalid JSON"));
+        assert!(invalid.get("decision").is_none());
+
+        let bad_operation = record(&config, r#"{"id":"x","user":"alice","operation":"execute"}"#);
+        assert_eq!(bad_operation["id"], "x");
+        assert!(bad_operation["error"].as_str().unwrap().contains("execute"));
+
+        let missing_user = record(&config, r#"{"id":2,"operation":"read"}"#);
+        assert_eq!(missing_user["line"], 3);
+        assert_eq!(missing_user["id"], 2);
+        assert!(missing_user["error"].as_str().unwrap().contains("missing field `user`"));
+
+        let not_an_object = record(&config, "[1, 2]");
+        assert!(not_an_object.get("id").is_none());
+        assert!(not_an_object["error"].as_str().unwrap().starts_with("Invalid request"));
+    }
+
+    #[test]
+    fn json_request_time_and_attributes_extend_the_environment() {
+        let base = Environment::new().with_attribute("tenant", "a").with_attribute("source-ip", "10.0.0.1");
+        let request = json!({"at": "2024-06-01T03:00:00Z", "attributes": {"source-ip": "192.0.2.1"}});
+        let env = request_environment(&request, &base).unwrap();
+        assert_eq!(env.now(), parse_rfc3339("2024-06-01T03:00:00Z").unwrap());
+        assert_eq!(env.attribute("source-ip"), Some("192.0.2.1"));
+        assert_eq!(env.attribute("tenant"), Some("a"));
+
+        let unchanged = request_environment(&json!({"user": "alice"}), &base).unwrap();
+        assert_eq!(unchanged.attribute("source-ip"), Some("10.0.0.1"));
+
+        let bad_time = request_environment(&json!({"at": "yesterday"}), &base).unwrap_err();
+        assert!(bad_time.starts_with("Invalid request"));
+        assert!(request_environment(&json!({"at": 1717210800}), &base).is_err());
+        assert!(request_environment(&json!({"attributes": ["source-ip"]}), &base).is_err());
+        assert!(request_environment(&json!({"attributes": {"port": 830}}), &base).is_err());
+
+        let config = policy();
+        let invalid = record(&config, r#"{"id":4,"user":"alice","operation":"exec","at":"yesterday"}"#);
+        assert_eq!(invalid["id"], 4);
+        assert!(invalid["error"].as_str().unwrap().contains("RFC 3339"));
+    }
+
+    #[test]
+    fn evaluation_supplies_the_clock_and_audit_log() {
+        // alice may only edit on Saturdays, and every permit is logged
+        let saturdays = RuleCondition { weekdays: vec![Weekday::Saturday], ..Default::default() };
+        let config = NacmConfig::builder()
+            .exec_default(RuleEffect::Deny)
+            .group("admin", ["alice"])
+            .rule_list("admin-acl", ["admin"], |rl| {
+                rl.permit().name("edit").rpc("edit-config").ops([Operation::Exec]).condition(saturdays).log_if_permit()
+            })
+            .build()
+            .unwrap();
+        let sink = Arc::new(MemorySink::new(10));
+        let saturday = Evaluation {
+            env: Environment::new().at(parse_rfc3339("2024-06-01T03:00:00Z").unwrap()),
+            audit_log: Some(sink.clone()),
+        };
+        let line = r#"{"user":"alice","operation":"exec","rpc":"edit-config"}"#;
+
+        let record = serde_json::to_value(process_json_line(&config, &saturday, line, None)).unwrap();
+        assert_eq!(record["decision"], "permit");
+        assert_eq!(sink.len(), 1);
+
+        let monday = saturday.for_request(&json!({"at": "2024-06-03T03:00:00Z"})).unwrap();
+        let record = serde_json::to_value(process_json_line(&config, &monday, line, None)).unwrap();
+        assert_eq!(record["decision"], "deny");
+        assert_eq!(sink.len(), 1);
+
+        let peer = saturday.for_peer(Some("192.0.2.1".parse().unwrap()));
+        assert_eq!(peer.env.attribute("source-ip"), Some("192.0.2.1"));
+        assert!(saturday.env.attribute("source-ip").is_none());
+    }
+
+    #[test]
+    fn attribute_option_splits_at_the_first_equals() {
+        assert_eq!(parse_attribute("a=b=c").unwrap(), ("a".to_string(), "b=c".to_string()));
+        assert_eq!(parse_attribute("empty=").unwrap(), ("empty".to_string(), String::new()));
+        assert!(parse_attribute("=value").is_err());
+        assert!(parse_attribute("name").is_err());
+    }
+
+    #[test]
+    fn json_stream_writes_one_record_per_line_and_a_summary() {
+        let input = concat!(
+            "{\"user\":\"alice\",\"operation\":\"exec\",\"rpc\":\"edit-config\"}\n",
+            "\n",
+            "{\"user\":\"bob\",\"operation\":\"exec\",\"rpc\":\"edit-config\"}\n",
+            "   \n",
+            "not json\n",
+            "{\"user\":\"bob\",\"operation\":\"exec\",\"rpc\":\"reboot\"}",
+        );
+        let mut output = Vec::new();
+        process_json_stream(&policy(), &Evaluation::default(), input.as_bytes(), &mut output).unwrap();
+
+        let records: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 5);
+        let lines: Vec<&Value> = records[..4].iter().map(|r| &r["line"]).collect();
+        assert_eq!(lines, [1, 3, 5, 6]);
+        assert_eq!(records[0]["decision"], "permit");
+        assert_eq!(records[1]["decision"], "deny");
+        assert!(records[2]["error"].is_string());
+        assert_eq!(records[4], json!({"summary": {"permit": 1, "deny": 2, "error": 1}}));
+
+        // Empty input still gets a trailer
+        let mut output = Vec::new();
+        process_json_stream(&policy(), &Evaluation::default(), "".as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "{\"summary\":{\"permit\":0,\"deny\":0,\"error\":0}}\n");
+    }
+
+    #[test]
+    fn later_files_only_merge_the_settings_they_set() {
+        let dir = std::env::temp_dir().join(format!("nacm-load-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let nacm = |body: &str| {
+            format!(
+                r#"<config xmlns="http://tail-f.com/ns/config/1.0">
+                     <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">{}</nacm>
+                   </config>"#,
+                body
+            )
+        };
+        std::fs::write(dir.join("00-base.xml"), nacm("<write-default>permit</write-default>")).unwrap();
+        let rules = nacm("<rule-list><name>product</name><group>*</group></rule-list>");
+        std::fs::write(dir.join("10-rules.xml"), rules).unwrap();
+
+        let config = load_config(std::slice::from_ref(&dir), &MergeStrategy::default());
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = config.unwrap();
+        assert_eq!(config.write_default, RuleEffect::Permit);
+        assert_eq!(config.rule_lists[0].name, "product");
+    }
+}
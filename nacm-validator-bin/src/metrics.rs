@@ -0,0 +1,157 @@
+//! # Statistics Endpoints
+//!
+//! Exposes the RFC 8341 denial counters collected by the `serve` subcommand:
+//!
+//! - `GET /metrics` - Prometheus text exposition format
+//! - `GET /v1/nacm` - RFC 8341 operational state as RFC 7951 JSON, or as XML
+//!   when the `Accept` header asks for XML
+//!
+//! The endpoints are served by a small built-in HTTP listener
+//! (`serve --metrics <addr>`) and, with the `http` feature, also by the
+//! decision API server.
+
+use crate::serve::SharedConfig;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Content type of the Prometheus text format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// How long a metrics client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A rendered statistics response
+pub struct StatsResponse {
+    /// HTTP status code
+    pub status: u16,
+    /// Value for the Content-Type header
+    pub content_type: &'static str,
+    /// Response body
+    pub body: String,
+}
+
+/// Render a statistics endpoint, or `None` if `path` is not one
+///
+/// ## Parameters
+///
+/// * `path` - Request path without query string
+/// * `accept` - Value of the `Accept` header, if any
+/// * `shared` - Configuration holding the counters
+pub fn render(path: &str, accept: Option<&str>, shared: &SharedConfig) -> Option<StatsResponse> {
+    if path != "/metrics" && path != "/v1/nacm" {
+        return None;
+    }
+
+    let stats = shared.read().unwrap_or_else(|e| e.into_inner()).stats.clone();
+    let Some(stats) = stats else {
+        return Some(StatsResponse {
+            status: 404,
+            content_type: "application/json",
+            body: r#"{"error":"Statistics are not being collected"}"#.to_string(),
+        });
+    };
+
+    let wants_xml = accept.is_some_and(|a| a.contains("xml"));
+    Some(match (path, wants_xml) {
+        ("/metrics", _) => StatsResponse {
+            status: 200,
+            content_type: PROMETHEUS_CONTENT_TYPE,
+            body: stats.to_prometheus(),
+        },
+        (_, true) => StatsResponse {
+            status: 200,
+            content_type: "application/yang-data+xml",
+            body: stats.to_xml(),
+        },
+        (_, false) => StatsResponse {
+            status: 200,
+            content_type: "application/yang-data+json",
+            body: stats.to_json(),
+        },
+    })
+}
+
+/// Serve the statistics endpoints on a localhost address
+///
+/// Runs until the process is terminated. Requests are handled one at a time,
+/// which is plenty for periodic scrapes.
+pub fn serve_metrics(addr: SocketAddr, shared: SharedConfig) -> io::Result<()> {
+    crate::serve::check_loopback(addr)?;
+
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("Serving statistics on http://{}/metrics", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| handle_metrics_request(stream, &shared));
+        if let Err(e) = result {
+            eprintln!("Metrics connection error: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Answer a single HTTP request and close the connection
+fn handle_metrics_request(stream: TcpStream, shared: &SharedConfig) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    // Request line, e.g. "GET /metrics HTTP/1.1"
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("");
+    let path = target.split('?').next().unwrap_or("");
+
+    // Headers up to the blank line; only Accept matters
+    let mut accept = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("accept") {
+                accept = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let response = match (method, render(path, accept.as_deref(), shared)) {
+        ("GET", Some(response)) => response,
+        (_, Some(_)) => error(405, format!("Method {} not allowed", method)),
+        (_, None) => error(404, format!("No such endpoint: {}", path)),
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason_phrase(response.status),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+/// JSON error response, in the same shape as the decision API's errors
+fn error(status: u16, message: String) -> StatsResponse {
+    StatsResponse {
+        status,
+        content_type: "application/json",
+        body: serde_json::json!({ "error": message }).to_string(),
+    }
+}
+
+/// Reason phrase for the status codes used here
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Error",
+    }
+}
@@ -0,0 +1,63 @@
+//! # RESTCONF Requests
+//!
+//! Implements the `restconf` subcommand: maps a RESTCONF method and URI
+//! (plus an optional body) to the NACM access requests a server checks for
+//! it, and evaluates them for the `--user`.
+//!
+//! ```bash
+//! nacm-validator --config config.xml --user alice restconf GET /restconf/data/ietf-interfaces:interfaces
+//! nacm-validator --config config.xml --user alice restconf POST \
+//!     /restconf/data/ietf-interfaces:interfaces --body interface.json
+//! ```
+//!
+//! Results are reported like a batch, so the output formats and exit codes
+//! are those of `--input`. The context defaults to restconf.
+
+use crate::batch;
+use crate::{Evaluation, OutputFormat};
+use clap::Args;
+use nacm_validator::restconf::map_request;
+use nacm_validator::{NacmConfig, RequestContext};
+use std::path::PathBuf;
+
+/// Arguments for the `restconf` subcommand
+#[derive(Args)]
+pub struct RestconfArgs {
+    /// HTTP method: GET, HEAD, POST, PUT, PATCH, DELETE or OPTIONS
+    pub method: String,
+
+    /// Request URI, e.g. /restconf/data/ietf-interfaces:interfaces/interface=eth0
+    pub uri: String,
+
+    /// Request body, JSON or XML (use "-" for stdin)
+    ///
+    /// Only needed for POST, to tell creating a child resource from invoking
+    /// an action.
+    #[arg(long, value_name = "FILE")]
+    pub body: Option<PathBuf>,
+}
+
+/// Map the request, evaluate every access request and print the results
+///
+/// ## Returns
+///
+/// * `Ok(i32)` - Exit code: 0 if everything is permitted, 1 if anything is
+///   denied, 2 if a decision could not be audited
+/// * `Err(String)` - The request could not be mapped
+pub fn run(
+    config: &NacmConfig,
+    user: &str,
+    context: &RequestContext,
+    args: &RestconfArgs,
+    evaluation: &Evaluation,
+    format: &OutputFormat,
+) -> Result<i32, String> {
+    let body = match &args.body {
+        Some(path) => Some(batch::read_text(path)?),
+        None => None,
+    };
+
+    let accesses = map_request(&args.method, &args.uri, body.as_deref())?;
+    let records = batch::evaluate_mapped(config, evaluation, user, Some(context), &accesses);
+    Ok(batch::output_records(&records, format))
+}
@@ -0,0 +1,383 @@
+//! # Validation Server Mode
+//!
+//! Long-running server for the `serve` subcommand. The NACM configuration is
+//! loaded once and shared between all connections, which avoids paying for
+//! process startup and a full XML reparse on every decision.
+//!
+//! ## Protocol
+//!
+//! Clients connect over a Unix domain socket or localhost TCP and send the
+//! same newline-delimited JSON requests accepted by `--json-input`. Each
+//! request line is answered with exactly one JSON line: a result object, or
+//! an object with an `error` field if the line could not be processed. Both
+//! carry the line number within the connection and echo the request `id`.
+//!
+//! Conditional rules see the client's address as the `source-ip` attribute
+//! on TCP connections; a request's own `attributes` take precedence.
+//!
+//! ```text
+//! > {"id":1,"user":"alice","operation":"read","module":"ietf-interfaces"}
+//! < {"line":1,"id":1,"decision":"permit","user":"alice",...,"should_log":false}
+//! > {"id":2,"user":"alice","operation":"fly"}
+//! < {"line":2,"id":2,"error":"Invalid operation 'fly': Unknown operation: fly"}
+//! ```
+//!
+//! With the `http` cargo feature, `--http` serves the same decisions as an
+//! HTTP/JSON API instead; see the `http` module.
+//!
+//! ## Connection Limit
+//!
+//! Each connection is served on its own thread. At most `--max-connections`
+//! connections are served at once; further clients wait in the listen
+//! backlog until a connection closes.
+//!
+//! ## Statistics
+//!
+//! The server counts denials as RFC 8341 `denied-operations` and
+//! `denied-data-writes`; denied Tail-f commands are not counted.
+//! `--stats-breakdown` adds per-user and per-rule counts. `--metrics <addr>`
+//! serves them for Prometheus; see the `metrics` module.
+//!
+//! ## Configuration Reload
+//!
+//! On Unix, sending SIGHUP reloads the configuration: every `--config`
+//! file is read again (directories are listed again) and merged as at
+//! startup. If the new configuration fails to load, the previous one stays
+//! in effect. Statistics carry over to the reloaded configuration.
+
+use crate::{load_config, process_json_line, Evaluation};
+use clap::Args;
+use nacm_validator::merge::MergeStrategy;
+use nacm_validator::stats::NacmStats;
+use nacm_validator::NacmConfig;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::thread;
+
+/// Arguments for the `serve` subcommand
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Where to answer validation requests
+    #[command(flatten)]
+    pub endpoint: Endpoint,
+
+    /// Localhost address to serve statistics on (e.g. 127.0.0.1:9100)
+    ///
+    /// Serves `/metrics` in Prometheus format and `/v1/nacm` as RFC 8341
+    /// operational state.
+    #[arg(long)]
+    pub metrics: Option<SocketAddr>,
+
+    /// Also count denials per user and per deciding rule
+    #[arg(long)]
+    pub stats_breakdown: bool,
+
+    /// Maximum number of connections served at the same time
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_connections: u32,
+}
+
+/// Listening endpoint for validation requests
+///
+/// Exactly one endpoint must be given.
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub struct Endpoint {
+    /// Unix domain socket path to listen on
+    #[arg(long)]
+    pub socket: Option<PathBuf>,
+
+    /// Localhost TCP address to listen on (e.g. 127.0.0.1:7878)
+    ///
+    /// Only loopback addresses are accepted; the server has no
+    /// authentication of its own.
+    #[arg(long)]
+    pub listen: Option<SocketAddr>,
+
+    /// Localhost address to serve the HTTP/JSON decision API on (e.g. 127.0.0.1:8181)
+    #[cfg(feature = "http")]
+    #[arg(long)]
+    pub http: Option<SocketAddr>,
+}
+
+/// Configuration shared between connection threads
+///
+/// Readers take the lock for the duration of a single validation; the
+/// SIGHUP handler takes the write lock only to swap in a new configuration.
+pub(crate) type SharedConfig = Arc<RwLock<NacmConfig>>;
+
+/// Counting semaphore bounding the number of connection threads
+struct ConnectionLimit {
+    active: Mutex<usize>,
+    released: Condvar,
+    max: usize,
+}
+
+/// A connection slot, given back when dropped
+struct ConnectionPermit(Arc<ConnectionLimit>);
+
+impl ConnectionLimit {
+    fn new(max: usize) -> Arc<Self> {
+        Arc::new(ConnectionLimit {
+            active: Mutex::new(0),
+            released: Condvar::new(),
+            max,
+        })
+    }
+
+    /// Wait until a slot is free and take it
+    fn acquire(self: &Arc<Self>) -> ConnectionPermit {
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        while *active >= self.max {
+            active = self.released.wait(active).unwrap_or_else(|e| e.into_inner());
+        }
+        *active += 1;
+        ConnectionPermit(Arc::clone(self))
+    }
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        let mut active = self.0.active.lock().unwrap_or_else(|e| e.into_inner());
+        *active -= 1;
+        self.0.released.notify_one();
+    }
+}
+
+/// Run the validation server until the process is terminated
+///
+/// ## Parameters
+///
+/// * `config_paths` - Files or directories the configuration was loaded from (used for reload)
+/// * `strategy` - How the configuration files are merged (used for reload)
+/// * `config` - The already loaded configuration
+/// * `args` - Listening endpoint selection
+/// * `evaluation` - Environment and audit log to decide in
+/// * `verbose` - Log connections to stderr
+///
+/// ## Returns
+///
+/// Only returns on setup errors, such as failing to bind the endpoint.
+pub fn run(
+    config_paths: &[PathBuf],
+    strategy: MergeStrategy,
+    mut config: NacmConfig,
+    args: &ServeArgs,
+    evaluation: Evaluation,
+    verbose: bool,
+) -> io::Result<()> {
+    let stats = if args.stats_breakdown {
+        NacmStats::with_breakdown()
+    } else {
+        NacmStats::new()
+    };
+    config.stats = Some(Arc::new(stats));
+
+    let shared: SharedConfig = Arc::new(RwLock::new(config));
+    install_reload_handler(config_paths, strategy, Arc::clone(&shared))?;
+
+    if let Some(addr) = args.metrics {
+        // Fail early on a bad address rather than in the background thread
+        check_loopback(addr)?;
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            if let Err(e) = crate::metrics::serve_metrics(addr, shared) {
+                eprintln!("Metrics server error: {}", e);
+            }
+        });
+    }
+
+    let endpoint = &args.endpoint;
+
+    #[cfg(feature = "http")]
+    if let Some(addr) = endpoint.http {
+        check_loopback(addr)?;
+        return crate::http::serve_http(addr, &shared, &evaluation, verbose);
+    }
+
+    let limit = ConnectionLimit::new(args.max_connections as usize);
+    if let Some(addr) = endpoint.listen {
+        serve_tcp(addr, &shared, &evaluation, &limit, verbose)
+    } else if let Some(path) = &endpoint.socket {
+        serve_unix(path, &shared, &evaluation, &limit, verbose)
+    } else {
+        // Unreachable through clap, which requires exactly one endpoint
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "a listening endpoint is required"))
+    }
+}
+
+/// Refuse non-loopback addresses, since the server has no authentication
+pub(crate) fn check_loopback(addr: SocketAddr) -> io::Result<()> {
+    if addr.ip().is_loopback() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("refusing to listen on non-loopback address {}", addr),
+        ))
+    }
+}
+
+/// Accept connections on a localhost TCP address
+fn serve_tcp(
+    addr: SocketAddr,
+    shared: &SharedConfig,
+    evaluation: &Evaluation,
+    limit: &Arc<ConnectionLimit>,
+    verbose: bool,
+) -> io::Result<()> {
+    check_loopback(addr)?;
+
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("Listening on tcp://{}", listener.local_addr()?);
+
+    loop {
+        // Take a slot before accepting, so waiting clients stay in the backlog
+        let permit = limit.acquire();
+        match listener.accept().and_then(|(s, peer)| Ok((s.try_clone()?, s, peer))) {
+            Ok((reader, writer, peer)) => {
+                let evaluation = evaluation.for_peer(Some(peer.ip()));
+                spawn_connection(reader, writer, evaluation, shared, permit, verbose)
+            }
+            // A failed accept only affects that client - keep serving
+            Err(e) => eprintln!("Error accepting connection: {}", e),
+        }
+    }
+}
+
+/// Accept connections on a Unix domain socket
+#[cfg(unix)]
+fn serve_unix(
+    path: &Path,
+    shared: &SharedConfig,
+    evaluation: &Evaluation,
+    limit: &Arc<ConnectionLimit>,
+    verbose: bool,
+) -> io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+
+    // Remove a stale socket left behind by a previous run, but never a regular file
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if metadata.file_type().is_socket() {
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    let listener = UnixListener::bind(path)?;
+    eprintln!("Listening on unix:{}", path.display());
+
+    loop {
+        let permit = limit.acquire();
+        match listener.accept().and_then(|(s, _)| Ok((s.try_clone()?, s))) {
+            Ok((reader, writer)) => spawn_connection(reader, writer, evaluation.clone(), shared, permit, verbose),
+            Err(e) => eprintln!("Error accepting connection: {}", e),
+        }
+    }
+}
+
+/// Unix domain sockets are not available on this platform
+#[cfg(not(unix))]
+fn serve_unix(
+    _path: &Path,
+    _shared: &SharedConfig,
+    _evaluation: &Evaluation,
+    _limit: &Arc<ConnectionLimit>,
+    _verbose: bool,
+) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix domain sockets are not supported on this platform; use --listen",
+    ))
+}
+
+/// Reload the configuration whenever the process receives SIGHUP
+#[cfg(unix)]
+fn install_reload_handler(config_paths: &[PathBuf], strategy: MergeStrategy, shared: SharedConfig) -> io::Result<()> {
+    use signal_hook::{consts::SIGHUP, iterator::Signals};
+
+    let mut signals = Signals::new([SIGHUP])?;
+    let paths = config_paths.to_vec();
+
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            match load_config(&paths, &strategy) {
+                Ok(mut new_config) => {
+                    // A poisoned lock only means a reader panicked; the data is still valid
+                    let mut config = shared.write().unwrap_or_else(|e| e.into_inner());
+                    // Keep counting into the same statistics across reloads
+                    new_config.stats = config.stats.clone();
+                    *config = new_config;
+                    eprintln!("Reloaded NACM config from: {:?}", paths);
+                }
+                Err(e) => eprintln!("Error reloading config, keeping previous: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// SIGHUP does not exist on this platform, so there is nothing to install
+#[cfg(not(unix))]
+fn install_reload_handler(_config_paths: &[PathBuf], _strategy: MergeStrategy, _shared: SharedConfig) -> io::Result<()> {
+    Ok(())
+}
+
+/// Serve one client connection on its own thread
+///
+/// The thread holds `permit` until the client disconnects. `evaluation`
+/// carries the client's address as `source-ip`, if it has one.
+fn spawn_connection<R, W>(
+    reader: R,
+    writer: W,
+    evaluation: Evaluation,
+    shared: &SharedConfig,
+    permit: ConnectionPermit,
+    verbose: bool,
+) where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    let shared = Arc::clone(shared);
+    thread::spawn(move || {
+        let _permit = permit;
+        if verbose {
+            eprintln!("Client connected");
+        }
+        if let Err(e) = handle_connection(reader, writer, &evaluation, &shared) {
+            eprintln!("Connection error: {}", e);
+        }
+        if verbose {
+            eprintln!("Client disconnected");
+        }
+    });
+}
+
+/// Answer newline-delimited JSON requests until the client disconnects
+///
+/// Blank lines are ignored. Every other line produces exactly one response
+/// line, so clients can pipeline requests and match responses by position.
+fn handle_connection<R: Read, W: Write>(
+    reader: R,
+    mut writer: W,
+    evaluation: &Evaluation,
+    shared: &SharedConfig,
+) -> io::Result<()> {
+    for (index, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record = {
+            let config = shared.read().unwrap_or_else(|e| e.into_inner());
+            process_json_line(&config, evaluation, &line, Some(index + 1))
+        };
+
+        writeln!(writer, "{}", serde_json::to_string(&record).unwrap())?;
+        writer.flush()?;
+    }
+    Ok(())
+}
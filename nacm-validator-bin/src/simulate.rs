@@ -0,0 +1,350 @@
+//! # Policy Simulation
+//!
+//! Implements the `simulate` subcommand: replays a corpus of requests
+//! against the `--config` policy and a proposed one, and reports only the
+//! requests whose decision or logging flag would change.
+//!
+//! ```bash
+//! # Which of last week's logged requests would a new deny rule affect?
+//! nacm-validator --config running.xml simulate audit.jsonl --patch add-deny.xml
+//!
+//! # Compare against a complete proposed policy
+//! nacm-validator --config running.xml --format json simulate requests.ndjson --proposed next.xml
+//! ```
+//!
+//! ## Proposed Policy
+//!
+//! Either a full configuration (`--proposed`, files or directories merged
+//! like `--config`) or a `/nacm` edit-config fragment (`--patch`) applied to
+//! the current configuration.
+//!
+//! ## Corpus
+//!
+//! Any `--input` format: NDJSON, a JSON array, YAML or CSV. An `--audit-log`
+//! file is NDJSON with the request fields, so it can be replayed as is.
+//!
+//! Conditional rules are evaluated at the time each request was made: its
+//! `at` field, or else the `timestamp` of an audit record. Requests with
+//! neither use `--at` or the current time. Request `attributes` apply as in
+//! `--json-input`.
+//!
+//! ## Output
+//!
+//! Changes are grouped by user and, within a user, by the rule responsible
+//! for the change (see `nacm_validator::simulate`), each with the corpus
+//! line number and the decisions before and after.
+//!
+//! ## Exit Codes
+//!
+//! - 0: no request changes
+//! - 1: at least one request changes
+//! - 2: the corpus has unreadable requests, or the input could not be read
+
+use crate::batch::{self, InputFormat};
+use crate::{load_config, request_environment, OutputFormat};
+use clap::{ArgGroup, Args};
+use nacm_validator::audit::parse_rfc3339;
+use nacm_validator::condition::Environment;
+use nacm_validator::merge::MergeStrategy;
+use nacm_validator::simulate::DecisionChange;
+use nacm_validator::{Decision, MatchedRule, NacmConfig, OwnedAccessRequest, RuleEffect};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+
+/// Arguments for the `simulate` subcommand
+#[derive(Args)]
+#[command(group(ArgGroup::new("change").required(true).args(["proposed", "patch"])))]
+pub struct SimulateArgs {
+    /// Requests to replay: NDJSON (e.g. an audit log), JSON array, YAML or CSV (use "-" for stdin)
+    pub corpus: PathBuf,
+
+    /// Proposed configuration file or directory (repeat to merge, like --config)
+    #[arg(long, value_name = "PATH")]
+    pub proposed: Vec<PathBuf>,
+
+    /// Proposed change as a /nacm edit-config fragment for the current configuration
+    #[arg(long, value_name = "FILE")]
+    pub patch: Option<PathBuf>,
+
+    /// Force the format of the corpus instead of auto-detecting it
+    #[arg(long)]
+    pub corpus_format: Option<InputFormat>,
+}
+
+/// One side of a changed decision
+#[derive(Serialize)]
+struct JsonDecision {
+    /// Access decision: "permit" or "deny"
+    decision: RuleEffect,
+    /// Whether the decision should be logged
+    should_log: bool,
+    /// Rule list of the deciding rule (None = default policy)
+    rule_list: Option<String>,
+    /// Name of the deciding rule (None = default policy)
+    rule: Option<String>,
+}
+
+/// A changed request, as written in JSON mode
+#[derive(Serialize)]
+struct JsonChange<'a> {
+    /// Corpus line or element number
+    line: usize,
+    /// Correlation id copied from the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'a serde_json::Value>,
+    /// The replayed request
+    #[serde(flatten)]
+    request: &'a OwnedAccessRequest,
+    /// The rule the change is attributed to ("default policy" if none)
+    responsible: String,
+    /// Decision under the current configuration
+    before: JsonDecision,
+    /// Decision under the proposed configuration
+    after: JsonDecision,
+}
+
+/// Trailer record of a simulation in JSON mode
+#[derive(Serialize)]
+struct JsonSimulationSummary<'a> {
+    /// Number of requests replayed
+    total: usize,
+    /// Number of requests whose result changes
+    changed: usize,
+    /// Number of corpus entries that could not be read
+    error: usize,
+    /// Changed requests per user
+    by_user: BTreeMap<&'a str, usize>,
+    /// Changed requests per responsible rule
+    by_rule: BTreeMap<String, usize>,
+}
+
+/// A corpus entry that could be read as a request
+struct CorpusEntry {
+    line: usize,
+    id: Option<serde_json::Value>,
+    /// The time and attributes the request is replayed with
+    env: Environment,
+}
+
+/// The environment a corpus entry is replayed with
+///
+/// An audit record's `timestamp` sets the clock, unless the entry has an
+/// `at` field of its own.
+fn entry_environment(value: &serde_json::Value, base: &Environment) -> Result<Environment, String> {
+    let base = match value.get("timestamp").and_then(|t| t.as_str()) {
+        Some(timestamp) => base.clone().at(parse_rfc3339(timestamp).map_err(|e| format!("Invalid request: {}", e))?),
+        None => base.clone(),
+    };
+    request_environment(value, &base)
+}
+
+/// Run the simulation and print the changed requests
+///
+/// ## Returns
+///
+/// * `Ok(i32)` - Exit code (see the module documentation)
+/// * `Err(String)` - The corpus or the proposed policy could not be read
+pub fn run(
+    config: &NacmConfig,
+    strategy: &MergeStrategy,
+    args: &SimulateArgs,
+    env: &Environment,
+    format: &OutputFormat,
+) -> Result<i32, String> {
+    let proposed = match &args.patch {
+        Some(path) => {
+            let mut proposed = config.clone();
+            proposed
+                .apply_nacm_edit(&batch::read_text(path)?)
+                .map_err(|e| format!("Error applying {:?}: {}", path, e))?;
+            proposed
+        }
+        None => load_config(&args.proposed, strategy).map_err(|e| format!("Error loading proposed config: {}", e))?,
+    };
+
+    // Keep the readable requests, reporting the others like batch mode does
+    let mut entries = Vec::new();
+    let mut requests = Vec::new();
+    let mut errors = 0;
+    for record in batch::read_input(&args.corpus, args.corpus_format)? {
+        let parsed = record.value.and_then(|value| {
+            let id = value.get("id").cloned();
+            let env = entry_environment(&value, env)?;
+            serde_json::from_value::<OwnedAccessRequest>(value)
+                .map(|request| (id, env, request))
+                .map_err(|e| format!("Invalid request: {}", e))
+        });
+        match parsed {
+            Ok((id, env, request)) => {
+                entries.push(CorpusEntry { line: record.line, id, env });
+                requests.push(request);
+            }
+            Err(error) => {
+                eprintln!("{}: ERROR {}", record.line, error);
+                errors += 1;
+            }
+        }
+    }
+
+    let simulation = config.simulate_with(&proposed, &requests, |index| entries[index].env.clone());
+
+    // Group by user, then by responsible rule; changes keep corpus order within a group
+    let mut grouped: BTreeMap<&str, BTreeMap<Option<MatchedRule>, Vec<&DecisionChange>>> = BTreeMap::new();
+    for change in &simulation.changes {
+        grouped
+            .entry(change.request.user.as_str())
+            .or_default()
+            .entry(change.responsible)
+            .or_default()
+            .push(change);
+    }
+
+    match format {
+        OutputFormat::Text => {
+            for (user, rules) in &grouped {
+                println!("{}", user);
+                for (rule, changes) in rules {
+                    println!("  {}", rule_label(*rule));
+                    for change in changes {
+                        println!(
+                            "    {}: {} -> {} {}",
+                            entries[change.index].line,
+                            decision_label(&change.before),
+                            decision_label(&change.after),
+                            describe(change.request)
+                        );
+                    }
+                }
+            }
+            println!(
+                "changed: {} of {} requests, error: {}",
+                simulation.changes.len(),
+                simulation.total,
+                errors
+            );
+        }
+        OutputFormat::Json => {
+            for change in grouped.values().flat_map(|rules| rules.values()).flatten() {
+                let entry = &entries[change.index];
+                let json = JsonChange {
+                    line: entry.line,
+                    id: entry.id.as_ref(),
+                    request: change.request,
+                    responsible: rule_label(change.responsible),
+                    before: json_decision(&change.before),
+                    after: json_decision(&change.after),
+                };
+                println!("{}", serde_json::to_string(&json).unwrap());
+            }
+            let summary = JsonSimulationSummary {
+                total: simulation.total,
+                changed: simulation.changes.len(),
+                error: errors,
+                by_user: simulation.by_user().into_iter().map(|(user, c)| (user, c.len())).collect(),
+                by_rule: simulation
+                    .by_rule()
+                    .into_iter()
+                    .map(|(rule, c)| (rule_label(rule), c.len()))
+                    .collect(),
+            };
+            println!("{{\"summary\":{}}}", serde_json::to_string(&summary).unwrap());
+        }
+        OutputFormat::Csv => {
+            let changes: Vec<&DecisionChange> = grouped.values().flat_map(|rules| rules.values()).flatten().copied().collect();
+            if let Err(e) = write_csv(&changes, &entries) {
+                eprintln!("Error writing CSV: {}", e);
+                return Ok(2);
+            }
+        }
+        OutputFormat::ExitCode => {
+            // Silent mode: the exit code says whether anything changes
+        }
+    }
+
+    Ok(if errors > 0 {
+        2
+    } else if simulation.changes.is_empty() {
+        0
+    } else {
+        1
+    })
+}
+
+/// Write one CSV row per changed request
+fn write_csv(changes: &[&DecisionChange], entries: &[CorpusEntry]) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    writer.write_record([
+        "line", "user", "responsible", "module", "rpc", "operation", "path", "context", "command",
+        "before", "before_rule", "after", "after_rule",
+    ])?;
+
+    for change in changes {
+        let request = change.request;
+        writer.write_record([
+            entries[change.index].line.to_string(),
+            request.user.clone(),
+            rule_label(change.responsible),
+            request.module_name.clone().unwrap_or_default(),
+            request.rpc_name.clone().unwrap_or_default(),
+            request.operation.to_string(),
+            request.path.clone().unwrap_or_default(),
+            request.context.as_ref().map(|ctx| ctx.to_string()).unwrap_or_default(),
+            request.command.clone().unwrap_or_default(),
+            effect_name(change.before.result.effect).to_string(),
+            rule_label(change.before.matched_rule),
+            effect_name(change.after.result.effect).to_string(),
+            rule_label(change.after.matched_rule),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// "list/rule", or "default policy" when no rule decided
+pub(crate) fn rule_label(rule: Option<MatchedRule>) -> String {
+    rule.map(|rule| rule.to_string()).unwrap_or_else(|| "default policy".to_string())
+}
+
+/// Lowercase name of an effect, as in the other output formats
+pub(crate) fn effect_name(effect: RuleEffect) -> &'static str {
+    match effect {
+        RuleEffect::Permit => "permit",
+        RuleEffect::Deny => "deny",
+    }
+}
+
+/// "PERMIT", "DENY [LOGGED]", ... for text output
+pub(crate) fn decision_label(decision: &Decision) -> String {
+    let log_indicator = if decision.result.should_log { " [LOGGED]" } else { "" };
+    format!("{}{}", effect_name(decision.result.effect).to_uppercase(), log_indicator)
+}
+
+/// The request fields that are set, for text output
+pub(crate) fn describe(request: &OwnedAccessRequest) -> String {
+    let mut parts = vec![format!("operation={}", request.operation)];
+    let optional = [
+        ("module", request.module_name.clone()),
+        ("rpc", request.rpc_name.clone()),
+        ("path", request.path.clone()),
+        ("context", request.context.as_ref().map(|ctx| ctx.to_string())),
+        ("command", request.command.clone()),
+    ];
+    for (name, value) in optional {
+        if let Some(value) = value {
+            parts.push(format!("{}={}", name, value));
+        }
+    }
+    parts.join(" ")
+}
+
+/// Serialized form of one side of a change
+fn json_decision(decision: &Decision) -> JsonDecision {
+    JsonDecision {
+        decision: decision.result.effect,
+        should_log: decision.result.should_log,
+        rule_list: decision.matched_rule.map(|m| m.rule_list.to_string()),
+        rule: decision.matched_rule.map(|m| m.rule.to_string()),
+    }
+}
@@ -0,0 +1,545 @@
+//! # Policy Test Suites
+//!
+//! Implements the `test` subcommand: runs a catalogue of requests with
+//! expected decisions against a NACM configuration and reports which
+//! expectations hold. Intended for CI, so results are available as TAP or
+//! JUnit XML and the exit code reflects the outcome.
+//!
+//! ## Suite Format
+//!
+//! Suites are YAML (or JSON). Each test has a `request` with the same fields
+//! as `--json-input` and an `expect` block. Only `decision` is required;
+//! `should_log` and `rule` are checked when present.
+//!
+//! ```yaml
+//! # Resolved relative to the suite file; --config overrides it
+//! config: tailf_acm_example.xml
+//! tests:
+//!   - name: alice can run show status from the CLI
+//!     request: {user: alice, operation: read, context: cli, command: show status}
+//!     expect: {decision: permit, should_log: true, rule: operator-rules/cli-show-status}
+//!   - name: bob cannot reboot
+//!     request: {user: bob, operation: exec, context: cli, command: reboot}
+//!     expect: {decision: deny}
+//!   - name: unmatched requests fall through to the default policy
+//!     request: {user: guest, operation: read, module: ietf-interfaces}
+//!     expect: {decision: deny, rule: null}
+//! ```
+//!
+//! An expected `rule` is either `list/rule` or just the rule name; `null`
+//! means no rule matched and the default policy decided.
+//!
+//! ## Exit Codes
+//!
+//! - **0**: All tests passed
+//! - **1**: At least one test failed
+//! - **2**: The suite or configuration could not be loaded, or the report
+//!   could not be written
+
+use crate::{load_config, process_json_value, Evaluation, JsonRecord};
+use clap::{Args, ValueEnum};
+use nacm_validator::merge::MergeStrategy;
+use serde::{Deserialize, Deserializer};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Arguments for the `test` subcommand
+#[derive(Args)]
+pub struct TestArgs {
+    /// Test suite file (YAML or JSON)
+    pub suite: PathBuf,
+
+    /// Report format
+    #[arg(long, default_value = "text")]
+    pub format: ReportFormat,
+}
+
+/// Output formats for test reports
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    /// Human-readable PASS/FAIL lines with differences
+    Text,
+    /// Test Anything Protocol (version 13)
+    Tap,
+    /// JUnit XML, as consumed by most CI systems
+    Junit,
+}
+
+/// A test suite as read from the suite file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Suite {
+    /// Configuration to test, relative to the suite file
+    config: Option<PathBuf>,
+    /// Test cases, run in order
+    tests: Vec<TestCase>,
+}
+
+/// One request with its expected outcome
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestCase {
+    /// Descriptive name shown in reports (defaults to "test N")
+    name: Option<String>,
+    /// The request, in `--json-input` format
+    request: serde_json::Value,
+    /// Expected outcome
+    expect: Expectation,
+}
+
+/// Expected outcome of a test case
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Expectation {
+    /// Expected decision: "permit" or "deny"
+    decision: ExpectedDecision,
+    /// Expected logging flag (not checked if absent)
+    should_log: Option<bool>,
+    /// Expected deciding rule (not checked if absent, `null` = default policy)
+    #[serde(default, deserialize_with = "present")]
+    rule: Option<Option<String>>,
+}
+
+/// Decision values accepted in `expect`
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ExpectedDecision {
+    Permit,
+    Deny,
+}
+
+impl ExpectedDecision {
+    fn as_str(self) -> &'static str {
+        match self {
+            ExpectedDecision::Permit => "permit",
+            ExpectedDecision::Deny => "deny",
+        }
+    }
+}
+
+/// Distinguish an explicit `null` from an absent field
+///
+/// Only called when the field is present, so `null` becomes `Some(None)`
+/// while `#[serde(default)]` turns an absent field into `None`.
+fn present<'de, D>(deserializer: D) -> Result<Option<Option<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer).map(Some)
+}
+
+/// A single expectation that did not hold
+struct Mismatch {
+    /// Which part of the outcome differs ("decision", "should_log", "rule", "error")
+    field: &'static str,
+    expected: String,
+    actual: String,
+}
+
+/// Outcome of one test case
+struct TestOutcome {
+    name: String,
+    mismatches: Vec<Mismatch>,
+}
+
+impl TestOutcome {
+    fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Run a test suite and print the report
+///
+/// ## Parameters
+///
+/// * `config_override` - Configuration from `--config`, taking precedence over the suite's
+/// * `strategy` - How multiple `--config` files are merged
+/// * `args` - Suite file and report format
+/// * `evaluation` - Environment and audit log to decide in
+/// * `verbose` - Log the configuration being tested to stderr
+///
+/// ## Returns
+///
+/// The process exit code (see the module documentation).
+pub fn run(
+    config_override: &[PathBuf],
+    strategy: &MergeStrategy,
+    args: &TestArgs,
+    evaluation: &Evaluation,
+    verbose: bool,
+) -> i32 {
+    let suite = match read_suite(&args.suite) {
+        Ok(suite) => suite,
+        Err(e) => {
+            eprintln!("Error loading test suite: {}", e);
+            return 2;
+        }
+    };
+
+    // --config wins; otherwise the suite's config is relative to the suite file
+    let config_paths = match (config_override, &suite.config) {
+        ([_, ..], _) => config_override.to_vec(),
+        ([], Some(path)) => vec![args
+            .suite
+            .parent()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|| path.clone())],
+        ([], None) => {
+            eprintln!("Error: the test suite names no config; pass --config");
+            return 2;
+        }
+    };
+
+    let config = match load_config(&config_paths, strategy) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error loading config {:?}: {}", config_paths, e);
+            return 2;
+        }
+    };
+    if verbose {
+        eprintln!("Testing NACM config from: {:?}", config_paths);
+    }
+
+    let outcomes: Vec<TestOutcome> = suite
+        .tests
+        .into_iter()
+        .enumerate()
+        .map(|(index, case)| {
+            let name = case.name.unwrap_or_else(|| format!("test {}", index + 1));
+            let record = process_json_value(&config, evaluation, case.request, None);
+            TestOutcome {
+                name,
+                mismatches: compare(&case.expect, &record),
+            }
+        })
+        .collect();
+
+    let suite_name = args.suite.display().to_string();
+    let mut out = io::stdout().lock();
+    let written = match args.format {
+        ReportFormat::Text => print_text(&mut out, &outcomes),
+        ReportFormat::Tap => print_tap(&mut out, &outcomes),
+        ReportFormat::Junit => print_junit(&mut out, &suite_name, &outcomes),
+    };
+    if let Err(e) = written {
+        eprintln!("Error writing test report: {}", e);
+        return 2;
+    }
+
+    if outcomes.iter().all(TestOutcome::passed) {
+        0
+    } else {
+        1
+    }
+}
+
+/// Read and parse a suite file
+fn read_suite(path: &Path) -> Result<Suite, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{:?}: {}", path, e))?;
+    // YAML is a superset of JSON, so this handles both
+    serde_yaml::from_str(&content).map_err(|e| format!("{:?}: {}", path, e))
+}
+
+/// Compare the actual outcome of a request with the expectation
+fn compare(expect: &Expectation, record: &JsonRecord) -> Vec<Mismatch> {
+    let result = match record {
+        JsonRecord::Result(result) => result,
+        JsonRecord::Error(error) => {
+            return vec![Mismatch {
+                field: "error",
+                expected: format!("a {} decision", expect.decision.as_str()),
+                actual: error.error.clone(),
+            }];
+        }
+    };
+
+    let mut mismatches = Vec::new();
+
+    if result.decision != expect.decision.as_str() {
+        mismatches.push(Mismatch {
+            field: "decision",
+            expected: expect.decision.as_str().to_string(),
+            actual: result.decision.clone(),
+        });
+    }
+
+    if let Some(should_log) = expect.should_log {
+        if result.should_log != should_log {
+            mismatches.push(Mismatch {
+                field: "should_log",
+                expected: should_log.to_string(),
+                actual: result.should_log.to_string(),
+            });
+        }
+    }
+
+    if let Some(expected_rule) = &expect.rule {
+        let actual = match (&result.rule_list, &result.rule) {
+            (Some(list), Some(rule)) => Some((list.as_str(), rule.as_str())),
+            _ => None,
+        };
+        let matches = match (expected_rule, actual) {
+            (None, None) => true,
+            // Either "list/rule" or just the rule name
+            (Some(expected), Some((list, rule))) => match expected.split_once('/') {
+                Some((expected_list, expected_name)) => expected_list == list && expected_name == rule,
+                None => expected == rule,
+            },
+            _ => false,
+        };
+        if !matches {
+            mismatches.push(Mismatch {
+                field: "rule",
+                expected: expected_rule.clone().unwrap_or_else(|| "default policy".to_string()),
+                actual: actual
+                    .map(|(list, rule)| format!("{}/{}", list, rule))
+                    .unwrap_or_else(|| "default policy".to_string()),
+            });
+        }
+    }
+
+    mismatches
+}
+
+/// Human-readable report
+fn print_text<W: Write>(out: &mut W, outcomes: &[TestOutcome]) -> io::Result<()> {
+    for outcome in outcomes {
+        if outcome.passed() {
+            writeln!(out, "PASS {}", outcome.name)?;
+        } else {
+            writeln!(out, "FAIL {}", outcome.name)?;
+            for m in &outcome.mismatches {
+                writeln!(out, "     {}: expected {}, got {}", m.field, m.expected, m.actual)?;
+            }
+        }
+    }
+
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    writeln!(
+        out,
+        "{} tests, {} passed, {} failed",
+        outcomes.len(),
+        outcomes.len() - failed,
+        failed
+    )
+}
+
+/// TAP version 13 report, with a YAML diagnostic block per failure
+fn print_tap<W: Write>(out: &mut W, outcomes: &[TestOutcome]) -> io::Result<()> {
+    writeln!(out, "TAP version 13")?;
+    writeln!(out, "1..{}", outcomes.len())?;
+    for (index, outcome) in outcomes.iter().enumerate() {
+        let status = if outcome.passed() { "ok" } else { "not ok" };
+        writeln!(out, "{} {} - {}", status, index + 1, outcome.name.replace('#', "\\#"))?;
+        if !outcome.passed() {
+            writeln!(out, "  ---")?;
+            for m in &outcome.mismatches {
+                writeln!(out, "  {}:", m.field)?;
+                writeln!(out, "    expected: {}", yaml_scalar(&m.expected))?;
+                writeln!(out, "    got: {}", yaml_scalar(&m.actual))?;
+            }
+            writeln!(out, "  ...")?;
+        }
+    }
+    Ok(())
+}
+
+/// JUnit XML report with one testcase per test
+fn print_junit<W: Write>(out: &mut W, suite_name: &str, outcomes: &[TestOutcome]) -> io::Result<()> {
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuites name="nacm-validator" tests="{}" failures="{}">"#,
+        outcomes.len(),
+        failed
+    )?;
+    writeln!(
+        out,
+        r#"  <testsuite name="{}" tests="{}" failures="{}">"#,
+        xml_escape(suite_name),
+        outcomes.len(),
+        failed
+    )?;
+    for outcome in outcomes {
+        let name = xml_escape(&outcome.name);
+        if outcome.passed() {
+            writeln!(out, r#"    <testcase name="{}" classname="nacm"/>"#, name)?;
+        } else {
+            let details: Vec<String> = outcome
+                .mismatches
+                .iter()
+                .map(|m| format!("{}: expected {}, got {}", m.field, m.expected, m.actual))
+                .collect();
+            writeln!(out, r#"    <testcase name="{}" classname="nacm">"#, name)?;
+            writeln!(
+                out,
+                r#"      <failure message="{}">{}</failure>"#,
+                xml_escape(&details[0]),
+                xml_escape(&details.join("\n"))
+            )?;
+            writeln!(out, "    </testcase>")?;
+        }
+    }
+    writeln!(out, "  </testsuite>")?;
+    writeln!(out, "</testsuites>")
+}
+
+/// Quote a value for a TAP YAML diagnostic block
+fn yaml_scalar(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+/// Escape text for XML attributes and content
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nacm_validator::{NacmConfig, Operation, RuleEffect};
+
+    fn policy() -> NacmConfig {
+        NacmConfig::builder()
+            .exec_default(RuleEffect::Deny)
+            .group("admin", ["alice"])
+            .rule_list("admin-acl", ["admin"], |rl| {
+                rl.permit().name("edit").rpc("edit-config").ops([Operation::Exec]).log_if_permit()
+            })
+            .build()
+            .unwrap()
+    }
+
+    const ALICE_EDITS: &str = "{user: alice, operation: exec, rpc: edit-config}";
+    const BOB_EDITS: &str = "{user: bob, operation: exec, rpc: edit-config}";
+
+    /// Run one test case given its request and expectation in suite YAML
+    fn check(request: &str, expect: &str) -> Vec<Mismatch> {
+        let case: TestCase = serde_yaml::from_str(&format!("request: {}\nexpect: {}", request, expect)).unwrap();
+        let record = process_json_value(&policy(), &Evaluation::default(), case.request, None);
+        compare(&case.expect, &record)
+    }
+
+    fn fields(mismatches: &[Mismatch]) -> Vec<(&str, &str, &str)> {
+        mismatches
+            .iter()
+            .map(|m| (m.field, m.expected.as_str(), m.actual.as_str()))
+            .collect()
+    }
+
+    fn report(print: impl Fn(&mut Vec<u8>) -> io::Result<()>) -> String {
+        let mut out = Vec::new();
+        print(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn outcomes() -> Vec<TestOutcome> {
+        vec![
+            TestOutcome { name: "alice edits".to_string(), mismatches: Vec::new() },
+            TestOutcome {
+                name: "bob <reboots> & \"logs\" #1".to_string(),
+                mismatches: vec![
+                    Mismatch { field: "decision", expected: "permit".to_string(), actual: "deny".to_string() },
+                    Mismatch { field: "rule", expected: "a/b".to_string(), actual: "default policy".to_string() },
+                ],
+            },
+        ]
+    }
+
+    #[test]
+    fn matching_expectations_pass() {
+        assert!(check(ALICE_EDITS, "{decision: permit, should_log: true, rule: admin-acl/edit}").is_empty());
+        // A bare rule name matches in any rule list
+        assert!(check(ALICE_EDITS, "{decision: permit, rule: edit}").is_empty());
+    }
+
+    #[test]
+    fn every_differing_field_is_reported() {
+        let mismatches = check(BOB_EDITS, "{decision: permit, should_log: true, rule: admin-acl/edit}");
+        assert_eq!(
+            fields(&mismatches),
+            [
+                ("decision", "permit", "deny"),
+                ("should_log", "true", "false"),
+                ("rule", "admin-acl/edit", "default policy"),
+            ]
+        );
+
+        // The rule list part must match too
+        let mismatches = check(ALICE_EDITS, "{decision: permit, rule: other/edit}");
+        assert_eq!(fields(&mismatches), [("rule", "other/edit", "admin-acl/edit")]);
+    }
+
+    #[test]
+    fn null_rule_means_default_policy_and_absent_rule_is_unchecked() {
+        let case: TestCase = serde_yaml::from_str("request: {}\nexpect: {decision: deny, rule: null}").unwrap();
+        assert_eq!(case.expect.rule, Some(None));
+        let case: TestCase = serde_yaml::from_str("request: {}\nexpect: {decision: deny}").unwrap();
+        assert_eq!(case.expect.rule, None);
+
+        assert!(check(BOB_EDITS, "{decision: deny, rule: null}").is_empty());
+        let permitted_by_rule = check(ALICE_EDITS, "{decision: permit, rule: null}");
+        assert_eq!(fields(&permitted_by_rule), [("rule", "default policy", "admin-acl/edit")]);
+        assert!(check(ALICE_EDITS, "{decision: permit}").is_empty());
+    }
+
+    #[test]
+    fn request_errors_are_a_single_mismatch() {
+        let mismatches = check("{user: bob, operation: fly}", "{decision: deny, rule: null}");
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].field, "error");
+        assert_eq!(mismatches[0].expected, "a deny decision");
+        assert!(mismatches[0].actual.contains("fly"));
+    }
+
+    #[test]
+    fn suite_files_reject_unknown_fields() {
+        assert!(serde_yaml::from_str::<TestCase>("request: {}\nexpect: {decision: permit, rules: x}").is_err());
+        assert!(serde_yaml::from_str::<TestCase>("request: {}\nexpect: {decision: allow}").is_err());
+        assert!(serde_yaml::from_str::<Suite>("tests: []\nconfgi: x.xml").is_err());
+    }
+
+    #[test]
+    fn text_report_lists_mismatches() {
+        let text = report(|out| print_text(out, &outcomes()));
+        assert_eq!(
+            text,
+            "PASS alice edits\n\
+             FAIL bob <reboots> & \"logs\" #1\n\
+             \x20    decision: expected permit, got deny\n\
+             \x20    rule: expected a/b, got default policy\n\
+             2 tests, 1 passed, 1 failed\n"
+        );
+    }
+
+    #[test]
+    fn tap_report_escapes_hashes_and_quotes_diagnostics() {
+        let tap = report(|out| print_tap(out, &outcomes()));
+        let lines: Vec<&str> = tap.lines().collect();
+        assert_eq!(lines[..3], ["TAP version 13", "1..2", "ok 1 - alice edits"]);
+        assert_eq!(lines[3], "not ok 2 - bob <reboots> & \"logs\" \\#1");
+        assert_eq!(lines[4..7], ["  ---", "  decision:", "    expected: \"permit\""]);
+        assert_eq!(lines.last(), Some(&"  ..."));
+    }
+
+    #[test]
+    fn junit_report_escapes_xml() {
+        let xml = report(|out| print_junit(out, "suites/<a&b>.yaml", &outcomes()));
+        assert!(xml.contains(r#"<testsuites name="nacm-validator" tests="2" failures="1">"#));
+        assert!(xml.contains(r#"<testsuite name="suites/&lt;a&amp;b&gt;.yaml" tests="2" failures="1">"#));
+        assert!(xml.contains(r#"<testcase name="alice edits" classname="nacm"/>"#));
+        assert!(xml.contains(r#"<testcase name="bob &lt;reboots&gt; &amp; &quot;logs&quot; #1" classname="nacm">"#));
+        assert!(xml.contains(
+            r#"<failure message="decision: expected permit, got deny">decision: expected permit, got deny
+rule: expected a/b, got default policy</failure>"#
+        ));
+        assert_eq!(xml_escape("it's"), "it&apos;s");
+        assert!(!xml.contains("<reboots>"));
+    }
+}
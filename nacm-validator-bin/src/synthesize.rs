@@ -0,0 +1,138 @@
+//! # Policy Synthesis
+//!
+//! Implements the `synthesize` subcommand: proposes a least-privilege rule
+//! list from a corpus of observed requests and prints it as XML.
+//!
+//! ```bash
+//! # Permit rules for the oper group, learned from bob's and carol's audit trail
+//! nacm-validator synthesize audit.jsonl --group oper --user bob --user carol
+//!
+//! # A complete configuration with deny defaults, ready for --config
+//! nacm-validator synthesize audit.jsonl --group oper --user bob --standalone > oper.xml
+//! ```
+//!
+//! ## Corpus
+//!
+//! Any `--input` format: NDJSON, a JSON array, YAML or CSV, so an
+//! `--audit-log` file can be used as is. Records with a `"decision":"deny"`
+//! field (audit log entries of denials) are skipped. With `--config`, only
+//! requests that the current configuration permits are used.
+//!
+//! ## Exit Codes
+//!
+//! - 0: the rule list was written
+//! - 2: the corpus has unreadable requests (the rule list is still written),
+//!   or the input could not be read
+
+use crate::batch::{self, InputFormat};
+use clap::Args;
+use nacm_validator::mutation::Position;
+use nacm_validator::synthesize::{synthesize_rule_list, SynthesisOptions};
+use nacm_validator::{NacmConfig, OwnedAccessRequest, RuleEffect};
+use std::path::PathBuf;
+
+/// Arguments for the `synthesize` subcommand
+#[derive(Args)]
+pub struct SynthesizeArgs {
+    /// Observed requests: NDJSON (e.g. an audit log), JSON array, YAML or CSV (use "-" for stdin)
+    pub corpus: PathBuf,
+
+    /// Group the rule list applies to (repeatable)
+    #[arg(long, required = true)]
+    pub group: Vec<String>,
+
+    /// Only learn from requests of this user (repeatable; default: all users)
+    #[arg(long)]
+    pub user: Vec<String>,
+
+    /// Name of the rule list
+    #[arg(long, default_value = "synthesized")]
+    pub name: String,
+
+    /// Cut data paths longer than this many steps to a `prefix/*` rule (0 = whole modules)
+    #[arg(long, value_name = "STEPS")]
+    pub path_depth: Option<usize>,
+
+    /// Print a complete configuration with deny defaults instead of only the rule list
+    ///
+    /// Each `--group` is defined with the `--user` names as members.
+    #[arg(long)]
+    pub standalone: bool,
+
+    /// Force the format of the corpus instead of auto-detecting it
+    #[arg(long)]
+    pub corpus_format: Option<InputFormat>,
+}
+
+/// Learn the rule list and print it
+///
+/// ## Parameters
+///
+/// * `config` - Current configuration, if given: only requests it permits are learned
+/// * `args` - Corpus and synthesis options
+/// * `verbose` - Report the number of requests used to stderr
+///
+/// ## Returns
+///
+/// * `Ok(i32)` - Exit code (see the module documentation)
+/// * `Err(String)` - The corpus could not be read
+pub fn run(config: Option<&NacmConfig>, args: &SynthesizeArgs, verbose: bool) -> Result<i32, String> {
+    let mut requests = Vec::new();
+    let mut errors = 0;
+    for record in batch::read_input(&args.corpus, args.corpus_format)? {
+        let value = match record.value {
+            Ok(value) => value,
+            Err(error) => {
+                eprintln!("{}: ERROR {}", record.line, error);
+                errors += 1;
+                continue;
+            }
+        };
+        // Audit logs also record denials, which must not be learned
+        if value.get("decision").and_then(|d| d.as_str()) == Some("deny") {
+            continue;
+        }
+        match serde_json::from_value::<OwnedAccessRequest>(value) {
+            Ok(request) => requests.push(request),
+            Err(e) => {
+                eprintln!("{}: ERROR Invalid request: {}", record.line, e);
+                errors += 1;
+            }
+        }
+    }
+
+    let observed = requests.len();
+    requests.retain(|request| args.user.is_empty() || args.user.contains(&request.user));
+    if let Some(config) = config {
+        requests.retain(|request| config.validate(&request.as_request()).effect == RuleEffect::Permit);
+    }
+    if verbose {
+        eprintln!("Learning from {} of {} requests", requests.len(), observed);
+    }
+
+    let options = SynthesisOptions {
+        path_depth: args.path_depth,
+    };
+    let rule_list = synthesize_rule_list(&args.name, &args.group, &requests, &options);
+
+    if args.standalone {
+        let mut builder = NacmConfig::builder()
+            .read_default(RuleEffect::Deny)
+            .write_default(RuleEffect::Deny)
+            .exec_default(RuleEffect::Deny)
+            .cmd_read_default(RuleEffect::Deny)
+            .cmd_exec_default(RuleEffect::Deny);
+        for group in &args.group {
+            builder = builder.group(group, &args.user);
+        }
+        let mut standalone = builder.build().map_err(|e| e.to_string())?;
+        standalone
+            .insert_rule_list(rule_list, Position::Last)
+            .map_err(|e| e.to_string())?;
+        print!("{}", standalone.to_xml());
+    } else {
+        print!("{}", rule_list.to_xml());
+    }
+
+    Ok(if errors > 0 { 2 } else { 0 })
+}
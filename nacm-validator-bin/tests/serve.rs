@@ -0,0 +1,383 @@
+//! Integration tests for the `serve` subcommand over a Unix domain socket
+//!
+//! Each test starts the real binary on a socket in its own temporary
+//! directory and talks the newline-delimited JSON protocol to it.
+
+#![cfg(unix)]
+
+use serde_json::Value;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A policy whose only setting is the exec default
+fn policy(exec_default: &str) -> String {
+    format!(
+        r#"<config xmlns="http://tail-f.com/ns/config/1.0">
+             <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+               <exec-default>{}</exec-default>
+             </nacm>
+           </config>"#,
+        exec_default
+    )
+}
+
+/// A policy permitting alice's edit-config only on Saturdays from `network`
+fn saturday_policy(network: &str) -> String {
+    format!(
+        r#"<config xmlns="http://tail-f.com/ns/config/1.0">
+             <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+               <exec-default>deny</exec-default>
+               <groups><group><name>admin</name><user-name>alice</user-name></group></groups>
+               <rule-list>
+                 <name>admin-acl</name>
+                 <group>admin</group>
+                 <rule>
+                   <name>saturday-edit</name>
+                   <rpc-name>edit-config</rpc-name>
+                   <action>permit</action>
+                   <condition xmlns="https://github.com/etnt/nacm-validator/condition">
+                     <weekday>saturday</weekday>
+                     <attribute><name>source-ip</name><value>{}</value></attribute>
+                   </condition>
+                 </rule>
+               </rule-list>
+             </nacm>
+           </config>"#,
+        network
+    )
+}
+
+/// 2024-06-01 was a Saturday
+const SATURDAY: &str = "2024-06-01T03:00:00Z";
+
+const EXEC_REQUEST: &str = r#"{"user":"alice","operation":"exec","rpc":"edit-config"}"#;
+
+/// Create an empty temporary directory holding `config` as nacm.xml
+fn temp_dir(name: &str, config: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nacm-serve-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("nacm.xml"), config).unwrap();
+    dir
+}
+
+fn server_command(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_nacm-validator"));
+    command
+        .arg("--config")
+        .arg(dir.join("nacm.xml"))
+        .args(["serve", "--socket"])
+        .arg(dir.join("nacm.sock"))
+        .stderr(Stdio::null());
+    command
+}
+
+/// A running server, killed and cleaned up on drop
+struct Server {
+    child: Child,
+    dir: PathBuf,
+}
+
+impl Server {
+    fn start(name: &str, config: &str, extra_args: &[&str]) -> Server {
+        let dir = temp_dir(name, config);
+        Server::spawn(dir, extra_args)
+    }
+
+    /// Start a server on `dir/nacm.sock` with the configuration `dir/nacm.xml`
+    fn spawn(dir: PathBuf, extra_args: &[&str]) -> Server {
+        Server::spawn_command(server_command(&dir).args(extra_args), dir)
+    }
+
+    /// Start a server from a command built by `server_command(&dir)`
+    fn spawn_command(command: &mut Command, dir: PathBuf) -> Server {
+        let child = command.spawn().unwrap();
+        let server = Server { child, dir };
+
+        // Wait for the socket to accept connections
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while UnixStream::connect(server.socket()).is_err() {
+            assert!(Instant::now() < deadline, "server did not start");
+            thread::sleep(Duration::from_millis(20));
+        }
+        server
+    }
+
+    fn socket(&self) -> PathBuf {
+        self.dir.join("nacm.sock")
+    }
+
+    fn config(&self) -> PathBuf {
+        self.dir.join("nacm.xml")
+    }
+
+    fn connect(&self) -> Client {
+        Client::new(UnixStream::connect(self.socket()).unwrap())
+    }
+
+    /// Forward the server's stderr lines to a channel, read on another thread
+    /// so the pipe never fills; the server must have been started with it piped
+    fn log(&mut self) -> Receiver<String> {
+        let stderr = BufReader::new(self.child.stderr.take().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stderr.lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+
+    fn reload(&self) {
+        let status = Command::new("kill")
+            .args(["-HUP", &self.child.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+struct Client {
+    writer: UnixStream,
+    reader: BufReader<UnixStream>,
+}
+
+impl Client {
+    fn new(stream: UnixStream) -> Client {
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        Client {
+            writer: stream.try_clone().unwrap(),
+            reader: BufReader::new(stream),
+        }
+    }
+
+    fn send(&mut self, line: &str) {
+        writeln!(self.writer, "{}", line).unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    fn decide(&mut self, line: &str) -> Value {
+        self.send(line);
+        self.receive()
+    }
+}
+
+/// Wait for the server to log a line containing `text`
+fn wait_for_log(log: &Receiver<String>, text: &str) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match log.recv_timeout(timeout) {
+            Ok(line) if line.contains(text) => return,
+            Ok(_) => {}
+            Err(_) => panic!("server did not log {:?}", text),
+        }
+    }
+}
+
+/// Ask for a decision on fresh connections until `expected` comes back
+fn wait_for_decision(server: &Server, expected: &str) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let response = server.connect().decide(EXEC_REQUEST);
+        if response["decision"] == expected {
+            return;
+        }
+        assert!(Instant::now() < deadline, "still {} after reload", response["decision"]);
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn answers_every_line_in_order() {
+    let server = Server::start("protocol", &policy("permit"), &[]);
+    let mut client = server.connect();
+
+    client.send(r#"{"id":"a","user":"alice","operation":"exec","rpc":"edit-config"}"#);
+    client.send("");
+    client.send("{not json");
+    client.send(r#"{"id":7,"user":"alice","operation":"fly"}"#);
+    client.send(r#"{"id":[1],"operation":"read"}"#);
+
+    let permit = client.receive();
+    assert_eq!(permit["line"], 1);
+    assert_eq!(permit["id"], "a");
+    assert_eq!(permit["decision"], "permit");
+    assert_eq!(permit["rpc"], "edit-config");
+
+    // The blank line is skipped but still counted
+    let invalid = client.receive();
+    assert_eq!(invalid["line"], 3);
+    assert!(invalid.get("id").is_none());
+    assert!(invalid["error"].as_str().unwrap().starts_with("Invalid JSON"));
+
+    let bad_operation = client.receive();
+    assert_eq!(bad_operation["line"], 4);
+    assert_eq!(bad_operation["id"], 7);
+    assert!(bad_operation["error"].as_str().unwrap().contains("fly"));
+
+    let missing_user = client.receive();
+    assert_eq!(missing_user["line"], 5);
+    assert_eq!(missing_user["id"], serde_json::json!([1]));
+    assert!(missing_user["error"].as_str().unwrap().contains("user"));
+}
+
+#[test]
+fn connections_count_lines_separately() {
+    let server = Server::start("lines", &policy("deny"), &[]);
+    let mut first = server.connect();
+    let mut second = server.connect();
+
+    assert_eq!(first.decide(EXEC_REQUEST)["line"], 1);
+    assert_eq!(first.decide(EXEC_REQUEST)["line"], 2);
+    let response = second.decide(EXEC_REQUEST);
+    assert_eq!(response["line"], 1);
+    assert_eq!(response["decision"], "deny");
+}
+
+#[test]
+fn sighup_reloads_the_configuration() {
+    let server = Server::start("reload", &policy("permit"), &[]);
+    assert_eq!(server.connect().decide(EXEC_REQUEST)["decision"], "permit");
+
+    std::fs::write(server.config(), policy("deny")).unwrap();
+    server.reload();
+    wait_for_decision(&server, "deny");
+}
+
+#[test]
+fn failed_reload_keeps_the_previous_configuration() {
+    let dir = temp_dir("bad-reload", &policy("permit"));
+    let mut server = Server::spawn_command(server_command(&dir).stderr(Stdio::piped()), dir);
+    let log = server.log();
+
+    std::fs::write(server.config(), "<nacm>").unwrap();
+    server.reload();
+    // The good configuration may only be written once the bad one has been
+    // read, or the reload could pick up either
+    wait_for_log(&log, "Error reloading config");
+    let mut client = server.connect();
+    assert_eq!(client.decide(EXEC_REQUEST)["decision"], "permit");
+
+    std::fs::write(server.config(), policy("deny")).unwrap();
+    server.reload();
+    wait_for_decision(&server, "deny");
+}
+
+#[test]
+fn connection_limit_queues_further_clients() {
+    let server = Server::start("limit", &policy("permit"), &["--max-connections", "1"]);
+    // Server::start's readiness probe connected once; wait for that slot to free
+    thread::sleep(Duration::from_millis(200));
+
+    let mut first = server.connect();
+    assert_eq!(first.decide(EXEC_REQUEST)["decision"], "permit");
+
+    // The second client connects (backlog) but is not served yet
+    let mut second = server.connect();
+    second.send(EXEC_REQUEST);
+    second.reader.get_ref().set_read_timeout(Some(Duration::from_millis(300))).unwrap();
+    let mut line = String::new();
+    let error = second.reader.read_line(&mut line).unwrap_err();
+    assert!(matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut));
+
+    // Closing the first connection lets the second one through
+    drop(first);
+    second.reader.get_ref().set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    assert_eq!(second.receive()["decision"], "permit");
+}
+
+#[test]
+fn stale_socket_is_replaced() {
+    let dir = temp_dir("stale", &policy("permit"));
+    drop(UnixListener::bind(dir.join("nacm.sock")).unwrap());
+    assert!(dir.join("nacm.sock").exists());
+
+    let server = Server::spawn(dir, &[]);
+    assert_eq!(server.connect().decide(EXEC_REQUEST)["decision"], "permit");
+}
+
+#[test]
+fn regular_file_at_socket_path_is_kept() {
+    let dir = temp_dir("file", &policy("permit"));
+    std::fs::write(dir.join("nacm.sock"), "keep me").unwrap();
+
+    let status = server_command(&dir).status().unwrap();
+    assert!(!status.success());
+    assert_eq!(std::fs::read_to_string(dir.join("nacm.sock")).unwrap(), "keep me");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn conditions_see_the_at_and_attribute_options() {
+    let args = ["--at", SATURDAY, "--attribute", "source-ip=10.1.2.3"];
+    let server = Server::start("conditions", &saturday_policy("10.0.0.0/8"), &args);
+    let mut client = server.connect();
+    assert_eq!(client.decide(EXEC_REQUEST)["decision"], "permit");
+
+    // A request's own time and attributes take precedence
+    let monday = r#"{"user":"alice","operation":"exec","rpc":"edit-config","at":"2024-06-03T03:00:00Z"}"#;
+    assert_eq!(client.decide(monday)["decision"], "deny");
+    let elsewhere = r#"{"user":"alice","operation":"exec","rpc":"edit-config","attributes":{"source-ip":"192.0.2.1"}}"#;
+    assert_eq!(client.decide(elsewhere)["decision"], "deny");
+
+    let invalid = client.decide(r#"{"user":"alice","operation":"exec","at":"saturday"}"#);
+    assert!(invalid["error"].as_str().unwrap().contains("RFC 3339"));
+}
+
+#[test]
+fn tcp_peer_address_is_the_source_ip() {
+    let dir = temp_dir("peer", &saturday_policy("127.0.0.0/8"));
+    let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let child = Command::new(env!("CARGO_BIN_EXE_nacm-validator"))
+        .arg("--config")
+        .arg(dir.join("nacm.xml"))
+        .args(["serve", "--listen", &addr.to_string(), "--at", SATURDAY])
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let _server = Server { child, dir };
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let stream = loop {
+        match TcpStream::connect(addr) {
+            Ok(stream) => break stream,
+            Err(_) => assert!(Instant::now() < deadline, "server did not start"),
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut decide = |line: &str| -> Value {
+        writeln!(writer, "{}", line).unwrap();
+        let mut response = String::new();
+        reader.read_line(&mut response).unwrap();
+        serde_json::from_str(&response).unwrap()
+    };
+
+    assert_eq!(decide(EXEC_REQUEST)["decision"], "permit");
+    let elsewhere = r#"{"user":"alice","operation":"exec","rpc":"edit-config","attributes":{"source-ip":"192.0.2.1"}}"#;
+    assert_eq!(decide(elsewhere)["decision"], "deny");
+}
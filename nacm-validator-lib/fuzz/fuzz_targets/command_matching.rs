@@ -0,0 +1,70 @@
+//! Fuzz command and context matching (Tail-f ACM)
+//!
+//! The input is up to four newline-separated fields: a command pattern, a
+//! command, a context pattern and a context name. Missing fields leave the
+//! command equal to the pattern and the context out. A single command rule
+//! is checked against the command; besides not panicking, whether it
+//! matches must not depend on its effect, and a rule without a context must
+//! match its own command, as `*` matches every command.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nacm_validator::{AccessRequest, NacmConfig, Operation, RequestContext, RuleEffect};
+
+/// A policy with one command rule of `effect` and the opposite defaults
+fn policy(pattern: &str, context_pattern: Option<&str>, effect: RuleEffect) -> Option<NacmConfig> {
+    let default = match effect {
+        RuleEffect::Permit => RuleEffect::Deny,
+        RuleEffect::Deny => RuleEffect::Permit,
+    };
+    NacmConfig::builder()
+        .cmd_read_default(default)
+        .cmd_exec_default(default)
+        .group("oper", ["bob"])
+        .rule_list("oper-acl", ["oper"], |rl| {
+            let rl = match effect {
+                RuleEffect::Permit => rl.permit_command(pattern),
+                RuleEffect::Deny => rl.deny_command(pattern),
+            };
+            match context_pattern {
+                Some(context_pattern) => rl.context(context_pattern),
+                None => rl,
+            }
+        })
+        .build()
+        .ok()
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut fields = text.splitn(4, '\n');
+    let pattern = fields.next().unwrap_or_default();
+    let command = fields.next().unwrap_or(pattern);
+    let context_pattern = fields.next();
+    let context: Option<RequestContext> = fields.next().and_then(|name| name.parse().ok());
+
+    let (Some(permit), Some(deny)) = (
+        policy(pattern, context_pattern, RuleEffect::Permit),
+        policy(pattern, context_pattern, RuleEffect::Deny),
+    ) else {
+        return;
+    };
+
+    for operation in [Operation::Read, Operation::Exec] {
+        let mut request = AccessRequest::builder().user("bob").op(operation).command(command);
+        if let Some(context) = &context {
+            request = request.context(context);
+        }
+        let request = request.build();
+        let permitted = permit.validate(&request).effect == RuleEffect::Permit;
+        let denied = deny.validate(&request).effect == RuleEffect::Deny;
+        // Either the rule matches on both sides or the defaults decide on both
+        assert_eq!(permitted, denied, "{:?} {:?} {:?}", pattern, command, context_pattern);
+        if context_pattern.is_none() && (pattern == command || pattern == "*") {
+            assert!(permitted, "{:?} {:?}", pattern, command);
+        }
+    }
+});
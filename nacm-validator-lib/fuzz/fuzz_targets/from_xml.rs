@@ -0,0 +1,58 @@
+//! Fuzz `NacmConfig::from_xml` with arbitrary text
+//!
+//! Configurations are pushed by operators and may come from anywhere, so
+//! parsing must fail with an error, never panic. A configuration that does
+//! parse must survive a round trip through `to_xml`, and every rule in it
+//! must be usable for decisions.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nacm_validator::{AccessRequest, NacmConfig, Operation};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(xml) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(config) = NacmConfig::from_xml(xml) else {
+        return;
+    };
+
+    let written = config.to_xml();
+    let reloaded = NacmConfig::from_xml(&written).expect("to_xml output reads back");
+    assert_eq!(reloaded.to_xml(), written, "to_xml is stable across a round trip");
+
+    // Requests built from the strings each rule mentions, for each member
+    let users: Vec<&str> = config
+        .groups
+        .values()
+        .flat_map(|group| group.users.iter().map(String::as_str))
+        .chain(["nobody"])
+        .collect();
+    for rule_list in &config.rule_lists {
+        for user in &users {
+            for rule in &rule_list.rules {
+                for operation in [Operation::Read, Operation::Exec] {
+                    let request = AccessRequest {
+                        user,
+                        module_name: rule.module_name.as_deref(),
+                        rpc_name: rule.rpc_name.as_deref(),
+                        operation,
+                        path: rule.path.as_deref(),
+                        context: None,
+                        command: None,
+                    };
+                    assert_eq!(config.validate(&request), reloaded.validate(&request));
+                }
+            }
+            for rule in &rule_list.command_rules {
+                let request = AccessRequest::builder()
+                    .user(user)
+                    .op(Operation::Exec)
+                    .command(rule.command.as_deref().unwrap_or(""))
+                    .build();
+                assert_eq!(config.validate(&request), reloaded.validate(&request));
+            }
+        }
+    }
+});
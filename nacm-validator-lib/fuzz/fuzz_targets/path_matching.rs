@@ -0,0 +1,64 @@
+//! Fuzz data path matching
+//!
+//! The input is a rule path and a request path, separated by the first
+//! newline (without one, the same text is used for both). A single rule
+//! with that path is checked against the request path for data and action
+//! access, which also splits the path into ancestor steps. Besides not
+//! panicking, whether the rule matches data access must not depend on its
+//! effect, and a rule must match its own path, as the root path matches
+//! every path. Actions are left at not panicking: they also need read
+//! access to their ancestors, so both effects can end in a denial.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nacm_validator::{AccessRequest, NacmConfig, Operation, RuleEffect};
+
+/// A policy with one rule of `effect` for `path` and the opposite defaults
+fn policy(path: &str, effect: RuleEffect) -> Option<NacmConfig> {
+    let default = match effect {
+        RuleEffect::Permit => RuleEffect::Deny,
+        RuleEffect::Deny => RuleEffect::Permit,
+    };
+    NacmConfig::builder()
+        .read_default(default)
+        .write_default(default)
+        .exec_default(default)
+        .group("oper", ["bob"])
+        .rule_list("oper-acl", ["oper"], |rl| match effect {
+            RuleEffect::Permit => rl.permit().path(path),
+            RuleEffect::Deny => rl.deny().path(path),
+        })
+        .build()
+        .ok()
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (rule_path, request_path) = text.split_once('\n').unwrap_or((text, text));
+
+    let (Some(permit), Some(deny)) = (policy(rule_path, RuleEffect::Permit), policy(rule_path, RuleEffect::Deny))
+    else {
+        return;
+    };
+
+    for operation in [Operation::Read, Operation::Update, Operation::Exec] {
+        let request = AccessRequest::builder()
+            .user("bob")
+            .op(operation.clone())
+            .path(request_path)
+            .build();
+        let permitted = permit.validate(&request).effect == RuleEffect::Permit;
+        let denied = deny.validate(&request).effect == RuleEffect::Deny;
+        if operation == Operation::Exec {
+            continue;
+        }
+        // Either the rule matches on both sides or the defaults decide on both
+        assert_eq!(permitted, denied, "{:?} {:?} {:?}", rule_path, request_path, operation);
+        if rule_path == request_path || rule_path == "/" {
+            assert!(permitted, "{:?} {:?} {:?}", rule_path, request_path, operation);
+        }
+    }
+});
@@ -0,0 +1,683 @@
+//! # Conflict and Redundancy Analysis
+//!
+//! Finds pairs of rules that interact in ways a policy author may not have
+//! intended, each with a concrete request that demonstrates the problem.
+//!
+//! Every rule selects a [`MatchSpace`]: the operations, context, module, RPC
+//! and path pattern (or, for command rules, command pattern) of the
+//! requests it matches. [`NacmConfig::analyze`] compares the spaces of every
+//! pair of rules (command rules only with command rules) in precedence
+//! order and reports a [`Finding`] when:
+//!
+//! - **Conflict**: two rules in *different* rule lists have opposite
+//!   effects, their spaces overlap, and some user is subject to both rule
+//!   lists. Such a user gets the effect of whichever rule list comes first,
+//!   while a user in only one of the groups gets that list's effect, so the
+//!   outcome depends on group membership and rule-list order.
+//! - **Shadowed**: an earlier rule with the opposite effect matches every
+//!   request the later rule matches, for every user the later rule applies
+//!   to, so the later rule can never decide anything.
+//! - **Redundant**: as shadowed, but both rules have the same effect, so
+//!   the later rule can be removed without changing any decision.
+//!
+//! A later rule is reported as shadowed or redundant only once, against the
+//! first rule that covers it. Two rules with opposite effects in the *same*
+//! rule list that merely overlap are not reported: ordering a narrow rule
+//! before a broad one is how exceptions are written.
+//!
+//! ## Counterexamples
+//!
+//! Each finding carries a [`counterexample`](Finding::counterexample): a
+//! request matched by both rules, for a configured user both rule lists
+//! apply to (any name will do when both lists apply to `*`). It is found by
+//! trying requests built from the values the two rules mention and keeping
+//! the first one both rules match, so the same matching code that makes
+//! decisions also confirms the overlap. The [`decision`](Finding::decision)
+//! for the counterexample shows which rule wins; for conflicts it is always
+//! the [`first`](Finding::first) rule, otherwise the conflict is masked by
+//! an even earlier rule and not reported.
+//!
+//! ## Conditional Rules
+//!
+//! A rule with a [condition](crate::condition) does not always apply, so it
+//! never shadows or makes redundant a later rule. Otherwise it is compared
+//! like any other rule, and counterexamples are decided as if every
+//! condition held. Conditions are not compared with each other, so a
+//! conflict between two conditional rules is reported even when the two
+//! conditions never hold at the same time.
+//!
+//! Group membership is taken from the configuration. Rule lists whose
+//! groups have no configured members (for example groups that only come
+//! from external authentication) produce no findings, since no request can
+//! demonstrate them.
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::{NacmConfig, Operation, RuleEffect};
+//! use nacm_validator::analysis::FindingKind;
+//!
+//! let config = NacmConfig::builder()
+//!     .group("oper", ["bob"])
+//!     .group("audit", ["bob", "carol"])
+//!     .rule_list("oper-acl", ["oper"], |rl| {
+//!         rl.deny().name("no-nacm").module("ietf-netconf-acm")
+//!             .permit().name("read-nacm").module("ietf-netconf-acm").ops([Operation::Read])
+//!     })
+//!     .rule_list("audit-acl", ["audit"], |rl| rl.permit().name("read-all").ops([Operation::Read]))
+//!     .build()
+//!     .unwrap();
+//!
+//! let findings = config.analyze();
+//! assert_eq!(findings.len(), 2);
+//!
+//! // read-nacm can never match: no-nacm comes first and covers it
+//! assert_eq!(findings[0].kind, FindingKind::Shadowed);
+//! assert_eq!(findings[0].second.to_string(), "oper-acl/read-nacm");
+//!
+//! // bob is in both groups, so oper-acl's deny beats audit-acl's permit for him
+//! let conflict = &findings[1];
+//! assert_eq!(conflict.kind, FindingKind::Conflict);
+//! assert_eq!(conflict.first.to_string(), "oper-acl/no-nacm");
+//! assert_eq!(conflict.counterexample.user, "bob");
+//! assert_eq!(conflict.counterexample.module_name.as_deref(), Some("ietf-netconf-acm"));
+//! assert_eq!(conflict.decision.result.effect, RuleEffect::Deny);
+//! ```
+
+use crate::condition::Environment;
+use crate::{
+    ContextPattern, Decision, MatchedRule, NacmCommandRule, NacmConfig, NacmRule, NacmRuleList, Operation,
+    OwnedAccessRequest, RequestContext, RuleEffect, DATA_OPERATIONS,
+};
+use std::collections::{BTreeSet, HashSet};
+
+/// User name for counterexamples when both rule lists apply to `*` and no
+/// user is configured
+const ANY_USER: &str = "anyone";
+
+/// What a rule selects besides operations and context
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target<'a> {
+    /// A data node or protocol operation rule
+    Data {
+        /// Required module (`None` = any)
+        module: Option<&'a str>,
+        /// Required RPC name (`None` or `*` = any)
+        rpc: Option<&'a str>,
+        /// Path pattern: exact, `prefix/*`, or `/` (`None` = any)
+        path: Option<&'a str>,
+    },
+    /// A command rule (Tail-f ACM)
+    Command {
+        /// Command pattern: exact, `prefix *`, or `*` (`None` = any)
+        command: Option<&'a str>,
+    },
+}
+
+/// The set of requests one rule matches
+///
+/// A product of independent dimensions, mirroring rule evaluation: a
+/// request is in the space when it is in every dimension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchSpace<'a> {
+    /// Operations the rule covers, in NACM order; all five for a rule
+    /// without `access-operations`
+    pub operations: Vec<Operation>,
+    /// Context pattern (`None` = any context, or none)
+    pub context: Option<&'a ContextPattern>,
+    /// Module, RPC and path, or command
+    pub target: Target<'a>,
+}
+
+impl<'a> From<&'a NacmRule> for MatchSpace<'a> {
+    fn from(rule: &'a NacmRule) -> Self {
+        MatchSpace {
+            operations: expand(&rule.access_operations),
+            context: rule.context.as_ref(),
+            target: Target::Data {
+                module: rule.module_name.as_deref(),
+                rpc: rule.rpc_name.as_deref(),
+                path: rule.path.as_deref(),
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a NacmCommandRule> for MatchSpace<'a> {
+    fn from(rule: &'a NacmCommandRule) -> Self {
+        MatchSpace {
+            operations: expand(&rule.access_operations),
+            context: rule.context.as_ref(),
+            target: Target::Command {
+                command: rule.command.as_deref(),
+            },
+        }
+    }
+}
+
+impl MatchSpace<'_> {
+    /// Whether every request in `other` is also in this space
+    ///
+    /// Conservative: `false` may be returned for glob patterns that do
+    /// cover each other in ways that are hard to tell, never the reverse.
+    pub fn covers(&self, other: &MatchSpace) -> bool {
+        other.operations.iter().all(|op| self.operations.contains(op))
+            && context_covers(self.context, other.context)
+            && match (self.target, other.target) {
+                (
+                    Target::Data { module, rpc, path },
+                    Target::Data {
+                        module: other_module,
+                        rpc: other_rpc,
+                        path: other_path,
+                    },
+                ) => {
+                    (module.is_none() || module == other_module)
+                        && (matches!(rpc, None | Some("*")) || rpc == other_rpc)
+                        && path_covers(path, other_path)
+                }
+                (Target::Command { command }, Target::Command { command: other_command }) => {
+                    command_covers(command, other_command)
+                }
+                _ => false,
+            }
+    }
+}
+
+/// How two rules interact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FindingKind {
+    /// Opposite effects in different rule lists for a user in both
+    Conflict,
+    /// The later rule never decides, and would decide differently
+    Shadowed,
+    /// The later rule never decides, and would decide the same
+    Redundant,
+}
+
+/// Formats as "conflict", "shadowed" or "redundant"
+impl std::fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            FindingKind::Conflict => "conflict",
+            FindingKind::Shadowed => "shadowed",
+            FindingKind::Redundant => "redundant",
+        })
+    }
+}
+
+/// One problem found by [`NacmConfig::analyze`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding<'a> {
+    /// What kind of interaction this is
+    pub kind: FindingKind,
+    /// The rule with higher precedence
+    pub first: MatchedRule<'a>,
+    /// Effect of the first rule
+    pub first_effect: RuleEffect,
+    /// The rule with lower precedence
+    pub second: MatchedRule<'a>,
+    /// Effect of the second rule
+    pub second_effect: RuleEffect,
+    /// A request matched by both rules that demonstrates the finding
+    pub counterexample: OwnedAccessRequest,
+    /// The decision for the counterexample
+    pub decision: Decision<'a>,
+}
+
+/// A rule or command rule together with where it sits in the configuration
+struct Entry<'a> {
+    rule_list: &'a NacmRuleList,
+    name: &'a str,
+    effect: RuleEffect,
+    order: u32,
+    space: MatchSpace<'a>,
+    rule: RuleRef<'a>,
+    /// Whether the rule has a condition, so that it does not always apply
+    conditional: bool,
+}
+
+impl<'a> Entry<'a> {
+    fn matched(&self) -> MatchedRule<'a> {
+        MatchedRule {
+            rule_list: &self.rule_list.name,
+            rule: self.name,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum RuleRef<'a> {
+    Data(&'a NacmRule),
+    Command(&'a NacmCommandRule),
+}
+
+impl NacmConfig {
+    /// Find conflicting, shadowed and redundant rules
+    ///
+    /// See the [module documentation](crate::analysis) for what is
+    /// reported. Analysis does not count towards the
+    /// [statistics](crate::stats).
+    ///
+    /// # Returns
+    ///
+    /// The findings ordered by the position of their second rule, data rules
+    /// before command rules; for one second rule, by the position of the
+    /// first rule.
+    pub fn analyze(&self) -> Vec<Finding<'_>> {
+        let mut data = Vec::new();
+        let mut commands = Vec::new();
+        for rule_list in &self.rule_lists {
+            for rule in &rule_list.rules {
+                data.push(Entry {
+                    rule_list,
+                    name: &rule.name,
+                    effect: rule.effect,
+                    order: rule.order,
+                    space: MatchSpace::from(rule),
+                    rule: RuleRef::Data(rule),
+                    conditional: rule.condition.is_some(),
+                });
+            }
+            for rule in &rule_list.command_rules {
+                commands.push(Entry {
+                    rule_list,
+                    name: &rule.name,
+                    effect: rule.effect,
+                    order: rule.order,
+                    space: MatchSpace::from(rule),
+                    rule: RuleRef::Command(rule),
+                    conditional: rule.condition.is_some(),
+                });
+            }
+        }
+
+        let mut findings = Vec::new();
+        for mut entries in [data, commands] {
+            // Evaluation order; the sort is stable, like the one in evaluation
+            entries.sort_by_key(|entry| entry.order);
+            for (j, second) in entries.iter().enumerate() {
+                let mut covered = false;
+                for first in &entries[..j] {
+                    if let Some(finding) = self.compare(first, second, &mut covered) {
+                        findings.push(finding);
+                    }
+                }
+            }
+        }
+        findings
+    }
+
+    /// Compare two rules, `first` with higher precedence
+    ///
+    /// `covered` records whether an earlier rule already covers `second`.
+    fn compare<'a>(&'a self, first: &Entry<'a>, second: &Entry<'a>, covered: &mut bool) -> Option<Finding<'a>> {
+        let same_list = std::ptr::eq(first.rule_list, second.rule_list);
+        let covers = !*covered
+            && !first.conditional
+            && applies_to_all_of(first.rule_list, second.rule_list)
+            && first.space.covers(&second.space);
+
+        let kind = if covers {
+            if first.effect == second.effect {
+                FindingKind::Redundant
+            } else {
+                FindingKind::Shadowed
+            }
+        } else if !same_list && first.effect != second.effect {
+            FindingKind::Conflict
+        } else {
+            return None;
+        };
+
+        let user = self.shared_user(first.rule_list, second.rule_list)?;
+        let mut fallback = None;
+        let env = Environment::assuming_conditions();
+        for request in candidates(&first.space, &second.space, &user) {
+            let access = request.as_request();
+            if !self.entry_matches(first, &access) || !self.entry_matches(second, &access) {
+                continue;
+            }
+            let decision = self.evaluate_with(&access, &env);
+            if decision.matched_rule == Some(first.matched()) {
+                *covered |= covers;
+                return Some(self.finding(kind, first, second, request, decision));
+            }
+            if kind != FindingKind::Conflict && fallback.is_none() {
+                fallback = Some((request, decision));
+            }
+        }
+
+        // A covered rule is dead even when a third rule decides the example
+        let (request, decision) = fallback?;
+        *covered = true;
+        Some(self.finding(kind, first, second, request, decision))
+    }
+
+    fn finding<'a>(
+        &'a self,
+        kind: FindingKind,
+        first: &Entry<'a>,
+        second: &Entry<'a>,
+        counterexample: OwnedAccessRequest,
+        decision: Decision<'a>,
+    ) -> Finding<'a> {
+        Finding {
+            kind,
+            first: first.matched(),
+            first_effect: first.effect,
+            second: second.matched(),
+            second_effect: second.effect,
+            counterexample,
+            decision,
+        }
+    }
+
+    fn entry_matches(&self, entry: &Entry, req: &crate::AccessRequest) -> bool {
+        match entry.rule {
+            RuleRef::Data(rule) => req.command.is_none() && self.rule_matches(rule, req),
+            RuleRef::Command(rule) => req.command.is_some() && self.command_rule_matches(rule, req),
+        }
+    }
+
+    /// A user both rule lists apply to, configured users in name order first
+    fn shared_user(&self, first: &NacmRuleList, second: &NacmRuleList) -> Option<String> {
+        let users: BTreeSet<&str> = self
+            .groups
+            .values()
+            .flat_map(|group| group.users.iter().map(String::as_str))
+            .collect();
+        let applies = |rule_list: &NacmRuleList, user: &str| {
+            rule_list.groups.iter().any(|group| {
+                group == "*" || self.groups.get(group).is_some_and(|g| g.users.iter().any(|u| u == user))
+            })
+        };
+
+        users
+            .into_iter()
+            .find(|user| applies(first, user) && applies(second, user))
+            .map(str::to_string)
+            .or_else(|| {
+                let any = |rule_list: &NacmRuleList| rule_list.groups.iter().any(|group| group == "*");
+                (any(first) && any(second)).then(|| ANY_USER.to_string())
+            })
+    }
+}
+
+/// Whether `first` applies to every user `second` applies to, by group names
+fn applies_to_all_of(first: &NacmRuleList, second: &NacmRuleList) -> bool {
+    std::ptr::eq(first, second)
+        || first.groups.iter().any(|group| group == "*")
+        || second.groups.iter().all(|group| group != "*" && first.groups.contains(group))
+}
+
+/// The operations a rule covers; none listed means all of them
+fn expand(operations: &HashSet<Operation>) -> Vec<Operation> {
+    DATA_OPERATIONS
+        .iter()
+        .filter(|op| operations.is_empty() || operations.contains(op))
+        .cloned()
+        .collect()
+}
+
+fn context_covers(outer: Option<&ContextPattern>, inner: Option<&ContextPattern>) -> bool {
+    match (outer, inner) {
+        (None, _) => true,
+        (Some(outer), _) if outer.matches_any() => true,
+        (Some(_), None) => false,
+        (Some(_), Some(inner)) if inner.matches_any() => false,
+        // Each inner glob, read as text, must be matched by an outer glob; a `?`
+        // matching an inner `*` would stand for one character only
+        (Some(outer), Some(inner)) => inner.alternatives.iter().all(|alt| {
+            outer.alternatives.iter().any(|outer_alt| {
+                crate::glob_matches(outer_alt, alt) && !(outer_alt.contains('?') && alt.contains('*'))
+            })
+        }),
+    }
+}
+
+fn path_covers(outer: Option<&str>, inner: Option<&str>) -> bool {
+    match (outer, inner) {
+        (None | Some("/"), _) => true,
+        (Some(_), None | Some("/")) => false,
+        (Some(outer), Some(inner)) => match outer.strip_suffix("/*") {
+            Some(prefix) => inner.strip_suffix("/*").unwrap_or(inner).starts_with(prefix),
+            None => outer == inner,
+        },
+    }
+}
+
+fn command_covers(outer: Option<&str>, inner: Option<&str>) -> bool {
+    match (outer, inner) {
+        (None | Some("*"), _) => true,
+        (Some(_), None | Some("*")) => false,
+        (Some(outer), Some(inner)) => match outer.strip_suffix('*') {
+            Some(prefix) => inner.strip_suffix('*').unwrap_or(inner).trim().starts_with(prefix.trim()),
+            None => outer == inner,
+        },
+    }
+}
+
+/// Requests built from the values two spaces mention, most specific first
+///
+/// Not all of them are in both spaces; the caller checks.
+fn candidates(first: &MatchSpace, second: &MatchSpace, user: &str) -> Vec<OwnedAccessRequest> {
+    let spaces = [first, second];
+
+    let mut contexts: Vec<Option<RequestContext>> = vec![None];
+    let alternatives = spaces
+        .iter()
+        .filter_map(|space| space.context)
+        .flat_map(|pattern| pattern.alternatives.iter());
+    for alt in alternatives {
+        for fill in ["", "x"] {
+            let name = alt.replace('*', fill).replace('?', "x");
+            if let Ok(context) = name.parse() {
+                push_unique(&mut contexts, Some(context));
+            }
+        }
+    }
+    for context in [RequestContext::NETCONF, RequestContext::CLI, RequestContext::WebUI] {
+        push_unique(&mut contexts, Some(context));
+    }
+
+    let mut requests = Vec::new();
+    match (first.target, second.target) {
+        (
+            Target::Data { module, rpc, path },
+            Target::Data {
+                module: other_module,
+                rpc: other_rpc,
+                path: other_path,
+            },
+        ) => {
+            let rpc = [rpc, other_rpc].into_iter().flatten().find(|rpc| *rpc != "*");
+            let mut paths = vec![None];
+            for pattern in [path, other_path].into_iter().flatten() {
+                match pattern.strip_suffix("/*") {
+                    Some(prefix) => {
+                        if !prefix.is_empty() {
+                            push_unique(&mut paths, Some(prefix.to_string()));
+                        }
+                        push_unique(&mut paths, Some(format!("{}/x", prefix)));
+                    }
+                    None if pattern == "/" => {}
+                    None => push_unique(&mut paths, Some(pattern.to_string())),
+                }
+            }
+
+            // Protocol operations are what an RPC rule is written for
+            let mut operations = first.operations.clone();
+            operations.retain(|op| second.operations.contains(op));
+            if rpc.is_some() {
+                operations.sort_by_key(|op| *op != Operation::Exec);
+            }
+
+            for operation in &operations {
+                for context in &contexts {
+                    for path in &paths {
+                        requests.push(OwnedAccessRequest {
+                            user: user.to_string(),
+                            module_name: module.or(other_module).map(str::to_string),
+                            rpc_name: rpc.map(str::to_string),
+                            operation: operation.clone(),
+                            path: path.clone(),
+                            context: context.clone(),
+                            command: None,
+                        });
+                    }
+                }
+            }
+        }
+        (Target::Command { command }, Target::Command { command: other_command }) => {
+            let mut commands = Vec::new();
+            for pattern in [command, other_command].into_iter().flatten() {
+                match pattern.strip_suffix('*') {
+                    Some(prefix) if !prefix.trim().is_empty() => {
+                        push_unique(&mut commands, prefix.trim().to_string());
+                        push_unique(&mut commands, format!("{} x", prefix.trim()));
+                    }
+                    Some(_) => {}
+                    None => push_unique(&mut commands, pattern.to_string()),
+                }
+            }
+            if commands.is_empty() {
+                commands.push("show".to_string());
+            }
+
+            let operations = first.operations.iter().filter(|op| second.operations.contains(op));
+            for operation in operations {
+                for context in &contexts {
+                    for command in &commands {
+                        requests.push(OwnedAccessRequest {
+                            user: user.to_string(),
+                            module_name: None,
+                            rpc_name: None,
+                            operation: operation.clone(),
+                            path: None,
+                            context: context.clone(),
+                            command: Some(command.clone()),
+                        });
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    requests
+}
+
+fn push_unique<T: PartialEq>(values: &mut Vec<T>, value: T) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> NacmConfig {
+        NacmConfig::builder()
+            .group("admin", ["alice", "bob"])
+            .group("oper", ["bob"])
+            .group("guest", ["carol"])
+            .rule_list("oper-acl", ["oper"], |rl| {
+                rl.deny().name("edit-config").rpc("edit-config").ops([Operation::Exec]).context("cli web*")
+                    .deny_command("reboot").name("reboot")
+            })
+            .rule_list("admin-acl", ["admin"], |rl| {
+                rl.permit().name("all")
+                    .permit().name("m").module("m").ops([Operation::Read])
+                    .permit_command("*").name("any-command")
+            })
+            .rule_list("guest-acl", ["guest"], |rl| {
+                rl.deny().name("no-a").path("/a/*")
+                    .permit().name("a-b").path("/a/b").ops([Operation::Read])
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn pattern(text: &str) -> ContextPattern {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn finds_conflicts_redundancy_and_shadowing() {
+        let summary: Vec<(FindingKind, String, String)> = policy()
+            .analyze()
+            .iter()
+            .map(|f| (f.kind, f.first.to_string(), f.second.to_string()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (FindingKind::Conflict, "oper-acl/edit-config".to_string(), "admin-acl/all".to_string()),
+                (FindingKind::Redundant, "admin-acl/all".to_string(), "admin-acl/m".to_string()),
+                (FindingKind::Shadowed, "guest-acl/no-a".to_string(), "guest-acl/a-b".to_string()),
+                (FindingKind::Conflict, "oper-acl/reboot".to_string(), "admin-acl/any-command".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn conflict_counterexample_reaches_both_rules() {
+        let config = policy();
+        let findings = config.analyze();
+
+        // Only bob is in both groups; the example uses a context the pattern accepts
+        let conflict = &findings[0];
+        let request = &conflict.counterexample;
+        assert_eq!(request.user, "bob");
+        assert_eq!(request.operation, Operation::Exec);
+        assert_eq!(request.rpc_name.as_deref(), Some("edit-config"));
+        assert_eq!(request.context, Some(RequestContext::CLI));
+        assert_eq!(conflict.decision.result.effect, RuleEffect::Deny);
+        assert_eq!(config.validate(&request.as_request()).effect, RuleEffect::Deny);
+
+        // The same request is permitted for a user only in the admin group
+        let alice = OwnedAccessRequest { user: "alice".to_string(), ..request.clone() };
+        assert_eq!(config.validate(&alice.as_request()).effect, RuleEffect::Permit);
+    }
+
+    #[test]
+    fn counterexamples_use_the_covered_values() {
+        let config = policy();
+        let findings = config.analyze();
+        // A prefix rule covers its own prefix path
+        assert_eq!(findings[2].counterexample.path.as_deref(), Some("/a/b"));
+        assert_eq!(findings[3].counterexample.command.as_deref(), Some("reboot"));
+    }
+
+    #[test]
+    fn disjoint_rules_have_no_findings() {
+        let clean = NacmConfig::builder()
+            .group("oper", ["bob"])
+            .rule_list("oper-acl", ["oper"], |rl| {
+                rl.permit().module("a").deny().module("b").permit_command("show *").deny_command("reboot")
+            })
+            .build()
+            .unwrap();
+        assert!(clean.analyze().is_empty());
+    }
+
+    #[test]
+    fn question_mark_does_not_cover_a_star() {
+        // Read as text, "we?" matches "we*", but "we*" also stands for "webui"
+        assert!(!context_covers(Some(&pattern("we?")), Some(&pattern("we*"))));
+        assert!(context_covers(Some(&pattern("we*")), Some(&pattern("we?"))));
+        assert!(context_covers(Some(&pattern("cli we?")), Some(&pattern("we?"))));
+
+        // So a rule for "we?" does not shadow a later one for "we*"
+        let config = NacmConfig::builder()
+            .group("oper", ["bob"])
+            .rule_list("oper-acl", ["oper"], |rl| {
+                rl.deny().name("narrow").module("m").context("we?")
+                    .permit().name("wide").module("m").context("we*")
+            })
+            .build()
+            .unwrap();
+        assert!(config.analyze().is_empty());
+    }
+}
@@ -0,0 +1,731 @@
+//! # Audit Logging
+//!
+//! Records access control decisions whose [`ValidationResult::should_log`]
+//! flag is set, as configured with the Tail-f ACM `log-if-*` settings.
+//!
+//! Decisions are turned into [`AuditRecord`]s and handed to an
+//! [`AuditSink`]. Three sinks are built in:
+//!
+//! - [`JsonLinesSink`] - one JSON object per line, to a file or any writer
+//! - [`SyslogSink`] - RFC 5424 messages to a local syslog socket (Unix only)
+//! - [`MemorySink`] - a bounded in-memory ring buffer, handy for tests
+//!
+//! The JSON field names match the request fields accepted by the CLI
+//! (`user`, `module`, `rpc`, `operation`, `path`, `context`, `command`), so
+//! an audit log can be fed back in as a batch of requests.
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::{NacmConfig, AccessRequest, Operation, RequestContext};
+//! use nacm_validator::audit::MemorySink;
+//!
+//! let xml = r#"
+//! <config xmlns="http://tail-f.com/ns/config/1.0">
+//!   <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+//!     <enable-nacm>true</enable-nacm>
+//!     <read-default>deny</read-default>
+//!     <write-default>deny</write-default>
+//!     <exec-default>deny</exec-default>
+//!     <log-if-default-deny xmlns="http://tail-f.com/yang/acm"/>
+//!     <groups>
+//!       <group>
+//!         <name>admin</name>
+//!         <user-name>alice</user-name>
+//!       </group>
+//!     </groups>
+//!     <rule-list>
+//!       <name>admin-acl</name>
+//!       <group>admin</group>
+//!       <rule>
+//!         <name>permit-all</name>
+//!         <action>permit</action>
+//!       </rule>
+//!     </rule-list>
+//!   </nacm>
+//! </config>"#;
+//! let config = NacmConfig::from_xml(xml).unwrap();
+//!
+//! let request = AccessRequest {
+//!     user: "mallory",
+//!     module_name: Some("ietf-interfaces"),
+//!     rpc_name: None,
+//!     operation: Operation::Read,
+//!     path: None,
+//!     context: Some(&RequestContext::NETCONF),
+//!     command: None,
+//! };
+//!
+//! let sink = MemorySink::new(100);
+//! let result = config.validate_and_audit(&request, &sink).unwrap();
+//! assert!(result.should_log);
+//! assert_eq!(sink.records()[0].user, "mallory");
+//! ```
+
+use crate::condition::Environment;
+use crate::{AccessRequest, Decision, NacmConfig, Operation, RequestContext, RuleEffect, ValidationResult};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A single audited access control decision
+///
+/// Owns all of its data so that it can outlive the request and the
+/// configuration that produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// When the decision was made
+    pub timestamp: SystemTime,
+    /// Username making the request
+    pub user: String,
+    /// YANG module name, if the request was module-specific
+    pub module: Option<String>,
+    /// RPC name, if the request was an RPC call
+    pub rpc: Option<String>,
+    /// Requested operation
+    pub operation: Operation,
+    /// Data path, if the request was path-specific
+    pub path: Option<String>,
+    /// Request context (NETCONF, CLI, WebUI, etc.)
+    pub context: Option<RequestContext>,
+    /// Command being executed, for command requests
+    pub command: Option<String>,
+    /// The access decision
+    pub decision: RuleEffect,
+    /// Rule list of the deciding rule (None = default policy)
+    pub rule_list: Option<String>,
+    /// Name of the deciding rule (None = default policy)
+    pub rule: Option<String>,
+}
+
+/// Serialized form of an `AuditRecord`, with request-compatible field names
+#[derive(Serialize)]
+struct JsonAuditRecord<'a> {
+    timestamp: String,
+    user: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    module: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rpc: Option<&'a str>,
+    operation: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<&'a str>,
+    decision: RuleEffect,
+    rule_list: Option<&'a str>,
+    rule: Option<&'a str>,
+}
+
+impl AuditRecord {
+    /// Build a record for a request and its decision, timestamped now
+    pub fn new(req: &AccessRequest, decision: &Decision) -> Self {
+        AuditRecord {
+            timestamp: SystemTime::now(),
+            user: req.user.to_string(),
+            module: req.module_name.map(str::to_string),
+            rpc: req.rpc_name.map(str::to_string),
+            operation: req.operation.clone(),
+            path: req.path.map(str::to_string),
+            context: req.context.cloned(),
+            command: req.command.map(str::to_string),
+            decision: decision.result.effect,
+            rule_list: decision.matched_rule.map(|m| m.rule_list.to_string()),
+            rule: decision.matched_rule.map(|m| m.rule.to_string()),
+        }
+    }
+
+    /// Serialize as a single-line JSON object
+    ///
+    /// The timestamp is RFC 3339 in UTC with millisecond precision.
+    /// Absent request fields are omitted; `rule_list` and `rule` are `null`
+    /// when the default policy decided.
+    pub fn to_json(&self) -> String {
+        let json = JsonAuditRecord {
+            timestamp: format_rfc3339(self.timestamp),
+            user: &self.user,
+            module: self.module.as_deref(),
+            rpc: self.rpc.as_deref(),
+            operation: self.operation.to_string(),
+            path: self.path.as_deref(),
+            context: self.context.as_ref().map(|c| c.to_string()),
+            command: self.command.as_deref(),
+            decision: self.decision,
+            rule_list: self.rule_list.as_deref(),
+            rule: self.rule.as_deref(),
+        };
+        // Serializing plain strings and enums cannot fail
+        serde_json::to_string(&json).expect("audit record serializes to JSON")
+    }
+
+    /// Format as an RFC 5424 syslog message
+    ///
+    /// Uses the `authpriv` facility, severity `notice` for permits and
+    /// `warning` for denials. The request details are carried as structured
+    /// data under the SD-ID `nacm@32473` (the documentation enterprise number
+    /// from RFC 5612), followed by a short human-readable message.
+    ///
+    /// # Arguments
+    ///
+    /// * `hostname` - HOSTNAME field, or `None` for the nil value `-`
+    /// * `app_name` - APP-NAME field
+    pub fn to_rfc5424(&self, hostname: Option<&str>, app_name: &str) -> String {
+        const FACILITY_AUTHPRIV: u8 = 10;
+        let severity = match self.decision {
+            RuleEffect::Permit => 5, // notice
+            RuleEffect::Deny => 4,   // warning
+        };
+        let decision = effect_name(self.decision);
+
+        let mut params = vec![
+            ("user", self.user.clone()),
+            ("operation", self.operation.to_string()),
+            ("decision", decision.to_string()),
+        ];
+        let optional = [
+            ("module", self.module.clone()),
+            ("rpc", self.rpc.clone()),
+            ("path", self.path.clone()),
+            ("context", self.context.as_ref().map(|c| c.to_string())),
+            ("command", self.command.clone()),
+            ("rule_list", self.rule_list.clone()),
+            ("rule", self.rule.clone()),
+        ];
+        params.extend(optional.into_iter().filter_map(|(name, value)| value.map(|v| (name, v))));
+
+        let structured_data: String = params
+            .iter()
+            .map(|(name, value)| format!(" {}=\"{}\"", name, escape_sd_value(value)))
+            .collect();
+
+        let rule = match (&self.rule_list, &self.rule) {
+            (Some(list), Some(rule)) => format!("{}/{}", list, rule),
+            _ => "default policy".to_string(),
+        };
+
+        format!(
+            "<{}>1 {} {} {} {} nacm-decision [nacm@32473{}] {} {} by {} ({})",
+            FACILITY_AUTHPRIV * 8 + severity,
+            format_rfc3339(self.timestamp),
+            hostname.unwrap_or("-"),
+            app_name,
+            std::process::id(),
+            structured_data,
+            decision,
+            self.operation,
+            self.user,
+            rule
+        )
+    }
+}
+
+/// Destination for audit records
+///
+/// Implementations take `&self` so that one sink can be shared between
+/// threads; sinks that write to a shared resource synchronize internally.
+pub trait AuditSink {
+    /// Record a single decision
+    fn record(&self, record: &AuditRecord) -> io::Result<()>;
+}
+
+/// Writes each record as one line of JSON
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::audit::JsonLinesSink;
+///
+/// // Any writer works; use `JsonLinesSink::open` to append to a file
+/// let sink = JsonLinesSink::new(Vec::new());
+/// ```
+pub struct JsonLinesSink<W: Write = File> {
+    writer: Mutex<W>,
+}
+
+impl JsonLinesSink<File> {
+    /// Open a file for appending, creating it if necessary
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonLinesSink::new(file))
+    }
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    /// Write records to an arbitrary writer
+    pub fn new(writer: W) -> Self {
+        JsonLinesSink { writer: Mutex::new(writer) }
+    }
+
+    /// Consume the sink and return the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<W: Write> AuditSink for JsonLinesSink<W> {
+    fn record(&self, record: &AuditRecord) -> io::Result<()> {
+        // Format first so the lock is only held for the write itself
+        let line = format!("{}\n", record.to_json());
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.write_all(line.as_bytes())?;
+        writer.flush()
+    }
+}
+
+/// Sends RFC 5424 messages to a local syslog daemon over a Unix datagram socket
+#[cfg(unix)]
+pub struct SyslogSink {
+    socket: std::os::unix::net::UnixDatagram,
+    hostname: Option<String>,
+    app_name: String,
+}
+
+#[cfg(unix)]
+impl SyslogSink {
+    /// Connect to the system log socket at `/dev/log`
+    pub fn new() -> io::Result<Self> {
+        Self::connect("/dev/log")
+    }
+
+    /// Connect to the syslog socket at `path`
+    pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(SyslogSink {
+            socket,
+            hostname: None,
+            app_name: "nacm-validator".to_string(),
+        })
+    }
+
+    /// Set the HOSTNAME field (the nil value `-` is sent by default)
+    pub fn with_hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Set the APP-NAME field (defaults to `nacm-validator`)
+    pub fn with_app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = app_name.into();
+        self
+    }
+}
+
+#[cfg(unix)]
+impl AuditSink for SyslogSink {
+    fn record(&self, record: &AuditRecord) -> io::Result<()> {
+        let message = record.to_rfc5424(self.hostname.as_deref(), &self.app_name);
+        self.socket.send(message.as_bytes()).map(|_| ())
+    }
+}
+
+/// Keeps the most recent records in memory
+///
+/// Once `capacity` records are held, each new record evicts the oldest.
+pub struct MemorySink {
+    capacity: usize,
+    records: Mutex<VecDeque<AuditRecord>>,
+}
+
+impl MemorySink {
+    /// Create a ring buffer holding at most `capacity` records
+    pub fn new(capacity: usize) -> Self {
+        MemorySink {
+            capacity,
+            records: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Copy of the buffered records, oldest first
+    pub fn records(&self) -> Vec<AuditRecord> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// Number of buffered records
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no records are buffered
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Remove all buffered records
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<AuditRecord>> {
+        // A poisoned lock only means another thread panicked mid-push; the queue is intact
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl AuditSink for MemorySink {
+    fn record(&self, record: &AuditRecord) -> io::Result<()> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        let mut records = self.lock();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+        Ok(())
+    }
+}
+
+impl NacmConfig {
+    /// Validate a request and audit the decision if it should be logged
+    ///
+    /// Same as [`validate`](Self::validate), but when the result has
+    /// `should_log` set an [`AuditRecord`] is written to `sink`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ValidationResult)` - The decision, audited if required
+    /// * `Err(io::Error)` - The sink failed; callers that must not act on
+    ///   unaudited decisions should treat this as a denial
+    pub fn validate_and_audit(&self, req: &AccessRequest, sink: &dyn AuditSink) -> io::Result<ValidationResult> {
+        self.decide_and_audit(req, sink).map(|decision| decision.result)
+    }
+
+    /// Like [`validate_and_audit`](Self::validate_and_audit), but also
+    /// returns the deciding rule as [`decide`](Self::decide) does
+    pub fn decide_and_audit(&self, req: &AccessRequest, sink: &dyn AuditSink) -> io::Result<Decision<'_>> {
+        self.decide_and_audit_with(req, &Environment::new(), sink)
+    }
+
+    /// Like [`decide_and_audit`](Self::decide_and_audit), but rule
+    /// conditions are checked in `env` as [`decide_with`](Self::decide_with) does
+    pub fn decide_and_audit_with(
+        &self,
+        req: &AccessRequest,
+        env: &Environment,
+        sink: &dyn AuditSink,
+    ) -> io::Result<Decision<'_>> {
+        let decision = self.decide_with(req, env);
+        if decision.result.should_log {
+            sink.record(&AuditRecord::new(req, &decision))?;
+        }
+        Ok(decision)
+    }
+}
+
+/// Lowercase name of a rule effect, as used in audit output
+fn effect_name(effect: RuleEffect) -> &'static str {
+    match effect {
+        RuleEffect::Permit => "permit",
+        RuleEffect::Deny => "deny",
+    }
+}
+
+/// Escape a structured data parameter value (RFC 5424 section 6.3.3)
+fn escape_sd_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Format a time as RFC 3339 in UTC with millisecond precision
+///
+/// Implemented directly to avoid a date/time dependency. Times before the
+/// Unix epoch are clamped to the epoch.
+fn format_rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let millis = since_epoch.subsec_millis();
+
+    let days = (secs / 86_400) as i64;
+    let secs_of_day = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        (secs_of_day % 3600) / 60,
+        secs_of_day % 60,
+        millis
+    )
+}
+
+/// Parse an RFC 3339 timestamp, such as those of [`AuditRecord`]s in JSON
+///
+/// Accepts `YYYY-MM-DDTHH:MM:SS` with optional fractional seconds and a
+/// `Z` or `+HH:MM`/`-HH:MM` offset. Times before the Unix epoch are
+/// rejected.
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::audit::parse_rfc3339;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let time = parse_rfc3339("2024-06-01T02:30:00Z").unwrap();
+/// assert_eq!(time, UNIX_EPOCH + Duration::from_secs(1_717_209_000));
+/// assert_eq!(parse_rfc3339("2024-06-01T04:30:00.000+02:00"), Ok(time));
+/// assert!(parse_rfc3339("2024-06-01 02:30").is_err());
+/// ```
+pub fn parse_rfc3339(text: &str) -> Result<SystemTime, String> {
+    let invalid = || format!("Invalid RFC 3339 time: {}", text);
+    let number = |range: std::ops::Range<usize>| -> Result<i64, String> {
+        let digits = text.get(range).filter(|d| d.bytes().all(|b| b.is_ascii_digit())).ok_or_else(invalid)?;
+        digits.parse().map_err(|_| invalid())
+    };
+
+    let separators = [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':')];
+    if text.len() < 20 || !separators.iter().all(|&(i, c)| text.as_bytes()[i].eq_ignore_ascii_case(&c)) {
+        return Err(invalid());
+    }
+    let (year, month, day) = (number(0..4)?, number(5..7)? as u32, number(8..10)? as u32);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+
+    // Optional fraction of a second, nanosecond precision
+    let mut rest = &text[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return Err(invalid());
+        }
+        nanos = fraction[..digits]
+            .bytes()
+            .chain(std::iter::repeat(b'0'))
+            .take(9)
+            .fold(0, |nanos, digit| nanos * 10 + u32::from(digit - b'0'));
+        rest = &fraction[digits..];
+    }
+
+    let offset = match rest.as_bytes() {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+            let offset = number(text.len() - 5..text.len() - 3)? * 3600 + number(text.len() - 2..text.len())? * 60;
+            if *sign == b'+' {
+                offset
+            } else {
+                -offset
+            }
+        }
+        _ => return Err(invalid()),
+    };
+
+    let days = days_from_civil(year, month, day);
+    if civil_from_days(days) != (year, month, day) || hour > 23 || minute > 59 || second > 60 {
+        return Err(invalid());
+    }
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    let secs = u64::try_from(secs).map_err(|_| format!("Time before 1970 is not supported: {}", text))?;
+    Ok(UNIX_EPOCH + std::time::Duration::new(secs, nanos))
+}
+
+/// Convert a (year, month, day) civil date to days since 1970-01-01
+///
+/// Howard Hinnant's `days_from_civil` algorithm, the inverse of
+/// [`civil_from_days`].
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400); // year of era [0, 399]
+    let mp = (i64::from(month) + 9) % 12; // month starting from March [0, 11]
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1; // day of year [0, 365]
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy; // day of era [0, 146096]
+    era * 146_097 + doe - 719_468
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) civil date
+///
+/// Howard Hinnant's `civil_from_days` algorithm for the proleptic
+/// Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097); // day of era [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365; // year of era [0, 399]
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // day of year [0, 365]
+    let mp = (5 * doy + 2) / 153; // month starting from March [0, 11]
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// bob may run `show *` quietly; a denied reboot is logged
+    fn policy() -> NacmConfig {
+        NacmConfig::from_xml(
+            r#"
+        <config xmlns="http://tail-f.com/ns/config/1.0">
+            <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+                <enable-nacm>true</enable-nacm>
+                <read-default>deny</read-default>
+                <write-default>deny</write-default>
+                <exec-default>deny</exec-default>
+                <groups>
+                    <group>
+                        <name>ops</name>
+                        <user-name>bob</user-name>
+                    </group>
+                </groups>
+                <rule-list>
+                    <name>ops</name>
+                    <group>ops</group>
+                    <cmdrule xmlns="http://tail-f.com/yang/acm">
+                        <name>deny-reboot</name>
+                        <command>reboot</command>
+                        <action>deny</action>
+                        <log-if-deny/>
+                    </cmdrule>
+                    <cmdrule xmlns="http://tail-f.com/yang/acm">
+                        <name>show</name>
+                        <command>show *</command>
+                        <action>permit</action>
+                    </cmdrule>
+                </rule-list>
+            </nacm>
+        </config>"#,
+        )
+        .unwrap()
+    }
+
+    fn reboot() -> AccessRequest<'static> {
+        AccessRequest {
+            user: "bob",
+            module_name: None,
+            rpc_name: None,
+            operation: Operation::Exec,
+            path: None,
+            context: Some(&RequestContext::CLI),
+            command: Some("reboot"),
+        }
+    }
+
+    /// The reboot denial with a fixed timestamp
+    fn reboot_record(config: &NacmConfig) -> AuditRecord {
+        let mut record = AuditRecord::new(&reboot(), &config.decide(&reboot()));
+        record.timestamp = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        record
+    }
+
+    #[test]
+    fn only_logged_decisions_reach_the_sink() {
+        let config = policy();
+        let show = AccessRequest { command: Some("show version"), operation: Operation::Read, ..reboot() };
+        let memory = MemorySink::new(10);
+
+        let result = config.validate_and_audit(&show, &memory).unwrap();
+        assert_eq!(result.effect, RuleEffect::Permit);
+        assert!(!result.should_log);
+        assert!(memory.is_empty());
+
+        let result = config.validate_and_audit(&reboot(), &memory).unwrap();
+        assert_eq!(result.effect, RuleEffect::Deny);
+        assert_eq!(memory.len(), 1);
+        let record = &memory.records()[0];
+        assert_eq!(record.command.as_deref(), Some("reboot"));
+        assert_eq!(record.rule_list.as_deref(), Some("ops"));
+        assert_eq!(record.rule.as_deref(), Some("deny-reboot"));
+    }
+
+    #[test]
+    fn memory_sink_keeps_the_newest_records() {
+        let config = policy();
+        let memory = MemorySink::new(2);
+        for user in ["bob", "bob", "bob"] {
+            config.validate_and_audit(&AccessRequest { user, ..reboot() }, &memory).unwrap();
+        }
+        let mut record = reboot_record(&config);
+        record.user = "newest".to_string();
+        memory.record(&record).unwrap();
+
+        let users: Vec<String> = memory.records().into_iter().map(|r| r.user).collect();
+        assert_eq!(users, ["bob", "newest"]);
+    }
+
+    #[test]
+    fn json_lines_use_request_field_names() {
+        let json = JsonLinesSink::new(Vec::new());
+        json.record(&reboot_record(&policy())).unwrap();
+        let line = String::from_utf8(json.into_inner()).unwrap();
+        assert_eq!(
+            line,
+            "{\"timestamp\":\"2023-11-14T22:13:20.123Z\",\"user\":\"bob\",\"operation\":\"exec\",\
+             \"context\":\"cli\",\"command\":\"reboot\",\"decision\":\"deny\",\
+             \"rule_list\":\"ops\",\"rule\":\"deny-reboot\"}\n"
+        );
+    }
+
+    #[test]
+    fn syslog_messages_escape_structured_data() {
+        let mut record = reboot_record(&policy());
+        record.command = Some("say \"hi]".to_string());
+        let syslog = record.to_rfc5424(Some("host1"), "nacm-validator");
+
+        // authpriv.warning for a denial
+        assert!(syslog.starts_with("<84>1 2023-11-14T22:13:20.123Z host1 nacm-validator "));
+        assert!(syslog.contains(" nacm-decision [nacm@32473 user=\"bob\" operation=\"exec\" decision=\"deny\""));
+        assert!(syslog.contains("command=\"say \\\"hi\\]\""));
+        assert!(syslog.ends_with("] deny exec by bob (ops/deny-reboot)"));
+    }
+
+    #[test]
+    fn timestamps_are_rfc3339_utc() {
+        assert_eq!(format_rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400);
+        assert_eq!(format_rfc3339(leap_day), "2000-02-29T00:00:00.000Z");
+    }
+
+    #[test]
+    fn parses_what_it_formats() {
+        for millis in [0, 951_782_400_000, 1_700_000_000_123, 4_102_444_799_999] {
+            let time = UNIX_EPOCH + Duration::from_millis(millis);
+            assert_eq!(parse_rfc3339(&format_rfc3339(time)), Ok(time));
+        }
+    }
+
+    #[test]
+    fn parses_offsets_and_fractions() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_717_209_000);
+        assert_eq!(parse_rfc3339("2024-06-01t02:30:00z"), Ok(time));
+        assert_eq!(parse_rfc3339("2024-05-31T21:00:00-05:30"), Ok(time));
+        assert_eq!(parse_rfc3339("2024-06-01T02:30:00.5Z"), Ok(time + Duration::from_millis(500)));
+        assert_eq!(parse_rfc3339("2024-06-01T02:30:00.123456789Z"), Ok(time + Duration::from_nanos(123_456_789)));
+    }
+
+    #[test]
+    fn rejects_malformed_times() {
+        for text in [
+            "",
+            "2024-06-01",
+            "2024-06-01T02:30:00",
+            "2024-06-01T02:30Z",
+            "2024-02-30T00:00:00Z",
+            "2024-06-01T24:00:00Z",
+            "2024-06-01T02:30:00.Z",
+            "2024-06-01T02:30:00+0200",
+            "2024-06-01T02:30:00+02:00x",
+            "+024-06-01T02:30:00Z",
+            "1969-12-31T23:59:59Z",
+        ] {
+            assert!(parse_rfc3339(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,769 @@
+//! # Policy Builder
+//!
+//! Builds a [`NacmConfig`] in code, for tools that generate policies.
+//! [`NacmConfig::builder`] starts from the RFC 8341 defaults (NACM enabled,
+//! read and exec permitted, writes denied). Then:
+//!
+//! - groups are added with [`group`](NacmConfigBuilder::group)
+//! - rule lists are added with [`rule_list`](NacmConfigBuilder::rule_list),
+//!   whose closure adds the rules in order
+//! - rule `order` values are assigned like [`NacmConfig::from_xml`] assigns
+//!   them, so earlier rule lists and earlier rules take precedence
+//!
+//! [`build`](NacmConfigBuilder::build) checks the policy before returning it
+//! and reports a [`BuildError`]. It rejects:
+//!
+//! - rule lists naming undefined groups (`*` is always allowed)
+//! - duplicate group, rule list or rule names
+//! - invalid context patterns
+//! - rule settings that do not apply, such as a `module` on a command rule
+//! - empty operation lists (use [`all_ops`](RuleListBuilder::all_ops))
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::{AccessRequest, NacmConfig, Operation::*, RuleEffect};
+//! use nacm_validator::builder::BuildError;
+//!
+//! let config = NacmConfig::builder()
+//!     .group("admin", ["alice"])
+//!     .group("oper", ["bob", "carol"])
+//!     .rule_list("admin-acl", ["admin"], |rl| rl.permit().name("permit-all"))
+//!     .rule_list("oper-acl", ["oper"], |rl| {
+//!         rl.permit().module("ietf-interfaces").ops([Read])
+//!             .deny().path("/nacm")
+//!             .permit_command("show *").context("cli")
+//!     })
+//!     .build()
+//!     .unwrap();
+//!
+//! let request = AccessRequest::builder().user("bob").op(Read).module("ietf-interfaces").build();
+//! assert_eq!(config.validate(&request).effect, RuleEffect::Permit);
+//! assert_eq!(config.rule_lists[1].rules[1].name, "rule-2");
+//!
+//! // Referential integrity is checked on build
+//! let error = NacmConfig::builder()
+//!     .rule_list("x", ["nobody"], |rl| rl.deny())
+//!     .build()
+//!     .unwrap_err();
+//! assert_eq!(error, BuildError::UndefinedGroup { rule_list: "x".into(), group: "nobody".into() });
+//! ```
+
+use crate::mutation::renumber;
+use crate::condition::RuleCondition;
+use crate::{
+    ContextPattern, NacmCommandRule, NacmConfig, NacmGroup, NacmRule, NacmRuleList, Operation, RuleEffect,
+    COMMAND_OPERATIONS, DATA_OPERATIONS,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Why a policy could not be built
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// Two groups have the same name
+    DuplicateGroup(String),
+    /// Two rule lists have the same name
+    DuplicateRuleList(String),
+    /// Two rules (or two command rules) of a rule list have the same name
+    DuplicateRule {
+        /// Rule list containing the rules
+        rule_list: String,
+        /// The repeated rule name
+        rule: String,
+    },
+    /// A rule list names a group that is not defined
+    UndefinedGroup {
+        /// Rule list naming the group
+        rule_list: String,
+        /// The undefined group
+        group: String,
+    },
+    /// A rule's context pattern does not parse
+    InvalidContext {
+        /// Rule list containing the rule
+        rule_list: String,
+        /// The offending pattern
+        pattern: String,
+        /// Why it does not parse
+        reason: String,
+    },
+    /// A setting was used before any rule was started, or on a kind of
+    /// rule it does not apply to
+    MisplacedSetting {
+        /// Rule list being built
+        rule_list: String,
+        /// The setting, e.g. `module`
+        setting: &'static str,
+    },
+    /// [`ops`](RuleListBuilder::ops) was given no operations; use
+    /// [`all_ops`](RuleListBuilder::all_ops) for a rule covering all of them
+    EmptyOperations {
+        /// Rule list containing the rule
+        rule_list: String,
+        /// The rule
+        rule: String,
+    },
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::DuplicateGroup(group) => write!(f, "Duplicate group: {}", group),
+            BuildError::DuplicateRuleList(rule_list) => write!(f, "Duplicate rule list: {}", rule_list),
+            BuildError::DuplicateRule { rule_list, rule } => {
+                write!(f, "Duplicate rule '{}' in rule list '{}'", rule, rule_list)
+            }
+            BuildError::UndefinedGroup { rule_list, group } => {
+                write!(f, "Rule list '{}' refers to undefined group '{}'", rule_list, group)
+            }
+            BuildError::InvalidContext { rule_list, pattern, reason } => {
+                write!(f, "Invalid context '{}' in rule list '{}': {}", pattern, rule_list, reason)
+            }
+            BuildError::MisplacedSetting { rule_list, setting } => {
+                write!(f, "Setting '{}' does not apply to the current rule in rule list '{}'", setting, rule_list)
+            }
+            BuildError::EmptyOperations { rule_list, rule } => {
+                write!(f, "Rule '{}' in rule list '{}' has an empty operation list", rule, rule_list)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl NacmConfig {
+    /// Start building a policy in code; see the [`builder`](crate::builder) module
+    pub fn builder() -> NacmConfigBuilder {
+        NacmConfigBuilder {
+            enable_nacm: true,
+            read_default: RuleEffect::Permit,
+            write_default: RuleEffect::Deny,
+            exec_default: RuleEffect::Permit,
+            cmd_read_default: RuleEffect::Permit,
+            cmd_exec_default: RuleEffect::Permit,
+            log_if_default_permit: false,
+            log_if_default_deny: false,
+            groups: Vec::new(),
+            rule_lists: Vec::new(),
+        }
+    }
+}
+
+/// Builder for a [`NacmConfig`], created by [`NacmConfig::builder`]
+#[derive(Debug, Clone)]
+pub struct NacmConfigBuilder {
+    enable_nacm: bool,
+    read_default: RuleEffect,
+    write_default: RuleEffect,
+    exec_default: RuleEffect,
+    cmd_read_default: RuleEffect,
+    cmd_exec_default: RuleEffect,
+    log_if_default_permit: bool,
+    log_if_default_deny: bool,
+    groups: Vec<NacmGroup>,
+    rule_lists: Vec<RuleListBuilder>,
+}
+
+impl NacmConfigBuilder {
+    /// Enable or disable access control as a whole (default: enabled)
+    pub fn enable_nacm(mut self, enable: bool) -> Self {
+        self.enable_nacm = enable;
+        self
+    }
+
+    /// Default for reads no rule matches (default: permit)
+    pub fn read_default(mut self, effect: RuleEffect) -> Self {
+        self.read_default = effect;
+        self
+    }
+
+    /// Default for writes no rule matches (default: deny)
+    pub fn write_default(mut self, effect: RuleEffect) -> Self {
+        self.write_default = effect;
+        self
+    }
+
+    /// Default for RPCs and actions no rule matches (default: permit)
+    pub fn exec_default(mut self, effect: RuleEffect) -> Self {
+        self.exec_default = effect;
+        self
+    }
+
+    /// Default for command reads no command rule matches (default: permit) - Tail-f extension
+    pub fn cmd_read_default(mut self, effect: RuleEffect) -> Self {
+        self.cmd_read_default = effect;
+        self
+    }
+
+    /// Default for command execs no command rule matches (default: permit) - Tail-f extension
+    pub fn cmd_exec_default(mut self, effect: RuleEffect) -> Self {
+        self.cmd_exec_default = effect;
+        self
+    }
+
+    /// Log decisions made by a permitting default - Tail-f extension
+    pub fn log_if_default_permit(mut self) -> Self {
+        self.log_if_default_permit = true;
+        self
+    }
+
+    /// Log decisions made by a denying default - Tail-f extension
+    pub fn log_if_default_deny(mut self) -> Self {
+        self.log_if_default_deny = true;
+        self
+    }
+
+    /// Add a group with its members
+    pub fn group<I, S>(mut self, name: impl Into<String>, users: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.groups.push(NacmGroup {
+            name: name.into(),
+            users: users.into_iter().map(Into::into).collect(),
+            gid: None,
+        });
+        self
+    }
+
+    /// Add a group with its members and a numerical group ID - Tail-f extension
+    pub fn group_with_gid<I, S>(self, name: impl Into<String>, gid: i32, users: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut builder = self.group(name, users);
+        if let Some(group) = builder.groups.last_mut() {
+            group.gid = Some(gid);
+        }
+        builder
+    }
+
+    /// Add a rule list for `groups`, with the rules added by `rules`
+    ///
+    /// Rule lists take precedence in the order they are added.
+    pub fn rule_list<I, S, F>(mut self, name: impl Into<String>, groups: I, rules: F) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+        F: FnOnce(RuleListBuilder) -> RuleListBuilder,
+    {
+        let rule_list = RuleListBuilder {
+            name: name.into(),
+            groups: groups.into_iter().map(Into::into).collect(),
+            rules: Vec::new(),
+            command_rules: Vec::new(),
+            current: None,
+            errors: Vec::new(),
+        };
+        self.rule_lists.push(rules(rule_list));
+        self
+    }
+
+    /// Check the policy and assemble the configuration
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(NacmConfig)` - The configuration, without statistics
+    /// * `Err(BuildError)` - The first problem found, in the order groups,
+    ///   then rule lists as they were added
+    pub fn build(self) -> Result<NacmConfig, BuildError> {
+        let mut groups = HashMap::new();
+        for group in self.groups {
+            if groups.contains_key(&group.name) {
+                return Err(BuildError::DuplicateGroup(group.name));
+            }
+            groups.insert(group.name.clone(), group);
+        }
+
+        let mut rule_list_names = HashSet::new();
+        let mut rule_lists = Vec::new();
+        for builder in self.rule_lists {
+            if let Some(error) = builder.errors.into_iter().next() {
+                return Err(error);
+            }
+            if !rule_list_names.insert(builder.name.clone()) {
+                return Err(BuildError::DuplicateRuleList(builder.name));
+            }
+            if let Some(group) = builder.groups.iter().find(|g| *g != "*" && !groups.contains_key(*g)) {
+                return Err(BuildError::UndefinedGroup {
+                    rule_list: builder.name.clone(),
+                    group: group.clone(),
+                });
+            }
+
+            let duplicate = |names: Vec<&String>| {
+                let mut seen = HashSet::new();
+                names.into_iter().find(|name| !seen.insert(*name)).cloned()
+            };
+            let duplicate_rule = duplicate(builder.rules.iter().map(|r| &r.name).collect())
+                .or_else(|| duplicate(builder.command_rules.iter().map(|r| &r.name).collect()));
+            if let Some(rule) = duplicate_rule {
+                return Err(BuildError::DuplicateRule { rule_list: builder.name, rule });
+            }
+
+            rule_lists.push(NacmRuleList {
+                name: builder.name,
+                groups: builder.groups,
+                rules: builder.rules,
+                command_rules: builder.command_rules,
+            });
+        }
+        // Same precedence scheme as from_xml
+        renumber(&mut rule_lists);
+
+        Ok(NacmConfig {
+            enable_nacm: self.enable_nacm,
+            read_default: self.read_default,
+            write_default: self.write_default,
+            exec_default: self.exec_default,
+            cmd_read_default: self.cmd_read_default,
+            cmd_exec_default: self.cmd_exec_default,
+            log_if_default_permit: self.log_if_default_permit,
+            log_if_default_deny: self.log_if_default_deny,
+            groups,
+            rule_lists,
+            stats: None,
+        })
+    }
+}
+
+/// The kind of rule the settings currently apply to
+#[derive(Debug, Clone, Copy)]
+enum Current {
+    Rule,
+    CommandRule,
+}
+
+/// Adds rules to a rule list, inside [`NacmConfigBuilder::rule_list`]
+///
+/// [`permit`](Self::permit) and [`deny`](Self::deny) start a data rule,
+/// [`permit_command`](Self::permit_command) and
+/// [`deny_command`](Self::deny_command) a command rule; the settings that
+/// follow apply to that rule. Rules are named `rule-1`, `rule-2`, ... and
+/// command rules `cmdrule-1`, ... by position unless given a
+/// [`name`](Self::name).
+///
+/// A data rule without [`ops`](Self::ops) covers every operation; a
+/// command rule covers read and exec.
+#[derive(Debug, Clone)]
+pub struct RuleListBuilder {
+    name: String,
+    groups: Vec<String>,
+    rules: Vec<NacmRule>,
+    command_rules: Vec<NacmCommandRule>,
+    current: Option<Current>,
+    errors: Vec<BuildError>,
+}
+
+impl RuleListBuilder {
+    /// Start a data rule that permits access
+    pub fn permit(self) -> Self {
+        self.start_rule(RuleEffect::Permit)
+    }
+
+    /// Start a data rule that denies access
+    pub fn deny(self) -> Self {
+        self.start_rule(RuleEffect::Deny)
+    }
+
+    /// Start a command rule permitting commands that match `command` - Tail-f extension
+    pub fn permit_command(self, command: impl Into<String>) -> Self {
+        self.start_command_rule(RuleEffect::Permit, command.into())
+    }
+
+    /// Start a command rule denying commands that match `command` - Tail-f extension
+    pub fn deny_command(self, command: impl Into<String>) -> Self {
+        self.start_command_rule(RuleEffect::Deny, command.into())
+    }
+
+    /// Name the current rule
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        match self.current {
+            Some(Current::Rule) => self.rules.last_mut().unwrap().name = name,
+            Some(Current::CommandRule) => self.command_rules.last_mut().unwrap().name = name,
+            None => self.misplaced("name"),
+        }
+        self
+    }
+
+    /// Restrict the current data rule to a YANG module
+    pub fn module(mut self, module_name: impl Into<String>) -> Self {
+        let module_name = module_name.into();
+        if let Some(rule) = self.rule("module") {
+            rule.module_name = Some(module_name);
+        }
+        self
+    }
+
+    /// Restrict the current data rule to an RPC (`*` for any)
+    pub fn rpc(mut self, rpc_name: impl Into<String>) -> Self {
+        let rpc_name = rpc_name.into();
+        if let Some(rule) = self.rule("rpc") {
+            rule.rpc_name = Some(rpc_name);
+        }
+        self
+    }
+
+    /// Restrict the current data rule to a data path
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        let path = path.into();
+        if let Some(rule) = self.rule("path") {
+            rule.path = Some(path);
+        }
+        self
+    }
+
+    /// Set the operations the current rule covers
+    ///
+    /// An empty list is rejected by [`build`](NacmConfigBuilder::build), as
+    /// `<access-operations>` must not be empty in XML; use
+    /// [`all_ops`](Self::all_ops) for a rule covering every operation.
+    pub fn ops(mut self, operations: impl IntoIterator<Item = Operation>) -> Self {
+        let operations: HashSet<Operation> = operations.into_iter().collect();
+        let rule = match self.current {
+            Some(Current::Rule) => {
+                let rule = self.rules.last_mut().unwrap();
+                rule.access_operations = operations;
+                rule.access_operations.is_empty().then(|| rule.name.clone())
+            }
+            Some(Current::CommandRule) => {
+                let rule = self.command_rules.last_mut().unwrap();
+                rule.access_operations = operations;
+                rule.access_operations.is_empty().then(|| rule.name.clone())
+            }
+            None => {
+                self.misplaced("ops");
+                None
+            }
+        };
+        if let Some(rule) = rule {
+            self.errors.push(BuildError::EmptyOperations {
+                rule_list: self.name.clone(),
+                rule,
+            });
+        }
+        self
+    }
+
+    /// Let the current rule cover every operation, like `*` in XML
+    pub fn all_ops(mut self) -> Self {
+        match self.current {
+            Some(Current::Rule) => {
+                self.rules.last_mut().unwrap().access_operations = DATA_OPERATIONS.into_iter().collect();
+            }
+            Some(Current::CommandRule) => {
+                self.command_rules.last_mut().unwrap().access_operations = COMMAND_OPERATIONS.into_iter().collect();
+            }
+            None => self.misplaced("all_ops"),
+        }
+        self
+    }
+
+    /// Restrict the current rule to a context pattern, e.g. `"cli webui"` - Tail-f extension
+    pub fn context(mut self, pattern: &str) -> Self {
+        let context = match pattern.parse::<ContextPattern>() {
+            Ok(context) => context,
+            Err(reason) => {
+                self.errors.push(BuildError::InvalidContext {
+                    rule_list: self.name.clone(),
+                    pattern: pattern.to_string(),
+                    reason,
+                });
+                return self;
+            }
+        };
+        match self.current {
+            Some(Current::Rule) => self.rules.last_mut().unwrap().context = Some(context),
+            Some(Current::CommandRule) => self.command_rules.last_mut().unwrap().context = Some(context),
+            None => self.misplaced("context"),
+        }
+        self
+    }
+
+    /// Log when the current rule permits access - Tail-f extension
+    pub fn log_if_permit(mut self) -> Self {
+        match self.current {
+            Some(Current::Rule) => self.rules.last_mut().unwrap().log_if_permit = true,
+            Some(Current::CommandRule) => self.command_rules.last_mut().unwrap().log_if_permit = true,
+            None => self.misplaced("log_if_permit"),
+        }
+        self
+    }
+
+    /// Log when the current rule denies access - Tail-f extension
+    pub fn log_if_deny(mut self) -> Self {
+        match self.current {
+            Some(Current::Rule) => self.rules.last_mut().unwrap().log_if_deny = true,
+            Some(Current::CommandRule) => self.command_rules.last_mut().unwrap().log_if_deny = true,
+            None => self.misplaced("log_if_deny"),
+        }
+        self
+    }
+
+    /// Apply the current rule only while `condition` holds (see [`crate::condition`])
+    pub fn condition(mut self, condition: RuleCondition) -> Self {
+        match self.current {
+            Some(Current::Rule) => self.rules.last_mut().unwrap().condition = Some(condition),
+            Some(Current::CommandRule) => self.command_rules.last_mut().unwrap().condition = Some(condition),
+            None => self.misplaced("condition"),
+        }
+        self
+    }
+
+    /// Describe the current command rule - Tail-f extension
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        match self.current {
+            Some(Current::CommandRule) => self.command_rules.last_mut().unwrap().comment = Some(comment.into()),
+            _ => self.misplaced("comment"),
+        }
+        self
+    }
+
+    fn start_rule(mut self, effect: RuleEffect) -> Self {
+        self.rules.push(NacmRule {
+            name: format!("rule-{}", self.rules.len() + 1),
+            module_name: None,
+            rpc_name: None,
+            path: None,
+            access_operations: HashSet::new(),
+            effect,
+            order: 0,
+            context: None,
+            log_if_permit: false,
+            log_if_deny: false,
+            condition: None,
+        });
+        self.current = Some(Current::Rule);
+        self
+    }
+
+    fn start_command_rule(mut self, effect: RuleEffect, command: String) -> Self {
+        self.command_rules.push(NacmCommandRule {
+            name: format!("cmdrule-{}", self.command_rules.len() + 1),
+            context: None,
+            command: Some(command),
+            access_operations: [Operation::Read, Operation::Exec].into_iter().collect(),
+            effect,
+            order: 0,
+            log_if_permit: false,
+            log_if_deny: false,
+            comment: None,
+            condition: None,
+        });
+        self.current = Some(Current::CommandRule);
+        self
+    }
+
+    /// The current rule, if it is a data rule; otherwise record `setting` as misplaced
+    fn rule(&mut self, setting: &'static str) -> Option<&mut NacmRule> {
+        match self.current {
+            Some(Current::Rule) => self.rules.last_mut(),
+            _ => {
+                self.misplaced(setting);
+                None
+            }
+        }
+    }
+
+    fn misplaced(&mut self, setting: &'static str) {
+        self.errors.push(BuildError::MisplacedSetting {
+            rule_list: self.name.clone(),
+            setting,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccessRequest, RequestContext};
+
+    /// A policy with data and command rules, as XML
+    const XML: &str = r#"
+        <config xmlns="http://tail-f.com/ns/config/1.0">
+          <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+            <enable-nacm>true</enable-nacm>
+            <read-default>deny</read-default>
+            <write-default>deny</write-default>
+            <exec-default>deny</exec-default>
+            <groups>
+              <group><name>admin</name><user-name>alice</user-name></group>
+              <group><name>oper</name><user-name>bob</user-name></group>
+            </groups>
+            <rule-list>
+              <name>oper-acl</name>
+              <group>oper</group>
+              <rule>
+                <name>no-nacm</name>
+                <module-name>ietf-netconf-acm</module-name>
+                <access-operations>*</access-operations>
+                <action>deny</action>
+              </rule>
+              <rule>
+                <name>read-all</name>
+                <access-operations>read</access-operations>
+                <action>permit</action>
+              </rule>
+              <cmdrule xmlns="http://tail-f.com/yang/acm">
+                <name>show</name>
+                <context>cli</context>
+                <command>show *</command>
+                <action>permit</action>
+              </cmdrule>
+            </rule-list>
+            <rule-list>
+              <name>admin-acl</name>
+              <group>admin</group>
+              <rule>
+                <name>permit-all</name>
+                <action>permit</action>
+              </rule>
+            </rule-list>
+          </nacm>
+        </config>"#;
+
+    /// The policy of [`XML`], built in code
+    fn built() -> NacmConfig {
+        NacmConfig::builder()
+            .read_default(RuleEffect::Deny)
+            .exec_default(RuleEffect::Deny)
+            .group("admin", ["alice"])
+            .group("oper", vec!["bob".to_string()])
+            .rule_list("oper-acl", ["oper"], |rl| {
+                rl.deny()
+                    .name("no-nacm")
+                    .module("ietf-netconf-acm")
+                    .all_ops()
+                    .permit()
+                    .name("read-all")
+                    .ops([Operation::Read])
+                    .permit_command("show *")
+                    .name("show")
+                    .context("cli")
+            })
+            .rule_list("admin-acl", ["admin"], |rl| rl.permit().name("permit-all"))
+            .build()
+            .unwrap()
+    }
+
+    fn error(builder: NacmConfigBuilder) -> BuildError {
+        builder.build().unwrap_err()
+    }
+
+    #[test]
+    fn orders_match_xml() {
+        let parsed = NacmConfig::from_xml(XML).unwrap();
+        let orders = |list: &NacmRuleList| -> Vec<(String, u32)> {
+            let rules = list.rules.iter().map(|r| (r.name.clone(), r.order));
+            rules.chain(list.command_rules.iter().map(|r| (r.name.clone(), r.order))).collect()
+        };
+        let parsed: Vec<_> = parsed.rule_lists.iter().map(orders).collect();
+        let built: Vec<_> = built().rule_lists.iter().map(orders).collect();
+        assert_eq!(parsed, built);
+    }
+
+    #[test]
+    fn decisions_match_xml() {
+        let parsed = NacmConfig::from_xml(XML).unwrap();
+        let built = built();
+        let cli = RequestContext::CLI;
+        for user in ["alice", "bob", "mallory"] {
+            let requests = [
+                AccessRequest::builder().user(user).op(Operation::Read).module("ietf-interfaces").build(),
+                AccessRequest::builder().user(user).op(Operation::Read).module("ietf-netconf-acm").build(),
+                AccessRequest::builder().user(user).op(Operation::Update).module("ietf-interfaces").build(),
+                AccessRequest::builder().user(user).op(Operation::Read).context(&cli).command("show version").build(),
+                AccessRequest::builder().user(user).op(Operation::Exec).context(&cli).command("reboot").build(),
+            ];
+            for request in &requests {
+                assert_eq!(built.decide(request), parsed.decide(request), "user {}", user);
+            }
+        }
+    }
+
+    #[test]
+    fn unnamed_rules_are_numbered() {
+        let config = NacmConfig::builder()
+            .rule_list("a", ["*"], |rl| rl.permit().deny_command("reboot").deny().permit_command("show"))
+            .build()
+            .unwrap();
+        let names: Vec<_> = config.rule_lists[0].rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["rule-1", "rule-2"]);
+        let names: Vec<_> = config.rule_lists[0].command_rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["cmdrule-1", "cmdrule-2"]);
+    }
+
+    #[test]
+    fn rejects_duplicate_names() {
+        assert_eq!(
+            error(NacmConfig::builder().group("admin", ["alice"]).group("admin", ["bob"])),
+            BuildError::DuplicateGroup("admin".to_string())
+        );
+        assert_eq!(
+            error(NacmConfig::builder().rule_list("a", ["*"], |rl| rl).rule_list("a", ["*"], |rl| rl)),
+            BuildError::DuplicateRuleList("a".to_string())
+        );
+        assert_eq!(
+            error(NacmConfig::builder().rule_list("a", ["*"], |rl| rl.permit().name("r").deny().name("r"))),
+            BuildError::DuplicateRule { rule_list: "a".to_string(), rule: "r".to_string() }
+        );
+    }
+
+    #[test]
+    fn rejects_misplaced_settings() {
+        assert_eq!(
+            error(NacmConfig::builder().rule_list("a", ["*"], |rl| rl.permit_command("show").module("m"))),
+            BuildError::MisplacedSetting { rule_list: "a".to_string(), setting: "module" }
+        );
+        assert_eq!(
+            error(NacmConfig::builder().rule_list("a", ["*"], |rl| rl.ops([Operation::Read]))),
+            BuildError::MisplacedSetting { rule_list: "a".to_string(), setting: "ops" }
+        );
+        assert_eq!(
+            error(NacmConfig::builder().rule_list("a", ["*"], |rl| rl.all_ops())),
+            BuildError::MisplacedSetting { rule_list: "a".to_string(), setting: "all_ops" }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_context() {
+        assert!(matches!(
+            error(NacmConfig::builder().rule_list("a", ["*"], |rl| rl.permit().context("  "))),
+            BuildError::InvalidContext { .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_undefined_group() {
+        assert_eq!(
+            error(NacmConfig::builder().rule_list("a", ["admin"], |rl| rl.permit())).to_string(),
+            "Rule list 'a' refers to undefined group 'admin'"
+        );
+    }
+
+    #[test]
+    fn rejects_empty_operation_list() {
+        assert_eq!(
+            error(NacmConfig::builder().rule_list("a", ["*"], |rl| rl.permit().name("r").ops([]))),
+            BuildError::EmptyOperations { rule_list: "a".to_string(), rule: "r".to_string() }
+        );
+        assert_eq!(
+            error(NacmConfig::builder().rule_list("a", ["*"], |rl| rl.deny_command("reboot").ops([]))),
+            BuildError::EmptyOperations { rule_list: "a".to_string(), rule: "cmdrule-1".to_string() }
+        );
+    }
+
+    #[test]
+    fn all_ops_covers_every_operation() {
+        let config = NacmConfig::builder()
+            .rule_list("a", ["*"], |rl| rl.deny().all_ops().deny_command("reboot").all_ops())
+            .build()
+            .unwrap();
+        let rule_list = &config.rule_lists[0];
+        assert_eq!(rule_list.rules[0].access_operations, DATA_OPERATIONS.into_iter().collect());
+        assert_eq!(rule_list.command_rules[0].access_operations, COMMAND_OPERATIONS.into_iter().collect());
+    }
+}
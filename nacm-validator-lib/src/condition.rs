@@ -0,0 +1,796 @@
+//! # Conditional Rules
+//!
+//! Rules that only apply some of the time or to some requests, such as
+//! change windows: "operators may write `/interfaces` Saturday 02:00-06:00
+//! UTC". A rule with a [`RuleCondition`] matches a request only when the
+//! condition holds as well; otherwise evaluation moves on to the next rule,
+//! as if the rule did not match.
+//!
+//! A condition combines three kinds of tests, all of which must pass:
+//!
+//! - **Weekdays**: the day of the week (UTC); none listed means every day
+//! - **Time ranges**: daily `HH:MM-HH:MM` windows (UTC, end exclusive); none
+//!   listed means all day. A range whose end is before its start runs past
+//!   midnight and belongs to the weekday it starts on.
+//! - **Attributes**: request attributes that are not part of NACM, such as
+//!   the source address. Each listed attribute must be present and match its
+//!   pattern: a glob with `*` and `?`, or an address prefix like
+//!   `10.0.0.0/8` for IP addresses.
+//!
+//! The moment of evaluation and the request attributes come from an
+//! [`Environment`], passed to
+//! [`decide_with`](crate::NacmConfig::decide_with) and
+//! [`validate_with`](crate::NacmConfig::validate_with). Its time comes from
+//! a [`Clock`], the system clock unless another one is injected, so
+//! conditional rules can be tested at any time of the week. Tree
+//! filtering, edit authorization and simulation have `_with` variants that
+//! take one too; the other evaluation functions use the system clock and no
+//! attributes.
+//!
+//! ## XML
+//!
+//! Conditions are a vendor extension: a `<condition>` element in the
+//! [`CONDITION_NAMESPACE`] inside a `<rule>` or Tail-f `<cmdrule>`. A
+//! `<condition>` in another namespace, or in none, is not ours:
+//! [`from_xml`](crate::NacmConfig::from_xml) ignores it like any other
+//! unknown element, and
+//! [`apply_nacm_edit`](crate::NacmConfig::apply_nacm_edit) rejects it.
+//!
+//! ```xml
+//! <rule>
+//!   <name>maintenance-window</name>
+//!   <path>/interfaces</path>
+//!   <access-operations>create update delete</access-operations>
+//!   <action>permit</action>
+//!   <condition xmlns="https://github.com/etnt/nacm-validator/condition">
+//!     <weekday>saturday</weekday>
+//!     <time-range>02:00-06:00</time-range>
+//!     <attribute>
+//!       <name>source-ip</name>
+//!       <value>10.0.0.0/8</value>
+//!     </attribute>
+//!   </condition>
+//! </rule>
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::{AccessRequest, NacmConfig, Operation, RuleEffect};
+//! use nacm_validator::condition::{Environment, RuleCondition, Weekday};
+//! use std::time::{Duration, SystemTime};
+//!
+//! let window = RuleCondition {
+//!     weekdays: vec![Weekday::Saturday],
+//!     time_ranges: vec!["02:00-06:00".parse().unwrap()],
+//!     ..Default::default()
+//! };
+//! let config = NacmConfig::builder()
+//!     .write_default(RuleEffect::Deny)
+//!     .group("oper", ["bob"])
+//!     .rule_list("oper-acl", ["oper"], |rl| {
+//!         rl.permit().name("maintenance").path("/interfaces").ops([Operation::Update]).condition(window)
+//!     })
+//!     .build()
+//!     .unwrap();
+//!
+//! let request = AccessRequest::builder().user("bob").op(Operation::Update).path("/interfaces").build();
+//!
+//! // 2024-06-01 was a Saturday
+//! let saturday = SystemTime::UNIX_EPOCH + Duration::from_secs(1_717_200_000);
+//! let at_3am = Environment::new().at(saturday + Duration::from_secs(3 * 3600));
+//! let at_noon = Environment::new().at(saturday + Duration::from_secs(12 * 3600));
+//! assert_eq!(config.validate_with(&request, &at_3am).effect, RuleEffect::Permit);
+//! assert_eq!(config.validate_with(&request, &at_noon).effect, RuleEffect::Deny);
+//! ```
+
+use crate::{glob_matches, push_leaf};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Namespace of the `<condition>` element in rules
+pub const CONDITION_NAMESPACE: &str = "https://github.com/etnt/nacm-validator/condition";
+
+/// Day of the week
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    /// All days, starting with Monday
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    /// The day before
+    pub fn pred(self) -> Weekday {
+        Weekday::ALL[(self as usize + 6) % 7]
+    }
+
+    /// The UTC day of the week of `days` days since 1970-01-01, a Thursday
+    fn from_days(days: u64) -> Weekday {
+        Weekday::ALL[((days + 3) % 7) as usize]
+    }
+}
+
+/// Implementation of `FromStr` trait for `Weekday`
+///
+/// Accepts the full English name or its first three letters, in any case.
+impl std::str::FromStr for Weekday {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        Weekday::ALL
+            .into_iter()
+            .find(|day| {
+                let full = day.to_string();
+                name == full || (name.len() == 3 && full.starts_with(&name))
+            })
+            .ok_or_else(|| format!("Unknown weekday: {}", s))
+    }
+}
+
+/// Implementation of `Display` trait for `Weekday`: the lowercase name
+impl std::fmt::Display for Weekday {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Weekday::Monday => "monday",
+            Weekday::Tuesday => "tuesday",
+            Weekday::Wednesday => "wednesday",
+            Weekday::Thursday => "thursday",
+            Weekday::Friday => "friday",
+            Weekday::Saturday => "saturday",
+            Weekday::Sunday => "sunday",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A daily window of time in UTC, e.g. `02:00-06:00`
+///
+/// The start is inclusive and the end exclusive; `24:00` ends a range at
+/// midnight. A range whose end comes before its start, like `22:00-02:00`,
+/// runs past midnight.
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::condition::TimeRange;
+///
+/// let night: TimeRange = "22:00-02:00".parse().unwrap();
+/// assert!(night.wraps());
+/// assert!(night.contains(23 * 60));
+/// assert!(night.contains(60));
+/// assert!(!night.contains(2 * 60));
+/// assert_eq!(night.to_string(), "22:00-02:00");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimeRange {
+    /// Minutes after midnight the range starts at
+    start: u16,
+    /// Minutes after midnight the range ends at (up to 1440)
+    end: u16,
+}
+
+impl TimeRange {
+    /// The range from `start` to `end`, both in minutes after midnight
+    ///
+    /// # Returns
+    ///
+    /// * `Err(String)` - a bound is past midnight, or the range is empty
+    pub fn new(start: u16, end: u16) -> Result<Self, String> {
+        if start >= MINUTES_PER_DAY || end > MINUTES_PER_DAY {
+            return Err(format!("Time range bound past 24:00: {}-{}", start, end));
+        }
+        if start == end {
+            return Err("Time range must not be empty".to_string());
+        }
+        Ok(TimeRange { start, end })
+    }
+
+    /// Minutes after midnight the range starts at
+    pub fn start(&self) -> u16 {
+        self.start
+    }
+
+    /// Minutes after midnight the range ends at
+    pub fn end(&self) -> u16 {
+        self.end
+    }
+
+    /// Whether the range runs past midnight
+    pub fn wraps(&self) -> bool {
+        self.end < self.start
+    }
+
+    /// Whether `minute` (minutes after midnight) is in the range
+    pub fn contains(&self, minute: u16) -> bool {
+        if self.wraps() {
+            minute >= self.start || minute < self.end
+        } else {
+            minute >= self.start && minute < self.end
+        }
+    }
+}
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Implementation of `FromStr` trait for `TimeRange`
+///
+/// Parses `HH:MM-HH:MM`.
+impl std::str::FromStr for TimeRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let clock_time = |text: &str| -> Option<u16> {
+            let (hours, minutes) = text.trim().split_once(':')?;
+            let (hours, minutes): (u16, u16) = (hours.parse().ok()?, minutes.parse().ok()?);
+            let minute = hours.checked_mul(60)?.checked_add(minutes)?;
+            (minutes < 60 && minute <= MINUTES_PER_DAY).then_some(minute)
+        };
+        let invalid = || format!("Invalid time range (expected HH:MM-HH:MM): {}", s);
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        TimeRange::new(clock_time(start).ok_or_else(invalid)?, clock_time(end).ok_or_else(invalid)?)
+    }
+}
+
+/// Implementation of `Display` trait for `TimeRange`: `HH:MM-HH:MM`
+impl std::fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}-{:02}:{:02}", self.start / 60, self.start % 60, self.end / 60, self.end % 60)
+    }
+}
+
+/// Serializes as the range text, e.g. `"02:00-06:00"`
+#[cfg(feature = "serde")]
+impl Serialize for TimeRange {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserializes range text, like `FromStr`
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for TimeRange {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A test on one request attribute
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AttributeMatch {
+    /// Attribute name, e.g. `source-ip`
+    pub name: String,
+    /// Glob pattern with `*` and `?`, or an address prefix like `10.0.0.0/8`
+    pub value: String,
+}
+
+impl AttributeMatch {
+    /// Test attribute `name` against `value`
+    ///
+    /// # Returns
+    ///
+    /// * `Err(String)` - `value` is an address prefix with an invalid length
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Result<Self, String> {
+        let value = value.into();
+        if let Some((address, length)) = value.split_once('/') {
+            if let Ok(address) = address.parse::<IpAddr>() {
+                if prefix_length(address, length).is_none() {
+                    return Err(format!("Invalid address prefix: {}", value));
+                }
+            }
+        }
+        Ok(AttributeMatch { name: name.into(), value })
+    }
+
+    /// Check an attribute value against the pattern
+    ///
+    /// With an address prefix, the value must be an IP address of the same
+    /// family in that prefix; otherwise the pattern is a glob.
+    pub fn matches(&self, value: &str) -> bool {
+        let prefix = self.value.split_once('/').and_then(|(address, length)| {
+            let address = address.parse::<IpAddr>().ok()?;
+            Some((address, prefix_length(address, length)?))
+        });
+        match prefix {
+            Some((network, length)) => value
+                .parse::<IpAddr>()
+                .is_ok_and(|address| in_prefix(address, network, length)),
+            None => glob_matches(&self.value, value),
+        }
+    }
+}
+
+/// Parse a prefix length valid for the address family of `address`
+fn prefix_length(address: IpAddr, length: &str) -> Option<u32> {
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    length.parse().ok().filter(|length| *length <= max)
+}
+
+fn in_prefix(address: IpAddr, network: IpAddr, length: u32) -> bool {
+    match (address, network) {
+        (IpAddr::V4(address), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - length).unwrap_or(0);
+            u32::from(address) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(address), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - length).unwrap_or(0);
+            u128::from(address) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// When and for which requests a rule applies
+///
+/// The default condition has no tests and always holds.
+///
+/// # Fields
+///
+/// * `weekdays` - Days the rule applies on (empty = every day)
+/// * `time_ranges` - Daily windows the rule applies in (empty = all day)
+/// * `attributes` - Request attributes that must all be present and match
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct RuleCondition {
+    /// Days the rule applies on, in UTC (empty = every day)
+    pub weekdays: Vec<Weekday>,
+    /// Daily windows the rule applies in, in UTC (empty = all day)
+    pub time_ranges: Vec<TimeRange>,
+    /// Request attributes that must all be present and match
+    pub attributes: Vec<AttributeMatch>,
+}
+
+impl RuleCondition {
+    /// Check the condition in an environment
+    ///
+    /// The time must be in one of the time ranges on one of the weekdays; a
+    /// range running past midnight counts for the weekday it starts on.
+    /// Every attribute must be present in the environment and match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nacm_validator::condition::{AttributeMatch, Environment, RuleCondition};
+    ///
+    /// let from_lan = RuleCondition {
+    ///     attributes: vec![AttributeMatch::new("source-ip", "192.0.2.0/24").unwrap()],
+    ///     ..Default::default()
+    /// };
+    /// assert!(from_lan.holds(&Environment::new().with_attribute("source-ip", "192.0.2.7")));
+    /// assert!(!from_lan.holds(&Environment::new().with_attribute("source-ip", "198.51.100.1")));
+    /// assert!(!from_lan.holds(&Environment::new()));
+    /// ```
+    pub fn holds(&self, env: &Environment) -> bool {
+        let attributes_match = self
+            .attributes
+            .iter()
+            .all(|test| env.attribute(&test.name).is_some_and(|value| test.matches(value)));
+        attributes_match && self.time_matches(env.now())
+    }
+
+    fn time_matches(&self, now: SystemTime) -> bool {
+        let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let today = Weekday::from_days(secs / 86_400);
+        let minute = ((secs % 86_400) / 60) as u16;
+        let on = |day: Weekday| self.weekdays.is_empty() || self.weekdays.contains(&day);
+
+        if self.time_ranges.is_empty() {
+            return on(today);
+        }
+        self.time_ranges.iter().any(|range| {
+            if range.wraps() && minute < range.end {
+                // The part after midnight of a range that started yesterday
+                on(today.pred())
+            } else {
+                range.contains(minute) && on(today)
+            }
+        })
+    }
+
+    /// Build a condition from the leaves of a `<condition>` element
+    pub(crate) fn from_leaves(
+        weekdays: &[String],
+        time_ranges: &[String],
+        attributes: &[(String, String)],
+    ) -> Result<Self, String> {
+        Ok(RuleCondition {
+            weekdays: weekdays.iter().map(|day| day.parse()).collect::<Result<_, _>>()?,
+            time_ranges: time_ranges.iter().map(|range| range.parse()).collect::<Result<_, _>>()?,
+            attributes: attributes
+                .iter()
+                .map(|(name, value)| AttributeMatch::new(name.trim(), value.trim()))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Append the `<condition>` element, indented by `indent`
+    pub(crate) fn write_xml(&self, out: &mut String, indent: &str) {
+        let inner = format!("{}  ", indent);
+        out.push_str(&format!("{}<condition xmlns=\"{}\">\n", indent, CONDITION_NAMESPACE));
+        for day in &self.weekdays {
+            push_leaf(out, &inner, "weekday", &day.to_string());
+        }
+        for range in &self.time_ranges {
+            push_leaf(out, &inner, "time-range", &range.to_string());
+        }
+        for attribute in &self.attributes {
+            out.push_str(&format!("{}<attribute>\n", inner));
+            push_leaf(out, &format!("{}  ", inner), "name", &attribute.name);
+            push_leaf(out, &format!("{}  ", inner), "value", &attribute.value);
+            out.push_str(&format!("{}</attribute>\n", inner));
+        }
+        out.push_str(&format!("{}</condition>\n", indent));
+    }
+}
+
+/// A source of the current time
+///
+/// Implemented by [`SystemClock`], and by `SystemTime` as a clock stopped at
+/// that time.
+pub trait Clock: Send + Sync {
+    /// The current time
+    fn now(&self) -> SystemTime;
+}
+
+/// The system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl Clock for SystemTime {
+    fn now(&self) -> SystemTime {
+        *self
+    }
+}
+
+/// What rule conditions are checked against: a clock and request attributes
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::condition::Environment;
+/// use std::time::{Duration, SystemTime};
+///
+/// let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_717_200_000);
+/// let env = Environment::new().at(time).with_attribute("source-ip", "192.0.2.7");
+/// assert_eq!(env.now(), time);
+/// assert_eq!(env.attribute("source-ip"), Some("192.0.2.7"));
+/// ```
+#[derive(Clone, Default)]
+pub struct Environment {
+    /// Time source (None = the system clock)
+    clock: Option<Arc<dyn Clock>>,
+    /// Request attributes by name
+    attributes: HashMap<String, String>,
+    /// Treat every condition as holding (for analysis)
+    assume_conditions: bool,
+}
+
+impl Environment {
+    /// The system clock and no attributes
+    pub fn new() -> Self {
+        Environment::default()
+    }
+
+    /// Take the time from `clock`
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Evaluate at a fixed `time`
+    pub fn at(self, time: SystemTime) -> Self {
+        self.with_clock(time)
+    }
+
+    /// Set a request attribute, e.g. `source-ip`
+    pub fn with_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+
+    /// The current time according to the clock
+    pub fn now(&self) -> SystemTime {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemTime::now(),
+        }
+    }
+
+    /// The value of a request attribute
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// An environment in which every condition holds
+    ///
+    /// Used by the analysis to find the requests conditional rules can
+    /// decide at some point.
+    pub(crate) fn assuming_conditions() -> Self {
+        Environment { assume_conditions: true, ..Environment::default() }
+    }
+
+    /// Whether a rule with this condition applies
+    pub(crate) fn allows(&self, condition: Option<&RuleCondition>) -> bool {
+        match condition {
+            None => true,
+            Some(condition) => self.assume_conditions || condition.holds(self),
+        }
+    }
+}
+
+impl std::fmt::Debug for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Environment")
+            .field("now", &self.now())
+            .field("attributes", &self.attributes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::FindingKind;
+    use crate::{AccessRequest, NacmConfig, Operation, RuleEffect};
+    use std::time::Duration;
+
+    /// bob may edit /interfaces from 10/8 in two Saturday windows, update it
+    /// at any time, and reboot from a console at weekends
+    const POLICY: &str = r#"
+        <config xmlns="http://tail-f.com/ns/config/1.0">
+            <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+                <write-default>deny</write-default>
+                <cmd-exec-default>deny</cmd-exec-default>
+                <groups>
+                    <group>
+                        <name>oper</name>
+                        <user-name>bob</user-name>
+                    </group>
+                </groups>
+                <rule-list>
+                    <name>oper-acl</name>
+                    <group>oper</group>
+                    <rule>
+                        <name>maintenance</name>
+                        <path>/interfaces</path>
+                        <access-operations>create update delete</access-operations>
+                        <action>permit</action>
+                        <condition xmlns="https://github.com/etnt/nacm-validator/condition">
+                            <weekday>sat</weekday>
+                            <time-range>02:00-06:00</time-range>
+                            <time-range>23:00-01:00</time-range>
+                            <attribute>
+                                <name>source-ip</name>
+                                <value>10.0.0.0/8</value>
+                            </attribute>
+                        </condition>
+                    </rule>
+                    <rule>
+                        <name>any-time-write</name>
+                        <path>/interfaces</path>
+                        <access-operations>update</access-operations>
+                        <action>permit</action>
+                    </rule>
+                    <cmdrule xmlns="http://tail-f.com/yang/acm">
+                        <name>weekend-reboot</name>
+                        <command>reboot</command>
+                        <action>permit</action>
+                        <condition xmlns="https://github.com/etnt/nacm-validator/condition">
+                            <weekday>saturday</weekday>
+                            <weekday>sunday</weekday>
+                            <attribute>
+                                <name>tty</name>
+                                <value>console*</value>
+                            </attribute>
+                        </condition>
+                    </cmdrule>
+                </rule-list>
+            </nacm>
+        </config>"#;
+
+    fn config() -> NacmConfig {
+        NacmConfig::from_xml(POLICY).unwrap()
+    }
+
+    /// 2024-06-01 00:00 UTC, a Saturday
+    fn saturday() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_717_200_000)
+    }
+
+    fn hours(h: u64) -> Duration {
+        Duration::from_secs(h * 3600)
+    }
+
+    /// An environment at `time` with a source address inside 10/8
+    fn inside(time: SystemTime) -> Environment {
+        Environment::new().at(time).with_attribute("source-ip", "10.20.30.40")
+    }
+
+    fn create() -> AccessRequest<'static> {
+        AccessRequest::builder().user("bob").op(Operation::Create).path("/interfaces").build()
+    }
+
+    fn create_effect(config: &NacmConfig, env: &Environment) -> RuleEffect {
+        config.validate_with(&create(), env).effect
+    }
+
+    #[test]
+    fn conditions_parse_from_xml() {
+        let config = config();
+        let condition = config.rule_lists[0].rules[0].condition.as_ref().unwrap();
+        assert_eq!(condition.weekdays, [Weekday::Saturday]);
+        assert_eq!(
+            condition.time_ranges,
+            ["02:00-06:00".parse::<TimeRange>().unwrap(), TimeRange::new(1380, 60).unwrap()]
+        );
+        assert_eq!(condition.attributes, [AttributeMatch::new("source-ip", "10.0.0.0/8").unwrap()]);
+        assert!(config.rule_lists[0].rules[1].condition.is_none());
+    }
+
+    #[test]
+    fn time_ranges_apply_on_their_weekdays() {
+        let config = config();
+        let saturday = saturday();
+        assert_eq!(create_effect(&config, &inside(saturday + hours(3))), RuleEffect::Permit);
+        assert_eq!(create_effect(&config, &inside(saturday + hours(6))), RuleEffect::Deny);
+        assert_eq!(create_effect(&config, &inside(saturday - hours(24) + hours(3))), RuleEffect::Deny);
+    }
+
+    #[test]
+    fn range_past_midnight_belongs_to_the_day_it_starts_on() {
+        // Saturday night, not Friday night
+        let config = config();
+        let saturday = saturday();
+        assert_eq!(create_effect(&config, &inside(saturday + hours(23))), RuleEffect::Permit);
+        assert_eq!(create_effect(&config, &inside(saturday + hours(24))), RuleEffect::Permit);
+        assert_eq!(create_effect(&config, &inside(saturday)), RuleEffect::Deny);
+    }
+
+    #[test]
+    fn attributes_must_be_present_and_match() {
+        let config = config();
+        let at_three = saturday() + hours(3);
+        let outside = Environment::new().at(at_three).with_attribute("source-ip", "192.0.2.1");
+        assert_eq!(create_effect(&config, &outside), RuleEffect::Deny);
+        assert_eq!(create_effect(&config, &Environment::new().at(at_three)), RuleEffect::Deny);
+    }
+
+    #[test]
+    fn unmet_condition_moves_on_to_the_next_rule() {
+        let update = AccessRequest { operation: Operation::Update, ..create() };
+        let config = config();
+        let decision = config.decide_with(&update, &inside(saturday()));
+        assert_eq!(decision.matched_rule.unwrap().rule, "any-time-write");
+    }
+
+    #[test]
+    fn command_rules_take_conditions() {
+        let config = config();
+        let reboot = AccessRequest::builder().user("bob").op(Operation::Exec).command("reboot").build();
+        let console = |time| Environment::new().at(time).with_attribute("tty", "console0");
+        let sunday = saturday() + hours(30);
+        assert_eq!(config.validate_with(&reboot, &console(sunday)).effect, RuleEffect::Permit);
+        assert_eq!(config.validate_with(&reboot, &console(sunday + hours(18))).effect, RuleEffect::Deny);
+    }
+
+    #[test]
+    fn conditions_survive_the_xml_round_trip() {
+        let config = config();
+        let reloaded = NacmConfig::from_xml(&config.to_xml()).unwrap();
+        assert_eq!(reloaded.rule_lists[0].rules[0].condition, config.rule_lists[0].rules[0].condition);
+        assert_eq!(
+            reloaded.rule_lists[0].command_rules[0].condition,
+            config.rule_lists[0].command_rules[0].condition
+        );
+    }
+
+    #[test]
+    fn bad_values_are_rejected() {
+        for (good, bad) in [("sat", "caturday"), ("02:00-06:00", "02:00-02:00"), ("10.0.0.0/8", "10.0.0.0/33")] {
+            assert!(NacmConfig::from_xml(&POLICY.replacen(good, bad, 1)).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn foreign_conditions_are_ignored() {
+        // Another vendor's <condition>, or one in the NACM namespace, is not ours
+        for xmlns in [r#" xmlns="http://example.com/other-vendor""#, ""] {
+            let ours = format!(r#"<condition xmlns="{}">"#, CONDITION_NAMESPACE);
+            let foreign = POLICY.replace(&ours, &format!("<condition{}>", xmlns));
+            let config = NacmConfig::from_xml(&foreign).unwrap();
+            assert!(config.rule_lists[0].rules[0].condition.is_none(), "{:?}", xmlns);
+            assert!(config.rule_lists[0].command_rules[0].condition.is_none(), "{:?}", xmlns);
+            assert_eq!(create_effect(&config, &Environment::new()), RuleEffect::Permit);
+        }
+
+        // Ours is still read next to a foreign one
+        let both = POLICY.replacen(
+            "<condition xmlns",
+            r#"<condition xmlns="http://example.com/other-vendor"><weekday>mon</weekday></condition><condition xmlns"#,
+            1,
+        );
+        let config = NacmConfig::from_xml(&both).unwrap();
+        assert_eq!(config.rule_lists[0].rules[0].condition.as_ref().unwrap().weekdays, [Weekday::Saturday]);
+    }
+
+    /// A `/nacm` edit setting the condition of the maintenance rule
+    fn condition_edit(condition: &str) -> String {
+        format!(
+            r#"<nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+                 <rule-list><name>oper-acl</name>
+                   <rule><name>maintenance</name>{}</rule>
+                 </rule-list>
+               </nacm>"#,
+            condition
+        )
+    }
+
+    #[test]
+    fn edit_replaces_the_condition_as_a_whole() {
+        let mut config = config();
+        let all_day = format!(
+            r#"<condition xmlns="{}"><time-range>00:00-24:00</time-range></condition>"#,
+            CONDITION_NAMESPACE
+        );
+        config.apply_nacm_edit(&condition_edit(&all_day)).unwrap();
+        let condition = config.rule_lists[0].rules[0].condition.as_ref().unwrap();
+        assert!(condition.weekdays.is_empty() && condition.attributes.is_empty());
+        assert_eq!(create_effect(&config, &Environment::new()), RuleEffect::Permit);
+    }
+
+    #[test]
+    fn edit_rejects_foreign_conditions() {
+        let mut config = config();
+        for foreign in [
+            r#"<condition xmlns="http://example.com/other-vendor"><time-range>00:00-24:00</time-range></condition>"#,
+            "<condition><time-range>00:00-24:00</time-range></condition>",
+        ] {
+            assert!(config.apply_nacm_edit(&condition_edit(foreign)).is_err(), "{}", foreign);
+        }
+        assert_eq!(config.rule_lists[0].rules[0].condition.as_ref().unwrap().weekdays, [Weekday::Saturday]);
+    }
+
+    #[test]
+    fn conditional_rules_shadow_nothing() {
+        let all_day = RuleCondition { time_ranges: vec![TimeRange::new(0, 1440).unwrap()], ..Default::default() };
+        let freeze = NacmConfig::builder()
+            .group("oper", ["bob"])
+            .rule_list("oper-acl", ["oper"], |rl| {
+                rl.deny().name("freeze").path("/interfaces").condition(all_day)
+                    .permit().name("edit").path("/interfaces")
+            })
+            .build()
+            .unwrap();
+        assert!(freeze.analyze().is_empty());
+        assert!(config().analyze().iter().all(|f| f.kind != FindingKind::Shadowed));
+    }
+}
@@ -0,0 +1,1026 @@
+//! # Edit Authorization
+//!
+//! A single `<edit-config>` or YANG Patch touches many data nodes, and NACM
+//! checks each of them with the access operation the edit implies for that
+//! node (RFC 8341, section 3.4.5). [`NacmConfig::authorize_edit`] derives
+//! those node-level operations from an edit payload and evaluates every one,
+//! so that a change can be pre-flighted and all denied nodes reported at once.
+//!
+//! ## Payloads
+//!
+//! Both edit encodings are supported through [`EditPayload`]:
+//!
+//! - **NETCONF**: the `<config>` element of an `<edit-config>`, with
+//!   `operation` attributes (normally `nc:operation`) where needed. A root
+//!   element with another name is itself the top-level data node.
+//! - **YANG Patch** (RFC 8072): an `ietf-yang-patch:yang-patch` JSON object
+//!   whose edit targets are relative to the datastore root
+//!   (`{+restconf}/data`), e.g. `/ietf-interfaces:interfaces/interface=eth0`.
+//!
+//! ## Derived Operations
+//!
+//! Whether a node already exists is looked up in the current datastore, if
+//! one is given. Without a datastore, every node is assumed to exist.
+//!
+//! | Edit operation     | Node does not exist                 | Node exists                                  |
+//! |--------------------|-------------------------------------|----------------------------------------------|
+//! | `create`, `insert` | create on the node and descendants  | create on the node and descendants           |
+//! | `merge`            | create on the node and descendants  | update on every leaf whose value changes     |
+//! | `replace`          | create on the node and descendants  | as merge, plus delete on dropped descendants |
+//! | `delete`           | delete on the node                  | delete on the node and its descendants       |
+//! | `remove`           | nothing                             | delete on the node and its descendants       |
+//! | `none`             | children are checked on their own   | children are checked on their own            |
+//! | `move`             | update on the node                  | update on the node                           |
+//!
+//! ## Evaluation
+//!
+//! As in the [`tree`](crate::tree) module, a node is checked with its schema
+//! path (without prefixes or list keys) and module, and a node that no rule
+//! matches falls back to its nearest ancestor that a rule matches for the
+//! same operation. Only then does the write default apply. Statistics are
+//! not updated, since nothing was actually denied yet. Rules with a
+//! [condition](crate::condition) are checked at the current time, unless an
+//! [`Environment`] is passed to [`NacmConfig::authorize_edit_with`].
+
+use crate::condition::Environment;
+use crate::restconf::{encode_key, parse_data_path, PathSegment};
+use crate::tree::{InstanceTree, ModuleResolver};
+use crate::{AccessRequest, Decision, NacmConfig, Operation, RequestContext, RuleEffect};
+use xmltree::{Element, XMLNode};
+
+/// An edit to authorize
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditPayload {
+    /// NETCONF `<config>` element of an `<edit-config>`
+    Config(Element),
+    /// RESTCONF YANG Patch (RFC 8072), JSON encoded
+    YangPatch(serde_json::Value),
+}
+
+/// Parses JSON as a YANG Patch and anything else as NETCONF XML
+impl std::str::FromStr for EditPayload {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim_start().starts_with('{') {
+            let value = serde_json::from_str(s).map_err(|e| format!("Invalid YANG Patch JSON: {}", e))?;
+            Ok(EditPayload::YangPatch(value))
+        } else {
+            let root = Element::parse(s.as_bytes()).map_err(|e| format!("Invalid edit XML: {}", e))?;
+            Ok(EditPayload::Config(root))
+        }
+    }
+}
+
+/// Edit operations of NETCONF (RFC 6241, section 7.2) and YANG Patch
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::edit::EditOperation;
+///
+/// assert_eq!("replace".parse::<EditOperation>().unwrap(), EditOperation::Replace);
+/// assert_eq!(EditOperation::default(), EditOperation::Merge);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EditOperation {
+    /// Merge into the existing data (the NETCONF default)
+    #[default]
+    Merge,
+    /// Replace the existing data
+    Replace,
+    /// Create data that must not exist yet
+    Create,
+    /// Delete data that must exist
+    Delete,
+    /// Delete data if it exists
+    Remove,
+    /// No operation on this node; only its descendants are edited
+    None,
+}
+
+impl std::str::FromStr for EditOperation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "merge" => Ok(EditOperation::Merge),
+            "replace" => Ok(EditOperation::Replace),
+            "create" => Ok(EditOperation::Create),
+            "delete" => Ok(EditOperation::Delete),
+            "remove" => Ok(EditOperation::Remove),
+            "none" => Ok(EditOperation::None),
+            _ => Err(format!("Unknown edit operation: {}", s)),
+        }
+    }
+}
+
+/// Formats as the lowercase operation name
+impl std::fmt::Display for EditOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            EditOperation::Merge => "merge",
+            EditOperation::Replace => "replace",
+            EditOperation::Create => "create",
+            EditOperation::Delete => "delete",
+            EditOperation::Remove => "remove",
+            EditOperation::None => "none",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An edit request to pre-flight
+#[derive(Debug, Clone, Copy)]
+pub struct EditRequest<'a> {
+    /// User making the edit
+    pub user: &'a str,
+    /// Request context (Tail-f extension), if any
+    pub context: Option<&'a RequestContext>,
+    /// The edit itself
+    pub payload: &'a EditPayload,
+    /// Current contents of the target datastore, if known
+    pub datastore: Option<&'a InstanceTree>,
+    /// Operation for nodes without an explicit one (NETCONF `default-operation`)
+    ///
+    /// Not used for YANG Patch, where every edit names its operation.
+    pub default_operation: EditOperation,
+}
+
+/// The access decision for one data node touched by an edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeAccess {
+    /// Instance path in RESTCONF style, without module prefixes, e.g.
+    /// `/interfaces/interface=eth0/enabled`
+    pub path: String,
+    /// YANG module of the node, if known
+    pub module: Option<String>,
+    /// Access operation required on the node
+    pub operation: Operation,
+    /// The access decision
+    pub effect: RuleEffect,
+    /// Whether the decision should be logged
+    pub should_log: bool,
+    /// Rule list of the deciding rule (None = default policy)
+    pub rule_list: Option<String>,
+    /// Name of the deciding rule (None = default policy)
+    pub rule: Option<String>,
+}
+
+/// Result of [`NacmConfig::authorize_edit`]: one decision per touched node
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EditAuthorization {
+    /// Every node-level check, in document order
+    pub nodes: Vec<NodeAccess>,
+}
+
+impl EditAuthorization {
+    /// Whether every node-level check was permitted
+    pub fn is_permitted(&self) -> bool {
+        self.nodes.iter().all(|node| node.effect == RuleEffect::Permit)
+    }
+
+    /// The denied node-level checks
+    pub fn denied(&self) -> impl Iterator<Item = &NodeAccess> {
+        self.nodes.iter().filter(|node| node.effect == RuleEffect::Deny)
+    }
+}
+
+impl NacmConfig {
+    /// Derive and check the node-level operations of an edit
+    ///
+    /// Uses a [`ModuleResolver`] that only knows IETF namespaces and checks
+    /// rule conditions at the current time; see
+    /// [`authorize_edit_with`](Self::authorize_edit_with) to supply a
+    /// resolver and an environment. See the [`edit`](crate::edit) module for
+    /// how operations are derived.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The edit, its user and the current datastore
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(EditAuthorization)` - The decision for every touched node
+    /// * `Err(String)` - The payload is malformed, e.g. an unknown
+    ///   `operation` attribute or a YANG Patch edit without a target
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nacm_validator::{NacmConfig, Operation, RuleEffect};
+    /// use nacm_validator::edit::{EditOperation, EditPayload, EditRequest};
+    /// use std::collections::HashMap;
+    ///
+    /// let config = NacmConfig {
+    ///     enable_nacm: true,
+    ///     read_default: RuleEffect::Permit,
+    ///     write_default: RuleEffect::Deny,
+    ///     exec_default: RuleEffect::Deny,
+    ///     cmd_read_default: RuleEffect::Deny,
+    ///     cmd_exec_default: RuleEffect::Deny,
+    ///     log_if_default_permit: false,
+    ///     log_if_default_deny: false,
+    ///     groups: HashMap::new(),
+    ///     rule_lists: vec![],
+    ///     stats: None,
+    /// };
+    ///
+    /// let payload: EditPayload = r#"
+    ///     <config xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"
+    ///             xmlns:nc="urn:ietf:params:xml:ns:netconf:base:1.0">
+    ///       <system xmlns="urn:ietf:params:xml:ns:yang:ietf-system">
+    ///         <hostname nc:operation="delete"/>
+    ///       </system>
+    ///     </config>"#.parse().unwrap();
+    ///
+    /// let request = EditRequest {
+    ///     user: "guest",
+    ///     context: None,
+    ///     payload: &payload,
+    ///     datastore: None,
+    ///     default_operation: EditOperation::Merge,
+    /// };
+    /// let authorization = config.authorize_edit(&request).unwrap();
+    /// assert!(!authorization.is_permitted());
+    ///
+    /// let denied: Vec<_> = authorization.denied().collect();
+    /// assert_eq!(denied[0].path, "/system/hostname");
+    /// assert_eq!(denied[0].operation, Operation::Delete);
+    /// ```
+    pub fn authorize_edit(&self, request: &EditRequest) -> Result<EditAuthorization, String> {
+        self.authorize_edit_with(request, &ModuleResolver::new(), &Environment::new())
+    }
+
+    /// Derive and check the node-level operations of an edit, resolving XML
+    /// namespaces with `resolver` and checking rule conditions in `env`
+    ///
+    /// Same as [`authorize_edit`](Self::authorize_edit) with an explicit
+    /// namespace-to-module mapping, used for both the payload and the
+    /// datastore, and the time and request attributes for
+    /// [conditional rules](crate::condition).
+    pub fn authorize_edit_with(
+        &self,
+        request: &EditRequest,
+        resolver: &ModuleResolver,
+        env: &Environment,
+    ) -> Result<EditAuthorization, String> {
+        let checks = plan_edit(request.payload, request.datastore, request.default_operation, resolver)?;
+        let nodes = checks
+            .iter()
+            .map(|check| check.evaluate(self, request.user, request.context, env))
+            .collect();
+        Ok(EditAuthorization { nodes })
+    }
+}
+
+/// Derive the node-level checks of an edit, without evaluating them
+pub(crate) fn plan_edit(
+    payload: &EditPayload,
+    datastore: Option<&InstanceTree>,
+    default_operation: EditOperation,
+    resolver: &ModuleResolver,
+) -> Result<Vec<PlannedCheck>, String> {
+    let existing = match datastore {
+        Some(datastore) => Some(datastore_nodes(datastore, resolver)?),
+        None => None,
+    };
+    let mut editor = Editor { checks: Vec::new() };
+
+    match payload {
+        EditPayload::Config(root) => {
+            let payload = if root.name == "config" {
+                xml_children(root, None, resolver)?
+            } else {
+                vec![Node::from_xml(root, None, resolver)?]
+            };
+            for node in &payload {
+                editor.edit(node, &payload, existing.as_deref(), default_operation, &mut Vec::new());
+            }
+        }
+        EditPayload::YangPatch(value) => {
+            for edit in yang_patch_edits(value)? {
+                editor.patch(&edit, existing.as_deref())?;
+            }
+        }
+    }
+    Ok(editor.checks)
+}
+
+/// A node-level check derived from an edit
+pub(crate) struct PlannedCheck {
+    /// The node and its ancestors, root first
+    frames: Vec<Frame>,
+    pub(crate) operation: Operation,
+}
+
+impl PlannedCheck {
+    /// The checked node
+    fn node(&self) -> &Frame {
+        self.frames.last().expect("a planned check has a node")
+    }
+
+    /// Schema path of the node
+    pub(crate) fn schema_path(&self) -> &str {
+        &self.node().schema
+    }
+
+    /// Module of the node, if known
+    pub(crate) fn module(&self) -> Option<&str> {
+        self.node().module.as_deref()
+    }
+
+    /// Evaluate the check, falling back to the node's ancestors
+    fn evaluate(
+        &self,
+        config: &NacmConfig,
+        user: &str,
+        context: Option<&RequestContext>,
+        env: &Environment,
+    ) -> NodeAccess {
+        let evaluate = |frame: &Frame| -> Decision {
+            let request = AccessRequest {
+                user,
+                module_name: frame.module.as_deref(),
+                rpc_name: None,
+                operation: self.operation.clone(),
+                path: Some(&frame.schema),
+                context,
+                command: None,
+            };
+            config.evaluate_with(&request, env)
+        };
+
+        // The nearest node a rule matches decides; otherwise the default does
+        let decision = self
+            .frames
+            .iter()
+            .rev()
+            .map(evaluate)
+            .find(|decision| decision.matched_rule.is_some())
+            .unwrap_or_else(|| evaluate(self.node()));
+
+        NodeAccess {
+            path: self.node().instance.clone(),
+            module: self.node().module.clone(),
+            operation: self.operation.clone(),
+            effect: decision.result.effect,
+            should_log: decision.result.should_log,
+            rule_list: decision.matched_rule.map(|m| m.rule_list.to_string()),
+            rule: decision.matched_rule.map(|m| m.rule.to_string()),
+        }
+    }
+}
+
+/// A data node of a payload or datastore, in either encoding
+#[derive(Debug)]
+struct Node {
+    name: String,
+    module: Option<String>,
+    /// Leaf value; `None` for interior nodes
+    value: Option<String>,
+    children: Vec<Node>,
+    /// Explicit `operation` attribute
+    operation: Option<EditOperation>,
+    /// Whether the node came from a JSON array
+    list_entry: bool,
+}
+
+impl Node {
+    /// Convert an XML element; elements without element children are leaves
+    fn from_xml(element: &Element, parent_module: Option<&str>, resolver: &ModuleResolver) -> Result<Node, String> {
+        // A namespace we cannot map means a module we do not know
+        let module = match &element.namespace {
+            Some(namespace) => resolver.module_for(namespace),
+            None => parent_module,
+        };
+        let operation = match element.attributes.get("operation") {
+            Some(operation) => Some(operation.parse::<EditOperation>()?),
+            None => None,
+        };
+        let children = xml_children(element, module, resolver)?;
+        let value = if children.is_empty() {
+            Some(element.get_text().map(|text| text.trim().to_string()).unwrap_or_default())
+        } else {
+            None
+        };
+
+        Ok(Node {
+            name: element.name.clone(),
+            module: module.map(str::to_string),
+            value,
+            children,
+            operation,
+            list_entry: false,
+        })
+    }
+
+    /// Convert the members of a JSON object; arrays become list entries
+    fn from_json_members(value: &serde_json::Value, parent_module: Option<&str>) -> Vec<Node> {
+        let Some(object) = value.as_object() else {
+            return Vec::new();
+        };
+
+        let mut nodes = Vec::new();
+        for (name, child) in object {
+            // RFC 7952 metadata members start with '@'
+            if name.starts_with('@') {
+                continue;
+            }
+            // RFC 7951: "module:node" starts a new module, plain names inherit it
+            let (module, name) = match name.split_once(':') {
+                Some((module, node)) => (Some(module), node),
+                None => (parent_module, name.as_str()),
+            };
+            match child {
+                serde_json::Value::Array(entries) => {
+                    nodes.extend(entries.iter().map(|entry| {
+                        let mut node = Node::from_json(name, module, entry);
+                        node.list_entry = true;
+                        node
+                    }));
+                }
+                child => nodes.push(Node::from_json(name, module, child)),
+            }
+        }
+        nodes
+    }
+
+    /// Convert one JSON member value
+    fn from_json(name: &str, module: Option<&str>, value: &serde_json::Value) -> Node {
+        let (value, children) = match value {
+            serde_json::Value::Object(_) => (None, Node::from_json_members(value, module)),
+            serde_json::Value::String(s) => (Some(s.clone()), Vec::new()),
+            // "empty" leaves are encoded as [null]
+            serde_json::Value::Null => (Some(String::new()), Vec::new()),
+            other => (Some(other.to_string()), Vec::new()),
+        };
+        Node {
+            name: name.to_string(),
+            module: module.map(str::to_string),
+            value,
+            children,
+            operation: None,
+            list_entry: false,
+        }
+    }
+
+    /// Whether the node is a leaf (or leaf-list entry)
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// The value identifying a list entry: its first leaf, or its own value
+    /// for a leaf-list entry
+    fn key(&self) -> Option<&str> {
+        if self.is_leaf() {
+            return self.value.as_deref();
+        }
+        self.children.iter().find(|child| child.is_leaf()).and_then(|child| child.value.as_deref())
+    }
+
+    /// Name of the first leaf child, which identifies list entries
+    fn key_name(&self) -> Option<&str> {
+        self.children.iter().find(|child| child.is_leaf()).map(|child| child.name.as_str())
+    }
+}
+
+/// Convert the element children of an XML element
+fn xml_children(element: &Element, module: Option<&str>, resolver: &ModuleResolver) -> Result<Vec<Node>, String> {
+    element
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            XMLNode::Element(child) => Some(Node::from_xml(child, module, resolver)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The top-level data nodes of a datastore
+fn datastore_nodes(datastore: &InstanceTree, resolver: &ModuleResolver) -> Result<Vec<Node>, String> {
+    match datastore {
+        InstanceTree::Xml(root) if root.name == "data" || root.name == "config" => xml_children(root, None, resolver),
+        InstanceTree::Xml(root) => Ok(vec![Node::from_xml(root, None, resolver)?]),
+        InstanceTree::Json(value) => {
+            let data = value.get("ietf-restconf:data").unwrap_or(value);
+            Ok(Node::from_json_members(data, None))
+        }
+    }
+}
+
+/// Whether `node` is an entry of a list or leaf-list
+///
+/// Without a schema this is a guess: JSON arrays are lists, and so is any
+/// name that occurs more than once among the payload or existing siblings.
+fn is_list_entry(node: &Node, siblings: &[Node], existing: &[Node]) -> bool {
+    let same_name = |candidate: &&Node| candidate.name == node.name;
+    node.list_entry
+        || siblings.iter().filter(same_name).count() > 1
+        || existing.iter().filter(same_name).count() > 1
+        || existing.iter().any(|candidate| candidate.name == node.name && candidate.list_entry)
+}
+
+/// Find the existing counterpart of a payload node among `existing`
+fn find_existing<'d>(node: &Node, siblings: &[Node], existing: &'d [Node]) -> Option<&'d Node> {
+    let mut candidates = existing.iter().filter(|candidate| candidate.name == node.name);
+    if !is_list_entry(node, siblings, existing) {
+        return candidates.next();
+    }
+    if node.is_leaf() {
+        return candidates.find(|candidate| candidate.value == node.value);
+    }
+    // List entries are identified by their first leaf, normally the key
+    let (key_name, key) = (node.key_name()?, node.key()?);
+    candidates.find(|candidate| candidate.key_name() == Some(key_name) && candidate.key() == Some(key))
+}
+
+/// A node on the path from the root to the node being checked
+#[derive(Debug, Clone)]
+struct Frame {
+    /// Schema path, used to evaluate rules
+    schema: String,
+    /// Instance path, used for reporting
+    instance: String,
+    module: Option<String>,
+}
+
+impl Frame {
+    /// The frame of a child of `parent`; `key` is set for list entries
+    fn child(parent: Option<&Frame>, name: &str, module: Option<&str>, key: Option<&str>) -> Frame {
+        let (schema, instance) = parent.map_or(("", ""), |p| (p.schema.as_str(), p.instance.as_str()));
+        let instance = match key {
+            Some(key) => format!("{}/{}={}", instance, name, encode_key(key)),
+            None => format!("{}/{}", instance, name),
+        };
+        Frame {
+            schema: format!("{}/{}", schema, name),
+            instance,
+            module: module.map(str::to_string),
+        }
+    }
+}
+
+/// The frame of a payload or datastore node below `parent`
+fn node_frame(parent: Option<&Frame>, node: &Node, siblings: &[Node], existing: &[Node]) -> Frame {
+    let key = if is_list_entry(node, siblings, existing) { node.key() } else { None };
+    Frame::child(parent, &node.name, node.module.as_deref(), key)
+}
+
+/// One YANG Patch edit
+struct PatchEdit {
+    edit_id: String,
+    operation: String,
+    target: Vec<PathSegment>,
+    value: Option<serde_json::Value>,
+}
+
+/// Extract the edits of a YANG Patch, with or without the outer member
+fn yang_patch_edits(value: &serde_json::Value) -> Result<Vec<PatchEdit>, String> {
+    let patch = value
+        .get("ietf-yang-patch:yang-patch")
+        .or_else(|| value.get("yang-patch"))
+        .unwrap_or(value);
+    let edits = patch
+        .get("edit")
+        .and_then(|edits| edits.as_array())
+        .ok_or("YANG Patch has no edit list")?;
+
+    edits
+        .iter()
+        .enumerate()
+        .map(|(index, edit)| {
+            let edit_id = match edit.get("edit-id") {
+                Some(serde_json::Value::String(id)) => id.clone(),
+                Some(id) => id.to_string(),
+                None => (index + 1).to_string(),
+            };
+            let field = |name: &str| {
+                edit.get(name)
+                    .and_then(|field| field.as_str())
+                    .ok_or_else(|| format!("YANG Patch edit {} has no {}", edit_id, name))
+            };
+            Ok(PatchEdit {
+                operation: field("operation")?.to_string(),
+                target: parse_data_path(field("target")?)?,
+                value: edit.get("value").cloned(),
+                edit_id,
+            })
+        })
+        .collect()
+}
+
+/// Find the existing node at a target path
+fn resolve<'d>(existing: &'d [Node], segments: &[PathSegment]) -> Option<&'d Node> {
+    let (segment, rest) = segments.split_first()?;
+    let node = existing.iter().find(|candidate| {
+        if candidate.name != segment.name {
+            return false;
+        }
+        if segment.keys.is_empty() {
+            return true;
+        }
+        if candidate.is_leaf() {
+            // A leaf-list entry is identified by its value
+            return candidate.value.as_deref() == Some(segment.keys[0].as_str());
+        }
+        // Keys come first in a list entry, in key order
+        let mut values = candidate.children.iter().filter(|child| child.is_leaf()).map(|child| child.value.as_deref());
+        segment.keys.iter().all(|key| values.next() == Some(Some(key.as_str())))
+    })?;
+    if rest.is_empty() {
+        Some(node)
+    } else {
+        resolve(&node.children, rest)
+    }
+}
+
+/// Walks an edit, collecting a check for every touched node
+struct Editor {
+    checks: Vec<PlannedCheck>,
+}
+
+impl Editor {
+    /// Plan a check of `operation` on the last frame
+    fn check(&mut self, frames: &[Frame], operation: Operation) {
+        if !frames.is_empty() {
+            self.checks.push(PlannedCheck { frames: frames.to_vec(), operation });
+        }
+    }
+
+    /// Check `operation` on a node and all of its descendants
+    ///
+    /// `existing` holds the existing siblings of the node, which help to
+    /// recognize list entries.
+    fn check_subtree(
+        &mut self,
+        node: &Node,
+        siblings: &[Node],
+        existing: &[Node],
+        operation: &Operation,
+        frames: &mut Vec<Frame>,
+    ) {
+        frames.push(node_frame(frames.last(), node, siblings, existing));
+        self.check(frames, operation.clone());
+        for child in &node.children {
+            self.check_subtree(child, &node.children, &[], operation, frames);
+        }
+        frames.pop();
+    }
+
+    /// Check delete access on the existing descendants of a deleted node
+    fn check_deleted_children(&mut self, existing: &Node, frames: &mut Vec<Frame>) {
+        for child in &existing.children {
+            self.check_subtree(child, &existing.children, &[], &Operation::Delete, frames);
+        }
+    }
+
+    /// Derive and check the operations for one payload node
+    ///
+    /// `existing` holds the existing siblings of the node, or `None` when
+    /// there is no datastore to consult.
+    fn edit(
+        &mut self,
+        node: &Node,
+        siblings: &[Node],
+        existing: Option<&[Node]>,
+        inherited: EditOperation,
+        frames: &mut Vec<Frame>,
+    ) {
+        let operation = node.operation.unwrap_or(inherited);
+        // Some(None) means the datastore is known and the node is not in it
+        let current = existing.map(|existing| find_existing(node, siblings, existing));
+
+        if matches!(operation, EditOperation::Create)
+            || matches!(operation, EditOperation::Merge | EditOperation::Replace) && matches!(current, Some(None))
+        {
+            self.check_subtree(node, siblings, existing.unwrap_or_default(), &Operation::Create, frames);
+            return;
+        }
+
+        frames.push(node_frame(frames.last(), node, siblings, existing.unwrap_or_default()));
+
+        match operation {
+            EditOperation::Delete | EditOperation::Remove => {
+                // A remove of missing data is a no-op; a delete fails anyway,
+                // but access is checked first (RFC 8341, section 3.4.5)
+                if operation == EditOperation::Delete || !matches!(current, Some(None)) {
+                    self.check(frames, Operation::Delete);
+                }
+                if let Some(Some(current)) = current {
+                    self.check_deleted_children(current, frames);
+                }
+            }
+            EditOperation::Merge | EditOperation::Replace if node.is_leaf() => {
+                let unchanged = matches!(current, Some(Some(current)) if current.value == node.value);
+                if !unchanged {
+                    self.check(frames, Operation::Update);
+                }
+            }
+            _ => {
+                let current_children = current.map(|current| current.map_or(&[][..], |c| c.children.as_slice()));
+
+                // Replace drops the existing children that the payload leaves out
+                if let (EditOperation::Replace, Some(existing_children)) = (operation, current_children) {
+                    for old in existing_children {
+                        let kept = node.children.iter().any(|new| {
+                            find_existing(new, &node.children, existing_children).is_some_and(|found| std::ptr::eq(found, old))
+                        });
+                        if !kept {
+                            self.check_subtree(old, existing_children, &[], &Operation::Delete, frames);
+                        }
+                    }
+                }
+
+                for child in &node.children {
+                    self.edit(child, &node.children, current_children, operation, frames);
+                }
+            }
+        }
+        frames.pop();
+    }
+
+    /// Derive and check the operations for one YANG Patch edit
+    fn patch(&mut self, edit: &PatchEdit, existing: Option<&[Node]>) -> Result<(), String> {
+        let Some((target, parents)) = edit.target.split_last() else {
+            return Err(format!("YANG Patch edit {} targets the datastore root", edit.edit_id));
+        };
+
+        // Frames of the target's ancestors; modules carry over like in RFC 7951
+        let mut frames = Vec::new();
+        let mut module = None;
+        for segment in parents {
+            module = segment.module.clone().or(module);
+            let key = segment.keys.first().map(String::as_str);
+            frames.push(Frame::child(frames.last(), &segment.name, module.as_deref(), key));
+        }
+        let module = target.module.clone().or(module);
+        let existing_parent = existing.map(|existing| {
+            if parents.is_empty() {
+                existing
+            } else {
+                resolve(existing, parents).map_or(&[][..], |parent| parent.children.as_slice())
+            }
+        });
+
+        let operation = match edit.operation.as_str() {
+            "move" => {
+                let key = target.keys.first().map(String::as_str);
+                frames.push(Frame::child(frames.last(), &target.name, module.as_deref(), key));
+                self.check(&frames, Operation::Update);
+                return Ok(());
+            }
+            "insert" => EditOperation::Create,
+            "none" => return Err(format!("YANG Patch edit {} has an unknown operation: none", edit.edit_id)),
+            other => other.parse()?,
+        };
+
+        if matches!(operation, EditOperation::Delete | EditOperation::Remove) {
+            let current = existing.map(|existing| resolve(existing, &edit.target));
+            if operation == EditOperation::Delete || !matches!(current, Some(None)) {
+                let key = target.keys.first().map(String::as_str);
+                frames.push(Frame::child(frames.last(), &target.name, module.as_deref(), key));
+                self.check(&frames, Operation::Delete);
+                if let Some(Some(current)) = current {
+                    self.check_deleted_children(current, &mut frames);
+                }
+            }
+            return Ok(());
+        }
+
+        // The value holds the target node itself, e.g. {"ietf-interfaces:interface": [{...}]}
+        let value = edit
+            .value
+            .as_ref()
+            .ok_or_else(|| format!("YANG Patch edit {} ({}) has no value", edit.edit_id, edit.operation))?;
+        let nodes = Node::from_json_members(value, module.as_deref());
+        if nodes.iter().any(|node| node.name != target.name) {
+            return Err(format!(
+                "YANG Patch edit {}: value does not match target node '{}'",
+                edit.edit_id, target.name
+            ));
+        }
+        for node in &nodes {
+            self.edit(node, &nodes, existing_parent, operation, &mut frames);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::{RuleCondition, TimeRange};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    /// oper may edit /interfaces but not create or delete interfaces
+    fn config() -> NacmConfig {
+        NacmConfig::from_xml(
+            r#"
+        <config xmlns="http://tail-f.com/ns/config/1.0">
+            <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+                <enable-nacm>true</enable-nacm>
+                <read-default>permit</read-default>
+                <write-default>deny</write-default>
+                <exec-default>deny</exec-default>
+                <groups>
+                    <group>
+                        <name>operators</name>
+                        <user-name>oper</user-name>
+                    </group>
+                </groups>
+                <rule-list>
+                    <name>operators</name>
+                    <group>operators</group>
+                    <rule>
+                        <name>no-new-interfaces</name>
+                        <module-name>ietf-interfaces</module-name>
+                        <path>/interfaces/interface</path>
+                        <access-operations>create delete</access-operations>
+                        <action>deny</action>
+                    </rule>
+                    <rule>
+                        <name>edit-interfaces</name>
+                        <module-name>ietf-interfaces</module-name>
+                        <path>/interfaces</path>
+                        <access-operations>create update delete</access-operations>
+                        <action>permit</action>
+                    </rule>
+                </rule-list>
+            </nacm>
+        </config>"#,
+        )
+        .unwrap()
+    }
+
+    /// eth0 (enabled, mtu 1500) and eth1 (enabled)
+    fn datastore() -> InstanceTree {
+        InstanceTree::Xml(
+            Element::parse(
+                r#"
+            <data xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+                <interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces">
+                    <interface><name>eth0</name><enabled>true</enabled><mtu>1500</mtu></interface>
+                    <interface><name>eth1</name><enabled>true</enabled></interface>
+                </interfaces>
+            </data>"#
+                    .as_bytes(),
+            )
+            .unwrap(),
+        )
+    }
+
+    /// Changes eth0 and adds eth2
+    const MERGE: &str = r#"
+            <config xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+                <interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces">
+                    <interface><name>eth0</name><enabled>false</enabled><mtu>1500</mtu></interface>
+                    <interface><name>eth2</name><enabled>true</enabled></interface>
+                </interfaces>
+            </config>"#;
+
+    fn authorize(payload: &str, datastore: Option<&InstanceTree>) -> Result<EditAuthorization, String> {
+        let payload: EditPayload = payload.parse().unwrap();
+        config().authorize_edit(&EditRequest {
+            user: "oper",
+            context: None,
+            payload: &payload,
+            datastore,
+            default_operation: EditOperation::Merge,
+        })
+    }
+
+    fn checks(authorization: &EditAuthorization) -> Vec<(&str, Operation, RuleEffect)> {
+        authorization
+            .nodes
+            .iter()
+            .map(|n| (n.path.as_str(), n.operation.clone(), n.effect))
+            .collect()
+    }
+
+    #[test]
+    fn merge_classifies_update_and_create() {
+        let authorization = authorize(MERGE, Some(&datastore())).unwrap();
+        assert_eq!(
+            checks(&authorization),
+            vec![
+                ("/interfaces/interface=eth0/enabled", Operation::Update, RuleEffect::Permit),
+                ("/interfaces/interface=eth2", Operation::Create, RuleEffect::Deny),
+                ("/interfaces/interface=eth2/name", Operation::Create, RuleEffect::Deny),
+                ("/interfaces/interface=eth2/enabled", Operation::Create, RuleEffect::Deny),
+            ]
+        );
+        assert!(!authorization.is_permitted());
+        assert_eq!(authorization.denied().count(), 3);
+        assert_eq!(authorization.denied().next().unwrap().rule.as_deref(), Some("no-new-interfaces"));
+    }
+
+    #[test]
+    fn without_datastore_every_node_exists() {
+        let authorization = authorize(MERGE, None).unwrap();
+        assert!(authorization.is_permitted());
+        assert!(authorization.nodes.iter().all(|n| n.operation == Operation::Update));
+    }
+
+    #[test]
+    fn conditional_rules_see_the_environment() {
+        // Interfaces may only be edited during the 02:00-04:00 window
+        let mut config = config();
+        config.rule_lists[0].rules[1].condition = Some(RuleCondition {
+            time_ranges: vec![TimeRange::new(120, 240).unwrap()],
+            ..Default::default()
+        });
+        let payload: EditPayload = MERGE.parse().unwrap();
+        let datastore = datastore();
+        let request = EditRequest {
+            user: "oper",
+            context: None,
+            payload: &payload,
+            datastore: Some(&datastore),
+            default_operation: EditOperation::Merge,
+        };
+        let resolver = ModuleResolver::new();
+        let day = UNIX_EPOCH + Duration::from_secs(19_875 * 86_400);
+
+        let in_window = Environment::new().at(day + Duration::from_secs(3 * 3600));
+        let authorization = config.authorize_edit_with(&request, &resolver, &in_window).unwrap();
+        assert_eq!(authorization.nodes[0].effect, RuleEffect::Permit);
+        assert_eq!(authorization.nodes[0].rule.as_deref(), Some("edit-interfaces"));
+
+        let outside = Environment::new().at(day + Duration::from_secs(12 * 3600));
+        let authorization = config.authorize_edit_with(&request, &resolver, &outside).unwrap();
+        assert_eq!(authorization.nodes[0].effect, RuleEffect::Deny);
+        assert_eq!(authorization.nodes[0].rule, None);
+    }
+
+    #[test]
+    fn replace_deletes_omitted_nodes() {
+        // The leaves fall back to the rule on their list entry
+        let payload = r#"
+            <config xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"
+                    xmlns:nc="urn:ietf:params:xml:ns:netconf:base:1.0">
+                <interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces" nc:operation="replace">
+                    <interface><name>eth0</name><enabled>true</enabled></interface>
+                </interfaces>
+            </config>"#;
+        let authorization = authorize(payload, Some(&datastore())).unwrap();
+        let denied: Vec<_> = authorization.denied().map(|n| (n.path.as_str(), n.operation.clone())).collect();
+        assert_eq!(
+            denied,
+            vec![
+                ("/interfaces/interface=eth1", Operation::Delete),
+                ("/interfaces/interface=eth1/name", Operation::Delete),
+                ("/interfaces/interface=eth1/enabled", Operation::Delete),
+                ("/interfaces/interface=eth0/mtu", Operation::Delete),
+            ]
+        );
+    }
+
+    #[test]
+    fn yang_patch_edits() {
+        // Removing a missing entry is a no-op; delete is checked
+        let payload = r#"{
+            "ietf-yang-patch:yang-patch": {
+                "patch-id": "cleanup",
+                "edit": [
+                    { "edit-id": "1", "operation": "remove", "target": "/ietf-interfaces:interfaces/interface=eth9" },
+                    { "edit-id": "2", "operation": "delete", "target": "/ietf-interfaces:interfaces/interface=eth1" },
+                    { "edit-id": "3", "operation": "merge", "target": "/ietf-interfaces:interfaces/interface=eth0",
+                      "value": { "ietf-interfaces:interface": [{ "name": "eth0", "mtu": 9000 }] } }
+                ]
+            }
+        }"#;
+        let authorization = authorize(payload, Some(&datastore())).unwrap();
+        assert_eq!(
+            checks(&authorization),
+            vec![
+                ("/interfaces/interface=eth1", Operation::Delete, RuleEffect::Deny),
+                ("/interfaces/interface=eth1/name", Operation::Delete, RuleEffect::Deny),
+                ("/interfaces/interface=eth1/enabled", Operation::Delete, RuleEffect::Deny),
+                ("/interfaces/interface=eth0/mtu", Operation::Update, RuleEffect::Permit),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_operation_is_an_error() {
+        let error = authorize(r#"<config><interfaces operation="upsert"/></config>"#, None).unwrap_err();
+        assert!(error.contains("upsert"), "{}", error);
+    }
+
+    #[test]
+    fn payload_format_is_sniffed() {
+        assert!(matches!("<config/>".parse::<EditPayload>(), Ok(EditPayload::Config(_))));
+        assert!(matches!("  {}".parse::<EditPayload>(), Ok(EditPayload::YangPatch(_))));
+        assert!("{".parse::<EditPayload>().unwrap_err().starts_with("Invalid YANG Patch JSON"));
+        assert!("<config>".parse::<EditPayload>().unwrap_err().starts_with("Invalid edit XML"));
+    }
+
+    #[test]
+    fn edit_operation_round_trips() {
+        for name in ["merge", "replace", "create", "delete", "remove", "none"] {
+            assert_eq!(name.parse::<EditOperation>().unwrap().to_string(), name);
+        }
+        assert!("upsert".parse::<EditOperation>().is_err());
+    }
+}
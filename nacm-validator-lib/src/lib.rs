@@ -8,6 +8,7 @@
 //! - Validate access requests against defined rules
 //! - Handle user groups and rule precedence
 //! - Support various operations (CRUD + exec) and path matching
+//! - Authorize YANG 1.1 actions: read on the ancestors, exec on the action node
 //!
 //! ## Tail-f ACM Extensions
 //! - **Command Rules**: Context-aware command access control (CLI, WebUI, NETCONF)
@@ -15,6 +16,21 @@
 //! - **ValidationResult**: Returns both access decision and logging indication
 //! - **Group ID Mapping**: External authentication system integration via GID
 //! - **Context Awareness**: Different access policies for different user interfaces
+//! - **Audit Logging**: Record decisions flagged `should_log` via pluggable sinks (see [`audit`])
+//! - **Statistics**: RFC 8341 denial counters with Prometheus export (see [`stats`])
+//! - **Data Tree Filtering**: Prune XML/JSON instance documents to what a user may read (see [`tree`])
+//! - **Policy Builder**: Build a validated `NacmConfig` in code (see [`builder`])
+//! - **Policy Mutation**: Insert, move, rename and delete rules and rule lists, or apply `/nacm` edits (see [`mutation`])
+//! - **Policy Merging**: Combine a base policy with per-product rule lists under a chosen conflict strategy (see [`merge`])
+//! - **Policy Simulation**: Replay a corpus of requests against a proposed policy and report what changes (see [`simulate`])
+//! - **Policy Synthesis**: Propose least-privilege permit rules from observed requests (see [`synthesize`])
+//! - **Policy Analysis**: Find conflicting, shadowed and redundant rules, each with a counterexample request (see [`analysis`])
+//! - **Conditional Rules**: Restrict rules to weekdays, daily time windows and request attributes, checked against an injectable clock (see [`condition`])
+//! - **Serialization**: `serde` feature adding `Serialize`/`Deserialize` to `NacmConfig`, its rule types,
+//!   `Operation` and `RequestContext`, for caching parsed configurations in any serde format
+//! - **Edit Authorization**: Pre-flight `<edit-config>` and YANG Patch payloads node by node (see [`edit`])
+//! - **RESTCONF Mapping**: Translate RESTCONF HTTP requests into access requests (see [`restconf`])
+//! - **NETCONF Mapping**: Translate NETCONF `<rpc>` messages into access requests (see [`netconf`])
 //!
 //! ## Quick Start
 //!
@@ -132,7 +148,32 @@
 //! ```
 
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+pub mod analysis;
+pub mod audit;
+pub mod builder;
+pub mod condition;
+pub mod edit;
+pub mod merge;
+pub mod mutation;
+pub mod netconf;
+pub mod restconf;
+pub mod simulate;
+pub mod stats;
+pub mod synthesize;
+pub mod tree;
+
+/// Re-exported so that callers build [`tree::InstanceTree`] documents with
+/// the same `xmltree` and `serde_json` versions as this crate
+pub use serde_json;
+pub use xmltree;
+
+use condition::{Environment, RuleCondition, CONDITION_NAMESPACE};
+use merge::GlobalSettings;
+use stats::{DenialCategory, NacmStats};
 
 /// NACM Rule effect (permit or deny)
 /// 
@@ -189,6 +230,45 @@ pub struct ValidationResult {
     pub should_log: bool,
 }
 
+/// Identifies the rule that produced an access control decision
+/// 
+/// Borrows the names from the `NacmConfig` that made the decision.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use nacm_validator::MatchedRule;
+/// 
+/// let matched = MatchedRule { rule_list: "admin-acl", rule: "permit-all" };
+/// assert_eq!(matched.to_string(), "admin-acl/permit-all");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MatchedRule<'a> {
+    /// Name of the rule list containing the rule
+    pub rule_list: &'a str,
+    /// Name of the rule (or command rule) within the list
+    pub rule: &'a str,
+}
+
+/// Formats as `rule-list/rule`
+impl std::fmt::Display for MatchedRule<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.rule_list, self.rule)
+    }
+}
+
+/// Access control decision together with the rule that produced it
+/// 
+/// Returned by [`NacmConfig::decide`]. `matched_rule` is `None` when no rule
+/// matched and a default policy was applied, or when NACM is disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision<'a> {
+    /// The access decision and logging flag, as returned by `validate`
+    pub result: ValidationResult,
+    /// The first matching rule, if any
+    pub matched_rule: Option<MatchedRule<'a>>,
+}
+
 /// Implementation of `FromStr` trait for `RuleEffect`
 /// 
 /// This allows parsing rule effects from strings (used when parsing XML).
@@ -264,7 +344,9 @@ pub enum RequestContext {
 impl RequestContext {
     /// Check if this context matches a pattern
     /// 
-    /// Supports wildcard matching where "*" matches any context.
+    /// Supports wildcard matching where "*" matches any context, glob
+    /// patterns such as "web*", and space-separated lists such as "cli webui".
+    /// See [`ContextPattern`] for the full syntax.
     /// 
     /// # Arguments
     /// 
@@ -273,21 +355,238 @@ impl RequestContext {
     /// # Returns
     /// 
     /// * `true` if the context matches the pattern
-    /// * `false` otherwise
+    /// * `false` otherwise (including when the pattern is empty)
     pub fn matches(&self, pattern: &str) -> bool {
-        if pattern == "*" {
-            return true;
-        }
-        
+        pattern
+            .split_whitespace()
+            .any(|alt| glob_matches_ignore_ascii_case(alt, self.name()))
+    }
+
+    /// The context name, as written by `Display`
+    fn name(&self) -> &str {
         match self {
-            RequestContext::NETCONF => pattern.eq_ignore_ascii_case("netconf"),
-            RequestContext::CLI => pattern.eq_ignore_ascii_case("cli"),
-            RequestContext::WebUI => pattern.eq_ignore_ascii_case("webui"),
-            RequestContext::Other(name) => pattern.eq_ignore_ascii_case(name),
+            RequestContext::NETCONF => "netconf",
+            RequestContext::CLI => "cli",
+            RequestContext::WebUI => "webui",
+            RequestContext::Other(name) => name,
         }
     }
 }
 
+/// Implementation of `FromStr` trait for `RequestContext`
+///
+/// The well-known contexts are recognised case-insensitively ("netconf",
+/// "cli", "webui"). Any other non-empty name, such as "restconf" or "snmp",
+/// becomes `RequestContext::Other` so that custom contexts round-trip
+/// through `Display`.
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::RequestContext;
+///
+/// assert_eq!("CLI".parse::<RequestContext>().unwrap(), RequestContext::CLI);
+/// assert_eq!(
+///     "restconf".parse::<RequestContext>().unwrap(),
+///     RequestContext::Other("restconf".to_string())
+/// );
+/// ```
+impl std::str::FromStr for RequestContext {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        if name.is_empty() {
+            return Err("Context name must not be empty".to_string());
+        }
+
+        match name.to_lowercase().as_str() {
+            "netconf" => Ok(RequestContext::NETCONF),
+            "cli" => Ok(RequestContext::CLI),
+            "webui" => Ok(RequestContext::WebUI),
+            _ => Ok(RequestContext::Other(name.to_string())),
+        }
+    }
+}
+
+/// Implementation of `Display` trait for `RequestContext`
+///
+/// Produces the context name as used in `<context>` elements of the
+/// configuration: "netconf", "cli", "webui", or the custom name itself.
+impl std::fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Serializes as the context name, like `Display`
+#[cfg(feature = "serde")]
+impl Serialize for RequestContext {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserializes from any name `FromStr` accepts
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for RequestContext {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Compiled context pattern from a rule's `<context>` element (Tail-f extension)
+///
+/// A context pattern is a space-separated list of alternatives, each of which
+/// is a glob where `*` matches any sequence of characters and `?` matches a
+/// single character. ASCII letters match regardless of case. The pattern matches a request context if
+/// any alternative matches the context name.
+///
+/// Patterns are compiled once when the configuration is loaded, so evaluation
+/// does not need to re-parse the `<context>` text for every request.
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::{ContextPattern, RequestContext};
+///
+/// let pattern: ContextPattern = "cli web*".parse().unwrap();
+/// assert!(pattern.matches(&RequestContext::CLI));
+/// assert!(pattern.matches(&RequestContext::WebUI));
+/// assert!(!pattern.matches(&RequestContext::NETCONF));
+/// assert_eq!(pattern.alternatives().collect::<Vec<_>>(), vec!["cli", "web*"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextPattern {
+    /// Normalised pattern text (alternatives joined by a single space)
+    source: String,
+    /// ASCII-lowercased glob alternatives
+    alternatives: Vec<String>,
+}
+
+impl ContextPattern {
+    /// Check if this pattern matches a request context
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The request context to test
+    ///
+    /// # Returns
+    ///
+    /// * `true` if any alternative matches the context name
+    /// * `false` otherwise
+    pub fn matches(&self, context: &RequestContext) -> bool {
+        self.alternatives
+            .iter()
+            .any(|alt| glob_matches_ignore_ascii_case(alt, context.name()))
+    }
+
+    /// Check if this pattern matches every context
+    ///
+    /// True when one of the alternatives is the bare wildcard `*`. Rules with
+    /// such a pattern also apply to requests that carry no context at all.
+    pub fn matches_any(&self) -> bool {
+        self.alternatives.iter().any(|alt| alt == "*")
+    }
+
+    /// Iterate over the individual glob alternatives of this pattern
+    pub fn alternatives(&self) -> impl Iterator<Item = &str> {
+        self.source.split(' ')
+    }
+
+    /// The pattern text in normalised form
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+/// Implementation of `FromStr` trait for `ContextPattern`
+///
+/// Splits the text on whitespace into alternatives. An empty pattern is
+/// rejected since it could never match any request.
+impl std::str::FromStr for ContextPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        if parts.is_empty() {
+            return Err("Context pattern must not be empty".to_string());
+        }
+
+        Ok(ContextPattern {
+            source: parts.join(" "),
+            alternatives: parts.iter().map(|p| p.to_ascii_lowercase()).collect(),
+        })
+    }
+}
+
+/// Implementation of `Display` trait for `ContextPattern`
+impl std::fmt::Display for ContextPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Serializes as the pattern text, e.g. `"cli web*"`
+#[cfg(feature = "serde")]
+impl Serialize for ContextPattern {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+/// Deserializes and compiles pattern text, like `FromStr`
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for ContextPattern {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        source.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Match `text` against a glob `pattern` supporting `*` and `?`
+///
+/// Uses the classic iterative algorithm with single-star backtracking, so it
+/// runs in O(pattern × text) time without recursion or allocation.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    glob_matches_by(pattern, text, |p, t| p == t)
+}
+
+/// Like [`glob_matches`], but ASCII letters match regardless of case
+fn glob_matches_ignore_ascii_case(pattern: &str, text: &str) -> bool {
+    glob_matches_by(pattern, text, |p, t| p.eq_ignore_ascii_case(&t))
+}
+
+fn glob_matches_by(pattern: &str, text: &str, eq: impl Fn(char, char) -> bool) -> bool {
+    // Byte offsets into pattern and text, always on character boundaries
+    let (mut p, mut t) = (0, 0);
+    // Position of the last '*' seen in the pattern, and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while let Some(tc) = text[t..].chars().next() {
+        let pc = pattern[p..].chars().next();
+        if let Some(pc) = pc.filter(|&pc| pc == '?' || eq(pc, tc)) {
+            p += pc.len_utf8();
+            t += tc.len_utf8();
+        } else if pc == Some('*') {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last '*' absorb one more character and retry
+            let absorbed = text[star_t..].chars().next().map_or(0, char::len_utf8);
+            backtrack = Some((star_p, star_t + absorbed));
+            p = star_p + 1;
+            t = star_t + absorbed;
+        } else {
+            return false;
+        }
+    }
+
+    // Any trailing '*'s can match the empty string
+    pattern[p..].chars().all(|c| c == '*')
+}
+
 /// Implementation of `FromStr` trait for `Operation`
 /// 
 /// Enables parsing operations from strings, used in CLI and XML parsing.
@@ -307,6 +606,39 @@ impl std::str::FromStr for Operation {
     }
 }
 
+/// Formats an operation as its lowercase NACM name, e.g. `read` or `exec`
+///
+/// The output parses back to the same operation with `FromStr`.
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operation::Read => "read",
+            Operation::Create => "create",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+            Operation::Exec => "exec",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Serializes as the lowercase name, like `Display`
+#[cfg(feature = "serde")]
+impl Serialize for Operation {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserializes from any name `FromStr` accepts
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Operation {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// NACM Rule structure (extended to match XML format)
 /// 
 /// Represents a single NACM access control rule. Each rule defines:
@@ -324,9 +656,10 @@ impl std::str::FromStr for Operation {
 /// * `access_operations` - Set of operations this rule covers
 /// * `effect` - Whether to permit or deny matching requests
 /// * `order` - Rule precedence (lower = higher priority)
-/// * `context` - Request context this rule applies to (Tail-f extension)
+/// * `context` - Request context pattern this rule applies to (Tail-f extension)
 /// * `log_if_permit` - Log when this rule permits access (Tail-f extension)
 /// * `log_if_deny` - Log when this rule denies access (Tail-f extension)
+/// * `condition` - When and for which requests the rule applies (see [`condition`])
 /// 
 /// # Examples
 /// 
@@ -348,9 +681,11 @@ impl std::str::FromStr for Operation {
 ///     context: None,
 ///     log_if_permit: false,
 ///     log_if_deny: false,
+///     condition: None,
 /// };
 /// ```
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NacmRule {
     /// Unique name for this rule
     pub name: String,
@@ -361,17 +696,21 @@ pub struct NacmRule {
     /// XPath or data path (None = any path)
     pub path: Option<String>,
     /// Set of operations covered by this rule
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_operations"))]
     pub access_operations: HashSet<Operation>,
     /// Whether this rule permits or denies access
     pub effect: RuleEffect,
     /// Rule precedence - lower numbers have higher priority
     pub order: u32,
     /// Request context pattern this rule applies to (Tail-f extension)
-    pub context: Option<String>,
+    pub context: Option<ContextPattern>,
     /// Log when this rule permits access (Tail-f extension)
     pub log_if_permit: bool,
     /// Log when this rule denies access (Tail-f extension)
     pub log_if_deny: bool,
+    /// When and for which requests the rule applies (None = always)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub condition: Option<RuleCondition>,
 }
 
 /// NACM Command Rule structure (Tail-f ACM extension)
@@ -383,7 +722,7 @@ pub struct NacmRule {
 /// # Fields
 /// 
 /// * `name` - Human-readable identifier for the command rule
-/// * `context` - Management interface pattern (e.g., "cli", "webui", "*")
+/// * `context` - Management interface pattern (e.g., "cli", "web*", "cli webui", "*")
 /// * `command` - Command pattern to match (supports wildcards)
 /// * `access_operations` - Set of command operations (read, exec)
 /// * `effect` - Whether to permit or deny matching command requests
@@ -391,6 +730,7 @@ pub struct NacmRule {
 /// * `log_if_permit` - Log when this rule permits access
 /// * `log_if_deny` - Log when this rule denies access
 /// * `comment` - Optional description of the rule
+/// * `condition` - When and for which requests the rule applies (see [`condition`])
 /// 
 /// # Examples
 /// 
@@ -404,7 +744,7 @@ pub struct NacmRule {
 /// 
 /// let cmd_rule = NacmCommandRule {
 ///     name: "cli-show-status".to_string(),
-///     context: Some("cli".to_string()),
+///     context: Some("cli".parse().unwrap()),
 ///     command: Some("show status".to_string()),
 ///     access_operations: ops,
 ///     effect: RuleEffect::Permit,
@@ -412,17 +752,20 @@ pub struct NacmRule {
 ///     log_if_permit: true,
 ///     log_if_deny: false,
 ///     comment: Some("Allow operators to view system status".to_string()),
+///     condition: None,
 /// };
 /// ```
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NacmCommandRule {
     /// Unique name for this command rule
     pub name: String,
-    /// Management interface pattern (e.g., "cli", "webui", "*")
-    pub context: Option<String>,
+    /// Management interface pattern (e.g., "cli", "web*", "cli webui", "*")
+    pub context: Option<ContextPattern>,
     /// Command pattern to match (supports wildcards)
     pub command: Option<String>,
     /// Set of command operations covered by this rule
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_operations"))]
     pub access_operations: HashSet<Operation>,
     /// Whether this rule permits or denies access
     pub effect: RuleEffect,
@@ -434,6 +777,9 @@ pub struct NacmCommandRule {
     pub log_if_deny: bool,
     /// Optional description of the rule
     pub comment: Option<String>,
+    /// When and for which requests the rule applies (None = always)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub condition: Option<RuleCondition>,
 }
 
 /// NACM Rule List with associated groups
@@ -462,6 +808,7 @@ pub struct NacmCommandRule {
 /// };
 /// ```
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NacmRuleList {
     /// Name of this rule list
     pub name: String,
@@ -496,6 +843,7 @@ pub struct NacmRuleList {
 /// };
 /// ```
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NacmGroup {
     /// Name of the group
     pub name: String,
@@ -526,6 +874,16 @@ pub struct NacmGroup {
 /// * `groups` - Map of group names to group definitions
 /// * `rule_lists` - List of rule lists, processed in order
 /// 
+/// # Serialization
+/// 
+/// With the `serde` feature, the configuration and its rule types implement
+/// `Serialize` and `Deserialize`, so a parsed policy can be cached or sent
+/// to another service in any serde format without going through XML. Field
+/// names are those of the Rust fields; operations, effects and contexts are
+/// their lowercase names. Groups and access operations are written in a
+/// fixed order, so the output is stable enough for snapshot tests. The
+/// statistics are not serialized.
+/// 
 /// # Examples
 /// 
 /// ```
@@ -543,9 +901,11 @@ pub struct NacmGroup {
 ///     log_if_default_deny: false,
 ///     groups: HashMap::new(),
 ///     rule_lists: vec![],
+///     stats: None,
 /// };
 /// ```
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NacmConfig {
     /// Global NACM enable flag - if false, all access is permitted
     pub enable_nacm: bool,
@@ -564,9 +924,17 @@ pub struct NacmConfig {
     /// Log when default policies deny access (Tail-f extension)
     pub log_if_default_deny: bool,
     /// Map of group name to group definition
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_groups"))]
     pub groups: HashMap<String, NacmGroup>,
     /// Ordered list of rule lists
     pub rule_lists: Vec<NacmRuleList>,
+    /// Denial counters updated by every validation (None = not collected)
+    /// 
+    /// Shared through an `Arc` so the counters survive cloning or replacing
+    /// the configuration. Not serialized: a restored configuration starts
+    /// without counters.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub stats: Option<Arc<NacmStats>>,
 }
 
 /// Represents an access request for validation
@@ -622,6 +990,465 @@ pub struct AccessRequest<'a> {
     pub command: Option<&'a str>,
 }
 
+/// What an access request asks for, as told from its fields
+///
+/// See [`AccessRequest::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind<'a> {
+    /// A command (Tail-f ACM extension): the request has a `command`
+    Command(&'a str),
+    /// A protocol operation (RPC): exec with an `rpc_name` and no `path`, or
+    /// a `path` that does not end in the `rpc_name`
+    Operation(&'a str),
+    /// A YANG 1.1 action: exec on the data node `path`, the action node,
+    /// without an `rpc_name` or with the action's name as `rpc_name`
+    Action {
+        /// Schema path of the action node, e.g. `/interfaces/interface/reset`
+        path: &'a str,
+        /// Action name, e.g. `reset`
+        name: &'a str,
+    },
+    /// Data node access, or a request without any target
+    Data,
+}
+
+impl<'a> AccessRequest<'a> {
+    /// Start building a request
+    ///
+    /// The user and the operation are required: `build` is only available
+    /// once both are set. Everything else defaults to `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nacm_validator::{AccessRequest, Operation, RequestContext};
+    ///
+    /// let request = AccessRequest::builder()
+    ///     .user("alice")
+    ///     .module("ietf-interfaces")
+    ///     .path("/interfaces")
+    ///     .op(Operation::Read)
+    ///     .context(&RequestContext::NETCONF)
+    ///     .build();
+    /// assert_eq!(request.module_name, Some("ietf-interfaces"));
+    /// assert_eq!(request.rpc_name, None);
+    /// ```
+    ///
+    /// Leaving out the user or the operation does not compile:
+    ///
+    /// ```compile_fail
+    /// use nacm_validator::AccessRequest;
+    ///
+    /// let request = AccessRequest::builder().user("alice").path("/interfaces").build();
+    /// ```
+    pub fn builder() -> AccessRequestBuilder<'a> {
+        AccessRequestBuilder {
+            user: (),
+            module_name: None,
+            rpc_name: None,
+            operation: (),
+            path: None,
+            context: None,
+            command: None,
+        }
+    }
+
+    /// Build a request to invoke the YANG 1.1 action at `path`
+    ///
+    /// The action name is the last step of the path. Per RFC 8341, section
+    /// 3.4.5, the request is permitted only if `user` has read access to
+    /// every ancestor of the action node and exec access to the node itself;
+    /// [`NacmConfig::validate`] checks both.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - Username invoking the action
+    /// * `module_name` - Module defining the action
+    /// * `path` - Data path of the action node
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nacm_validator::{AccessRequest, Operation, RequestKind};
+    ///
+    /// let request = AccessRequest::action("alice", Some("ietf-interfaces"), "/interfaces/interface/reset");
+    /// assert_eq!(request.operation, Operation::Exec);
+    /// assert_eq!(request.rpc_name, Some("reset"));
+    /// assert_eq!(
+    ///     request.kind(),
+    ///     RequestKind::Action { path: "/interfaces/interface/reset", name: "reset" }
+    /// );
+    /// ```
+    pub fn action(user: &'a str, module_name: Option<&'a str>, path: &'a str) -> Self {
+        AccessRequest {
+            user,
+            module_name,
+            rpc_name: Some(node_name(path_steps(path).last().copied().unwrap_or(""))),
+            operation: Operation::Exec,
+            path: Some(path),
+            context: None,
+            command: None,
+        }
+    }
+
+    /// Classify the request
+    ///
+    /// An exec request with a `path` is an action invocation if it has no
+    /// `rpc_name`, or if its `rpc_name` is the last step of the path, as
+    /// [`AccessRequest::action`] builds it. An exec request whose `rpc_name`
+    /// names anything else, e.g. `edit-config` with the path it edits, is a
+    /// protocol operation, so rules selecting that `rpc-name` still apply.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nacm_validator::{AccessRequest, Operation, RequestKind};
+    ///
+    /// let exec = AccessRequest::builder().user("alice").op(Operation::Exec);
+    /// let edit = exec.clone().rpc("edit-config").path("/interfaces").build();
+    /// assert_eq!(edit.kind(), RequestKind::Operation("edit-config"));
+    ///
+    /// let reset = exec.path("/interfaces/interface/reset").build();
+    /// assert_eq!(reset.kind(), RequestKind::Action { path: "/interfaces/interface/reset", name: "reset" });
+    /// ```
+    pub fn kind(&self) -> RequestKind<'a> {
+        if let Some(command) = self.command {
+            return RequestKind::Command(command);
+        }
+        match (&self.operation, self.path, self.rpc_name) {
+            (Operation::Exec, Some(path), rpc_name) => {
+                let name = node_name(path_steps(path).last().copied().unwrap_or(""));
+                match rpc_name {
+                    Some(rpc_name) if rpc_name != name => RequestKind::Operation(rpc_name),
+                    _ => RequestKind::Action { path, name },
+                }
+            }
+            (Operation::Exec, None, Some(rpc_name)) => RequestKind::Operation(rpc_name),
+            _ => RequestKind::Data,
+        }
+    }
+}
+
+/// Split a data path into its steps, keeping `/` inside predicates
+fn path_steps(path: &str) -> Vec<&str> {
+    let mut steps = Vec::new();
+    let mut start = 0;
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in path.char_indices() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('[', None) => depth += 1,
+            // A stray ']' is part of the step, not the end of a predicate
+            (']', None) if depth > 0 => depth -= 1,
+            ('/', None) if depth == 0 => {
+                if i > start {
+                    steps.push(&path[start..i]);
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < path.len() {
+        steps.push(&path[start..]);
+    }
+    steps
+}
+
+/// Node name of a path step, without prefix or predicates
+fn node_name(step: &str) -> &str {
+    let name = step.split('[').next().unwrap_or(step);
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// An access request derived from a protocol message
+/// 
+/// Returned by the [`restconf`] and [`netconf`] mappings. Owns its fields,
+/// since they are built from the message, and leaves out who is asking;
+/// borrow it as an [`AccessRequest`] with [`to_request`](Self::to_request).
+/// 
+/// # Examples
+/// 
+/// ```
+/// use nacm_validator::{MappedAccess, Operation, RequestContext};
+/// 
+/// let access = MappedAccess {
+///     module_name: Some("ietf-system".to_string()),
+///     rpc_name: Some("system-restart".to_string()),
+///     operation: Operation::Exec,
+///     path: None,
+/// };
+/// let request = access.to_request("alice", Some(&RequestContext::NETCONF));
+/// assert_eq!(request.rpc_name, Some("system-restart"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedAccess {
+    /// YANG module of the target node or RPC
+    pub module_name: Option<String>,
+    /// RPC or action name, for invocations
+    pub rpc_name: Option<String>,
+    /// Required access operation
+    pub operation: Operation,
+    /// Schema path of the target data node (None for RPCs)
+    pub path: Option<String>,
+}
+
+impl MappedAccess {
+    /// Borrow as an access request by `user`
+    pub fn to_request<'a>(&'a self, user: &'a str, context: Option<&'a RequestContext>) -> AccessRequest<'a> {
+        AccessRequest {
+            user,
+            module_name: self.module_name.as_deref(),
+            rpc_name: self.rpc_name.as_deref(),
+            operation: self.operation.clone(),
+            path: self.path.as_deref(),
+            context,
+            command: None,
+        }
+    }
+}
+
+/// Builder for [`AccessRequest`], created by [`AccessRequest::builder`]
+///
+/// The type parameters track whether the user (`U`) and the operation (`O`)
+/// have been set, so that a request cannot be built without them.
+#[derive(Debug, Clone)]
+pub struct AccessRequestBuilder<'a, U = (), O = ()> {
+    user: U,
+    module_name: Option<&'a str>,
+    rpc_name: Option<&'a str>,
+    operation: O,
+    path: Option<&'a str>,
+    context: Option<&'a RequestContext>,
+    command: Option<&'a str>,
+}
+
+impl<'a, U, O> AccessRequestBuilder<'a, U, O> {
+    /// Set the user making the request
+    pub fn user(self, user: &'a str) -> AccessRequestBuilder<'a, &'a str, O> {
+        AccessRequestBuilder {
+            user,
+            module_name: self.module_name,
+            rpc_name: self.rpc_name,
+            operation: self.operation,
+            path: self.path,
+            context: self.context,
+            command: self.command,
+        }
+    }
+
+    /// Set the operation being performed
+    pub fn op(self, operation: Operation) -> AccessRequestBuilder<'a, U, Operation> {
+        AccessRequestBuilder {
+            user: self.user,
+            module_name: self.module_name,
+            rpc_name: self.rpc_name,
+            operation,
+            path: self.path,
+            context: self.context,
+            command: self.command,
+        }
+    }
+
+    /// Set the YANG module being accessed
+    pub fn module(mut self, module_name: &'a str) -> Self {
+        self.module_name = Some(module_name);
+        self
+    }
+
+    /// Set the RPC (or action) name
+    pub fn rpc(mut self, rpc_name: &'a str) -> Self {
+        self.rpc_name = Some(rpc_name);
+        self
+    }
+
+    /// Set the data path being accessed
+    pub fn path(mut self, path: &'a str) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// Set the request context (Tail-f extension)
+    pub fn context(mut self, context: &'a RequestContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Set the command being executed (Tail-f extension)
+    pub fn command(mut self, command: &'a str) -> Self {
+        self.command = Some(command);
+        self
+    }
+}
+
+impl<'a> AccessRequestBuilder<'a, &'a str, Operation> {
+    /// Finish the request
+    pub fn build(self) -> AccessRequest<'a> {
+        AccessRequest {
+            user: self.user,
+            module_name: self.module_name,
+            rpc_name: self.rpc_name,
+            operation: self.operation,
+            path: self.path,
+            context: self.context,
+            command: self.command,
+        }
+    }
+}
+
+/// An access request that owns its fields
+///
+/// Unlike [`AccessRequest`], it can be stored, sent to other threads and
+/// serialized. The serde form is the JSON request format of the CLI, with
+/// absent optional fields left out:
+///
+/// ```json
+/// {"user": "alice", "module": "ietf-interfaces", "operation": "read", "path": "/interfaces", "context": "netconf"}
+/// ```
+///
+/// Operations and contexts are parsed like their `FromStr` implementations,
+/// so `"READ"` is accepted, and any unknown fields are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::{AccessRequest, OwnedAccessRequest, Operation};
+///
+/// let owned: OwnedAccessRequest =
+///     serde_json::from_str(r#"{"user": "alice", "rpc": "edit-config", "operation": "exec"}"#).unwrap();
+/// let request = owned.as_request();
+/// assert_eq!(request.rpc_name, Some("edit-config"));
+/// assert_eq!(request.operation, Operation::Exec);
+///
+/// // And back from the borrowed form
+/// assert_eq!(OwnedAccessRequest::from(&request), owned);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedAccessRequest {
+    /// Username making the access request
+    pub user: String,
+    /// YANG module name being accessed
+    #[serde(rename = "module", default, skip_serializing_if = "Option::is_none")]
+    pub module_name: Option<String>,
+    /// RPC or action name
+    #[serde(rename = "rpc", default, skip_serializing_if = "Option::is_none")]
+    pub rpc_name: Option<String>,
+    /// Type of operation being performed
+    #[serde(with = "by_name")]
+    pub operation: Operation,
+    /// Data path being accessed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Request context - Tail-f extension
+    #[serde(default, with = "by_name::option", skip_serializing_if = "Option::is_none")]
+    pub context: Option<RequestContext>,
+    /// Command being executed - Tail-f extension
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+/// Serde for `OwnedAccessRequest` fields by their `Display`/`FromStr` names
+///
+/// Requests are read and written without the `serde` feature, which only
+/// adds `Serialize`/`Deserialize` to the public types themselves.
+mod by_name {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn serialize<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<T: Display, S: Serializer>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => serializer.collect_str(value),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            T: FromStr,
+            T::Err: Display,
+            D: Deserializer<'de>,
+        {
+            Option::<String>::deserialize(deserializer)?
+                .map(|name| name.parse().map_err(serde::de::Error::custom))
+                .transpose()
+        }
+    }
+}
+
+impl OwnedAccessRequest {
+    /// Borrow as an [`AccessRequest`] for validation
+    pub fn as_request(&self) -> AccessRequest<'_> {
+        AccessRequest {
+            user: &self.user,
+            module_name: self.module_name.as_deref(),
+            rpc_name: self.rpc_name.as_deref(),
+            operation: self.operation.clone(),
+            path: self.path.as_deref(),
+            context: self.context.as_ref(),
+            command: self.command.as_deref(),
+        }
+    }
+}
+
+impl From<&AccessRequest<'_>> for OwnedAccessRequest {
+    fn from(request: &AccessRequest<'_>) -> Self {
+        OwnedAccessRequest {
+            user: request.user.to_string(),
+            module_name: request.module_name.map(str::to_string),
+            rpc_name: request.rpc_name.map(str::to_string),
+            operation: request.operation.clone(),
+            path: request.path.map(str::to_string),
+            context: request.context.cloned(),
+            command: request.command.map(str::to_string),
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedAccessRequest> for AccessRequest<'a> {
+    fn from(request: &'a OwnedAccessRequest) -> Self {
+        request.as_request()
+    }
+}
+
+/// Serialize a set of operations in NACM order (read, create, update, delete, exec)
+#[cfg(feature = "serde")]
+fn serialize_operations<S: serde::Serializer>(operations: &HashSet<Operation>, serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeSeq;
+
+    let mut seq = serializer.serialize_seq(Some(operations.len()))?;
+    for operation in DATA_OPERATIONS.iter().filter(|operation| operations.contains(operation)) {
+        seq.serialize_element(operation)?;
+    }
+    seq.end()
+}
+
+/// Serialize the groups sorted by name
+#[cfg(feature = "serde")]
+fn serialize_groups<S: serde::Serializer>(groups: &HashMap<String, NacmGroup>, serializer: S) -> Result<S::Ok, S::Error> {
+    let sorted: std::collections::BTreeMap<&String, &NacmGroup> = groups.iter().collect();
+    sorted.serialize(serializer)
+}
+
 // ============================================================================
 // XML Parsing Structures
 // ============================================================================
@@ -654,26 +1481,29 @@ struct XmlConfig {
 /// 
 /// Maps to the `<nacm>` element and contains all NACM settings:
 /// global flags, default policies, groups, and rule lists.
+/// Leaves that are left out are `None` here and take their RFC 8341
+/// defaults in [`NacmConfig`], so a file may hold nothing but rule lists to
+/// be merged into a base policy without resetting its settings.
 #[derive(Debug, Deserialize)]
 struct XmlNacm {
     /// Global NACM enable flag (XML: <enable-nacm>)
-    #[serde(rename = "enable-nacm")]
-    pub enable_nacm: bool,
+    #[serde(rename = "enable-nacm", default)]
+    pub enable_nacm: Option<bool>,
     /// Default policy for read operations (XML: <read-default>)
-    #[serde(rename = "read-default")]
-    pub read_default: String,
+    #[serde(rename = "read-default", default)]
+    pub read_default: Option<String>,
     /// Default policy for write operations (XML: <write-default>)
-    #[serde(rename = "write-default")]
-    pub write_default: String,
+    #[serde(rename = "write-default", default)]
+    pub write_default: Option<String>,
     /// Default policy for exec operations (XML: <exec-default>)
-    #[serde(rename = "exec-default")]
-    pub exec_default: String,
+    #[serde(rename = "exec-default", default)]
+    pub exec_default: Option<String>,
     /// Default policy for command read operations (XML: <cmd-read-default>) - Tail-f extension
-    #[serde(rename = "cmd-read-default", default = "default_permit")]
-    pub cmd_read_default: String,
+    #[serde(rename = "cmd-read-default", default)]
+    pub cmd_read_default: Option<String>,
     /// Default policy for command exec operations (XML: <cmd-exec-default>) - Tail-f extension
-    #[serde(rename = "cmd-exec-default", default = "default_permit")]
-    pub cmd_exec_default: String,
+    #[serde(rename = "cmd-exec-default", default)]
+    pub cmd_exec_default: Option<String>,
     /// Log when default policies permit access (XML: <log-if-default-permit/>) - Tail-f extension
     #[serde(rename = "log-if-default-permit", default)]
     pub log_if_default_permit: Option<()>,
@@ -681,23 +1511,55 @@ struct XmlNacm {
     #[serde(rename = "log-if-default-deny", default)]
     pub log_if_default_deny: Option<()>,
     /// Container for all groups (XML: <groups>)
+    #[serde(default)]
     pub groups: XmlGroups,
     /// List of rule lists (XML: <rule-list> elements)
-    #[serde(rename = "rule-list")]
+    #[serde(rename = "rule-list", default)]
     pub rule_lists: Vec<XmlRuleList>,
 }
 
-/// Default function for cmd-read-default and cmd-exec-default
-fn default_permit() -> String {
-    "permit".to_string()
+/// Operations covered by `*` in a rule's `<access-operations>`
+pub(crate) const DATA_OPERATIONS: [Operation; 5] =
+    [Operation::Read, Operation::Create, Operation::Update, Operation::Delete, Operation::Exec];
+
+/// Operations covered by `*` in a command rule's `<access-operations>` (Tail-f extension)
+pub(crate) const COMMAND_OPERATIONS: [Operation; 2] = [Operation::Read, Operation::Exec];
+
+/// Parse an `<access-operations>` value: `*` or space-separated operation names
+///
+/// Unknown names and an empty value are errors: an empty set of operations
+/// means "all operations" to rule matching, so a misspelled value must not
+/// widen the rule. The error lists every unknown name.
+pub(crate) fn parse_access_operations(text: &str, wildcard: &[Operation]) -> Result<HashSet<Operation>, String> {
+    if text.trim() == "*" {
+        return Ok(wildcard.iter().cloned().collect());
+    }
+    if text.trim().is_empty() {
+        return Err("access-operations must not be empty".to_string());
+    }
+    let mut operations = HashSet::new();
+    let mut unknown = Vec::new();
+    for name in text.split_whitespace() {
+        match name.parse() {
+            Ok(operation) => {
+                operations.insert(operation);
+            }
+            Err(_) => unknown.push(name),
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(format!("Unknown access-operations: {}", unknown.join(", ")));
+    }
+    Ok(operations)
 }
 
 /// Container for group definitions from XML
 /// 
 /// Maps to the `<groups>` element which contains multiple `<group>` elements.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct XmlGroups {
-    /// List of individual group definitions
+    /// List of individual group definitions (an empty `<groups>` has none)
+    #[serde(default)]
     pub group: Vec<XmlGroup>,
 }
 
@@ -725,8 +1587,9 @@ struct XmlGroup {
 struct XmlRuleList {
     /// Rule list name (XML: <name>)
     pub name: String,
-    /// Group this rule list applies to (XML: <group>)
-    pub group: String,
+    /// Groups this rule list applies to (XML: <group> elements, a leaf-list;
+    /// at least one is required)
+    pub group: Vec<String>,
     /// List of rules in this rule list (XML: <rule> elements)
     /// The `default` attribute provides an empty vector if no rules are specified
     #[serde(default)]
@@ -766,6 +1629,9 @@ struct XmlRule {
     /// Log when this rule denies access (XML: <log-if-deny/>) - Tail-f extension
     #[serde(rename = "log-if-deny", default)]
     pub log_if_deny: Option<()>,
+    /// When the rule applies (XML: <condition>) - vendor extension
+    #[serde(default)]
+    pub condition: Option<XmlCondition>,
 }
 
 /// Individual command access control rule from XML (Tail-f extension)
@@ -796,6 +1662,308 @@ struct XmlCommandRule {
     /// Optional description (XML: <comment>)
     #[serde(default)]
     pub comment: Option<String>,
+    /// When the command rule applies (XML: <condition>) - vendor extension
+    #[serde(default)]
+    pub condition: Option<XmlCondition>,
+}
+
+/// Rule condition from XML (vendor extension, see [`condition`])
+/// 
+/// Maps to a `<condition>` element in a `<rule>` or `<cmdrule>`.
+#[derive(Debug, Deserialize)]
+struct XmlCondition {
+    /// Days the rule applies on (XML: <weekday> elements)
+    #[serde(default)]
+    pub weekday: Vec<String>,
+    /// Daily windows the rule applies in (XML: <time-range> elements)
+    #[serde(rename = "time-range", default)]
+    pub time_range: Vec<String>,
+    /// Request attribute tests (XML: <attribute> elements)
+    #[serde(default)]
+    pub attribute: Vec<XmlAttributeMatch>,
+}
+
+/// Request attribute test from XML (XML: <attribute>)
+#[derive(Debug, Deserialize)]
+struct XmlAttributeMatch {
+    /// Attribute name (XML: <name>)
+    pub name: String,
+    /// Pattern the value must match (XML: <value>)
+    pub value: String,
+}
+
+impl XmlCondition {
+    fn parse(&self) -> Result<RuleCondition, String> {
+        let attributes: Vec<(String, String)> =
+            self.attribute.iter().map(|a| (a.name.clone(), a.value.clone())).collect();
+        RuleCondition::from_leaves(&self.weekday, &self.time_range, &attributes)
+    }
+}
+
+/// Remove `<condition>` elements outside [`CONDITION_NAMESPACE`] from rules
+///
+/// serde_xml_rs matches elements by local name only, so another vendor's
+/// `<condition>` would be read as ours and could limit or widen a rule.
+/// Like any other unknown element, it is ignored instead. Documents
+/// without a `<condition>` are passed through unparsed.
+fn without_foreign_conditions(xml_content: &str) -> Result<Cow<'_, str>, Box<dyn std::error::Error>> {
+    fn strip(element: &mut xmltree::Element) -> bool {
+        let mut stripped = false;
+        if element.name == "rule" || element.name == "cmdrule" {
+            let before = element.children.len();
+            element.children.retain(|child| match child {
+                xmltree::XMLNode::Element(child) => {
+                    child.name != "condition" || child.namespace.as_deref() == Some(CONDITION_NAMESPACE)
+                }
+                _ => true,
+            });
+            stripped = element.children.len() != before;
+        }
+        for child in element.children.iter_mut() {
+            if let xmltree::XMLNode::Element(child) = child {
+                stripped |= strip(child);
+            }
+        }
+        stripped
+    }
+
+    if !xml_content.contains("condition") {
+        return Ok(Cow::Borrowed(xml_content));
+    }
+    let mut root = xmltree::Element::parse(xml_content.as_bytes())?;
+    if !strip(&mut root) {
+        return Ok(Cow::Borrowed(xml_content));
+    }
+    let mut out = Vec::new();
+    root.write(&mut out)?;
+    Ok(Cow::Owned(String::from_utf8(out)?))
+}
+
+/// Namespace of the `ietf-netconf-acm` YANG module
+const NACM_NAMESPACE: &str = "urn:ietf:params:xml:ns:yang:ietf-netconf-acm";
+
+/// Namespace of the Tail-f ACM extensions
+const TAILF_ACM_NAMESPACE: &str = "http://tail-f.com/yang/acm";
+
+/// Escape text for use in XML element content
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// `<access-operations>` value for a set of operations
+///
+/// `None` for the empty set (the element is left out, which covers every
+/// operation), `*` when the set is the whole `wildcard`, otherwise the
+/// names in NACM order.
+fn access_operations_text(operations: &HashSet<Operation>, wildcard: &[Operation]) -> Option<String> {
+    if operations.is_empty() {
+        None
+    } else if wildcard.iter().all(|op| operations.contains(op)) {
+        Some("*".to_string())
+    } else {
+        let names: Vec<String> = DATA_OPERATIONS
+            .iter()
+            .filter(|op| operations.contains(op))
+            .map(|op| op.to_string())
+            .collect();
+        Some(names.join(" "))
+    }
+}
+
+/// Append `<name>text</name>` on its own line
+fn push_leaf(out: &mut String, indent: &str, name: &str, text: &str) {
+    out.push_str(&format!("{}<{}>{}</{}>\n", indent, name, xml_escape(text), name));
+}
+
+impl NacmRuleList {
+    /// Serialize the rule list as a `<rule-list>` XML element
+    ///
+    /// The element carries the `ietf-netconf-acm` namespace, so it can be
+    /// placed in a `<nacm>` edit (see [`NacmConfig::apply_nacm_edit`]) or a
+    /// configuration file as is. Rules are written in list order; Tail-f
+    /// extensions use the Tail-f ACM namespace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nacm_validator::{NacmConfig, Operation};
+    ///
+    /// let config = NacmConfig::builder()
+    ///     .group("oper", ["bob"])
+    ///     .rule_list("oper-acl", ["oper"], |rl| rl.permit().name("read-all").ops([Operation::Read]))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let xml = config.rule_lists[0].to_xml();
+    /// assert!(xml.starts_with("<rule-list xmlns=\"urn:ietf:params:xml:ns:yang:ietf-netconf-acm\">"));
+    /// assert!(xml.contains("<access-operations>read</access-operations>"));
+    /// ```
+    pub fn to_xml(&self) -> String {
+        let mut out = String::new();
+        self.write_xml(&mut out, "", true);
+        out
+    }
+
+    /// Append the `<rule-list>` element, indented by `indent`
+    fn write_xml(&self, out: &mut String, indent: &str, with_namespace: bool) {
+        let inner = format!("{}  ", indent);
+        let leaf_indent = format!("{}    ", indent);
+        if with_namespace {
+            out.push_str(&format!("{}<rule-list xmlns=\"{}\">\n", indent, NACM_NAMESPACE));
+        } else {
+            out.push_str(&format!("{}<rule-list>\n", indent));
+        }
+        push_leaf(out, &inner, "name", &self.name);
+        for group in &self.groups {
+            push_leaf(out, &inner, "group", group);
+        }
+
+        for rule in &self.rules {
+            out.push_str(&format!("{}<rule>\n", inner));
+            push_leaf(out, &leaf_indent, "name", &rule.name);
+            if let Some(module) = &rule.module_name {
+                push_leaf(out, &leaf_indent, "module-name", module);
+            }
+            if let Some(rpc) = &rule.rpc_name {
+                push_leaf(out, &leaf_indent, "rpc-name", rpc);
+            }
+            if let Some(path) = &rule.path {
+                push_leaf(out, &leaf_indent, "path", path);
+            }
+            if let Some(operations) = access_operations_text(&rule.access_operations, &DATA_OPERATIONS) {
+                push_leaf(out, &leaf_indent, "access-operations", &operations);
+            }
+            push_leaf(out, &leaf_indent, "action", rule_effect_name(rule.effect));
+            if let Some(context) = &rule.context {
+                out.push_str(&format!(
+                    "{}<context xmlns=\"{}\">{}</context>\n",
+                    leaf_indent,
+                    TAILF_ACM_NAMESPACE,
+                    xml_escape(&context.to_string())
+                ));
+            }
+            for (flag, name) in [(rule.log_if_permit, "log-if-permit"), (rule.log_if_deny, "log-if-deny")] {
+                if flag {
+                    out.push_str(&format!("{}<{} xmlns=\"{}\"/>\n", leaf_indent, name, TAILF_ACM_NAMESPACE));
+                }
+            }
+            if let Some(condition) = &rule.condition {
+                condition.write_xml(out, &leaf_indent);
+            }
+            out.push_str(&format!("{}</rule>\n", inner));
+        }
+
+        for rule in &self.command_rules {
+            out.push_str(&format!("{}<cmdrule xmlns=\"{}\">\n", inner, TAILF_ACM_NAMESPACE));
+            push_leaf(out, &leaf_indent, "name", &rule.name);
+            if let Some(context) = &rule.context {
+                push_leaf(out, &leaf_indent, "context", &context.to_string());
+            }
+            if let Some(command) = &rule.command {
+                push_leaf(out, &leaf_indent, "command", command);
+            }
+            if let Some(operations) = access_operations_text(&rule.access_operations, &COMMAND_OPERATIONS) {
+                push_leaf(out, &leaf_indent, "access-operations", &operations);
+            }
+            push_leaf(out, &leaf_indent, "action", rule_effect_name(rule.effect));
+            for (flag, name) in [(rule.log_if_permit, "log-if-permit"), (rule.log_if_deny, "log-if-deny")] {
+                if flag {
+                    out.push_str(&format!("{}<{}/>\n", leaf_indent, name));
+                }
+            }
+            if let Some(comment) = &rule.comment {
+                push_leaf(out, &leaf_indent, "comment", comment);
+            }
+            if let Some(condition) = &rule.condition {
+                condition.write_xml(out, &leaf_indent);
+            }
+            out.push_str(&format!("{}</cmdrule>\n", inner));
+        }
+        out.push_str(&format!("{}</rule-list>\n", indent));
+    }
+}
+
+/// Lowercase XML name of a rule effect
+fn rule_effect_name(effect: RuleEffect) -> &'static str {
+    match effect {
+        RuleEffect::Permit => "permit",
+        RuleEffect::Deny => "deny",
+    }
+}
+
+impl NacmConfig {
+    /// Serialize the configuration as an XML document
+    ///
+    /// Produces a `<config>` document in the format read by
+    /// [`from_xml`](Self::from_xml), so the result loads back into an
+    /// equivalent configuration. Groups are written in name order. Rule
+    /// `order` values are not written: they follow from the list order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nacm_validator::{NacmConfig, RuleEffect};
+    ///
+    /// let config = NacmConfig::builder()
+    ///     .read_default(RuleEffect::Deny)
+    ///     .group("admin", ["alice"])
+    ///     .rule_list("admin-acl", ["admin"], |rl| rl.permit().name("permit-all"))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let reloaded = NacmConfig::from_xml(&config.to_xml()).unwrap();
+    /// assert_eq!(reloaded.read_default, RuleEffect::Deny);
+    /// assert_eq!(reloaded.groups["admin"].users, ["alice"]);
+    /// assert_eq!(reloaded.rule_lists[0].rules[0].name, "permit-all");
+    /// ```
+    pub fn to_xml(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<config xmlns=\"http://tail-f.com/ns/config/1.0\">\n");
+        out.push_str(&format!("  <nacm xmlns=\"{}\">\n", NACM_NAMESPACE));
+        push_leaf(&mut out, "    ", "enable-nacm", &self.enable_nacm.to_string());
+        push_leaf(&mut out, "    ", "read-default", rule_effect_name(self.read_default));
+        push_leaf(&mut out, "    ", "write-default", rule_effect_name(self.write_default));
+        push_leaf(&mut out, "    ", "exec-default", rule_effect_name(self.exec_default));
+        for (effect, name) in [(self.cmd_read_default, "cmd-read-default"), (self.cmd_exec_default, "cmd-exec-default")] {
+            out.push_str(&format!(
+                "    <{} xmlns=\"{}\">{}</{}>\n",
+                name,
+                TAILF_ACM_NAMESPACE,
+                rule_effect_name(effect),
+                name
+            ));
+        }
+        for (flag, name) in [
+            (self.log_if_default_permit, "log-if-default-permit"),
+            (self.log_if_default_deny, "log-if-default-deny"),
+        ] {
+            if flag {
+                out.push_str(&format!("    <{} xmlns=\"{}\"/>\n", name, TAILF_ACM_NAMESPACE));
+            }
+        }
+
+        out.push_str("    <groups>\n");
+        let mut groups: Vec<&NacmGroup> = self.groups.values().collect();
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        for group in groups {
+            out.push_str("      <group>\n");
+            push_leaf(&mut out, "        ", "name", &group.name);
+            for user in &group.users {
+                push_leaf(&mut out, "        ", "user-name", user);
+            }
+            if let Some(gid) = group.gid {
+                out.push_str(&format!("        <gid xmlns=\"{}\">{}</gid>\n", TAILF_ACM_NAMESPACE, gid));
+            }
+            out.push_str("      </group>\n");
+        }
+        out.push_str("    </groups>\n");
+
+        for rule_list in &self.rule_lists {
+            rule_list.write_xml(&mut out, "    ", false);
+        }
+        out.push_str("  </nacm>\n</config>\n");
+        out
+    }
 }
 
 impl NacmConfig {
@@ -844,9 +2012,31 @@ impl NacmConfig {
     /// assert_eq!(config.enable_nacm, true);
     /// ```
     pub fn from_xml(xml_content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_xml_partial(xml_content).map(|(config, _)| config)
+    }
+    
+    /// Parse NACM configuration from XML string, noting the global settings it sets
+    /// 
+    /// Like [`from_xml`](Self::from_xml), but also returns which global
+    /// leaves the file actually contains. Pass them to
+    /// [`merge_partial`](Self::merge_partial) so that a file holding only
+    /// rule lists does not reset the base policy's settings to the RFC 8341
+    /// defaults filled in here.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `xml_content` - String slice containing the XML configuration
+    /// 
+    /// # Returns
+    /// 
+    /// * `Ok((NacmConfig, GlobalSettings))` - The configuration and the leaves it sets
+    /// * `Err(Box<dyn Error>)` - Parsing failed (malformed XML, unknown values, etc.)
+    pub fn from_xml_partial(xml_content: &str) -> Result<(Self, GlobalSettings), Box<dyn std::error::Error>> {
         // Step 1: Parse XML into intermediate structures
-        // serde_xml_rs automatically deserializes the XML based on our struct definitions
-        let xml_config: XmlConfig = serde_xml_rs::from_str(xml_content)?;
+        // serde_xml_rs automatically deserializes the XML based on our struct definitions.
+        // It ignores namespaces, so other vendors' <condition> elements are dropped first.
+        let xml_content = without_foreign_conditions(xml_content)?;
+        let xml_config: XmlConfig = serde_xml_rs::from_str(&xml_content)?;
         
         // Step 2: Convert XML groups to our internal representation
         // Transform from XML format to HashMap for efficient lookups
@@ -870,29 +2060,20 @@ impl NacmConfig {
             for (rule_order, xml_rule) in xml_rule_list.rule.iter().enumerate() {
                 // Step 3a: Parse access operations from string format
                 // Handle both wildcard ("*") and space-separated operation lists
-                let mut access_operations = HashSet::new();
-                if let Some(ops_str) = &xml_rule.access_operations {
-                    if ops_str.trim() == "*" {
-                        // Wildcard means all operations
-                        access_operations.insert(Operation::Read);
-                        access_operations.insert(Operation::Create);
-                        access_operations.insert(Operation::Update);
-                        access_operations.insert(Operation::Delete);
-                        access_operations.insert(Operation::Exec);
-                    } else {
-                        // Parse space-separated operation names like "read write"
-                        for op in ops_str.split_whitespace() {
-                            if let Ok(operation) = op.parse::<Operation>() {
-                                access_operations.insert(operation);
-                            }
-                            // Note: Invalid operations are silently ignored
-                        }
-                    }
-                }
+                let access_operations = match &xml_rule.access_operations {
+                    Some(ops_str) => parse_access_operations(ops_str, &DATA_OPERATIONS)?,
+                    None => HashSet::new(),
+                };
                 
                 // Step 3b: Parse the rule effect (permit/deny)
                 let effect = xml_rule.action.parse::<RuleEffect>()?;
                 
+                // Compile the context pattern once, up front (Tail-f extension)
+                let context = match &xml_rule.context {
+                    Some(pattern) => Some(pattern.parse::<ContextPattern>()?),
+                    None => None,
+                };
+                
                 // Step 3c: Create internal rule representation
                 // Calculate rule order: rule list order * 1000 + rule position
                 // This ensures rules in earlier rule lists have higher priority
@@ -906,9 +2087,10 @@ impl NacmConfig {
                     // Calculate rule priority: list_position * 1000 + rule_position
                     // This ensures proper ordering across multiple rule lists
                     order: (order_base * 1000 + rule_order) as u32,
-                    context: xml_rule.context.clone(), // Tail-f extension
+                    context, // Tail-f extension
                     log_if_permit: xml_rule.log_if_permit.is_some(), // Tail-f extension
                     log_if_deny: xml_rule.log_if_deny.is_some(), // Tail-f extension
+                    condition: xml_rule.condition.as_ref().map(XmlCondition::parse).transpose()?,
                 });
             }
             
@@ -916,33 +2098,25 @@ impl NacmConfig {
             let mut command_rules = Vec::new();
             for (cmd_rule_order, xml_cmd_rule) in xml_rule_list.cmdrule.iter().enumerate() {
                 // Parse command access operations
-                let mut cmd_access_operations = HashSet::new();
-                if let Some(ops_str) = &xml_cmd_rule.access_operations {
-                    if ops_str.trim() == "*" {
-                        // For command rules, wildcard typically means read and exec
-                        cmd_access_operations.insert(Operation::Read);
-                        cmd_access_operations.insert(Operation::Exec);
-                    } else {
-                        // Parse space-separated operation names like "read exec"
-                        for op in ops_str.split_whitespace() {
-                            if let Ok(operation) = op.parse::<Operation>() {
-                                cmd_access_operations.insert(operation);
-                            }
-                        }
-                    }
-                } else {
-                    // Default to all command operations if not specified
-                    cmd_access_operations.insert(Operation::Read);
-                    cmd_access_operations.insert(Operation::Exec);
-                }
+                // For command rules, the wildcard and the default mean read and exec
+                let cmd_access_operations = match &xml_cmd_rule.access_operations {
+                    Some(ops_str) => parse_access_operations(ops_str, &COMMAND_OPERATIONS)?,
+                    None => COMMAND_OPERATIONS.into_iter().collect(),
+                };
                 
                 // Parse command rule effect
                 let cmd_effect = xml_cmd_rule.action.parse::<RuleEffect>()?;
                 
+                // Compile the context pattern once, up front
+                let cmd_context = match &xml_cmd_rule.context {
+                    Some(pattern) => Some(pattern.parse::<ContextPattern>()?),
+                    None => None,
+                };
+                
                 // Create internal command rule representation
                 command_rules.push(NacmCommandRule {
                     name: xml_cmd_rule.name.clone(),
-                    context: xml_cmd_rule.context.clone(),
+                    context: cmd_context,
                     command: xml_cmd_rule.command.clone(),
                     access_operations: cmd_access_operations,
                     effect: cmd_effect,
@@ -950,98 +2124,289 @@ impl NacmConfig {
                     log_if_permit: xml_cmd_rule.log_if_permit.is_some(),
                     log_if_deny: xml_cmd_rule.log_if_deny.is_some(),
                     comment: xml_cmd_rule.comment.clone(),
+                    condition: xml_cmd_rule.condition.as_ref().map(XmlCondition::parse).transpose()?,
                 });
             }
             
             // Step 3d: Create the rule list with its associated group
-            // Note: XML format has single group per rule list, our internal format supports multiple
+            // <group> is a leaf-list: a rule list may apply to several groups
             rule_lists.push(NacmRuleList {
                 name: xml_rule_list.name.clone(),
-                groups: vec![xml_rule_list.group.clone()],  // Wrap single group in Vec
+                groups: xml_rule_list.group.clone(),
                 rules,
                 command_rules, // Tail-f extension
             });
         }
         
-        // Step 4: Create the final configuration object
-        // Parse default policies from strings and assemble everything
-        Ok(NacmConfig {
+        // Step 4: Collect the global settings the file sets
+        // Parse default policy strings ("permit"/"deny") to enum values
+        let parse = |leaf: &Option<String>| leaf.as_deref().map(str::parse::<RuleEffect>).transpose();
+        let settings = GlobalSettings {
             enable_nacm: xml_config.nacm.enable_nacm,
-            // Parse default policy strings ("permit"/"deny") to enum values
-            read_default: xml_config.nacm.read_default.parse()?,
-            write_default: xml_config.nacm.write_default.parse()?,
-            exec_default: xml_config.nacm.exec_default.parse()?,
-            // Parse Tail-f command default policies
-            cmd_read_default: xml_config.nacm.cmd_read_default.parse()?,
-            cmd_exec_default: xml_config.nacm.cmd_exec_default.parse()?,
-            // Parse Tail-f logging settings (empty elements become true if present)
-            log_if_default_permit: xml_config.nacm.log_if_default_permit.is_some(),
-            log_if_default_deny: xml_config.nacm.log_if_default_deny.is_some(),
+            read_default: parse(&xml_config.nacm.read_default)?,
+            write_default: parse(&xml_config.nacm.write_default)?,
+            exec_default: parse(&xml_config.nacm.exec_default)?,
+            // Tail-f command default policies
+            cmd_read_default: parse(&xml_config.nacm.cmd_read_default)?,
+            cmd_exec_default: parse(&xml_config.nacm.cmd_exec_default)?,
+            // Tail-f logging settings (empty elements are only ever set)
+            log_if_default_permit: xml_config.nacm.log_if_default_permit.map(|()| true),
+            log_if_default_deny: xml_config.nacm.log_if_default_deny.map(|()| true),
+        };
+        
+        // Step 5: Create the final configuration object
+        // Leaves the file left out take their RFC 8341 defaults
+        let config = NacmConfig {
+            enable_nacm: settings.enable_nacm.unwrap_or(true),
+            read_default: settings.read_default.unwrap_or(RuleEffect::Permit),
+            write_default: settings.write_default.unwrap_or(RuleEffect::Deny),
+            exec_default: settings.exec_default.unwrap_or(RuleEffect::Permit),
+            cmd_read_default: settings.cmd_read_default.unwrap_or(RuleEffect::Permit),
+            cmd_exec_default: settings.cmd_exec_default.unwrap_or(RuleEffect::Permit),
+            log_if_default_permit: settings.log_if_default_permit.unwrap_or(false),
+            log_if_default_deny: settings.log_if_default_deny.unwrap_or(false),
             groups,
             rule_lists,
-        })
+            stats: None,
+        };
+        Ok((config, settings))
+    }
+    
+    /// Validate an access request against the NACM configuration
+    /// 
+    /// This is the main validation function that determines whether an access
+    /// request should be permitted or denied based on the NACM rules, including
+    /// command rules from the Tail-f ACM extensions.
+    /// 
+    /// # Algorithm
+    /// 
+    /// 1. If NACM is disabled globally, permit all access
+    /// 2. Find all groups the user belongs to
+    /// 3. If this is a command request, check command rules first
+    /// 4. Otherwise, check standard NACM data access rules
+    /// 5. Sort rules by precedence (order field)
+    /// 6. Return the effect and logging info of the first matching rule
+    /// 7. If no rules match, apply the appropriate default policy
+    /// 
+    /// Rules with a condition (see [`condition`]) match only while it holds,
+    /// here at the system time and without request attributes. Use
+    /// [`validate_with`](Self::validate_with) to supply them.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `req` - The access request to validate
+    /// 
+    /// # Returns
+    /// 
+    /// * `ValidationResult` - Contains the access decision and logging flag
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use nacm_validator::{NacmConfig, AccessRequest, Operation, RequestContext, ValidationResult, RuleEffect};
+    /// 
+    /// # let config = NacmConfig {
+    /// #     enable_nacm: true,
+    /// #     read_default: RuleEffect::Deny,
+    /// #     write_default: RuleEffect::Deny,
+    /// #     exec_default: RuleEffect::Deny,
+    /// #     cmd_read_default: RuleEffect::Permit,
+    /// #     cmd_exec_default: RuleEffect::Permit,
+    /// #     log_if_default_permit: false,
+    /// #     log_if_default_deny: false,
+    /// #     groups: std::collections::HashMap::new(),
+    /// #     rule_lists: vec![],
+    /// #     stats: None,
+    /// # };
+    /// let request = AccessRequest {
+    ///     user: "alice",
+    ///     module_name: Some("ietf-interfaces"),
+    ///     rpc_name: None,
+    ///     operation: Operation::Read,
+    ///     path: Some("/interfaces"),
+    ///     context: Some(&RequestContext::NETCONF),
+    ///     command: None,
+    /// };
+    /// 
+    /// let result = config.validate(&request);
+    /// // Result contains both the access decision and logging flag
+    /// ```
+    pub fn validate(&self, req: &AccessRequest) -> ValidationResult {
+        self.decide(req).result
+    }
+    
+    /// Validate an access request and report which rule decided it
+    /// 
+    /// Performs the same evaluation as [`validate`](Self::validate) but also
+    /// returns the first matching rule, which is useful for audit trails and
+    /// for explaining a decision to the user.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `req` - The access request to validate
+    /// 
+    /// # Returns
+    /// 
+    /// * `Decision` - The validation result plus the matching rule, if any
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust
+    /// use nacm_validator::{NacmConfig, AccessRequest, Operation, RuleEffect};
+    /// 
+    /// let xml = r#"
+    /// <config xmlns="http://tail-f.com/ns/config/1.0">
+    ///   <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+    ///     <enable-nacm>true</enable-nacm>
+    ///     <read-default>deny</read-default>
+    ///     <write-default>deny</write-default>
+    ///     <exec-default>deny</exec-default>
+    ///     <groups>
+    ///       <group>
+    ///         <name>admin</name>
+    ///         <user-name>alice</user-name>
+    ///       </group>
+    ///     </groups>
+    ///     <rule-list>
+    ///       <name>admin-acl</name>
+    ///       <group>admin</group>
+    ///       <rule>
+    ///         <name>permit-all</name>
+    ///         <action>permit</action>
+    ///       </rule>
+    ///     </rule-list>
+    ///   </nacm>
+    /// </config>"#;
+    /// let config = NacmConfig::from_xml(xml).unwrap();
+    /// 
+    /// let request = AccessRequest {
+    ///     user: "alice",
+    ///     module_name: None,
+    ///     rpc_name: Some("edit-config"),
+    ///     operation: Operation::Exec,
+    ///     path: None,
+    ///     context: None,
+    ///     command: None,
+    /// };
+    /// 
+    /// let decision = config.decide(&request);
+    /// assert_eq!(decision.result.effect, RuleEffect::Permit);
+    /// assert_eq!(decision.matched_rule.unwrap().to_string(), "admin-acl/permit-all");
+    /// ```
+    pub fn decide(&self, req: &AccessRequest) -> Decision<'_> {
+        self.decide_with(req, &Environment::new())
     }
     
-    /// Validate an access request against the NACM configuration
+    /// Validate an access request in a given environment
     /// 
-    /// This is the main validation function that determines whether an access
-    /// request should be permitted or denied based on the NACM rules, including
-    /// command rules from the Tail-f ACM extensions.
+    /// Like [`validate`](Self::validate), but rule conditions are checked
+    /// against the clock and request attributes of `env` (see [`condition`]).
     /// 
-    /// # Algorithm
+    /// # Arguments
     /// 
-    /// 1. If NACM is disabled globally, permit all access
-    /// 2. Find all groups the user belongs to
-    /// 3. If this is a command request, check command rules first
-    /// 4. Otherwise, check standard NACM data access rules
-    /// 5. Sort rules by precedence (order field)
-    /// 6. Return the effect and logging info of the first matching rule
-    /// 7. If no rules match, apply the appropriate default policy
+    /// * `req` - The access request to validate
+    /// * `env` - The time and request attributes for rule conditions
+    /// 
+    /// # Returns
+    /// 
+    /// * `ValidationResult` - Contains the access decision and logging flag
+    pub fn validate_with(&self, req: &AccessRequest, env: &Environment) -> ValidationResult {
+        self.decide_with(req, env).result
+    }
+    
+    /// Validate an access request in a given environment and report which rule decided it
+    /// 
+    /// Like [`decide`](Self::decide), but rule conditions are checked
+    /// against the clock and request attributes of `env`.
     /// 
     /// # Arguments
     /// 
     /// * `req` - The access request to validate
+    /// * `env` - The time and request attributes for rule conditions
     /// 
     /// # Returns
     /// 
-    /// * `ValidationResult` - Contains the access decision and logging flag
+    /// * `Decision` - The validation result plus the matching rule, if any
     /// 
     /// # Examples
     /// 
     /// ```rust
-    /// use nacm_validator::{NacmConfig, AccessRequest, Operation, RequestContext, ValidationResult, RuleEffect};
+    /// use nacm_validator::{NacmConfig, AccessRequest, Operation, RuleEffect};
+    /// use nacm_validator::condition::{AttributeMatch, Environment, RuleCondition};
     /// 
-    /// # let config = NacmConfig {
-    /// #     enable_nacm: true,
-    /// #     read_default: RuleEffect::Deny,
-    /// #     write_default: RuleEffect::Deny,
-    /// #     exec_default: RuleEffect::Deny,
-    /// #     cmd_read_default: RuleEffect::Permit,
-    /// #     cmd_exec_default: RuleEffect::Permit,
-    /// #     log_if_default_permit: false,
-    /// #     log_if_default_deny: false,
-    /// #     groups: std::collections::HashMap::new(),
-    /// #     rule_lists: vec![],
-    /// # };
-    /// let request = AccessRequest {
-    ///     user: "alice",
-    ///     module_name: Some("ietf-interfaces"),
-    ///     rpc_name: None,
-    ///     operation: Operation::Read,
-    ///     path: Some("/interfaces"),
-    ///     context: Some(&RequestContext::NETCONF),
-    ///     command: None,
+    /// let from_lan = RuleCondition {
+    ///     attributes: vec![AttributeMatch::new("source-ip", "10.0.0.0/8").unwrap()],
+    ///     ..Default::default()
     /// };
+    /// let config = NacmConfig::builder()
+    ///     .exec_default(RuleEffect::Deny)
+    ///     .group("oper", ["bob"])
+    ///     .rule_list("oper-acl", ["oper"], |rl| rl.permit().name("lan-only").rpc("*").condition(from_lan))
+    ///     .build()
+    ///     .unwrap();
     /// 
-    /// let result = config.validate(&request);
-    /// // Result contains both the access decision and logging flag
+    /// let request = AccessRequest::builder().user("bob").op(Operation::Exec).rpc("reboot").build();
+    /// let lan = Environment::new().with_attribute("source-ip", "10.1.2.3");
+    /// assert_eq!(config.decide_with(&request, &lan).matched_rule.unwrap().to_string(), "oper-acl/lan-only");
+    /// assert_eq!(config.decide(&request).result.effect, RuleEffect::Deny);
     /// ```
-    pub fn validate(&self, req: &AccessRequest) -> ValidationResult {
+    pub fn decide_with(&self, req: &AccessRequest, env: &Environment) -> Decision<'_> {
+        let decision = self.evaluate_with(req, env);
+        
+        // Count denials for the RFC 8341 statistics (reads and commands are not counted)
+        if let Some(stats) = &self.stats {
+            if self.enable_nacm && decision.result.effect == RuleEffect::Deny {
+                if let Some(category) = DenialCategory::for_request(req) {
+                    stats.record_denial(category, req.user, decision.matched_rule);
+                }
+            }
+        }
+        
+        decision
+    }
+    
+    /// Decide whether a notification may be sent to a user
+    /// 
+    /// RFC 8341 delivers a notification only if the user has read access to
+    /// it, so the request is evaluated as a read regardless of its
+    /// `operation`. A denial is counted as `denied-notifications` in the
+    /// attached statistics.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `req` - The notification, identified by its module and path
+    /// 
+    /// # Returns
+    /// 
+    /// * `ValidationResult` - Permit means the notification may be delivered
+    pub fn validate_notification(&self, req: &AccessRequest) -> ValidationResult {
+        let read_req = AccessRequest { operation: Operation::Read, ..*req };
+        let decision = self.evaluate(&read_req);
+        
+        if let Some(stats) = &self.stats {
+            if self.enable_nacm && decision.result.effect == RuleEffect::Deny {
+                stats.record_denial(DenialCategory::Notification, req.user, decision.matched_rule);
+            }
+        }
+        
+        decision.result
+    }
+    
+    /// Evaluate a request without updating statistics, at the current time
+    fn evaluate(&self, req: &AccessRequest) -> Decision<'_> {
+        self.evaluate_with(req, &Environment::new())
+    }
+    
+    /// Evaluate a request in an environment without updating statistics
+    fn evaluate_with(&self, req: &AccessRequest, env: &Environment) -> Decision<'_> {
         // Step 1: If NACM is disabled, permit all access without logging
         if !self.enable_nacm {
-            return ValidationResult {
-                effect: RuleEffect::Permit,
-                should_log: false,
+            return Decision {
+                result: ValidationResult {
+                    effect: RuleEffect::Permit,
+                    should_log: false,
+                },
+                matched_rule: None,
             };
         }
         
@@ -1060,11 +2425,60 @@ impl NacmConfig {
         
         // Step 3: Check if this is a command request
         if req.command.is_some() {
-            return self.validate_command_request(req, &user_groups);
+            return self.validate_command_request(req, &user_groups, env);
         }
         
-        // Step 4: Standard NACM data access validation
-        self.validate_data_request(req, &user_groups)
+        // Step 4: Actions need read access on the way to the action node
+        if let RequestKind::Action { path, .. } = req.kind() {
+            return self.validate_action_request(req, path, &user_groups, env);
+        }
+
+        // Step 5: Standard NACM data access validation
+        self.validate_data_request(req, &user_groups, env)
+    }
+
+    /// Validate a YANG 1.1 action invocation (RFC 8341, section 3.4.5)
+    ///
+    /// Every ancestor of the action node is checked for read access, root
+    /// first, and the action node itself for exec access. The first denial
+    /// decides; if there is none, the exec check does. Ancestors are checked
+    /// with the module of the action, since the request names no other.
+    ///
+    /// # Arguments
+    ///
+    /// * `req` - The action request
+    /// * `path` - Data path of the action node
+    /// * `user_groups` - List of groups the user belongs to
+    /// * `env` - The time and request attributes for rule conditions
+    ///
+    /// # Returns
+    ///
+    /// * `Decision` - Contains the access decision, logging flag and matching rule
+    fn validate_action_request(
+        &self,
+        req: &AccessRequest,
+        path: &str,
+        user_groups: &[&str],
+        env: &Environment,
+    ) -> Decision<'_> {
+        let steps = path_steps(path);
+        let mut ancestor = String::new();
+        for step in steps.iter().take(steps.len().saturating_sub(1)) {
+            ancestor.push('/');
+            ancestor.push_str(step);
+            let read = AccessRequest {
+                rpc_name: None,
+                operation: Operation::Read,
+                path: Some(&ancestor),
+                ..*req
+            };
+            let decision = self.validate_data_request(&read, user_groups, env);
+            if decision.result.effect == RuleEffect::Deny {
+                return decision;
+            }
+        }
+
+        self.validate_data_request(req, user_groups, env)
     }
     
     /// Validate a command access request (Tail-f ACM extension)
@@ -1076,11 +2490,12 @@ impl NacmConfig {
     /// 
     /// * `req` - The access request containing command information
     /// * `user_groups` - List of groups the user belongs to
+    /// * `env` - The time and request attributes for rule conditions
     /// 
     /// # Returns
     /// 
-    /// * `ValidationResult` - Contains the access decision and logging flag
-    fn validate_command_request(&self, req: &AccessRequest, user_groups: &[&str]) -> ValidationResult {
+    /// * `Decision` - Contains the access decision, logging flag and matching rule
+    fn validate_command_request(&self, req: &AccessRequest, user_groups: &[&str], env: &Environment) -> Decision<'_> {
         let mut matching_cmd_rules = Vec::new();
         
         // Collect all matching command rules from applicable rule lists
@@ -1093,26 +2508,32 @@ impl NacmConfig {
             if applies {
                 // Check each command rule in this rule list
                 for cmd_rule in &rule_list.command_rules {
-                    if self.command_rule_matches(cmd_rule, req) {
-                        matching_cmd_rules.push(cmd_rule);
+                    if self.command_rule_matches(cmd_rule, req) && env.allows(cmd_rule.condition.as_ref()) {
+                        matching_cmd_rules.push((rule_list, cmd_rule));
                     }
                 }
             }
         }
         
         // Sort command rules by precedence (lower order = higher priority)
-        matching_cmd_rules.sort_by_key(|r| r.order);
+        matching_cmd_rules.sort_by_key(|(_, r)| r.order);
         
         // Return the effect of the first matching command rule
-        if let Some(cmd_rule) = matching_cmd_rules.first() {
+        if let Some((rule_list, cmd_rule)) = matching_cmd_rules.first() {
             let should_log = match cmd_rule.effect {
                 RuleEffect::Permit => cmd_rule.log_if_permit,
                 RuleEffect::Deny => cmd_rule.log_if_deny,
             };
             
-            ValidationResult {
-                effect: cmd_rule.effect,
-                should_log,
+            Decision {
+                result: ValidationResult {
+                    effect: cmd_rule.effect,
+                    should_log,
+                },
+                matched_rule: Some(MatchedRule {
+                    rule_list: &rule_list.name,
+                    rule: &cmd_rule.name,
+                }),
             }
         } else {
             // No command rules matched - apply command default policy
@@ -1126,9 +2547,12 @@ impl NacmConfig {
                 RuleEffect::Deny => self.log_if_default_deny,
             };
             
-            ValidationResult {
-                effect: default_effect,
-                should_log,
+            Decision {
+                result: ValidationResult {
+                    effect: default_effect,
+                    should_log,
+                },
+                matched_rule: None,
             }
         }
     }
@@ -1142,11 +2566,12 @@ impl NacmConfig {
     /// 
     /// * `req` - The access request containing data access information
     /// * `user_groups` - List of groups the user belongs to
+    /// * `env` - The time and request attributes for rule conditions
     /// 
     /// # Returns
     /// 
-    /// * `ValidationResult` - Contains the access decision and logging flag
-    fn validate_data_request(&self, req: &AccessRequest, user_groups: &[&str]) -> ValidationResult {
+    /// * `Decision` - Contains the access decision, logging flag and matching rule
+    fn validate_data_request(&self, req: &AccessRequest, user_groups: &[&str], env: &Environment) -> Decision<'_> {
         let mut matching_rules = Vec::new();
         
         // Collect all matching rules from applicable rule lists
@@ -1159,26 +2584,32 @@ impl NacmConfig {
             if applies {
                 // Check each rule in this rule list
                 for rule in &rule_list.rules {
-                    if self.rule_matches(rule, req) {
-                        matching_rules.push(rule);
+                    if self.rule_matches(rule, req) && env.allows(rule.condition.as_ref()) {
+                        matching_rules.push((rule_list, rule));
                     }
                 }
             }
         }
         
         // Sort rules by precedence (lower order = higher priority)
-        matching_rules.sort_by_key(|r| r.order);
+        matching_rules.sort_by_key(|(_, r)| r.order);
         
         // Return the effect of the first matching rule
-        if let Some(rule) = matching_rules.first() {
+        if let Some((rule_list, rule)) = matching_rules.first() {
             let should_log = match rule.effect {
                 RuleEffect::Permit => rule.log_if_permit,
                 RuleEffect::Deny => rule.log_if_deny,
             };
             
-            ValidationResult {
-                effect: rule.effect,
-                should_log,
+            Decision {
+                result: ValidationResult {
+                    effect: rule.effect,
+                    should_log,
+                },
+                matched_rule: Some(MatchedRule {
+                    rule_list: &rule_list.name,
+                    rule: &rule.name,
+                }),
             }
         } else {
             // No rules matched - apply default policy based on operation type
@@ -1194,9 +2625,12 @@ impl NacmConfig {
                 RuleEffect::Deny => self.log_if_default_deny,
             };
             
-            ValidationResult {
-                effect: default_effect,
-                should_log,
+            Decision {
+                result: ValidationResult {
+                    effect: default_effect,
+                    should_log,
+                },
+                matched_rule: None,
             }
         }
     }
@@ -1231,10 +2665,10 @@ impl NacmConfig {
         // Check 2: Context matching
         if let Some(rule_context) = &cmd_rule.context {
             if let Some(req_context) = req.context {
-                if !req_context.matches(rule_context) {
+                if !rule_context.matches(req_context) {
                     return false;
                 }
-            } else if rule_context != "*" {
+            } else if !rule_context.matches_any() {
                 // Rule specifies context but request has none
                 return false;
             }
@@ -1300,7 +2734,8 @@ impl NacmConfig {
     /// 
     /// * **Operations**: Rule must cover the requested operation
     /// * **Module**: Rule's module must match (or be unspecified)
-    /// * **RPC**: Rule's RPC must match (or be wildcard/unspecified)
+    /// * **RPC**: Rule's RPC must match (or be wildcard/unspecified); a
+    ///   specific RPC name never matches an action
     /// * **Path**: Rule's path must match (with wildcard support)
     /// 
     /// # Arguments
@@ -1322,10 +2757,10 @@ impl NacmConfig {
         // Check 2: Context matching (Tail-f extension)
         if let Some(rule_context) = &rule.context {
             if let Some(req_context) = req.context {
-                if !req_context.matches(rule_context) {
+                if !rule_context.matches(req_context) {
                     return false;  // Context doesn't match
                 }
-            } else if rule_context != "*" {
+            } else if !rule_context.matches_any() {
                 // Rule specifies context but request has none
                 return false;
             }
@@ -1348,6 +2783,8 @@ impl NacmConfig {
         if let Some(rule_rpc) = &rule.rpc_name {
             if rule_rpc == "*" {
                 // Wildcard matches any RPC (or no RPC)
+            } else if matches!(req.kind(), RequestKind::Action { .. }) {
+                return false;  // RPC names only select protocol operations, actions go by path
             } else if let Some(req_rpc) = req.rpc_name {
                 if rule_rpc != req_rpc {
                     return false;  // Different RPC names
@@ -1363,9 +2800,8 @@ impl NacmConfig {
             if rule_path == "/" {
                 // Root path matches everything (universal path rule)
             } else if let Some(req_path) = req.path {
-                if rule_path.ends_with("/*") {
+                if let Some(prefix) = rule_path.strip_suffix("/*") {
                     // Wildcard path: "/interfaces/*" matches "/interfaces/interface[1]"
-                    let prefix = &rule_path[..rule_path.len() - 2];
                     if !req_path.starts_with(prefix) {
                         return false;  // Path doesn't match prefix
                     }
@@ -1421,6 +2857,33 @@ mod tests {
         assert_eq!(config.read_default, RuleEffect::Deny);
         assert_eq!(config.groups.len(), 1);
         assert_eq!(config.rule_lists.len(), 1);
+
+        // <group> is a leaf-list, but a rule list still needs at least one
+        let several = xml.replace("<group>admin</group>", "<group>admin</group><group>oper</group>");
+        let config = NacmConfig::from_xml(&several).unwrap();
+        assert_eq!(config.rule_lists[0].groups, ["admin", "oper"]);
+        assert!(NacmConfig::from_xml(&xml.replace("<group>admin</group>", "")).is_err());
+
+        // A misspelled or empty access-operations must not turn into "all operations"
+        for ops in ["exce", "*,", ""] {
+            let typo = xml.replace(
+                "<access-operations>exec</access-operations>",
+                &format!("<access-operations>{}</access-operations>", ops),
+            );
+            assert!(NacmConfig::from_xml(&typo).is_err(), "{:?}", ops);
+        }
+        // Every rejected name is reported, not just the first
+        let error = parse_access_operations("read exce creat", &DATA_OPERATIONS).unwrap_err();
+        assert_eq!(error, "Unknown access-operations: exce, creat");
+
+        // <groups> may be empty
+        let empty = several.replace(
+            &several[several.find("<groups>").unwrap()..several.find("</groups>").unwrap()],
+            "<groups>",
+        );
+        let config = NacmConfig::from_xml(&empty).unwrap();
+        assert!(config.groups.is_empty());
+        assert_eq!(config.rule_lists[0].groups, ["admin", "oper"]);
     }
 
     #[test]
@@ -1623,7 +3086,7 @@ mod tests {
         
         let show_status_rule = &rule_list.command_rules[0];
         assert_eq!(show_status_rule.name, "cli-show-status");
-        assert_eq!(show_status_rule.context.as_deref(), Some("cli"));
+        assert_eq!(show_status_rule.context.as_ref().map(|c| c.as_str()), Some("cli"));
         assert_eq!(show_status_rule.command.as_deref(), Some("show status"));
         assert_eq!(show_status_rule.effect, RuleEffect::Permit);
         assert!(show_status_rule.log_if_permit);
@@ -1685,4 +3148,377 @@ mod tests {
         assert_eq!(unknown_result.effect, RuleEffect::Deny); // cmd-exec-default is deny
         assert!(unknown_result.should_log); // log-if-default-deny is true
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_request_context_round_trip() {
+        for name in ["netconf", "cli", "webui", "restconf", "snmp", "gnmi", "maapi"] {
+            let context: RequestContext = name.parse().unwrap();
+            assert_eq!(context.to_string(), name);
+            assert!(context.matches(name));
+        }
+
+        assert_eq!("NETCONF".parse::<RequestContext>().unwrap(), RequestContext::NETCONF);
+        assert_eq!("WebUI".parse::<RequestContext>().unwrap(), RequestContext::WebUI);
+        assert_eq!(
+            "gnmi".parse::<RequestContext>().unwrap(),
+            RequestContext::Other("gnmi".to_string())
+        );
+        assert!("  ".parse::<RequestContext>().is_err());
+    }
+
+    #[test]
+    fn test_context_patterns() {
+        let xml = r#"
+        <config xmlns="http://tail-f.com/ns/config/1.0">
+            <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+                <enable-nacm>true</enable-nacm>
+                <read-default>deny</read-default>
+                <write-default>deny</write-default>
+                <exec-default>deny</exec-default>
+                <cmd-read-default xmlns="http://tail-f.com/yang/acm">deny</cmd-read-default>
+                <cmd-exec-default xmlns="http://tail-f.com/yang/acm">deny</cmd-exec-default>
+                <groups>
+                    <group>
+                        <name>operators</name>
+                        <user-name>oper</user-name>
+                    </group>
+                </groups>
+                <rule-list>
+                    <name>operators</name>
+                    <group>operators</group>
+                    <rule>
+                        <name>read-interfaces-web</name>
+                        <module-name>ietf-interfaces</module-name>
+                        <access-operations>read</access-operations>
+                        <action>permit</action>
+                        <context xmlns="http://tail-f.com/yang/acm">web*</context>
+                    </rule>
+                    <cmdrule xmlns="http://tail-f.com/yang/acm">
+                        <name>show-interactive</name>
+                        <context>cli  WebUI</context>
+                        <command>show *</command>
+                        <action>permit</action>
+                    </cmdrule>
+                </rule-list>
+            </nacm>
+        </config>"#;
+
+        let config = NacmConfig::from_xml(xml).unwrap();
+        let cmd_rule = &config.rule_lists[0].command_rules[0];
+        assert_eq!(cmd_rule.context.as_ref().unwrap().to_string(), "cli WebUI");
+
+        let webui = RequestContext::WebUI;
+        let web_api = RequestContext::Other("web-api".to_string());
+        let netconf = RequestContext::NETCONF;
+
+        // Glob pattern on a data rule
+        for (context, expected) in [(&webui, RuleEffect::Permit), (&web_api, RuleEffect::Permit), (&netconf, RuleEffect::Deny)] {
+            let req = AccessRequest {
+                user: "oper",
+                module_name: Some("ietf-interfaces"),
+                rpc_name: None,
+                operation: Operation::Read,
+                path: None,
+                context: Some(context),
+                command: None,
+            };
+            assert_eq!(config.validate(&req).effect, expected, "context {}", context);
+        }
+
+        // Space-separated list on a command rule
+        for (context, expected) in [(&RequestContext::CLI, RuleEffect::Permit), (&webui, RuleEffect::Permit), (&web_api, RuleEffect::Deny)] {
+            let req = AccessRequest {
+                user: "oper",
+                module_name: None,
+                rpc_name: None,
+                operation: Operation::Read,
+                path: None,
+                context: Some(context),
+                command: Some("show status"),
+            };
+            assert_eq!(config.validate(&req).effect, expected, "context {}", context);
+        }
+
+        // A rule with a non-wildcard pattern does not apply to context-less requests
+        let no_context_req = AccessRequest {
+            user: "oper",
+            module_name: Some("ietf-interfaces"),
+            rpc_name: None,
+            operation: Operation::Read,
+            path: None,
+            context: None,
+            command: None,
+        };
+        assert_eq!(config.validate(&no_context_req).effect, RuleEffect::Deny);
+
+        assert!("".parse::<ContextPattern>().is_err());
+        assert!("* cli".parse::<ContextPattern>().unwrap().matches_any());
+        assert!(RequestContext::CLI.matches("c?i"));
+        assert!(!RequestContext::CLI.matches(""));
+
+        // ASCII letters match regardless of case; `?` is one character, not one byte
+        let restconf = RequestContext::Other("RESTCONF".to_string());
+        assert!(restconf.matches("netconf Rest*"));
+        assert!("REST*".parse::<ContextPattern>().unwrap().matches(&restconf));
+        assert!(RequestContext::Other("api-ü".to_string()).matches("API-?"));
+        assert!(!RequestContext::Other("api-ü".to_string()).matches("api-??"));
+    }
+
+    #[test]
+    fn test_decide_reports_matched_rule() {
+        use std::path::Path;
+
+        let xml_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("examples")
+            .join("data")
+            .join("aaa_ncm_init.xml");
+        let xml = std::fs::read_to_string(&xml_path).unwrap();
+        let config = NacmConfig::from_xml(&xml).unwrap();
+
+        let oper_req = AccessRequest {
+            user: "oper",
+            module_name: None,
+            rpc_name: Some("edit-config"),
+            operation: Operation::Exec,
+            path: None,
+            context: Some(&RequestContext::NETCONF),
+            command: None,
+        };
+        let decision = config.decide(&oper_req);
+        assert_eq!(decision.result, config.validate(&oper_req));
+        assert_eq!(decision.result.effect, RuleEffect::Deny);
+        assert_eq!(
+            decision.matched_rule,
+            Some(MatchedRule { rule_list: "oper", rule: "edit-config" })
+        );
+
+        // Only the "any-rpc" exec rule exists, so a read falls through to the default
+        let rpc_only = NacmConfig::from_xml(r#"
+        <config xmlns="http://tail-f.com/ns/config/1.0">
+            <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+                <enable-nacm>true</enable-nacm>
+                <read-default>deny</read-default>
+                <write-default>deny</write-default>
+                <exec-default>deny</exec-default>
+                <groups>
+                    <group>
+                        <name>admin</name>
+                        <user-name>admin</user-name>
+                    </group>
+                </groups>
+                <rule-list>
+                    <name>admin</name>
+                    <group>admin</group>
+                    <rule>
+                        <name>any-rpc</name>
+                        <rpc-name>*</rpc-name>
+                        <access-operations>exec</access-operations>
+                        <action>permit</action>
+                    </rule>
+                </rule-list>
+            </nacm>
+        </config>"#).unwrap();
+        let default_req = AccessRequest {
+            user: "admin",
+            module_name: Some("ietf-interfaces"),
+            rpc_name: None,
+            operation: Operation::Read,
+            path: Some("/interfaces"),
+            context: Some(&RequestContext::NETCONF),
+            command: None,
+        };
+        let decision = rpc_only.decide(&default_req);
+        assert_eq!(decision.result.effect, RuleEffect::Deny);
+        assert_eq!(decision.matched_rule, None);
+    }
+
+    #[test]
+    fn test_action_requests() {
+        let xml = r#"
+        <config xmlns="http://tail-f.com/ns/config/1.0">
+          <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+            <enable-nacm>true</enable-nacm>
+            <read-default>permit</read-default>
+            <write-default>deny</write-default>
+            <exec-default>deny</exec-default>
+            <groups>
+              <group><name>oper</name><user-name>bob</user-name></group>
+            </groups>
+            <rule-list>
+              <name>oper-acl</name>
+              <group>oper</group>
+              <rule>
+                <name>hide-system</name>
+                <path>/system</path>
+                <access-operations>read</access-operations>
+                <action>deny</action>
+              </rule>
+              <rule>
+                <name>reset-interfaces</name>
+                <path>/interfaces/interface/reset</path>
+                <access-operations>exec</access-operations>
+                <action>permit</action>
+              </rule>
+              <rule>
+                <name>restart-system</name>
+                <path>/system/restart</path>
+                <access-operations>exec</access-operations>
+                <action>permit</action>
+              </rule>
+              <rule>
+                <name>reset-rpc</name>
+                <rpc-name>reset</rpc-name>
+                <action>permit</action>
+              </rule>
+            </rule-list>
+          </nacm>
+        </config>"#;
+        let config = NacmConfig::from_xml(xml).unwrap();
+
+        // Exec on a path is an action; predicates may contain slashes
+        let action = || AccessRequest::action("bob", None, "/interfaces/interface[name='eth0/1']/reset");
+        assert_eq!(action().rpc_name, Some("reset"));
+        assert_eq!(
+            AccessRequest { rpc_name: None, ..action() }.kind(),
+            RequestKind::Action { path: "/interfaces/interface[name='eth0/1']/reset", name: "reset" }
+        );
+        assert_eq!(AccessRequest { path: None, ..action() }.kind(), RequestKind::Operation("reset"));
+        assert_eq!(
+            AccessRequest { rpc_name: Some("edit-config"), ..action() }.kind(),
+            RequestKind::Operation("edit-config")
+        );
+        assert_eq!(AccessRequest { operation: Operation::Read, ..action() }.kind(), RequestKind::Data);
+
+        // Readable ancestors and an exec rule on the action node permit
+        let request = AccessRequest::action("bob", None, "/interfaces/interface/reset");
+        let decision = config.decide(&request);
+        assert_eq!(decision.result.effect, RuleEffect::Permit);
+        assert_eq!(decision.matched_rule.unwrap().to_string(), "oper-acl/reset-interfaces");
+
+        // An unreadable ancestor denies, whatever the exec rules say
+        let decision = config.decide(&AccessRequest::action("bob", None, "/system/restart"));
+        assert_eq!(decision.result.effect, RuleEffect::Deny);
+        assert_eq!(decision.matched_rule.unwrap().to_string(), "oper-acl/hide-system");
+
+        // A rule naming an RPC does not select an action of the same name
+        let decision = config.decide(&AccessRequest::action("bob", None, "/hardware/component/reset"));
+        assert_eq!(decision.result.effect, RuleEffect::Deny);
+        assert!(decision.matched_rule.is_none());
+        let rpc = AccessRequest { path: None, ..AccessRequest::action("bob", None, "/reset") };
+        assert_eq!(config.validate(&rpc).effect, RuleEffect::Permit);
+
+        // An RPC request that also names a path still matches its rpc-name rule
+        let rpc_with_path = AccessRequest { rpc_name: Some("reset"), ..AccessRequest::action("bob", None, "/hardware") };
+        assert_eq!(rpc_with_path.kind(), RequestKind::Operation("reset"));
+        let decision = config.decide(&rpc_with_path);
+        assert_eq!(decision.result.effect, RuleEffect::Permit);
+        assert_eq!(decision.matched_rule.unwrap().to_string(), "oper-acl/reset-rpc");
+
+        // Mapped RESTCONF action invocations are checked the same way
+        let accesses = crate::restconf::map_request("POST", "/restconf/data/ietf-system:system/restart", None).unwrap();
+        assert_eq!(config.validate(&accesses[0].to_request("bob", None)).effect, RuleEffect::Deny);
+    }
+
+    /// oper's edit-config over NETCONF, built with the builder
+    fn builder_request() -> AccessRequest<'static> {
+        AccessRequest::builder()
+            .op(Operation::Exec)
+            .user("oper")
+            .rpc("edit-config")
+            .context(&RequestContext::NETCONF)
+            .build()
+    }
+
+    #[test]
+    fn test_request_builder_matches_literal() {
+        let xml = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/aaa_ncm_init.xml"))
+            .unwrap();
+        let config = NacmConfig::from_xml(&xml).unwrap();
+        let request = builder_request();
+        let literal = AccessRequest {
+            user: "oper",
+            module_name: None,
+            rpc_name: Some("edit-config"),
+            operation: Operation::Exec,
+            path: None,
+            context: Some(&RequestContext::NETCONF),
+            command: None,
+        };
+        assert_eq!(OwnedAccessRequest::from(&request), OwnedAccessRequest::from(&literal));
+        assert_eq!(config.validate(&request), config.validate(&literal));
+    }
+
+    #[test]
+    fn test_owned_request_json_format() {
+        // Owned requests serialize in the CLI's JSON request format
+        let owned = OwnedAccessRequest::from(&builder_request());
+        let json = serde_json::to_string(&owned).unwrap();
+        assert_eq!(json, r#"{"user":"oper","rpc":"edit-config","operation":"exec","context":"netconf"}"#);
+        assert_eq!(serde_json::from_str::<OwnedAccessRequest>(&json).unwrap(), owned);
+    }
+
+    #[test]
+    fn test_owned_request_parsing() {
+        // Names parse like FromStr; unknown fields such as "id" are ignored
+        let parsed: OwnedAccessRequest = serde_json::from_str(
+            r#"{"id": 7, "user": "oper", "operation": "EXEC", "rpc": "edit-config", "context": "NETCONF"}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed, OwnedAccessRequest::from(&builder_request()));
+        let error = serde_json::from_str::<OwnedAccessRequest>(r#"{"user": "oper", "operation": "write"}"#).unwrap_err();
+        assert!(error.to_string().contains("Unknown operation: write"));
+    }
+
+    #[test]
+    fn test_owned_request_is_send() {
+        let xml = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/aaa_ncm_init.xml"))
+            .unwrap();
+        let config = NacmConfig::from_xml(&xml).unwrap();
+        let owned = OwnedAccessRequest::from(&builder_request());
+        let handle = std::thread::spawn(move || config.validate(&owned.as_request()).effect);
+        assert_eq!(handle.join().unwrap(), RuleEffect::Deny);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let xml = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/tailf_acm_example.xml"))
+            .unwrap();
+        let config = NacmConfig {
+            stats: Some(Arc::new(NacmStats::new())),
+            ..NacmConfig::from_xml(&xml).unwrap()
+        };
+
+        // Stable output: the same config always serializes the same way
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(json, serde_json::to_string(&NacmConfig::from_xml(&xml).unwrap()).unwrap());
+        assert!(json.contains(r#""read_default":"deny""#));
+        assert!(!json.contains("stats"));
+
+        let restored: NacmConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+        assert!(restored.stats.is_none());
+
+        // Context patterns are compiled again on the way in
+        let rule = &restored.rule_lists[0].command_rules[0];
+        assert_eq!(rule.context, config.rule_lists[0].command_rules[0].context);
+
+        let requests = [
+            AccessRequest::builder().user("alice").op(Operation::Read).context(&RequestContext::CLI).command("show status").build(),
+            AccessRequest::builder().user("admin").op(Operation::Exec).rpc("edit-config").build(),
+            AccessRequest::builder().user("alice").op(Operation::Update).module("ietf-interfaces").path("/interfaces").build(),
+        ];
+        for request in &requests {
+            assert_eq!(restored.validate(request), config.validate(request));
+        }
+
+        let invalid = json.replacen(r#""read_default":"deny""#, r#""read_default":"maybe""#, 1);
+        assert!(serde_json::from_str::<NacmConfig>(&invalid).is_err());
+
+        // Operations and contexts use their names, as in requests
+        assert_eq!(serde_json::to_string(&Operation::Exec).unwrap(), r#""exec""#);
+        assert_eq!(serde_json::from_str::<RequestContext>(r#""cli""#).unwrap(), RequestContext::CLI);
+        assert!(serde_json::from_str::<Operation>(r#""execute""#).is_err());
+    }
+
+}
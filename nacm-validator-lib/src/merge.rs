@@ -0,0 +1,510 @@
+//! # Policy Merging
+//!
+//! Combines NACM configurations that are maintained separately, such as a
+//! platform-wide base policy and per-product rule lists, into the single
+//! configuration that is enforced.
+//!
+//! [`NacmConfig::merge`] folds one configuration into another. What happens
+//! on conflicts is chosen with a [`MergeStrategy`]:
+//!
+//! - **Global settings** (`enable-nacm`, the `*-default` leaves and the
+//!   default logging flags) follow [`DefaultsMerge`]: keep the base value,
+//!   take the other value, or take the stricter of the two.
+//! - **Groups** are always combined: a group defined on both sides gets the
+//!   union of its members, base members first. A conflicting `gid` is
+//!   resolved like the global settings, except that "strictest" keeps the
+//!   base value.
+//! - **Rule lists** follow [`RuleListMerge`]: the other configuration's
+//!   rule lists go after the base ones, before them, or replace base rule
+//!   lists of the same name.
+//!
+//! Afterwards the `order` of all rules is recomputed from list positions,
+//! as in [`NacmConfig::from_xml`]. A merge that fails leaves the base
+//! configuration untouched.
+//!
+//! Only the global settings the other configuration sets take part. A
+//! [`NacmConfig`] always has every setting, so [`NacmConfig::merge`] treats
+//! them all as set. A file read with [`NacmConfig::from_xml_partial`] comes
+//! with the [`GlobalSettings`] it actually contains; merge it with
+//! [`NacmConfig::merge_partial`] so that a file holding only rule lists
+//! leaves the base settings alone.
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::{NacmConfig, Operation, RuleEffect};
+//! use nacm_validator::merge::{MergeStrategy, RuleListMerge};
+//!
+//! let mut base = NacmConfig::builder()
+//!     .group("admin", ["alice"])
+//!     .rule_list("admin-acl", ["admin"], |rl| rl.permit().name("permit-all"))
+//!     .build()
+//!     .unwrap();
+//!
+//! let product = NacmConfig::builder()
+//!     .read_default(RuleEffect::Deny)
+//!     .group("admin", ["bob"])
+//!     .rule_list("product-acl", ["admin"], |rl| rl.deny().module("product").ops([Operation::Update]))
+//!     .build()
+//!     .unwrap();
+//!
+//! base.merge(product, &MergeStrategy::default()).unwrap();
+//!
+//! // The stricter read default wins and the group has both members
+//! assert_eq!(base.read_default, RuleEffect::Deny);
+//! assert_eq!(base.groups["admin"].users, ["alice", "bob"]);
+//! // Appended rule lists rank below the existing ones
+//! assert_eq!(base.rule_lists[1].name, "product-acl");
+//! assert!(base.rule_lists[0].rules[0].order < base.rule_lists[1].rules[0].order);
+//!
+//! // Merging the same rule list again is only allowed when replacing by name
+//! let again = NacmConfig::builder()
+//!     .group("admin", ["bob"])
+//!     .rule_list("product-acl", ["admin"], |rl| rl.permit().module("product"))
+//!     .build()
+//!     .unwrap();
+//! assert!(base.clone().merge(again.clone(), &MergeStrategy::default()).is_err());
+//!
+//! let replace = MergeStrategy { rule_lists: RuleListMerge::Replace, ..MergeStrategy::default() };
+//! base.merge(again, &replace).unwrap();
+//! assert_eq!(base.rule_lists.len(), 2);
+//! assert_eq!(base.rule_lists[1].rules[0].effect, RuleEffect::Permit);
+//! ```
+
+use crate::mutation::{renumber, MutationError};
+use crate::{NacmConfig, RuleEffect};
+use std::str::FromStr;
+
+/// How conflicting global settings are resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DefaultsMerge {
+    /// The base configuration's settings stay as they are
+    Keep,
+    /// The other configuration's settings replace the base ones
+    Override,
+    /// The stricter setting wins: `deny` over `permit`, NACM enabled over
+    /// disabled, and logging on over off
+    #[default]
+    Strictest,
+}
+
+impl FromStr for DefaultsMerge {
+    type Err = String;
+
+    /// Parse "keep", "override" or "strictest" (case-insensitive)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(DefaultsMerge::Keep),
+            "override" => Ok(DefaultsMerge::Override),
+            "strictest" => Ok(DefaultsMerge::Strictest),
+            _ => Err(format!("Unknown defaults merge: {}", s)),
+        }
+    }
+}
+
+/// Where the other configuration's rule lists end up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RuleListMerge {
+    /// After the base rule lists (lower precedence); a name that exists on
+    /// both sides is an error
+    #[default]
+    Append,
+    /// Before the base rule lists (higher precedence); a name that exists on
+    /// both sides is an error
+    Prepend,
+    /// A rule list with the name of a base rule list replaces it in place;
+    /// the others are appended
+    Replace,
+}
+
+impl FromStr for RuleListMerge {
+    type Err = String;
+
+    /// Parse "append", "prepend" or "replace" (case-insensitive)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "append" => Ok(RuleListMerge::Append),
+            "prepend" => Ok(RuleListMerge::Prepend),
+            "replace" => Ok(RuleListMerge::Replace),
+            _ => Err(format!("Unknown rule list merge: {}", s)),
+        }
+    }
+}
+
+/// How [`NacmConfig::merge`] resolves conflicts
+///
+/// The default takes the strictest global settings and appends rule lists,
+/// so a merged-in configuration can never loosen the base defaults or
+/// outrank the base rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MergeStrategy {
+    /// Resolution of `enable-nacm`, the defaults and the logging flags
+    pub defaults: DefaultsMerge,
+    /// Placement of the other configuration's rule lists
+    pub rule_lists: RuleListMerge,
+}
+
+/// The global settings a configuration sets
+///
+/// `None` marks a leaf that was left out, which then keeps the base value
+/// in [`NacmConfig::merge_partial`]. The logging flags are empty leaves in
+/// XML, so a file can only ever set them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GlobalSettings {
+    /// `enable-nacm`
+    pub enable_nacm: Option<bool>,
+    /// `read-default`
+    pub read_default: Option<RuleEffect>,
+    /// `write-default`
+    pub write_default: Option<RuleEffect>,
+    /// `exec-default`
+    pub exec_default: Option<RuleEffect>,
+    /// `cmd-read-default` (Tail-f extension)
+    pub cmd_read_default: Option<RuleEffect>,
+    /// `cmd-exec-default` (Tail-f extension)
+    pub cmd_exec_default: Option<RuleEffect>,
+    /// `log-if-default-permit` (Tail-f extension)
+    pub log_if_default_permit: Option<bool>,
+    /// `log-if-default-deny` (Tail-f extension)
+    pub log_if_default_deny: Option<bool>,
+}
+
+impl From<&NacmConfig> for GlobalSettings {
+    /// Every setting of `config`, all of them set
+    fn from(config: &NacmConfig) -> Self {
+        GlobalSettings {
+            enable_nacm: Some(config.enable_nacm),
+            read_default: Some(config.read_default),
+            write_default: Some(config.write_default),
+            exec_default: Some(config.exec_default),
+            cmd_read_default: Some(config.cmd_read_default),
+            cmd_exec_default: Some(config.cmd_exec_default),
+            log_if_default_permit: Some(config.log_if_default_permit),
+            log_if_default_deny: Some(config.log_if_default_deny),
+        }
+    }
+}
+
+/// Resolve one global setting, leaving the base value alone when `other` is unset
+fn resolve<T: Copy>(base: &mut T, other: Option<T>, defaults: DefaultsMerge, stricter: fn(T, T) -> T) {
+    if let Some(other) = other {
+        match defaults {
+            DefaultsMerge::Keep => {}
+            DefaultsMerge::Override => *base = other,
+            DefaultsMerge::Strictest => *base = stricter(*base, other),
+        }
+    }
+}
+
+/// The stricter of two effects
+fn stricter(a: RuleEffect, b: RuleEffect) -> RuleEffect {
+    if a == RuleEffect::Deny || b == RuleEffect::Deny {
+        RuleEffect::Deny
+    } else {
+        RuleEffect::Permit
+    }
+}
+
+impl NacmConfig {
+    /// Merge another configuration into this one
+    ///
+    /// See the [module documentation](crate::merge) for the exact rules.
+    /// Statistics stay with this configuration; those of `other` are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Configuration to fold in
+    /// * `strategy` - How to resolve conflicts
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The configurations were merged
+    /// * `Err(MutationError::DuplicateRuleList)` - Appending or prepending a
+    ///   rule list whose name exists already; nothing was changed
+    pub fn merge(&mut self, other: NacmConfig, strategy: &MergeStrategy) -> Result<(), MutationError> {
+        let settings = GlobalSettings::from(&other);
+        self.merge_partial(other, &settings, strategy)
+    }
+
+    /// Merge another configuration into this one, taking only the given global settings
+    ///
+    /// Like [`merge`](Self::merge), but the global settings of `other` are
+    /// ignored in favour of `settings`, usually the leaves its file set as
+    /// returned by [`from_xml_partial`](Self::from_xml_partial).
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Configuration to fold in
+    /// * `settings` - The global settings of `other` that take part
+    /// * `strategy` - How to resolve conflicts
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The configurations were merged
+    /// * `Err(MutationError::DuplicateRuleList)` - Appending or prepending a
+    ///   rule list whose name exists already; nothing was changed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nacm_validator::{NacmConfig, RuleEffect};
+    /// use nacm_validator::merge::{DefaultsMerge, MergeStrategy};
+    ///
+    /// let mut base = NacmConfig::builder().write_default(RuleEffect::Permit).build().unwrap();
+    /// let (rules_only, settings) = NacmConfig::from_xml_partial(r#"
+    ///     <config xmlns="http://tail-f.com/ns/config/1.0">
+    ///       <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+    ///         <rule-list><name>product</name><group>admin</group></rule-list>
+    ///       </nacm>
+    ///     </config>"#).unwrap();
+    ///
+    /// // The file's write default is filled in, but it never set one
+    /// assert_eq!(rules_only.write_default, RuleEffect::Deny);
+    /// assert_eq!(settings.write_default, None);
+    ///
+    /// base.merge_partial(rules_only, &settings, &MergeStrategy::default()).unwrap();
+    /// assert_eq!(base.write_default, RuleEffect::Permit);
+    /// ```
+    pub fn merge_partial(
+        &mut self,
+        other: NacmConfig,
+        settings: &GlobalSettings,
+        strategy: &MergeStrategy,
+    ) -> Result<(), MutationError> {
+        if strategy.rule_lists != RuleListMerge::Replace {
+            if let Some(duplicate) = other
+                .rule_lists
+                .iter()
+                .find(|rl| self.rule_lists.iter().any(|existing| existing.name == rl.name))
+            {
+                return Err(MutationError::DuplicateRuleList(duplicate.name.clone()));
+            }
+        }
+
+        let defaults = strategy.defaults;
+        let either = |a: bool, b: bool| a || b;
+        resolve(&mut self.enable_nacm, settings.enable_nacm, defaults, either);
+        resolve(&mut self.read_default, settings.read_default, defaults, stricter);
+        resolve(&mut self.write_default, settings.write_default, defaults, stricter);
+        resolve(&mut self.exec_default, settings.exec_default, defaults, stricter);
+        resolve(&mut self.cmd_read_default, settings.cmd_read_default, defaults, stricter);
+        resolve(&mut self.cmd_exec_default, settings.cmd_exec_default, defaults, stricter);
+        resolve(&mut self.log_if_default_permit, settings.log_if_default_permit, defaults, either);
+        resolve(&mut self.log_if_default_deny, settings.log_if_default_deny, defaults, either);
+
+        for (name, group) in other.groups {
+            let Some(existing) = self.groups.get_mut(&name) else {
+                self.groups.insert(name, group);
+                continue;
+            };
+            for user in group.users {
+                if !existing.users.contains(&user) {
+                    existing.users.push(user);
+                }
+            }
+            if existing.gid.is_none() || strategy.defaults == DefaultsMerge::Override && group.gid.is_some() {
+                existing.gid = group.gid;
+            }
+        }
+
+        match strategy.rule_lists {
+            RuleListMerge::Append => self.rule_lists.extend(other.rule_lists),
+            RuleListMerge::Prepend => {
+                let base = std::mem::replace(&mut self.rule_lists, other.rule_lists);
+                self.rule_lists.extend(base);
+            }
+            RuleListMerge::Replace => {
+                for rule_list in other.rule_lists {
+                    match self.rule_lists.iter_mut().find(|existing| existing.name == rule_list.name) {
+                        Some(existing) => *existing = rule_list,
+                        None => self.rule_lists.push(rule_list),
+                    }
+                }
+            }
+        }
+        renumber(&mut self.rule_lists);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccessRequest, Operation, RequestContext};
+
+    /// The example policy, with a gid on the oper group
+    fn base() -> NacmConfig {
+        let xml = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/aaa_ncm_init.xml"))
+            .unwrap();
+        let mut base = NacmConfig::from_xml(&xml).unwrap();
+        base.groups.get_mut("oper").unwrap().gid = Some(20);
+        base
+    }
+
+    /// A product policy with looser writes, an overlapping group and a rule
+    /// list named like one of the base ones
+    fn product() -> NacmConfig {
+        NacmConfig::builder()
+            .write_default(RuleEffect::Permit)
+            .group_with_gid("oper", 30, ["oper", "dave"])
+            .group("product", ["erin"])
+            .rule_list("product-acl", ["product"], |rl| rl.permit().module("product"))
+            .rule_list("admin", ["oper"], |rl| rl.deny().name("only-rule"))
+            .build()
+            .unwrap()
+    }
+
+    fn names(config: &NacmConfig) -> Vec<&str> {
+        config.rule_lists.iter().map(|rl| rl.name.as_str()).collect()
+    }
+
+    /// A file holding nothing but one rule list
+    const RULES_ONLY: &str = r#"
+        <config xmlns="http://tail-f.com/ns/config/1.0">
+          <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+            <rule-list><name>product</name><group>admin</group></rule-list>
+          </nacm>
+        </config>"#;
+
+    fn strategy(defaults: DefaultsMerge) -> MergeStrategy {
+        MergeStrategy { defaults, ..MergeStrategy::default() }
+    }
+
+    #[test]
+    fn partial_parse_reports_only_present_leaves() {
+        let (config, settings) = NacmConfig::from_xml_partial(RULES_ONLY).unwrap();
+        assert_eq!(settings, GlobalSettings::default());
+        assert!(config.enable_nacm);
+        assert_eq!(config.write_default, RuleEffect::Deny);
+
+        let (_, settings) = NacmConfig::from_xml_partial(
+            r#"<config xmlns="http://tail-f.com/ns/config/1.0">
+                 <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+                   <enable-nacm>false</enable-nacm>
+                   <exec-default>deny</exec-default>
+                   <log-if-default-deny/>
+                 </nacm>
+               </config>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            settings,
+            GlobalSettings {
+                enable_nacm: Some(false),
+                exec_default: Some(RuleEffect::Deny),
+                log_if_default_deny: Some(true),
+                ..GlobalSettings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn unset_leaves_keep_the_base_settings() {
+        let base = NacmConfig::builder().write_default(RuleEffect::Permit).build().unwrap();
+        let update = AccessRequest::builder()
+            .user("alice")
+            .op(Operation::Update)
+            .context(&RequestContext::NETCONF)
+            .build();
+        assert_eq!(base.validate(&update).effect, RuleEffect::Permit);
+
+        for defaults in [DefaultsMerge::Keep, DefaultsMerge::Override, DefaultsMerge::Strictest] {
+            let mut merged = base.clone();
+            let (rules_only, settings) = NacmConfig::from_xml_partial(RULES_ONLY).unwrap();
+            merged.merge_partial(rules_only, &settings, &strategy(defaults)).unwrap();
+            assert_eq!(merged.write_default, RuleEffect::Permit, "{:?}", defaults);
+            assert_eq!(merged.validate(&update).effect, RuleEffect::Permit, "{:?}", defaults);
+            assert_eq!(merged.rule_lists[0].name, "product");
+        }
+    }
+
+    #[test]
+    fn set_leaves_follow_the_strategy() {
+        let base = NacmConfig::builder().build().unwrap();
+        let other = NacmConfig::builder().build().unwrap();
+        let settings = GlobalSettings {
+            enable_nacm: Some(false),
+            read_default: Some(RuleEffect::Deny),
+            ..GlobalSettings::default()
+        };
+
+        let mut kept = base.clone();
+        kept.merge_partial(other.clone(), &settings, &strategy(DefaultsMerge::Keep)).unwrap();
+        assert!(kept.enable_nacm);
+        assert_eq!(kept.read_default, RuleEffect::Permit);
+
+        // Only an explicit override can disable NACM
+        let mut overridden = base.clone();
+        overridden.merge_partial(other.clone(), &settings, &strategy(DefaultsMerge::Override)).unwrap();
+        assert!(!overridden.enable_nacm);
+        assert_eq!(overridden.read_default, RuleEffect::Deny);
+
+        let mut strictest = base;
+        strictest.merge_partial(other, &settings, &strategy(DefaultsMerge::Strictest)).unwrap();
+        assert!(strictest.enable_nacm);
+        assert_eq!(strictest.read_default, RuleEffect::Deny);
+    }
+
+    #[test]
+    fn whole_configurations_set_every_leaf() {
+        let mut base = NacmConfig::builder().write_default(RuleEffect::Permit).build().unwrap();
+        let other = NacmConfig { enable_nacm: false, ..NacmConfig::builder().build().unwrap() };
+        assert_eq!(GlobalSettings::from(&other).write_default, Some(RuleEffect::Deny));
+
+        base.merge(other, &strategy(DefaultsMerge::Override)).unwrap();
+        assert!(!base.enable_nacm);
+        assert_eq!(base.write_default, RuleEffect::Deny);
+    }
+
+    #[test]
+    fn duplicate_rule_list_is_rejected_without_changes() {
+        let base = base();
+        let mut merged = base.clone();
+        assert_eq!(
+            merged.merge(product(), &MergeStrategy::default()),
+            Err(MutationError::DuplicateRuleList("admin".to_string()))
+        );
+        assert_eq!(merged.rule_lists.len(), base.rule_lists.len());
+        assert_eq!(merged.groups.len(), base.groups.len());
+    }
+
+    #[test]
+    fn strictest_replace_keeps_deny_and_unions_groups() {
+        let mut merged = base();
+        let replace = MergeStrategy { rule_lists: RuleListMerge::Replace, ..MergeStrategy::default() };
+        merged.merge(product(), &replace).unwrap();
+
+        assert_eq!(merged.write_default, RuleEffect::Deny);
+        assert!(merged.log_if_default_permit);
+        // Groups are unioned and the base gid is kept
+        assert_eq!(merged.groups["oper"].users, ["oper", "public", "dave"]);
+        assert_eq!(merged.groups["oper"].gid, Some(20));
+        assert_eq!(merged.groups["product"].users, ["erin"]);
+        // The admin rule list is replaced in place, the new one appended
+        assert_eq!(names(&merged), ["oper", "admin", "any-group", "product-acl"]);
+        assert_eq!(merged.rule_lists[1].rules.len(), 1);
+        assert_eq!(merged.rule_lists[1].rules[0].order, 1000);
+        assert_eq!(merged.rule_lists[3].rules[0].order, 3000);
+    }
+
+    #[test]
+    fn override_prepend_takes_other_settings_and_ranks_first() {
+        let mut merged = base();
+        merged.delete_rule_list("admin").unwrap();
+        let strategy = MergeStrategy { defaults: DefaultsMerge::Override, rule_lists: RuleListMerge::Prepend };
+        merged.merge(product(), &strategy).unwrap();
+
+        assert_eq!(merged.write_default, RuleEffect::Permit);
+        assert!(!merged.log_if_default_permit);
+        assert_eq!(merged.groups["oper"].gid, Some(30));
+        assert_eq!(names(&merged), ["product-acl", "admin", "oper", "any-group"]);
+        assert_eq!(merged.rule_lists[2].rules[0].order, 2000);
+    }
+
+    #[test]
+    fn strategies_parse_case_insensitively() {
+        assert_eq!("Strictest".parse::<DefaultsMerge>(), Ok(DefaultsMerge::Strictest));
+        assert_eq!("replace".parse::<RuleListMerge>(), Ok(RuleListMerge::Replace));
+        assert!("merge".parse::<RuleListMerge>().is_err());
+    }
+}
@@ -0,0 +1,1256 @@
+//! # Policy Mutation
+//!
+//! Incremental changes to a live [`NacmConfig`], mirroring what NETCONF
+//! edits on the `/nacm` subtree do to the running configuration:
+//!
+//! - rule lists: [`insert_rule_list`](NacmConfig::insert_rule_list),
+//!   [`move_rule_list`](NacmConfig::move_rule_list),
+//!   [`delete_rule_list`](NacmConfig::delete_rule_list) and
+//!   [`rename_rule_list`](NacmConfig::rename_rule_list)
+//! - rules within a rule list: [`insert_rule`](NacmConfig::insert_rule),
+//!   [`move_rule`](NacmConfig::move_rule),
+//!   [`delete_rule`](NacmConfig::delete_rule) and
+//!   [`rename_rule`](NacmConfig::rename_rule)
+//! - command rules within a rule list (Tail-f extension):
+//!   [`insert_command_rule`](NacmConfig::insert_command_rule),
+//!   [`move_command_rule`](NacmConfig::move_command_rule),
+//!   [`delete_command_rule`](NacmConfig::delete_command_rule) and
+//!   [`rename_command_rule`](NacmConfig::rename_command_rule)
+//! - groups: [`insert_group`](NacmConfig::insert_group),
+//!   [`delete_group`](NacmConfig::delete_group),
+//!   [`add_group_member`](NacmConfig::add_group_member) and
+//!   [`remove_group_member`](NacmConfig::remove_group_member)
+//! - [`apply_nacm_edit`](NacmConfig::apply_nacm_edit): an `<edit-config>`
+//!   fragment on `/nacm`
+//!
+//! Rule lists, rules and command rules are `ordered-by user` lists, so
+//! insertions and moves take a [`Position`] like the YANG `insert`
+//! attribute. After every change
+//! the `order` of all rules and command rules is recomputed the way
+//! [`NacmConfig::from_xml`] assigns it, so precedence always follows the
+//! list order. A failed change leaves the configuration untouched.
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::{NacmConfig, Operation};
+//! use nacm_validator::mutation::Position;
+//!
+//! let mut config = NacmConfig::builder()
+//!     .group("oper", ["bob"])
+//!     .rule_list("oper-acl", ["oper"], |rl| rl.permit().name("read-all").ops([Operation::Read]))
+//!     .build()
+//!     .unwrap();
+//!
+//! // Deny the NACM module before the catch-all read rule
+//! config.apply_nacm_edit(r#"
+//!   <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm"
+//!         xmlns:yang="urn:ietf:params:xml:ns:yang:1">
+//!     <rule-list>
+//!       <name>oper-acl</name>
+//!       <rule yang:insert="before" yang:key="[name='read-all']">
+//!         <name>no-nacm</name>
+//!         <module-name>ietf-netconf-acm</module-name>
+//!         <action>deny</action>
+//!       </rule>
+//!     </rule-list>
+//!   </nacm>"#).unwrap();
+//!
+//! let rules = &config.rule_lists[0].rules;
+//! assert_eq!(rules[0].name, "no-nacm");
+//! assert!(rules[0].order < rules[1].order);
+//!
+//! config.move_rule("oper-acl", "no-nacm", Position::Last).unwrap();
+//! assert_eq!(config.rule_lists[0].rules[1].name, "no-nacm");
+//! ```
+
+use crate::condition::{RuleCondition, CONDITION_NAMESPACE};
+use crate::edit::EditOperation;
+use crate::{
+    parse_access_operations, ContextPattern, NacmCommandRule, NacmConfig, NacmGroup, NacmRule, NacmRuleList,
+    RuleEffect, COMMAND_OPERATIONS, DATA_OPERATIONS,
+};
+use std::collections::HashSet;
+use xmltree::{Element, XMLNode};
+
+/// Where to put an entry of an `ordered-by user` list
+///
+/// Mirrors the YANG `insert` attribute (RFC 7950, section 7.8.6). The
+/// anchor of `Before` and `After` is the name of another entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position<'a> {
+    /// At the start: highest precedence
+    First,
+    /// At the end: lowest precedence
+    Last,
+    /// Right before the named entry
+    Before(&'a str),
+    /// Right after the named entry
+    After(&'a str),
+}
+
+/// Why a change could not be made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MutationError {
+    /// No rule list has this name
+    UnknownRuleList(String),
+    /// The rule list has no rule (or command rule) with this name
+    UnknownRule {
+        /// Rule list searched
+        rule_list: String,
+        /// The missing rule
+        rule: String,
+    },
+    /// No group has this name
+    UnknownGroup(String),
+    /// The user is not a member of the group
+    UnknownMember {
+        /// Group searched
+        group: String,
+        /// The missing user
+        user: String,
+    },
+    /// A rule list with this name exists already
+    DuplicateRuleList(String),
+    /// The rule list has a rule (or command rule) with this name already
+    DuplicateRule {
+        /// Rule list containing the rule
+        rule_list: String,
+        /// The existing rule
+        rule: String,
+    },
+    /// A group with this name exists already
+    DuplicateGroup(String),
+    /// An edit fragment is malformed or edits something that is not supported
+    InvalidFragment(String),
+}
+
+impl std::fmt::Display for MutationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MutationError::UnknownRuleList(rule_list) => write!(f, "Unknown rule list: {}", rule_list),
+            MutationError::UnknownRule { rule_list, rule } => {
+                write!(f, "Unknown rule '{}' in rule list '{}'", rule, rule_list)
+            }
+            MutationError::UnknownGroup(group) => write!(f, "Unknown group: {}", group),
+            MutationError::UnknownMember { group, user } => {
+                write!(f, "User '{}' is not a member of group '{}'", user, group)
+            }
+            MutationError::DuplicateRuleList(rule_list) => write!(f, "Rule list exists already: {}", rule_list),
+            MutationError::DuplicateRule { rule_list, rule } => {
+                write!(f, "Rule '{}' exists already in rule list '{}'", rule, rule_list)
+            }
+            MutationError::DuplicateGroup(group) => write!(f, "Group exists already: {}", group),
+            MutationError::InvalidFragment(reason) => write!(f, "Invalid /nacm edit: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for MutationError {}
+
+/// Recompute rule precedence from list positions, like `from_xml`
+///
+/// Rules in earlier rule lists come first; within a list, earlier rules do.
+pub(crate) fn renumber(rule_lists: &mut [NacmRuleList]) {
+    for (order_base, rule_list) in rule_lists.iter_mut().enumerate() {
+        for (position, rule) in rule_list.rules.iter_mut().enumerate() {
+            rule.order = (order_base * 1000 + position) as u32;
+        }
+        for (position, rule) in rule_list.command_rules.iter_mut().enumerate() {
+            rule.order = (order_base * 1000 + position) as u32;
+        }
+    }
+}
+
+/// Insert `item` into `items` at `position`
+///
+/// # Returns
+///
+/// * `Ok(())` - The item was inserted
+/// * `Err(item)` - The anchor of the position does not exist; the item is
+///   handed back
+fn place<T>(items: &mut Vec<T>, item: T, position: Position, name: impl Fn(&T) -> &str) -> Result<(), T> {
+    let index = match position {
+        Position::First => 0,
+        Position::Last => items.len(),
+        Position::Before(anchor) | Position::After(anchor) => {
+            let Some(index) = items.iter().position(|existing| name(existing) == anchor) else {
+                return Err(item);
+            };
+            if matches!(position, Position::After(_)) {
+                index + 1
+            } else {
+                index
+            }
+        }
+    };
+    items.insert(index, item);
+    Ok(())
+}
+
+/// Anchor name of a position, if it has one
+fn anchor<'a>(position: Position<'a>) -> Option<&'a str> {
+    match position {
+        Position::Before(anchor) | Position::After(anchor) => Some(anchor),
+        Position::First | Position::Last => None,
+    }
+}
+
+impl NacmConfig {
+    /// Add a rule list at `position`
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The rule list was added and precedence recomputed
+    /// * `Err(MutationError)` - The name is taken or the anchor does not exist
+    pub fn insert_rule_list(&mut self, rule_list: NacmRuleList, position: Position) -> Result<(), MutationError> {
+        if self.rule_lists.iter().any(|existing| existing.name == rule_list.name) {
+            return Err(MutationError::DuplicateRuleList(rule_list.name));
+        }
+        place(&mut self.rule_lists, rule_list, position, |rl| &rl.name)
+            .map_err(|_| MutationError::UnknownRuleList(anchor(position).unwrap_or_default().to_string()))?;
+        renumber(&mut self.rule_lists);
+        Ok(())
+    }
+
+    /// Move a rule list to `position`
+    ///
+    /// Moving an entry before or after itself leaves it where it is.
+    pub fn move_rule_list(&mut self, name: &str, position: Position) -> Result<(), MutationError> {
+        let index = self.rule_list_index(name)?;
+        if anchor(position) == Some(name) {
+            return Ok(());
+        }
+        if let Some(anchor) = anchor(position) {
+            self.rule_list_index(anchor)?;
+        }
+        let rule_list = self.rule_lists.remove(index);
+        // The anchor exists and is not the moved entry, so this cannot fail
+        let _ = place(&mut self.rule_lists, rule_list, position, |rl| &rl.name);
+        renumber(&mut self.rule_lists);
+        Ok(())
+    }
+
+    /// Remove a rule list, returning it
+    pub fn delete_rule_list(&mut self, name: &str) -> Result<NacmRuleList, MutationError> {
+        let index = self.rule_list_index(name)?;
+        let rule_list = self.rule_lists.remove(index);
+        renumber(&mut self.rule_lists);
+        Ok(rule_list)
+    }
+
+    /// Rename a rule list, keeping its position
+    pub fn rename_rule_list(&mut self, name: &str, new_name: &str) -> Result<(), MutationError> {
+        let index = self.rule_list_index(name)?;
+        if name != new_name && self.rule_lists.iter().any(|rl| rl.name == new_name) {
+            return Err(MutationError::DuplicateRuleList(new_name.to_string()));
+        }
+        self.rule_lists[index].name = new_name.to_string();
+        Ok(())
+    }
+
+    /// Add a rule to a rule list at `position`
+    ///
+    /// The rule's `order` is overwritten to match its new position.
+    pub fn insert_rule(&mut self, rule_list: &str, rule: NacmRule, position: Position) -> Result<(), MutationError> {
+        let index = self.rule_list_index(rule_list)?;
+        let rules = &mut self.rule_lists[index].rules;
+        if rules.iter().any(|existing| existing.name == rule.name) {
+            return Err(MutationError::DuplicateRule {
+                rule_list: rule_list.to_string(),
+                rule: rule.name,
+            });
+        }
+        place(rules, rule, position, |r| &r.name).map_err(|_| MutationError::UnknownRule {
+            rule_list: rule_list.to_string(),
+            rule: anchor(position).unwrap_or_default().to_string(),
+        })?;
+        renumber(&mut self.rule_lists);
+        Ok(())
+    }
+
+    /// Move a rule to `position` within its rule list
+    ///
+    /// Moving a rule before or after itself leaves it where it is.
+    pub fn move_rule(&mut self, rule_list: &str, rule: &str, position: Position) -> Result<(), MutationError> {
+        let (list_index, rule_index) = self.rule_index(rule_list, rule)?;
+        if anchor(position) == Some(rule) {
+            return Ok(());
+        }
+        if let Some(anchor) = anchor(position) {
+            self.rule_index(rule_list, anchor)?;
+        }
+        let rules = &mut self.rule_lists[list_index].rules;
+        let moved = rules.remove(rule_index);
+        // The anchor exists and is not the moved rule, so this cannot fail
+        let _ = place(rules, moved, position, |r| &r.name);
+        renumber(&mut self.rule_lists);
+        Ok(())
+    }
+
+    /// Remove a rule from a rule list, returning it
+    pub fn delete_rule(&mut self, rule_list: &str, rule: &str) -> Result<NacmRule, MutationError> {
+        let (list_index, rule_index) = self.rule_index(rule_list, rule)?;
+        let removed = self.rule_lists[list_index].rules.remove(rule_index);
+        renumber(&mut self.rule_lists);
+        Ok(removed)
+    }
+
+    /// Rename a rule, keeping its position
+    pub fn rename_rule(&mut self, rule_list: &str, rule: &str, new_name: &str) -> Result<(), MutationError> {
+        let (list_index, rule_index) = self.rule_index(rule_list, rule)?;
+        let rules = &mut self.rule_lists[list_index].rules;
+        if rule != new_name && rules.iter().any(|r| r.name == new_name) {
+            return Err(MutationError::DuplicateRule {
+                rule_list: rule_list.to_string(),
+                rule: new_name.to_string(),
+            });
+        }
+        rules[rule_index].name = new_name.to_string();
+        Ok(())
+    }
+
+    /// Add a command rule to a rule list at `position` - Tail-f extension
+    ///
+    /// Command rules are a list of their own, so the anchor of `position`
+    /// names another command rule. The rule's `order` is overwritten to
+    /// match its new position.
+    pub fn insert_command_rule(
+        &mut self,
+        rule_list: &str,
+        rule: NacmCommandRule,
+        position: Position,
+    ) -> Result<(), MutationError> {
+        let index = self.rule_list_index(rule_list)?;
+        let rules = &mut self.rule_lists[index].command_rules;
+        if rules.iter().any(|existing| existing.name == rule.name) {
+            return Err(MutationError::DuplicateRule {
+                rule_list: rule_list.to_string(),
+                rule: rule.name,
+            });
+        }
+        place(rules, rule, position, |r| &r.name).map_err(|_| MutationError::UnknownRule {
+            rule_list: rule_list.to_string(),
+            rule: anchor(position).unwrap_or_default().to_string(),
+        })?;
+        renumber(&mut self.rule_lists);
+        Ok(())
+    }
+
+    /// Move a command rule to `position` among the command rules of its list
+    ///
+    /// Moving a command rule before or after itself leaves it where it is.
+    pub fn move_command_rule(&mut self, rule_list: &str, rule: &str, position: Position) -> Result<(), MutationError> {
+        let (list_index, rule_index) = self.command_rule_index(rule_list, rule)?;
+        if anchor(position) == Some(rule) {
+            return Ok(());
+        }
+        if let Some(anchor) = anchor(position) {
+            self.command_rule_index(rule_list, anchor)?;
+        }
+        let rules = &mut self.rule_lists[list_index].command_rules;
+        let moved = rules.remove(rule_index);
+        // The anchor exists and is not the moved rule, so this cannot fail
+        let _ = place(rules, moved, position, |r| &r.name);
+        renumber(&mut self.rule_lists);
+        Ok(())
+    }
+
+    /// Remove a command rule from a rule list, returning it
+    pub fn delete_command_rule(&mut self, rule_list: &str, rule: &str) -> Result<NacmCommandRule, MutationError> {
+        let (list_index, rule_index) = self.command_rule_index(rule_list, rule)?;
+        let removed = self.rule_lists[list_index].command_rules.remove(rule_index);
+        renumber(&mut self.rule_lists);
+        Ok(removed)
+    }
+
+    /// Rename a command rule, keeping its position
+    pub fn rename_command_rule(&mut self, rule_list: &str, rule: &str, new_name: &str) -> Result<(), MutationError> {
+        let (list_index, rule_index) = self.command_rule_index(rule_list, rule)?;
+        let rules = &mut self.rule_lists[list_index].command_rules;
+        if rule != new_name && rules.iter().any(|r| r.name == new_name) {
+            return Err(MutationError::DuplicateRule {
+                rule_list: rule_list.to_string(),
+                rule: new_name.to_string(),
+            });
+        }
+        rules[rule_index].name = new_name.to_string();
+        Ok(())
+    }
+
+    /// Add a group
+    pub fn insert_group(&mut self, group: NacmGroup) -> Result<(), MutationError> {
+        if self.groups.contains_key(&group.name) {
+            return Err(MutationError::DuplicateGroup(group.name));
+        }
+        self.groups.insert(group.name.clone(), group);
+        Ok(())
+    }
+
+    /// Remove a group, returning it
+    ///
+    /// Rule lists naming the group are left alone: with external groups, a
+    /// rule list may apply to a group that is not configured.
+    pub fn delete_group(&mut self, name: &str) -> Result<NacmGroup, MutationError> {
+        self.groups
+            .remove(name)
+            .ok_or_else(|| MutationError::UnknownGroup(name.to_string()))
+    }
+
+    /// Add a user to a group; adding an existing member changes nothing
+    pub fn add_group_member(&mut self, group: &str, user: &str) -> Result<(), MutationError> {
+        let entry = self
+            .groups
+            .get_mut(group)
+            .ok_or_else(|| MutationError::UnknownGroup(group.to_string()))?;
+        if !entry.users.iter().any(|member| member == user) {
+            entry.users.push(user.to_string());
+        }
+        Ok(())
+    }
+
+    /// Remove a user from a group
+    pub fn remove_group_member(&mut self, group: &str, user: &str) -> Result<(), MutationError> {
+        let entry = self
+            .groups
+            .get_mut(group)
+            .ok_or_else(|| MutationError::UnknownGroup(group.to_string()))?;
+        let index = entry
+            .users
+            .iter()
+            .position(|member| member == user)
+            .ok_or_else(|| MutationError::UnknownMember {
+                group: group.to_string(),
+                user: user.to_string(),
+            })?;
+        entry.users.remove(index);
+        Ok(())
+    }
+
+    /// Apply an `<edit-config>` fragment on the `/nacm` subtree
+    ///
+    /// The fragment is a `<nacm>` element, or a `<config>` element that
+    /// contains one, with NETCONF `operation` attributes (RFC 6241, section
+    /// 7.2) and YANG `insert`/`key` attributes for rule lists, rules and
+    /// command rules.
+    /// Elements are matched by local name, so namespace prefixes do not
+    /// matter. The exception is `<condition>`, which must be in the
+    /// [`CONDITION_NAMESPACE`] so that another vendor's condition is not
+    /// taken for ours. The default operation is merge. Deleting or removing
+    /// a leaf restores its YANG default.
+    ///
+    /// The edit is all or nothing: on error the configuration is unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `fragment` - The XML fragment
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The edit was applied and precedence recomputed
+    /// * `Err(MutationError)` - The fragment is malformed, or an operation
+    ///   failed (e.g. `create` of an existing rule, `delete` of a missing one)
+    pub fn apply_nacm_edit(&mut self, fragment: &str) -> Result<(), MutationError> {
+        let root = Element::parse(fragment.as_bytes())
+            .map_err(|e| MutationError::InvalidFragment(format!("not well-formed XML: {}", e)))?;
+        let nacm = if root.name == "nacm" {
+            &root
+        } else {
+            root.get_child("nacm")
+                .ok_or_else(|| invalid(format!("expected a <nacm> element, found <{}>", root.name)))?
+        };
+
+        let mut edited = self.clone();
+        edited.edit_nacm(nacm, EditOperation::Merge)?;
+        renumber(&mut edited.rule_lists);
+        *self = edited;
+        Ok(())
+    }
+
+    fn rule_list_index(&self, name: &str) -> Result<usize, MutationError> {
+        self.rule_lists
+            .iter()
+            .position(|rl| rl.name == name)
+            .ok_or_else(|| MutationError::UnknownRuleList(name.to_string()))
+    }
+
+    fn rule_index(&self, rule_list: &str, rule: &str) -> Result<(usize, usize), MutationError> {
+        let list_index = self.rule_list_index(rule_list)?;
+        let rule_index = self.rule_lists[list_index]
+            .rules
+            .iter()
+            .position(|r| r.name == rule)
+            .ok_or_else(|| MutationError::UnknownRule {
+                rule_list: rule_list.to_string(),
+                rule: rule.to_string(),
+            })?;
+        Ok((list_index, rule_index))
+    }
+
+    fn command_rule_index(&self, rule_list: &str, rule: &str) -> Result<(usize, usize), MutationError> {
+        let list_index = self.rule_list_index(rule_list)?;
+        let rule_index = self.rule_lists[list_index]
+            .command_rules
+            .iter()
+            .position(|r| r.name == rule)
+            .ok_or_else(|| MutationError::UnknownRule {
+                rule_list: rule_list.to_string(),
+                rule: rule.to_string(),
+            })?;
+        Ok((list_index, rule_index))
+    }
+
+    fn edit_nacm(&mut self, nacm: &Element, parent: EditOperation) -> Result<(), MutationError> {
+        let operation = operation_of(nacm, parent)?;
+        match operation {
+            EditOperation::Delete | EditOperation::Remove => {
+                return Err(invalid("the /nacm container cannot be deleted"));
+            }
+            EditOperation::Replace => {
+                // Everything not in the fragment goes back to its default
+                for leaf in DEFAULT_LEAVES {
+                    self.reset_leaf(leaf);
+                }
+                self.groups.clear();
+                self.rule_lists.clear();
+            }
+            _ => {}
+        }
+
+        for child in elements(nacm) {
+            match child.name.as_str() {
+                "groups" => self.edit_groups(child, operation)?,
+                "rule-list" => self.edit_rule_list(child, operation)?,
+                name if DEFAULT_LEAVES.contains(&name) => self.edit_leaf(child, operation)?,
+                name => return Err(invalid(format!("unsupported /nacm node <{}>", name))),
+            }
+        }
+        Ok(())
+    }
+
+    fn edit_leaf(&mut self, leaf: &Element, parent: EditOperation) -> Result<(), MutationError> {
+        let name = leaf.name.as_str();
+        match operation_of(leaf, parent)? {
+            EditOperation::Delete | EditOperation::Remove => {
+                self.reset_leaf(name);
+                return Ok(());
+            }
+            EditOperation::None => return Ok(()),
+            _ => {}
+        }
+
+        let value = text(leaf);
+        match name {
+            "enable-nacm" => {
+                self.enable_nacm = value
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid enable-nacm value: {}", value)))?;
+            }
+            // Empty leaves: present means true
+            "log-if-default-permit" => self.log_if_default_permit = true,
+            "log-if-default-deny" => self.log_if_default_deny = true,
+            _ => {
+                let effect = value.parse::<RuleEffect>().map_err(invalid)?;
+                match name {
+                    "read-default" => self.read_default = effect,
+                    "write-default" => self.write_default = effect,
+                    "exec-default" => self.exec_default = effect,
+                    "cmd-read-default" => self.cmd_read_default = effect,
+                    _ => self.cmd_exec_default = effect,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Restore the YANG default of a top-level leaf
+    fn reset_leaf(&mut self, name: &str) {
+        match name {
+            "enable-nacm" => self.enable_nacm = true,
+            "read-default" => self.read_default = RuleEffect::Permit,
+            "write-default" => self.write_default = RuleEffect::Deny,
+            "exec-default" => self.exec_default = RuleEffect::Permit,
+            "cmd-read-default" => self.cmd_read_default = RuleEffect::Permit,
+            "cmd-exec-default" => self.cmd_exec_default = RuleEffect::Permit,
+            "log-if-default-permit" => self.log_if_default_permit = false,
+            _ => self.log_if_default_deny = false,
+        }
+    }
+
+    fn edit_groups(&mut self, groups: &Element, parent: EditOperation) -> Result<(), MutationError> {
+        let operation = operation_of(groups, parent)?;
+        match operation {
+            EditOperation::Delete | EditOperation::Remove | EditOperation::Replace => self.groups.clear(),
+            _ => {}
+        }
+        if matches!(operation, EditOperation::Delete | EditOperation::Remove) {
+            return Ok(());
+        }
+
+        for group in elements(groups).filter(|child| child.name == "group") {
+            let name = key(group, "group")?;
+            let operation = operation_of(group, operation)?;
+            match (operation, self.groups.contains_key(&name)) {
+                (EditOperation::Delete, _) => {
+                    self.delete_group(&name)?;
+                }
+                (EditOperation::Remove, _) => {
+                    self.groups.remove(&name);
+                }
+                (EditOperation::Create, true) => return Err(MutationError::DuplicateGroup(name)),
+                (EditOperation::None, false) => return Err(MutationError::UnknownGroup(name)),
+                (operation, exists) => {
+                    if !exists || operation == EditOperation::Replace {
+                        self.groups.insert(name.clone(), NacmGroup { name: name.clone(), users: Vec::new(), gid: None });
+                    }
+                    self.edit_group_members(&name, group, operation)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn edit_group_members(&mut self, name: &str, group: &Element, parent: EditOperation) -> Result<(), MutationError> {
+        for child in elements(group) {
+            let operation = operation_of(child, parent)?;
+            match child.name.as_str() {
+                "name" => {}
+                "user-name" => {
+                    let user = text(child);
+                    match operation {
+                        EditOperation::Delete => self.remove_group_member(name, &user)?,
+                        EditOperation::Remove => {
+                            let _ = self.remove_group_member(name, &user);
+                        }
+                        EditOperation::None => {}
+                        _ => self.add_group_member(name, &user)?,
+                    }
+                }
+                "gid" => {
+                    let gid = match operation {
+                        EditOperation::Delete | EditOperation::Remove => None,
+                        _ => Some(text(child).parse().map_err(|_| invalid(format!("invalid gid: {}", text(child))))?),
+                    };
+                    if let Some(group) = self.groups.get_mut(name) {
+                        group.gid = gid;
+                    }
+                }
+                other => return Err(invalid(format!("unsupported group node <{}>", other))),
+            }
+        }
+        Ok(())
+    }
+
+    fn edit_rule_list(&mut self, element: &Element, parent: EditOperation) -> Result<(), MutationError> {
+        let name = key(element, "rule-list")?;
+        let operation = operation_of(element, parent)?;
+        let position = insert_position(element)?;
+        let exists = self.rule_list_index(&name).is_ok();
+
+        match (operation, exists) {
+            (EditOperation::Delete, _) => {
+                self.delete_rule_list(&name)?;
+                return Ok(());
+            }
+            (EditOperation::Remove, _) => {
+                let _ = self.delete_rule_list(&name);
+                return Ok(());
+            }
+            (EditOperation::Create, true) => return Err(MutationError::DuplicateRuleList(name)),
+            (EditOperation::None, false) => return Err(MutationError::UnknownRuleList(name)),
+            (EditOperation::Replace, true) => {
+                let index = self.rule_list_index(&name)?;
+                let rule_list = &mut self.rule_lists[index];
+                rule_list.groups.clear();
+                rule_list.rules.clear();
+                rule_list.command_rules.clear();
+            }
+            (_, false) => {
+                let rule_list = NacmRuleList { name: name.clone(), groups: Vec::new(), rules: Vec::new(), command_rules: Vec::new() };
+                self.insert_rule_list(rule_list, position.unwrap_or(Position::Last))?;
+            }
+            _ => {}
+        }
+        if exists {
+            if let Some(position) = position {
+                self.move_rule_list(&name, position)?;
+            }
+        }
+
+        for child in elements(element) {
+            let child_operation = operation_of(child, operation)?;
+            let index = self.rule_list_index(&name)?;
+            let rule_list = &mut self.rule_lists[index];
+            match child.name.as_str() {
+                "name" => {}
+                "group" => {
+                    let group = text(child);
+                    let index = rule_list.groups.iter().position(|g| *g == group);
+                    match (child_operation, index) {
+                        (EditOperation::Delete, None) => {
+                            return Err(invalid(format!("group '{}' is not in rule list '{}'", group, name)));
+                        }
+                        (EditOperation::Delete | EditOperation::Remove, Some(index)) => {
+                            rule_list.groups.remove(index);
+                        }
+                        (EditOperation::Remove | EditOperation::None, _) | (_, Some(_)) => {}
+                        (_, None) => rule_list.groups.push(group),
+                    }
+                }
+                "rule" => self.edit_rule(&name, child, child_operation)?,
+                "cmdrule" => self.edit_command_rule(&name, child, child_operation)?,
+                other => return Err(invalid(format!("unsupported rule-list node <{}>", other))),
+            }
+        }
+        Ok(())
+    }
+
+    fn edit_rule(&mut self, rule_list: &str, element: &Element, operation: EditOperation) -> Result<(), MutationError> {
+        let name = key(element, "rule")?;
+        let position = insert_position(element)?;
+        let exists = self.rule_index(rule_list, &name).is_ok();
+
+        match (operation, exists) {
+            (EditOperation::Delete, _) => {
+                self.delete_rule(rule_list, &name)?;
+                return Ok(());
+            }
+            (EditOperation::Remove, _) => {
+                let _ = self.delete_rule(rule_list, &name);
+                return Ok(());
+            }
+            (EditOperation::Create, true) => {
+                return Err(MutationError::DuplicateRule { rule_list: rule_list.to_string(), rule: name });
+            }
+            (EditOperation::None, false) => {
+                return Err(MutationError::UnknownRule { rule_list: rule_list.to_string(), rule: name });
+            }
+            _ => {}
+        }
+
+        let mut rule = match (operation, exists) {
+            (EditOperation::Replace, _) | (_, false) => NacmRule {
+                name: name.clone(),
+                module_name: None,
+                rpc_name: None,
+                path: None,
+                access_operations: HashSet::new(),
+                effect: RuleEffect::Deny,
+                order: 0,
+                context: None,
+                log_if_permit: false,
+                log_if_deny: false,
+                condition: None,
+            },
+            _ => {
+                let (list_index, rule_index) = self.rule_index(rule_list, &name)?;
+                self.rule_lists[list_index].rules[rule_index].clone()
+            }
+        };
+
+        let mut has_action = exists && operation != EditOperation::Replace;
+        for child in elements(element) {
+            let clear = matches!(operation_of(child, operation)?, EditOperation::Delete | EditOperation::Remove);
+            let value = || if clear { None } else { Some(text(child)) };
+            match child.name.as_str() {
+                "name" => {}
+                "module-name" => rule.module_name = value(),
+                "rpc-name" => rule.rpc_name = value(),
+                "path" => rule.path = value(),
+                "access-operations" => {
+                    rule.access_operations = value()
+                        .map(|ops| parse_access_operations(&ops, &DATA_OPERATIONS).map_err(invalid))
+                        .transpose()?
+                        .unwrap_or_default();
+                }
+                "action" if !clear => {
+                    rule.effect = text(child).parse().map_err(invalid)?;
+                    has_action = true;
+                }
+                "context" => rule.context = value().map(|pattern| parse_context(&pattern)).transpose()?,
+                "log-if-permit" => rule.log_if_permit = !clear,
+                "log-if-deny" => rule.log_if_deny = !clear,
+                "condition" => rule.condition = if clear { None } else { Some(parse_condition(child)?) },
+                // Not kept in NacmRule
+                "comment" => {}
+                other => return Err(invalid(format!("unsupported rule node <{}>", other))),
+            }
+        }
+        if !has_action {
+            return Err(invalid(format!("rule '{}' has no action", name)));
+        }
+
+        if exists {
+            let (list_index, rule_index) = self.rule_index(rule_list, &name)?;
+            self.rule_lists[list_index].rules[rule_index] = rule;
+            if let Some(position) = position {
+                self.move_rule(rule_list, &name, position)?;
+            }
+        } else {
+            self.insert_rule(rule_list, rule, position.unwrap_or(Position::Last))?;
+        }
+        Ok(())
+    }
+
+    fn edit_command_rule(
+        &mut self,
+        rule_list: &str,
+        element: &Element,
+        operation: EditOperation,
+    ) -> Result<(), MutationError> {
+        let name = key(element, "cmdrule")?;
+        let position = insert_position(element)?;
+        let index = self.command_rule_index(rule_list, &name).ok().map(|(_, index)| index);
+
+        match (operation, index) {
+            (EditOperation::Delete, _) => {
+                self.delete_command_rule(rule_list, &name)?;
+                return Ok(());
+            }
+            (EditOperation::Remove, _) => {
+                let _ = self.delete_command_rule(rule_list, &name);
+                return Ok(());
+            }
+            (EditOperation::Create, Some(_)) => {
+                return Err(MutationError::DuplicateRule { rule_list: rule_list.to_string(), rule: name });
+            }
+            (EditOperation::None, None) => {
+                return Err(MutationError::UnknownRule { rule_list: rule_list.to_string(), rule: name });
+            }
+            _ => {}
+        }
+
+        let mut rule = match (operation, index) {
+            (EditOperation::Replace, _) | (_, None) => NacmCommandRule {
+                name: name.clone(),
+                context: None,
+                command: None,
+                access_operations: COMMAND_OPERATIONS.into_iter().collect(),
+                effect: RuleEffect::Deny,
+                order: 0,
+                log_if_permit: false,
+                log_if_deny: false,
+                comment: None,
+                condition: None,
+            },
+            (_, Some(_)) => {
+                let (list_index, rule_index) = self.command_rule_index(rule_list, &name)?;
+                self.rule_lists[list_index].command_rules[rule_index].clone()
+            }
+        };
+
+        let mut has_action = index.is_some() && operation != EditOperation::Replace;
+        for child in elements(element) {
+            let clear = matches!(operation_of(child, operation)?, EditOperation::Delete | EditOperation::Remove);
+            let value = || if clear { None } else { Some(text(child)) };
+            match child.name.as_str() {
+                "name" => {}
+                "context" => rule.context = value().map(|pattern| parse_context(&pattern)).transpose()?,
+                "command" => rule.command = value(),
+                "access-operations" => {
+                    rule.access_operations = match value() {
+                        Some(ops) => parse_access_operations(&ops, &COMMAND_OPERATIONS).map_err(invalid)?,
+                        None => COMMAND_OPERATIONS.into_iter().collect(),
+                    };
+                }
+                "action" if !clear => {
+                    rule.effect = text(child).parse().map_err(invalid)?;
+                    has_action = true;
+                }
+                "log-if-permit" => rule.log_if_permit = !clear,
+                "log-if-deny" => rule.log_if_deny = !clear,
+                "comment" => rule.comment = value(),
+                "condition" => rule.condition = if clear { None } else { Some(parse_condition(child)?) },
+                other => return Err(invalid(format!("unsupported cmdrule node <{}>", other))),
+            }
+        }
+        if !has_action {
+            return Err(invalid(format!("cmdrule '{}' has no action", name)));
+        }
+
+        match index {
+            Some(rule_index) => {
+                let list_index = self.rule_list_index(rule_list)?;
+                self.rule_lists[list_index].command_rules[rule_index] = rule;
+                if let Some(position) = position {
+                    self.move_command_rule(rule_list, &name, position)?;
+                }
+            }
+            None => self.insert_command_rule(rule_list, rule, position.unwrap_or(Position::Last))?,
+        }
+        Ok(())
+    }
+}
+
+/// Top-level `/nacm` leaves that can be edited
+const DEFAULT_LEAVES: [&str; 8] = [
+    "enable-nacm",
+    "read-default",
+    "write-default",
+    "exec-default",
+    "cmd-read-default",
+    "cmd-exec-default",
+    "log-if-default-permit",
+    "log-if-default-deny",
+];
+
+fn invalid(reason: impl Into<String>) -> MutationError {
+    MutationError::InvalidFragment(reason.into())
+}
+
+/// The element children of an element
+fn elements(element: &Element) -> impl Iterator<Item = &Element> {
+    element.children.iter().filter_map(|child| match child {
+        XMLNode::Element(child) => Some(child),
+        _ => None,
+    })
+}
+
+/// Trimmed text content of a leaf
+fn text(element: &Element) -> String {
+    element.get_text().map(|text| text.trim().to_string()).unwrap_or_default()
+}
+
+/// The `operation` attribute of an element, or the inherited one
+fn operation_of(element: &Element, parent: EditOperation) -> Result<EditOperation, MutationError> {
+    match element.attributes.get("operation") {
+        Some(operation) => operation.parse().map_err(invalid),
+        None => Ok(parent),
+    }
+}
+
+/// The `name` key of a list entry
+fn key(element: &Element, list: &str) -> Result<String, MutationError> {
+    element
+        .get_child("name")
+        .map(text)
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| invalid(format!("<{}> entry without a <name> key", list)))
+}
+
+/// Position from the YANG `insert` and `key` attributes, if any
+///
+/// The anchor of `before` and `after` borrows from the `key` attribute.
+fn insert_position(element: &Element) -> Result<Option<Position<'_>>, MutationError> {
+    let Some(insert) = element.attributes.get("insert") else {
+        return Ok(None);
+    };
+    let anchor = || -> Result<&str, MutationError> {
+        let key = element
+            .attributes
+            .get("key")
+            .ok_or_else(|| invalid(format!("insert=\"{}\" needs a key attribute", insert)))?;
+        // [name='x'] or [prefix:name="x"]: the anchor is the quoted value
+        let quoted = key
+            .split(['\'', '"'])
+            .nth(1)
+            .ok_or_else(|| invalid(format!("invalid key attribute: {}", key)))?;
+        Ok(quoted)
+    };
+    match insert.as_str() {
+        "first" => Ok(Some(Position::First)),
+        "last" => Ok(Some(Position::Last)),
+        "before" => Ok(Some(Position::Before(anchor()?))),
+        "after" => Ok(Some(Position::After(anchor()?))),
+        other => Err(invalid(format!("invalid insert attribute: {}", other))),
+    }
+}
+
+fn parse_context(pattern: &str) -> Result<ContextPattern, MutationError> {
+    pattern.parse().map_err(invalid)
+}
+
+/// A `<condition>` element; it replaces the rule's condition as a whole
+fn parse_condition(element: &Element) -> Result<RuleCondition, MutationError> {
+    if element.namespace.as_deref() != Some(CONDITION_NAMESPACE) {
+        return Err(invalid(format!("<condition> must be in the {} namespace", CONDITION_NAMESPACE)));
+    }
+    let (mut weekdays, mut time_ranges, mut attributes) = (Vec::new(), Vec::new(), Vec::new());
+    for child in elements(element) {
+        match child.name.as_str() {
+            "weekday" => weekdays.push(text(child)),
+            "time-range" => time_ranges.push(text(child)),
+            "attribute" => {
+                let leaf = |name: &str| child.get_child(name).map(text);
+                match (leaf("name"), leaf("value")) {
+                    (Some(name), Some(value)) => attributes.push((name, value)),
+                    _ => return Err(invalid("<attribute> needs a <name> and a <value>")),
+                }
+            }
+            other => return Err(invalid(format!("unsupported condition node <{}>", other))),
+        }
+    }
+    RuleCondition::from_leaves(&weekdays, &time_ranges, &attributes).map_err(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Operation;
+
+    /// oper-acl (no-nacm, read-all) and admin-acl (permit-all)
+    fn config() -> NacmConfig {
+        NacmConfig::builder()
+            .group("admin", ["alice"])
+            .group("oper", ["bob", "carol"])
+            .rule_list("oper-acl", ["oper"], |rl| {
+                rl.deny().name("no-nacm").module("ietf-netconf-acm").permit().name("read-all").ops([Operation::Read])
+            })
+            .rule_list("admin-acl", ["admin"], |rl| rl.permit().name("permit-all"))
+            .build()
+            .unwrap()
+    }
+
+    /// (rule list, rule) pairs, checking that precedence follows list order
+    /// across rule lists
+    fn layout(config: &NacmConfig) -> Vec<(&str, &str)> {
+        let in_list_order: Vec<(u32, &str, &str)> = config
+            .rule_lists
+            .iter()
+            .flat_map(|rl| rl.rules.iter().map(move |r| (r.order, rl.name.as_str(), r.name.as_str())))
+            .collect();
+        let mut by_precedence = in_list_order.clone();
+        by_precedence.sort();
+        assert_eq!(in_list_order, by_precedence);
+        in_list_order.into_iter().map(|(_, list, rule)| (list, rule)).collect()
+    }
+
+    #[test]
+    fn insert_and_move_follow_positions() {
+        let mut config = config();
+        config.move_rule_list("admin-acl", Position::First).unwrap();
+        config.move_rule("oper-acl", "read-all", Position::Before("no-nacm")).unwrap();
+        let mut audit = config.rule_lists[1].rules[0].clone();
+        audit.name = "audit".to_string();
+        config.insert_rule("oper-acl", audit, Position::After("read-all")).unwrap();
+        assert_eq!(
+            layout(&config),
+            [
+                ("admin-acl", "permit-all"),
+                ("oper-acl", "read-all"),
+                ("oper-acl", "audit"),
+                ("oper-acl", "no-nacm"),
+            ]
+        );
+    }
+
+    #[test]
+    fn moving_next_to_itself_is_a_no_op() {
+        let mut config = config();
+        config.move_rule("oper-acl", "read-all", Position::After("read-all")).unwrap();
+        config.move_rule_list("admin-acl", Position::Before("admin-acl")).unwrap();
+        assert_eq!(layout(&config), layout(&self::config()));
+    }
+
+    #[test]
+    fn rename_and_delete() {
+        let mut config = config();
+        config.rename_rule("oper-acl", "read-all", "read-everything").unwrap();
+        assert_eq!(config.delete_rule("oper-acl", "read-everything").unwrap().name, "read-everything");
+        config.rename_rule_list("admin-acl", "superusers").unwrap();
+        assert_eq!(layout(&config), [("oper-acl", "no-nacm"), ("superusers", "permit-all")]);
+        assert_eq!(
+            config.rename_rule_list("superusers", "oper-acl"),
+            Err(MutationError::DuplicateRuleList("oper-acl".to_string()))
+        );
+    }
+
+    #[test]
+    fn unknown_names_are_errors() {
+        let mut config = config();
+        assert_eq!(
+            config.move_rule("oper-acl", "read-all", Position::After("missing")),
+            Err(MutationError::UnknownRule { rule_list: "oper-acl".to_string(), rule: "missing".to_string() })
+        );
+        assert_eq!(config.delete_rule_list("nope").unwrap_err(), MutationError::UnknownRuleList("nope".to_string()));
+        config.remove_group_member("oper", "carol").unwrap();
+        assert_eq!(
+            config.remove_group_member("oper", "carol"),
+            Err(MutationError::UnknownMember { group: "oper".to_string(), user: "carol".to_string() })
+        );
+    }
+
+    #[test]
+    fn nacm_edit_fragment() {
+        let mut config = config();
+        config
+            .apply_nacm_edit(
+                r#"<config xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"
+                        xmlns:nc="urn:ietf:params:xml:ns:netconf:base:1.0"
+                        xmlns:yang="urn:ietf:params:xml:ns:yang:1">
+                  <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+                    <write-default>permit</write-default>
+                    <exec-default nc:operation="delete"/>
+                    <groups>
+                      <group>
+                        <name>oper</name><user-name nc:operation="delete">bob</user-name><user-name>dave</user-name>
+                      </group>
+                      <group nc:operation="create"><name>guest</name><user-name>eve</user-name></group>
+                    </groups>
+                    <rule-list yang:insert="first">
+                      <name>guest-acl</name>
+                      <group>guest</group>
+                      <rule><name>deny-all</name><action>deny</action></rule>
+                    </rule-list>
+                    <rule-list>
+                      <name>oper-acl</name>
+                      <rule yang:insert="last"><name>no-nacm</name><path>/nacm</path></rule>
+                      <rule nc:operation="remove"><name>no-such-rule</name></rule>
+                    </rule-list>
+                  </nacm>
+                </config>"#,
+            )
+            .unwrap();
+        assert_eq!(config.write_default, RuleEffect::Permit);
+        // Deleting a leaf restores its default
+        assert_eq!(config.exec_default, RuleEffect::Permit);
+        assert_eq!(config.groups["oper"].users, vec!["carol", "dave"]);
+        assert_eq!(config.groups["guest"].users, vec!["eve"]);
+        assert_eq!(
+            layout(&config),
+            [
+                ("guest-acl", "deny-all"),
+                ("oper-acl", "read-all"),
+                ("oper-acl", "no-nacm"),
+                ("admin-acl", "permit-all"),
+            ]
+        );
+        // Merged, not replaced: the module is kept next to the new path
+        let no_nacm = &config.rule_lists[1].rules[1];
+        assert_eq!(
+            (no_nacm.module_name.as_deref(), no_nacm.path.as_deref()),
+            (Some("ietf-netconf-acm"), Some("/nacm"))
+        );
+    }
+
+    #[test]
+    fn failed_edit_changes_nothing() {
+        let mut config = config();
+        let before = config.clone();
+        let error = config
+            .apply_nacm_edit(
+                r#"<nacm>
+                    <read-default>deny</read-default>
+                    <rule-list operation="create"><name>oper-acl</name></rule-list>
+                  </nacm>"#,
+            )
+            .unwrap_err();
+        assert_eq!(error, MutationError::DuplicateRuleList("oper-acl".to_string()));
+        assert_eq!(config.read_default, RuleEffect::Permit);
+        assert_eq!(layout(&config), layout(&before));
+    }
+
+    #[test]
+    fn malformed_fragments_are_rejected() {
+        let mut config = config();
+        assert!(matches!(
+            config.apply_nacm_edit("<nacm><rule-list><name>x</name><rule><name>r</name></rule></rule-list></nacm>"),
+            Err(MutationError::InvalidFragment(_))
+        ));
+        assert!(matches!(config.apply_nacm_edit("<interfaces/>"), Err(MutationError::InvalidFragment(_))));
+        assert!(matches!(config.apply_nacm_edit("<nacm>"), Err(MutationError::InvalidFragment(_))));
+    }
+
+    /// oper-acl with the command rules `show` and `reboot`
+    fn with_command_rules() -> NacmConfig {
+        NacmConfig::builder()
+            .group("oper", ["bob"])
+            .rule_list("oper-acl", ["oper"], |rl| {
+                rl.permit_command("show *").name("show").deny_command("reboot").name("reboot")
+            })
+            .build()
+            .unwrap()
+    }
+
+    /// Command rule names of oper-acl, checking that precedence follows list order
+    fn command_rules(config: &NacmConfig) -> Vec<&str> {
+        let rules = &config.rule_lists[0].command_rules;
+        assert!(rules.windows(2).all(|pair| pair[0].order < pair[1].order));
+        rules.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    #[test]
+    fn command_rules_insert_move_delete_rename() {
+        let mut config = with_command_rules();
+        let mut audit = config.rule_lists[0].command_rules[0].clone();
+        audit.name = "audit".to_string();
+        config.insert_command_rule("oper-acl", audit, Position::Before("reboot")).unwrap();
+        assert_eq!(command_rules(&config), ["show", "audit", "reboot"]);
+
+        config.move_command_rule("oper-acl", "reboot", Position::First).unwrap();
+        assert_eq!(command_rules(&config), ["reboot", "show", "audit"]);
+
+        config.rename_command_rule("oper-acl", "audit", "show-again").unwrap();
+        assert_eq!(
+            config.rename_command_rule("oper-acl", "show-again", "show"),
+            Err(MutationError::DuplicateRule { rule_list: "oper-acl".to_string(), rule: "show".to_string() })
+        );
+        assert_eq!(config.delete_command_rule("oper-acl", "show-again").unwrap().name, "show-again");
+        assert_eq!(command_rules(&config), ["reboot", "show"]);
+
+        assert_eq!(
+            config.move_command_rule("oper-acl", "show", Position::After("missing")),
+            Err(MutationError::UnknownRule { rule_list: "oper-acl".to_string(), rule: "missing".to_string() })
+        );
+        // Data rules are a separate list
+        assert!(config.delete_rule("oper-acl", "show").is_err());
+    }
+
+    #[test]
+    fn command_rule_edits_honour_insert() {
+        let mut config = with_command_rules();
+        config
+            .apply_nacm_edit(
+                r#"<nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm"
+                         xmlns:yang="urn:ietf:params:xml:ns:yang:1">
+                    <rule-list>
+                      <name>oper-acl</name>
+                      <cmdrule xmlns="http://tail-f.com/yang/acm" yang:insert="first">
+                        <name>deny-configure</name>
+                        <command>configure</command>
+                        <action>deny</action>
+                      </cmdrule>
+                      <cmdrule xmlns="http://tail-f.com/yang/acm" yang:insert="before" yang:key="[name='show']">
+                        <name>reboot</name>
+                      </cmdrule>
+                    </rule-list>
+                  </nacm>"#,
+            )
+            .unwrap();
+        assert_eq!(command_rules(&config), ["deny-configure", "reboot", "show"]);
+        // Moved, not replaced
+        assert_eq!(config.rule_lists[0].command_rules[1].command.as_deref(), Some("reboot"));
+
+        let error = config
+            .apply_nacm_edit(
+                r#"<nacm><rule-list><name>oper-acl</name>
+                    <cmdrule insert="after" key="[name='missing']"><name>show</name></cmdrule>
+                  </rule-list></nacm>"#,
+            )
+            .unwrap_err();
+        assert_eq!(
+            error,
+            MutationError::UnknownRule { rule_list: "oper-acl".to_string(), rule: "missing".to_string() }
+        );
+        assert_eq!(command_rules(&config), ["deny-configure", "reboot", "show"]);
+    }
+
+    #[test]
+    fn command_rule_edits_delete_and_remove() {
+        let mut config = with_command_rules();
+        config
+            .apply_nacm_edit(
+                r#"<nacm><rule-list><name>oper-acl</name>
+                    <cmdrule operation="delete"><name>show</name></cmdrule>
+                    <cmdrule operation="remove"><name>missing</name></cmdrule>
+                  </rule-list></nacm>"#,
+            )
+            .unwrap();
+        assert_eq!(command_rules(&config), ["reboot"]);
+        let delete_again = r#"<nacm><rule-list><name>oper-acl</name>
+                <cmdrule operation="delete"><name>show</name></cmdrule>
+              </rule-list></nacm>"#;
+        assert!(config.apply_nacm_edit(delete_again).is_err());
+        assert_eq!(config.rule_lists[0].command_rules[0].access_operations, [Operation::Read, Operation::Exec].into());
+    }
+}
@@ -0,0 +1,537 @@
+//! # NETCONF RPC Mapping
+//!
+//! Translates a NETCONF `<rpc>` message into the NACM access requests a
+//! server checks for it (RFC 8341, section 3.4):
+//!
+//! 1. The protocol operation: exec on the RPC, with the module resolved
+//!    from the operation's XML namespace (`ietf-netconf` for the base
+//!    operations). This is always the first request.
+//! 2. The data nodes the operation touches:
+//!    - `<get>`, `<get-config>` and NMDA `<get-data>`: read on every selection
+//!      node of a subtree filter, or on the `select` path of an XPath filter,
+//!      or on `/` without a filter
+//!    - `<edit-config>`, NMDA `<edit-data>` and `<copy-config>` with inline
+//!      `<config>`: the node-level create, update and delete checks derived by
+//!      the [`edit`](crate::edit) module, assuming every node exists
+//!    - `<action>`: exec on the action node, an action request that also
+//!      needs read access to the node's ancestors (see
+//!      [`RequestKind::Action`](crate::RequestKind::Action))
+//!
+//! Every other operation, including custom RPCs, only needs the protocol
+//! operation check. Data paths are schema paths without prefixes or list
+//! keys, as in the other modules.
+//!
+//! Each request is checked on its own. For edits,
+//! [`NacmConfig::authorize_edit`](crate::NacmConfig::authorize_edit)
+//! gives a more precise answer: it consults the current datastore, and a
+//! node that no rule matches falls back to the rules of its ancestors.
+//! [`rpc_edit`] extracts the edit of a message for it.
+//!
+//! The requests do not name a context; NETCONF requests are normally checked
+//! with [`RequestContext::NETCONF`](crate::RequestContext::NETCONF).
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::{Operation, RequestContext};
+//! use nacm_validator::netconf::map_rpc;
+//!
+//! let rpc = r#"
+//!   <rpc message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+//!     <get-config>
+//!       <source><running/></source>
+//!       <filter type="subtree">
+//!         <system xmlns="urn:ietf:params:xml:ns:yang:ietf-system"><ntp/></system>
+//!       </filter>
+//!     </get-config>
+//!   </rpc>"#;
+//!
+//! let accesses = map_rpc(rpc).unwrap();
+//! assert_eq!(accesses[0].rpc_name.as_deref(), Some("get-config"));
+//! assert_eq!(accesses[0].module_name.as_deref(), Some("ietf-netconf"));
+//! assert_eq!(accesses[1].operation, Operation::Read);
+//! assert_eq!(accesses[1].path.as_deref(), Some("/system/ntp"));
+//!
+//! let request = accesses[1].to_request("alice", Some(&RequestContext::NETCONF));
+//! assert_eq!(request.module_name, Some("ietf-system"));
+//! ```
+
+use crate::edit::{plan_edit, EditOperation, EditPayload};
+use crate::tree::{ModuleResolver, NETCONF_BASE_NS};
+use crate::{MappedAccess, Operation};
+use xmltree::{Element, XMLNode};
+
+/// Namespace of the YANG 1.1 `<action>` operation (RFC 7950, section 7.15.2)
+const YANG_ACTION_NS: &str = "urn:ietf:params:xml:ns:yang:1";
+
+/// Namespace of the NMDA operations `<get-data>` and `<edit-data>` (RFC 8526)
+const NMDA_NS: &str = "urn:ietf:params:xml:ns:yang:ietf-netconf-nmda";
+
+/// Module defining the base protocol operations
+const NETCONF_MODULE: &str = "ietf-netconf";
+
+/// Translate a NETCONF `<rpc>` message into access requests
+///
+/// Uses a [`ModuleResolver`] that only knows IETF namespaces; see
+/// [`map_rpc_with`] to supply more.
+///
+/// # Arguments
+///
+/// * `message` - The `<rpc>` element, or the bare operation element
+///
+/// # Returns
+///
+/// * `Ok(Vec<MappedAccess>)` - The protocol operation check, followed by the
+///   data node checks
+/// * `Err(String)` - The message is not well-formed or has no operation
+pub fn map_rpc(message: &str) -> Result<Vec<MappedAccess>, String> {
+    map_rpc_with(message, &ModuleResolver::new())
+}
+
+/// Translate a NETCONF `<rpc>` message, resolving namespaces with `resolver`
+pub fn map_rpc_with(message: &str, resolver: &ModuleResolver) -> Result<Vec<MappedAccess>, String> {
+    let root = parse_message(message)?;
+    let operation = rpc_operation(&root)?;
+
+    let namespace = operation.namespace.as_deref();
+    let module = match namespace {
+        Some(NETCONF_BASE_NS) => Some(NETCONF_MODULE),
+        Some(YANG_ACTION_NS) => None,
+        Some(namespace) => resolver.module_for(namespace),
+        None => None,
+    };
+    let mut accesses = vec![MappedAccess {
+        module_name: module.map(str::to_string),
+        rpc_name: Some(operation.name.clone()),
+        operation: Operation::Exec,
+        path: None,
+    }];
+
+    match (namespace, operation.name.as_str()) {
+        (Some(NETCONF_BASE_NS), "get" | "get-config") => {
+            accesses.extend(filter_reads(operation.get_child("filter"), resolver));
+        }
+        (Some(NMDA_NS), "get-data") => {
+            let filter = operation
+                .get_child("subtree-filter")
+                .or_else(|| operation.get_child("xpath-filter"));
+            accesses.extend(filter_reads(filter, resolver));
+        }
+        (Some(NETCONF_BASE_NS), "edit-config" | "copy-config") | (Some(NMDA_NS), "edit-data") => {
+            if let Some((config, default_operation)) = inline_edit(operation)? {
+                accesses.extend(edit_checks(config, default_operation, resolver)?);
+            }
+        }
+        (Some(YANG_ACTION_NS), "action") => {
+            accesses.push(action_exec(operation, resolver)?);
+        }
+        _ => {}
+    }
+    Ok(accesses)
+}
+
+/// The edit carried by an `<rpc>` message, for [`NacmConfig::authorize_edit`](crate::NacmConfig::authorize_edit)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcEdit {
+    /// The inline `<config>` element
+    pub payload: EditPayload,
+    /// The `<default-operation>` of the edit (`replace` for `<copy-config>`)
+    pub default_operation: EditOperation,
+}
+
+/// Extract the inline edit of an `<edit-config>`, `<edit-data>` or `<copy-config>`
+///
+/// The node-level checks of [`map_rpc`] evaluate each node on its own and
+/// assume every node exists. Passing the edit to
+/// [`NacmConfig::authorize_edit`] instead lets nodes without a matching rule
+/// fall back to their ancestors' rules, and can take the current datastore
+/// into account.
+///
+/// # Returns
+///
+/// * `Ok(Some(RpcEdit))` - The message is an edit with inline data
+/// * `Ok(None)` - Any other operation, or an edit whose data is a `<url>`
+/// * `Err(String)` - The message is not well-formed, has no operation or
+///   an unknown `<default-operation>`
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::edit::EditOperation;
+/// use nacm_validator::netconf::rpc_edit;
+///
+/// let rpc = r#"
+///   <rpc message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+///     <edit-config>
+///       <target><running/></target>
+///       <default-operation>none</default-operation>
+///       <config><system xmlns="urn:ietf:params:xml:ns:yang:ietf-system"/></config>
+///     </edit-config>
+///   </rpc>"#;
+///
+/// let edit = rpc_edit(rpc).unwrap().unwrap();
+/// assert_eq!(edit.default_operation, EditOperation::None);
+/// assert!(rpc_edit("<get xmlns=\"urn:ietf:params:xml:ns:netconf:base:1.0\"/>").unwrap().is_none());
+/// ```
+///
+/// [`NacmConfig::authorize_edit`]: crate::NacmConfig::authorize_edit
+pub fn rpc_edit(message: &str) -> Result<Option<RpcEdit>, String> {
+    let root = parse_message(message)?;
+    let operation = rpc_operation(&root)?;
+    let edit = match (operation.namespace.as_deref(), operation.name.as_str()) {
+        (Some(NETCONF_BASE_NS), "edit-config" | "copy-config") | (Some(NMDA_NS), "edit-data") => {
+            inline_edit(operation)?
+        }
+        _ => None,
+    };
+    Ok(edit.map(|(config, default_operation)| RpcEdit {
+        payload: EditPayload::Config(config.clone()),
+        default_operation,
+    }))
+}
+
+/// The inline `<config>` of an edit operation and its default operation
+///
+/// A `<url>` instead of inline data has nothing to check.
+fn inline_edit(operation: &Element) -> Result<Option<(&Element, EditOperation)>, String> {
+    if operation.name == "copy-config" {
+        // Copying inline data replaces the target datastore
+        let config = operation.get_child("source").and_then(|s| s.get_child("config"));
+        return Ok(config.map(|config| (config, EditOperation::Replace)));
+    }
+    let default_operation = match operation.get_child("default-operation").and_then(|e| e.get_text()) {
+        Some(text) => text.parse()?,
+        None => EditOperation::Merge,
+    };
+    Ok(operation.get_child("config").map(|config| (config, default_operation)))
+}
+
+/// Parse a message, which must be well-formed XML
+fn parse_message(message: &str) -> Result<Element, String> {
+    Element::parse(message.as_bytes()).map_err(|e| format!("Invalid NETCONF message: {}", e))
+}
+
+/// The operation element of an `<rpc>`, or the root itself if it is bare
+fn rpc_operation(root: &Element) -> Result<&Element, String> {
+    if root.name == "rpc" && root.namespace.as_deref() == Some(NETCONF_BASE_NS) {
+        Ok(first_child(root).ok_or("NETCONF <rpc> has no operation")?)
+    } else {
+        Ok(root)
+    }
+}
+
+/// The first element child of an element
+fn first_child(element: &Element) -> Option<&Element> {
+    element_children(element).next()
+}
+
+/// The element children of an element
+fn element_children(element: &Element) -> impl Iterator<Item = &Element> {
+    element.children.iter().filter_map(|child| match child {
+        XMLNode::Element(child) => Some(child),
+        _ => None,
+    })
+}
+
+/// Module of an element: its namespace's, or inherited from the parent
+fn element_module(element: &Element, resolver: &ModuleResolver, parent_module: Option<&str>) -> Option<String> {
+    match &element.namespace {
+        Some(namespace) => resolver.module_for(namespace).map(str::to_string),
+        None => parent_module.map(str::to_string),
+    }
+}
+
+/// Read checks for a retrieval filter
+fn filter_reads(filter: Option<&Element>, resolver: &ModuleResolver) -> Vec<MappedAccess> {
+    let read = |module: Option<String>, path: String| MappedAccess {
+        module_name: module,
+        rpc_name: None,
+        operation: Operation::Read,
+        path: Some(path),
+    };
+
+    let Some(filter) = filter else {
+        // No filter retrieves the whole datastore
+        return vec![read(None, "/".to_string())];
+    };
+
+    // An XPath filter: the <filter select="..."> attribute or the <xpath-filter> text
+    let select = match filter.name.as_str() {
+        "xpath-filter" => filter.get_text().map(|text| text.into_owned()),
+        _ if filter.attributes.get("type").map(String::as_str) == Some("xpath") => {
+            filter.attributes.get("select").cloned()
+        }
+        _ => None,
+    };
+    if let Some(select) = select {
+        let (module, path) = xpath_to_path(&select, filter, resolver);
+        return vec![read(module, path)];
+    }
+
+    let mut reads: Vec<MappedAccess> = Vec::new();
+    for node in element_children(filter) {
+        collect_selections(node, "", None, resolver, &mut |module, path| {
+            let access = read(module, path);
+            if !reads.contains(&access) {
+                reads.push(access);
+            }
+        });
+    }
+    reads
+}
+
+/// Whether a subtree filter node is a content match node (a leaf with a value)
+fn is_content_match(element: &Element) -> bool {
+    first_child(element).is_none() && element.get_text().is_some_and(|text| !text.trim().is_empty())
+}
+
+/// Report the nodes a subtree filter selects (RFC 6241, section 6.2)
+///
+/// A node whose children are all content match nodes selects itself, as
+/// does an empty node; otherwise the selection continues in its other
+/// children.
+fn collect_selections(
+    element: &Element,
+    parent_path: &str,
+    parent_module: Option<&str>,
+    resolver: &ModuleResolver,
+    report: &mut dyn FnMut(Option<String>, String),
+) {
+    let path = format!("{}/{}", parent_path, element.name);
+    let module = element_module(element, resolver, parent_module);
+
+    let nested: Vec<&Element> = element_children(element).filter(|child| !is_content_match(child)).collect();
+    if nested.is_empty() {
+        report(module, path);
+        return;
+    }
+    for child in nested {
+        collect_selections(child, &path, module.as_deref(), resolver, report);
+    }
+}
+
+/// Turn an XPath `select` expression into a schema path and module
+///
+/// Predicates are dropped and prefixes removed; the module comes from the
+/// prefix of the first step, looked up in the namespaces in scope at the
+/// filter element.
+fn xpath_to_path(select: &str, filter: &Element, resolver: &ModuleResolver) -> (Option<String>, String) {
+    let mut module = None;
+    let mut path = String::new();
+    for step in select.split('/').filter(|step| !step.is_empty()) {
+        // Drop predicates such as [name='eth0']
+        let step = step.split('[').next().unwrap_or(step).trim();
+        let name = match step.split_once(':') {
+            Some((prefix, name)) => {
+                if module.is_none() {
+                    module = filter
+                        .namespaces
+                        .as_ref()
+                        .and_then(|namespaces| namespaces.get(prefix))
+                        .and_then(|namespace| resolver.module_for(namespace))
+                        .map(str::to_string);
+                }
+                name
+            }
+            None => step,
+        };
+        path.push('/');
+        path.push_str(name);
+    }
+    if path.is_empty() {
+        path.push('/');
+    }
+    (module, path)
+}
+
+/// The node-level checks of an inline `<config>`
+fn edit_checks(
+    config: &Element,
+    default_operation: EditOperation,
+    resolver: &ModuleResolver,
+) -> Result<Vec<MappedAccess>, String> {
+    let payload = EditPayload::Config(config.clone());
+    let checks = plan_edit(&payload, None, default_operation, resolver)?;
+    Ok(checks
+        .iter()
+        .map(|check| MappedAccess {
+            module_name: check.module().map(str::to_string),
+            rpc_name: None,
+            operation: check.operation.clone(),
+            path: Some(check.schema_path().to_string()),
+        })
+        .collect())
+}
+
+/// Exec check on the action node of an `<action>` operation
+///
+/// Without a schema the action node is found by walking down the data path
+/// through the only child that is not a key leaf (a leaf with a value); the
+/// action is where that walk ends. An action whose only input parameter is
+/// a container would be mistaken for that container.
+fn action_exec(action: &Element, resolver: &ModuleResolver) -> Result<MappedAccess, String> {
+    let mut node = first_child(action).ok_or("<action> has no data path")?;
+    let mut module = element_module(node, resolver, None);
+    let mut path = format!("/{}", node.name);
+
+    loop {
+        let mut nested = element_children(node).filter(|child| !is_content_match(child));
+        match (nested.next(), nested.next()) {
+            (Some(child), None) => {
+                module = element_module(child, resolver, module.as_deref());
+                path = format!("{}/{}", path, child.name);
+                node = child;
+            }
+            _ => break,
+        }
+    }
+
+    Ok(MappedAccess {
+        module_name: module,
+        rpc_name: Some(node.name.clone()),
+        operation: Operation::Exec,
+        path: Some(path),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NacmConfig, RequestContext, RuleEffect};
+
+    type Row = (Operation, Option<String>, Option<String>, Option<String>);
+
+    /// The mapped accesses of an RPC as (operation, module, rpc, path)
+    fn summary(message: &str) -> Vec<Row> {
+        map_rpc(message)
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.operation, a.module_name, a.rpc_name, a.path))
+            .collect()
+    }
+
+    fn s(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    const GET: &str = r#"<rpc message-id="1" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+            <get><filter type="subtree">
+                <interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces">
+                    <interface><name>eth0</name></interface>
+                </interfaces>
+                <system xmlns="urn:ietf:params:xml:ns:yang:ietf-system"/>
+            </filter></get>
+        </rpc>"#;
+
+    #[test]
+    fn subtree_filter_content_match_selects_parent() {
+        assert_eq!(
+            summary(GET),
+            vec![
+                (Operation::Exec, s("ietf-netconf"), s("get"), None),
+                (Operation::Read, s("ietf-interfaces"), None, s("/interfaces/interface")),
+                (Operation::Read, s("ietf-system"), None, s("/system")),
+            ]
+        );
+    }
+
+    #[test]
+    fn xpath_filter_resolves_prefixes_in_scope() {
+        let get_config = r#"<rpc message-id="2" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+            <get-config>
+                <source><running/></source>
+                <filter type="xpath" xmlns:if="urn:ietf:params:xml:ns:yang:ietf-interfaces"
+                        select="/if:interfaces/if:interface[if:name='eth0']"/>
+            </get-config>
+        </rpc>"#;
+        assert_eq!(
+            summary(get_config)[1],
+            (Operation::Read, s("ietf-interfaces"), None, s("/interfaces/interface"))
+        );
+    }
+
+    #[test]
+    fn edit_config_honours_default_operation() {
+        let edit_config = r#"<rpc message-id="3" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+            <edit-config>
+                <target><running/></target>
+                <default-operation>none</default-operation>
+                <config>
+                    <system xmlns="urn:ietf:params:xml:ns:yang:ietf-system">
+                        <hostname operation="replace">r1</hostname>
+                        <ntp operation="delete"/>
+                    </system>
+                </config>
+            </edit-config>
+        </rpc>"#;
+        assert_eq!(
+            summary(edit_config),
+            vec![
+                (Operation::Exec, s("ietf-netconf"), s("edit-config"), None),
+                (Operation::Update, s("ietf-system"), None, s("/system/hostname")),
+                (Operation::Delete, s("ietf-system"), None, s("/system/ntp")),
+            ]
+        );
+    }
+
+    #[test]
+    fn action_execs_below_list_entry() {
+        let action = r#"<rpc message-id="4" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+            <action xmlns="urn:ietf:params:xml:ns:yang:1">
+                <interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces">
+                    <interface><name>eth0</name><reset><delay>5</delay></reset></interface>
+                </interfaces>
+            </action>
+        </rpc>"#;
+        assert_eq!(
+            summary(action),
+            vec![
+                (Operation::Exec, None, s("action"), None),
+                (Operation::Exec, s("ietf-interfaces"), s("reset"), s("/interfaces/interface/reset")),
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_rpc_needs_only_operation_check() {
+        let custom = r#"<rpc message-id="5" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+            <system-restart xmlns="urn:ietf:params:xml:ns:yang:ietf-system"/>
+        </rpc>"#;
+        assert_eq!(summary(custom), vec![(Operation::Exec, s("ietf-system"), s("system-restart"), None)]);
+    }
+
+    #[test]
+    fn mapped_requests_are_validated() {
+        let xml = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/aaa_ncm_init_secure.xml"))
+            .unwrap();
+        let config = NacmConfig::from_xml(&xml).unwrap();
+        let accesses = map_rpc(GET).unwrap();
+        let request = accesses[0].to_request("unknown", Some(&RequestContext::NETCONF));
+        assert_eq!(config.validate(&request).effect, RuleEffect::Deny);
+    }
+
+    #[test]
+    fn rpc_edit_extracts_inline_data() {
+        let copy = r#"<copy-config xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+            <target><running/></target>
+            <source><config><system xmlns="urn:ietf:params:xml:ns:yang:ietf-system"/></config></source>
+        </copy-config>"#;
+        let edit = rpc_edit(copy).unwrap().unwrap();
+        assert_eq!(edit.default_operation, EditOperation::Replace);
+        assert!(matches!(edit.payload, EditPayload::Config(ref config) if config.name == "config"));
+
+        let from_url = r#"<edit-config xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+            <target><running/></target>
+            <url>file:///change.xml</url>
+        </edit-config>"#;
+        assert_eq!(rpc_edit(from_url).unwrap(), None);
+        assert_eq!(rpc_edit(GET).unwrap(), None);
+        assert!(rpc_edit("not xml").is_err());
+    }
+
+    #[test]
+    fn rejects_empty_or_malformed_messages() {
+        assert!(map_rpc("<rpc xmlns=\"urn:ietf:params:xml:ns:netconf:base:1.0\"/>").is_err());
+        assert!(map_rpc("not xml").is_err());
+    }
+}
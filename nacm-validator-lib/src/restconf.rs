@@ -0,0 +1,434 @@
+//! # RESTCONF Request Mapping
+//!
+//! Translates RESTCONF (RFC 8040) HTTP requests into the NACM access
+//! requests a server has to check for them, following RFC 8341, section 3.2.
+//!
+//! | Request                               | Access requests                          |
+//! |---------------------------------------|------------------------------------------|
+//! | `GET`, `HEAD` on a data resource      | read on the target                       |
+//! | `PUT`, `PATCH` on a data resource     | update on the target                     |
+//! | `DELETE` on a data resource           | delete on the target                     |
+//! | `POST` on a data resource             | create on the child node in the body     |
+//! | `POST` on an action (`input` body)    | exec on the action                       |
+//! | `POST` on `/operations/<module>:<rpc>`| exec on the RPC                          |
+//! | `OPTIONS`                             | none                                     |
+//!
+//! The module is that of the target node: the last module prefix in the URI
+//! (or the body's, for a created child), as names inherit the module of
+//! their parent in RFC 7951. The data path is
+//! the schema path without prefixes or list keys, e.g.
+//! `/interfaces/interface/enabled` for
+//! `/restconf/data/ietf-interfaces:interfaces/interface=eth0/enabled`, as
+//! used by the [`tree`](crate::tree) and [`edit`](crate::edit) modules.
+//!
+//! An action invocation is an exec request with a path, so
+//! [`NacmConfig::validate`](crate::NacmConfig::validate) also checks read
+//! access to the action's ancestors.
+//!
+//! A `PUT` may create its target, but that cannot be told from the request
+//! alone; use [`NacmConfig::authorize_edit`](crate::NacmConfig::authorize_edit)
+//! with the current datastore for node-level detail.
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::Operation;
+//! use nacm_validator::restconf::map_request;
+//!
+//! let accesses = map_request("GET", "/restconf/data/ietf-interfaces:interfaces/interface=eth0", None).unwrap();
+//! assert_eq!(accesses[0].operation, Operation::Read);
+//! assert_eq!(accesses[0].module_name.as_deref(), Some("ietf-interfaces"));
+//! assert_eq!(accesses[0].path.as_deref(), Some("/interfaces/interface"));
+//!
+//! let accesses = map_request("POST", "/restconf/operations/ietf-system:system-restart", None).unwrap();
+//! assert_eq!(accesses[0].operation, Operation::Exec);
+//! assert_eq!(accesses[0].rpc_name.as_deref(), Some("system-restart"));
+//! ```
+
+use crate::tree::ModuleResolver;
+use crate::{MappedAccess, Operation};
+use xmltree::Element;
+
+/// HTTP methods defined for RESTCONF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// Retrieve data
+    Get,
+    /// Retrieve headers only
+    Head,
+    /// Create a child resource, or invoke an RPC or action
+    Post,
+    /// Create or replace the target resource
+    Put,
+    /// Merge into the target resource (plain patch)
+    Patch,
+    /// Delete the target resource
+    Delete,
+    /// Discover the allowed methods
+    Options,
+}
+
+/// Case-insensitive, like the HTTP method tokens in practice
+impl std::str::FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "GET" => Ok(Method::Get),
+            "HEAD" => Ok(Method::Head),
+            "POST" => Ok(Method::Post),
+            "PUT" => Ok(Method::Put),
+            "PATCH" => Ok(Method::Patch),
+            "DELETE" => Ok(Method::Delete),
+            "OPTIONS" => Ok(Method::Options),
+            _ => Err(format!("Unsupported RESTCONF method: {}", s)),
+        }
+    }
+}
+
+/// One step of a RESTCONF data resource path: `module:name=key1,key2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathSegment {
+    /// Module prefix, if the segment has one
+    pub module: Option<String>,
+    /// Node name
+    pub name: String,
+    /// Percent-decoded list keys (or leaf-list value), in order
+    pub keys: Vec<String>,
+}
+
+/// Parse a data resource path relative to `{+restconf}/data`
+///
+/// Empty segments are skipped, so a leading `/` is optional. An empty path
+/// is the datastore root.
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::restconf::parse_data_path;
+///
+/// let segments = parse_data_path("/ietf-interfaces:interfaces/interface=eth0%2F1").unwrap();
+/// assert_eq!(segments[0].module.as_deref(), Some("ietf-interfaces"));
+/// assert_eq!(segments[1].name, "interface");
+/// assert_eq!(segments[1].keys, vec!["eth0/1"]);
+/// ```
+pub fn parse_data_path(path: &str) -> Result<Vec<PathSegment>, String> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let (identifier, keys) = match segment.split_once('=') {
+                Some((identifier, keys)) => (identifier, keys.split(',').map(decode_key).collect()),
+                None => (segment, Vec::new()),
+            };
+            let (module, name) = match identifier.split_once(':') {
+                Some((module, name)) => (Some(module.to_string()), name.to_string()),
+                None => (None, identifier.to_string()),
+            };
+            if name.is_empty() || module.as_deref() == Some("") {
+                return Err(format!("Invalid RESTCONF path segment: {}", segment));
+            }
+            Ok(PathSegment { module, name, keys })
+        })
+        .collect()
+}
+
+/// Encode a list key value for a RESTCONF path (RFC 8040, section 3.5.3)
+pub(crate) fn encode_key(key: &str) -> String {
+    let mut encoded = String::new();
+    for byte in key.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~:@!$'()*+;".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Decode a percent-encoded RESTCONF path component
+pub(crate) fn decode_key(key: &str) -> String {
+    let bytes = key.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// RESTCONF resources that involve NACM checks
+enum Resource {
+    /// `{+restconf}/data/...` or an NMDA datastore `{+restconf}/ds/<datastore>/...`
+    Data(Vec<PathSegment>),
+    /// `{+restconf}/operations/<module>:<rpc>`
+    Operation(PathSegment),
+}
+
+/// Locate the resource in a request URI
+///
+/// The RESTCONF root (normally `/restconf`) may be anything; the resource
+/// starts at the first `data`, `ds` or `operations` segment. The query
+/// string is ignored.
+fn parse_uri(uri: &str) -> Result<Resource, String> {
+    // Accept absolute URIs by dropping scheme and authority
+    let path = match uri.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("", |start| &rest[start..]),
+        None => uri,
+    };
+    let path = path.split(['?', '#']).next().unwrap_or("");
+
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+    loop {
+        match segments.next() {
+            Some("data") => break,
+            Some("ds") => {
+                // The datastore identity, e.g. ietf-datastores:running
+                segments
+                    .next()
+                    .ok_or_else(|| format!("RESTCONF datastore missing in URI: {}", uri))?;
+                break;
+            }
+            Some("operations") => {
+                let rest: Vec<&str> = segments.collect();
+                let [operation] = rest.as_slice() else {
+                    return Err(format!("Expected /operations/<module>:<rpc> in URI: {}", uri));
+                };
+                let segment = parse_data_path(operation)?.remove(0);
+                if segment.module.is_none() {
+                    return Err(format!("RPC name needs a module prefix: {}", operation));
+                }
+                return Ok(Resource::Operation(segment));
+            }
+            Some(_) => continue,
+            None => return Err(format!("Not a RESTCONF data or operations resource: {}", uri)),
+        }
+    }
+
+    let rest: Vec<&str> = segments.collect();
+    let target = parse_data_path(&rest.join("/"))?;
+    if target.first().is_some_and(|first| first.module.is_none()) {
+        return Err(format!("Top-level node needs a module prefix: {}", target[0].name));
+    }
+    Ok(Resource::Data(target))
+}
+
+/// First node of a request body: its module (if named) and local name
+fn body_root(body: &str) -> Result<(Option<String>, String), String> {
+    let body = body.trim_start();
+    if body.starts_with('{') {
+        let value: serde_json::Value =
+            serde_json::from_str(body).map_err(|e| format!("Invalid JSON body: {}", e))?;
+        let name = value
+            .as_object()
+            .and_then(|object| object.keys().next())
+            .ok_or("JSON body must be an object with one member")?;
+        Ok(match name.split_once(':') {
+            Some((module, name)) => (Some(module.to_string()), name.to_string()),
+            None => (None, name.clone()),
+        })
+    } else {
+        let root = Element::parse(body.as_bytes()).map_err(|e| format!("Invalid XML body: {}", e))?;
+        let module = root
+            .namespace
+            .as_deref()
+            .and_then(|namespace| ModuleResolver::new().module_for(namespace).map(str::to_string));
+        Ok((module, root.name))
+    }
+}
+
+/// Translate a RESTCONF request into the access requests NACM checks
+///
+/// # Arguments
+///
+/// * `method` - HTTP method, e.g. `"GET"`
+/// * `uri` - Request URI or path, e.g. `/restconf/data/ietf-system:system`
+/// * `body` - Request body, JSON or XML, if any. Only used by `POST`, to
+///   tell an action invocation (an `input` body, or none) from the creation
+///   of a child resource.
+///
+/// # Returns
+///
+/// * `Ok(Vec<MappedAccess>)` - The access requests; empty for `OPTIONS`
+/// * `Err(String)` - Unsupported method, malformed URI or body, or a method
+///   not allowed on the resource
+pub fn map_request(method: &str, uri: &str, body: Option<&str>) -> Result<Vec<MappedAccess>, String> {
+    let method: Method = method.parse()?;
+
+    let target = match parse_uri(uri)? {
+        Resource::Operation(_) if method == Method::Options => return Ok(Vec::new()),
+        Resource::Operation(rpc) => {
+            if method != Method::Post {
+                return Err(format!("Method {:?} not allowed on an operation resource", method));
+            }
+            return Ok(vec![MappedAccess {
+                module_name: rpc.module,
+                rpc_name: Some(rpc.name),
+                operation: Operation::Exec,
+                path: None,
+            }]);
+        }
+        Resource::Data(target) => target,
+    };
+
+    // The target's module is the last prefix on the way down
+    let module = target.iter().rev().find_map(|segment| segment.module.clone());
+    let path = if target.is_empty() {
+        "/".to_string()
+    } else {
+        target.iter().map(|segment| format!("/{}", segment.name)).collect()
+    };
+    let access = |operation: Operation| MappedAccess {
+        module_name: module.clone(),
+        rpc_name: None,
+        operation,
+        path: Some(path.clone()),
+    };
+
+    let accesses = match method {
+        Method::Get | Method::Head => vec![access(Operation::Read)],
+        Method::Put | Method::Patch => vec![access(Operation::Update)],
+        Method::Delete if target.is_empty() => return Err("DELETE is not allowed on the datastore root".to_string()),
+        Method::Delete => vec![access(Operation::Delete)],
+        Method::Post => match body.map(body_root).transpose()? {
+            // Create the child resource named in the body
+            Some((child_module, child)) if child != "input" => vec![MappedAccess {
+                module_name: child_module.or(module.clone()),
+                rpc_name: None,
+                operation: Operation::Create,
+                path: Some(format!("{}/{}", path.trim_end_matches('/'), child)),
+            }],
+            // No body or an "input" node: an action invocation
+            _ => {
+                let Some(action) = target.last() else {
+                    return Err("POST to the datastore root needs a body".to_string());
+                };
+                vec![MappedAccess {
+                    module_name: module.clone(),
+                    rpc_name: Some(action.name.clone()),
+                    operation: Operation::Exec,
+                    path: Some(path.clone()),
+                }]
+            }
+        },
+        Method::Options => Vec::new(),
+    };
+    Ok(accesses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NacmConfig, RuleEffect};
+
+    const INTERFACES: &str = "https://router.example.com/restconf/data/ietf-interfaces:interfaces";
+
+    fn access(operation: Operation, module: &str, rpc: Option<&str>, path: Option<&str>) -> MappedAccess {
+        MappedAccess {
+            module_name: Some(module.to_string()),
+            rpc_name: rpc.map(str::to_string),
+            operation,
+            path: path.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn data_path_drops_keys_and_query() {
+        assert_eq!(
+            map_request("GET", &format!("{}/interface=eth0%2F1/enabled?depth=1", INTERFACES), None).unwrap(),
+            vec![access(Operation::Read, "ietf-interfaces", None, Some("/interfaces/interface/enabled"))]
+        );
+    }
+
+    #[test]
+    fn methods_map_to_operations() {
+        assert_eq!(
+            map_request("patch", "/restconf/data/ietf-interfaces:interfaces/interface=eth0", None).unwrap()[0].operation,
+            Operation::Update
+        );
+        assert_eq!(
+            map_request("DELETE", "/restconf/ds/ietf-datastores:running/ietf-system:system/ntp", None).unwrap(),
+            vec![access(Operation::Delete, "ietf-system", None, Some("/system/ntp"))]
+        );
+        assert!(map_request("OPTIONS", INTERFACES, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn augmenting_prefix_sets_module() {
+        let uri = format!("{}/interface=eth0/ietf-ip:ipv4", INTERFACES);
+        assert_eq!(
+            map_request("GET", &uri, None).unwrap()[0].module_name.as_deref(),
+            Some("ietf-ip")
+        );
+    }
+
+    #[test]
+    fn post_creates_child_from_body() {
+        let json = r#"{"ietf-interfaces:interface": [{"name": "eth1"}]}"#;
+        assert_eq!(
+            map_request("POST", INTERFACES, Some(json)).unwrap(),
+            vec![access(Operation::Create, "ietf-interfaces", None, Some("/interfaces/interface"))]
+        );
+        let xml = r#"<interface xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces"><name>eth1</name></interface>"#;
+        assert_eq!(
+            map_request("POST", INTERFACES, Some(xml)).unwrap(),
+            map_request("POST", INTERFACES, Some(json)).unwrap()
+        );
+    }
+
+    #[test]
+    fn post_invokes_actions_and_rpcs() {
+        let reset = format!("{}/interface=eth0/example-actions:reset", INTERFACES);
+        assert_eq!(
+            map_request("POST", &reset, Some(r#"{"example-actions:input": {"delay": 5}}"#)).unwrap(),
+            vec![access(Operation::Exec, "example-actions", Some("reset"), Some("/interfaces/interface/reset"))]
+        );
+        assert_eq!(
+            map_request("POST", "/restconf/operations/ietf-system:system-restart", None).unwrap(),
+            vec![access(Operation::Exec, "ietf-system", Some("system-restart"), None)]
+        );
+    }
+
+    #[test]
+    fn mapped_requests_are_validated() {
+        let xml = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/aaa_ncm_init.xml"))
+            .unwrap();
+        let config = NacmConfig::from_xml(&xml).unwrap();
+        let [read] = map_request("GET", INTERFACES, None).unwrap().try_into().unwrap();
+        assert_eq!(config.validate(&read.to_request("admin", None)).effect, RuleEffect::Permit);
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        assert!(map_request("GET", "/restconf/data/interfaces", None).is_err());
+        assert!(map_request("PUT", "/restconf/operations/ietf-system:system-restart", None).is_err());
+        assert!(map_request("DELETE", "/restconf/data", None).is_err());
+        assert!(map_request("TRACE", INTERFACES, None).is_err());
+    }
+
+    #[test]
+    fn keys_round_trip_through_encoding() {
+        for key in ["eth0/1", "a b,c", "100%", "ü"] {
+            assert_eq!(decode_key(&encode_key(key)), key);
+        }
+        assert_eq!(encode_key("eth0/1"), "eth0%2F1");
+        // A stray percent sign is kept as is
+        assert_eq!(decode_key("50%zz"), "50%zz");
+    }
+
+    #[test]
+    fn empty_path_segments_are_invalid() {
+        assert!(parse_data_path("").unwrap().is_empty());
+        assert!(parse_data_path("/:interfaces").is_err());
+        assert!(parse_data_path("/ietf-interfaces:").is_err());
+    }
+}
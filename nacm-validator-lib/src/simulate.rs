@@ -0,0 +1,306 @@
+//! # Policy Simulation
+//!
+//! What-if evaluation of a proposed policy change: a corpus of access
+//! requests, such as last week's audit log, is evaluated against both the
+//! current and the proposed configuration, and only the requests whose
+//! [`ValidationResult`](crate::ValidationResult) differs are reported.
+//!
+//! Each [`DecisionChange`] names the rule responsible for it:
+//!
+//! - a rule that is new in the proposed configuration and now decides the
+//!   request, such as an inserted deny rule
+//! - otherwise the rule that decided the request before, if any, because it
+//!   was removed, changed or moved and no longer decides it
+//! - otherwise the rule that decides it now (e.g. after a change to its
+//!   groups), or none when both decisions come from default policies
+//!
+//! Rules are identified by rule list and rule name. A [`Simulation`] groups
+//! the changes by user and by responsible rule.
+//!
+//! Simulation does not count towards the [statistics](crate::stats) of
+//! either configuration. Rules with a [condition](crate::condition) are
+//! checked at the current time, unless
+//! [`simulate_with`](NacmConfig::simulate_with) supplies an [`Environment`]
+//! per request, such as one at the time the request was logged.
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::{NacmConfig, Operation, OwnedAccessRequest, RuleEffect};
+//! use nacm_validator::mutation::Position;
+//!
+//! let current = NacmConfig::builder()
+//!     .group("oper", ["bob", "carol"])
+//!     .rule_list("oper-acl", ["oper"], |rl| rl.permit().name("read-all").ops([Operation::Read]))
+//!     .build()
+//!     .unwrap();
+//!
+//! // Proposed: hide the NACM module itself from operators
+//! let mut proposed = current.clone();
+//! proposed.apply_nacm_edit(r#"
+//!   <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+//!     <rule-list>
+//!       <name>oper-acl</name>
+//!       <rule><name>no-nacm</name><module-name>ietf-netconf-acm</module-name><action>deny</action></rule>
+//!     </rule-list>
+//!   </nacm>"#).unwrap();
+//! proposed.move_rule("oper-acl", "no-nacm", Position::First).unwrap();
+//!
+//! let corpus: Vec<OwnedAccessRequest> = [
+//!     r#"{"user":"bob","operation":"read","module":"ietf-netconf-acm"}"#,
+//!     r#"{"user":"bob","operation":"read","module":"ietf-interfaces"}"#,
+//!     r#"{"user":"carol","operation":"read","module":"ietf-netconf-acm"}"#,
+//! ]
+//! .iter()
+//! .map(|json| serde_json::from_str(json).unwrap())
+//! .collect();
+//!
+//! let simulation = current.simulate(&proposed, &corpus);
+//! assert_eq!(simulation.total, 3);
+//! assert_eq!(simulation.changes.len(), 2);
+//! assert_eq!(simulation.changes[0].after.result.effect, RuleEffect::Deny);
+//! assert_eq!(simulation.changes[0].responsible.unwrap().to_string(), "oper-acl/no-nacm");
+//! assert_eq!(simulation.by_user()["carol"].len(), 1);
+//! ```
+
+use crate::condition::Environment;
+use crate::{Decision, MatchedRule, NacmConfig, OwnedAccessRequest};
+use std::collections::BTreeMap;
+
+/// A request whose outcome differs between the two configurations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecisionChange<'a> {
+    /// Position of the request in the corpus (0-based)
+    pub index: usize,
+    /// The request itself
+    pub request: &'a OwnedAccessRequest,
+    /// Decision under the current configuration
+    pub before: Decision<'a>,
+    /// Decision under the proposed configuration
+    pub after: Decision<'a>,
+    /// The rule the change is attributed to (see the module documentation)
+    ///
+    /// `None` means that both decisions came from default policies, i.e. a
+    /// changed default or `enable-nacm` setting.
+    pub responsible: Option<MatchedRule<'a>>,
+}
+
+/// Outcome of [`NacmConfig::simulate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simulation<'a> {
+    /// Number of requests evaluated
+    pub total: usize,
+    /// The requests whose validation result changed, in corpus order
+    pub changes: Vec<DecisionChange<'a>>,
+}
+
+impl<'a> Simulation<'a> {
+    /// Changes grouped by user, users in name order
+    pub fn by_user(&self) -> BTreeMap<&'a str, Vec<&DecisionChange<'a>>> {
+        let mut groups: BTreeMap<&'a str, Vec<&DecisionChange<'a>>> = BTreeMap::new();
+        for change in &self.changes {
+            groups.entry(change.request.user.as_str()).or_default().push(change);
+        }
+        groups
+    }
+
+    /// Changes grouped by [responsible rule](DecisionChange::responsible)
+    ///
+    /// Changes caused by default policies come first, under `None`.
+    pub fn by_rule(&self) -> BTreeMap<Option<MatchedRule<'a>>, Vec<&DecisionChange<'a>>> {
+        let mut groups: BTreeMap<Option<MatchedRule<'a>>, Vec<&DecisionChange<'a>>> = BTreeMap::new();
+        for change in &self.changes {
+            groups.entry(change.responsible).or_default().push(change);
+        }
+        groups
+    }
+}
+
+impl NacmConfig {
+    /// Evaluate a corpus of requests against this and a proposed configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `proposed` - The configuration after the change under review
+    /// * `requests` - Requests to replay, e.g. read back from an audit log
+    ///
+    /// # Returns
+    ///
+    /// The requests whose [`ValidationResult`](crate::ValidationResult) (effect or logging flag)
+    /// differs. A request that is decided by a different rule with the same
+    /// result is not a change.
+    pub fn simulate<'a>(&'a self, proposed: &'a NacmConfig, requests: &'a [OwnedAccessRequest]) -> Simulation<'a> {
+        self.simulate_with(proposed, requests, |_| Environment::new())
+    }
+
+    /// Evaluate a corpus of requests, each in its own environment
+    ///
+    /// Same as [`simulate`](Self::simulate), but rule conditions are checked
+    /// in the environment `env` returns for the position of each request in
+    /// the corpus, e.g. one at the time the request was logged.
+    ///
+    /// # Arguments
+    ///
+    /// * `proposed` - The configuration after the change under review
+    /// * `requests` - Requests to replay, e.g. read back from an audit log
+    /// * `env` - The time and request attributes for the request at an index
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nacm_validator::{NacmConfig, Operation, OwnedAccessRequest, RuleEffect};
+    /// use nacm_validator::condition::{Environment, RuleCondition, Weekday};
+    /// use std::time::{Duration, SystemTime};
+    ///
+    /// // Proposed: operators may only read at weekends
+    /// let current = NacmConfig::builder().group("oper", ["bob"]).build().unwrap();
+    /// let weekends = RuleCondition {
+    ///     weekdays: vec![Weekday::Saturday, Weekday::Sunday],
+    ///     ..Default::default()
+    /// };
+    /// let proposed = NacmConfig::builder()
+    ///     .read_default(RuleEffect::Deny)
+    ///     .group("oper", ["bob"])
+    ///     .rule_list("oper-acl", ["oper"], |rl| {
+    ///         rl.permit().name("weekend").ops([Operation::Read]).condition(weekends)
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let read: OwnedAccessRequest = serde_json::from_str(r#"{"user":"bob","operation":"read"}"#).unwrap();
+    /// let corpus = [read.clone(), read];
+    /// // 2024-06-01 was a Saturday, 2024-06-03 a Monday
+    /// let saturday = SystemTime::UNIX_EPOCH + Duration::from_secs(1_717_200_000);
+    /// let logged_at = [saturday, saturday + Duration::from_secs(2 * 86_400)];
+    ///
+    /// let simulation = current.simulate_with(&proposed, &corpus, |i| Environment::new().at(logged_at[i]));
+    /// assert_eq!(simulation.changes.len(), 1);
+    /// assert_eq!(simulation.changes[0].index, 1);
+    /// ```
+    pub fn simulate_with<'a>(
+        &'a self,
+        proposed: &'a NacmConfig,
+        requests: &'a [OwnedAccessRequest],
+        env: impl Fn(usize) -> Environment,
+    ) -> Simulation<'a> {
+        let changes = requests
+            .iter()
+            .enumerate()
+            .filter_map(|(index, request)| {
+                let access = request.as_request();
+                let env = env(index);
+                let before = self.evaluate_with(&access, &env);
+                let after = proposed.evaluate_with(&access, &env);
+                (before.result != after.result).then(|| DecisionChange {
+                    index,
+                    request,
+                    before,
+                    after,
+                    responsible: self.responsible_rule(&before, &after),
+                })
+            })
+            .collect();
+
+        Simulation {
+            total: requests.len(),
+            changes,
+        }
+    }
+
+    /// Attribute a changed decision to a rule; `self` is the current configuration
+    fn responsible_rule<'a>(&self, before: &Decision<'a>, after: &Decision<'a>) -> Option<MatchedRule<'a>> {
+        match (before.matched_rule, after.matched_rule) {
+            (_, Some(now)) if !self.has_rule(now) => Some(now),
+            (Some(was), _) => Some(was),
+            (None, now) => now,
+        }
+    }
+
+    /// Whether a rule or command rule with these names exists
+    fn has_rule(&self, rule: MatchedRule) -> bool {
+        self.rule_lists.iter().any(|rule_list| {
+            rule_list.name == rule.rule_list
+                && (rule_list.rules.iter().any(|r| r.name == rule.rule)
+                    || rule_list.command_rules.iter().any(|r| r.name == rule.rule))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RuleEffect;
+
+    fn current() -> NacmConfig {
+        let xml = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/aaa_ncm_init.xml"))
+            .unwrap();
+        NacmConfig::from_xml(&xml).unwrap()
+    }
+
+    fn corpus() -> Vec<OwnedAccessRequest> {
+        [
+            r#"{"user":"oper","operation":"exec","rpc":"edit-config","context":"netconf"}"#,
+            r#"{"user":"oper","operation":"read","module":"ietf-interfaces"}"#,
+            r#"{"user":"admin","operation":"exec","rpc":"edit-config","context":"netconf"}"#,
+            r#"{"user":"public","operation":"exec","rpc":"edit-config","context":"netconf","decision":"deny"}"#,
+            r#"{"user":"nobody","operation":"exec","rpc":"get"}"#,
+        ]
+        .iter()
+        .map(|json| serde_json::from_str(json).unwrap())
+        .collect()
+    }
+
+    /// Drop the oper edit-config deny, and deny get to everyone with a new rule
+    fn proposed() -> NacmConfig {
+        let mut proposed = current();
+        proposed.delete_rule("oper", "edit-config").unwrap();
+        proposed.apply_nacm_edit(r#"
+          <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm"
+                xmlns:yang="urn:ietf:params:xml:ns:yang:1">
+            <rule-list>
+              <name>any-group</name>
+              <rule yang:insert="first"><name>no-get</name><rpc-name>get</rpc-name><action>deny</action></rule>
+            </rule-list>
+          </nacm>"#).unwrap();
+        proposed
+    }
+
+    #[test]
+    fn identical_policy_changes_nothing() {
+        let (current, corpus) = (current(), corpus());
+        let unchanged = current.simulate(&current, &corpus);
+        assert_eq!(unchanged.total, 5);
+        assert!(unchanged.changes.is_empty());
+    }
+
+    #[test]
+    fn reports_flipped_decisions_in_corpus_order() {
+        let (current, proposed, corpus) = (current(), proposed(), corpus());
+        let simulation = current.simulate(&proposed, &corpus);
+        let flips: Vec<(usize, RuleEffect)> =
+            simulation.changes.iter().map(|c| (c.index, c.after.result.effect)).collect();
+        assert_eq!(flips, [(0, RuleEffect::Permit), (3, RuleEffect::Permit), (4, RuleEffect::Deny)]);
+    }
+
+    #[test]
+    fn groups_changes_by_user() {
+        let (current, proposed, corpus) = (current(), proposed(), corpus());
+        let simulation = current.simulate(&proposed, &corpus);
+        let by_user = simulation.by_user();
+        assert_eq!(by_user.keys().copied().collect::<Vec<_>>(), ["nobody", "oper", "public"]);
+    }
+
+    #[test]
+    fn blames_removed_and_added_rules() {
+        let (current, proposed, corpus) = (current(), proposed(), corpus());
+        let simulation = current.simulate(&proposed, &corpus);
+
+        // The removed rule is blamed for its former matches, the new rule for its own
+        let by_rule = simulation.by_rule();
+        let removed = MatchedRule { rule_list: "oper", rule: "edit-config" };
+        let added = MatchedRule { rule_list: "any-group", rule: "no-get" };
+        assert_eq!(by_rule.keys().copied().collect::<Vec<_>>(), [Some(added), Some(removed)]);
+        assert_eq!(by_rule[&Some(removed)].len(), 2);
+        assert_eq!(by_rule[&Some(added)][0].request.user, "nobody");
+    }
+}
@@ -0,0 +1,451 @@
+//! # NACM Statistics
+//!
+//! Thread-safe counters for the read-only statistics that RFC 8341 defines
+//! under `/nacm`:
+//!
+//! - `denied-operations` - protocol operations (RPCs, `exec`) denied
+//! - `denied-data-writes` - create, update and delete requests denied
+//! - `denied-notifications` - notifications dropped because read access was
+//!   denied (see [`NacmConfig::validate_notification`](crate::NacmConfig::validate_notification))
+//!
+//! Attach a [`NacmStats`] to a configuration via its `stats` field and every
+//! `validate`/`decide` call updates it. Denied reads are not counted, as in
+//! RFC 8341, and nothing is counted while NACM is disabled. Denied Tail-f
+//! commands are not counted either: they are CLI/WebUI requests, not
+//! protocol operations, and RFC 8341 has no counter for them.
+//!
+//! Counters can optionally be broken down per user and per deciding rule,
+//! and exported as RFC 8341 operational state (XML or RFC 7951 JSON) or in
+//! the Prometheus text exposition format.
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::{NacmConfig, AccessRequest, Operation, RuleEffect};
+//! use nacm_validator::stats::NacmStats;
+//! use std::collections::HashMap;
+//! use std::sync::Arc;
+//!
+//! let stats = Arc::new(NacmStats::new());
+//! let config = NacmConfig {
+//!     enable_nacm: true,
+//!     read_default: RuleEffect::Permit,
+//!     write_default: RuleEffect::Deny,
+//!     exec_default: RuleEffect::Deny,
+//!     cmd_read_default: RuleEffect::Permit,
+//!     cmd_exec_default: RuleEffect::Permit,
+//!     log_if_default_permit: false,
+//!     log_if_default_deny: false,
+//!     groups: HashMap::new(),
+//!     rule_lists: vec![],
+//!     stats: Some(Arc::clone(&stats)),
+//! };
+//!
+//! let request = AccessRequest {
+//!     user: "bob",
+//!     module_name: Some("ietf-interfaces"),
+//!     rpc_name: None,
+//!     operation: Operation::Update,
+//!     path: Some("/interfaces"),
+//!     context: None,
+//!     command: None,
+//! };
+//! config.validate(&request);
+//!
+//! assert_eq!(stats.totals().data_writes, 1);
+//! assert!(stats.to_xml().contains("<denied-data-writes>1</denied-data-writes>"));
+//! ```
+
+use crate::{AccessRequest, MatchedRule, Operation};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// The RFC 8341 statistics a denial is counted under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DenialCategory {
+    /// `denied-operations`: a protocol operation request was denied
+    Operation,
+    /// `denied-data-writes`: a request to alter a datastore was denied
+    DataWrite,
+    /// `denied-notifications`: a notification was dropped
+    Notification,
+}
+
+impl DenialCategory {
+    /// Category a denied request with this operation is counted under
+    ///
+    /// Returns `None` for reads, which RFC 8341 does not count.
+    pub fn for_operation(operation: &Operation) -> Option<Self> {
+        match operation {
+            Operation::Exec => Some(DenialCategory::Operation),
+            Operation::Create | Operation::Update | Operation::Delete => Some(DenialCategory::DataWrite),
+            Operation::Read => None,
+        }
+    }
+
+    /// Category a denied request is counted under
+    ///
+    /// Like [`for_operation`](Self::for_operation), but also returns `None`
+    /// for Tail-f command requests, which RFC 8341 does not count.
+    pub fn for_request(req: &AccessRequest) -> Option<Self> {
+        if req.command.is_some() {
+            return None;
+        }
+        Self::for_operation(&req.operation)
+    }
+}
+
+/// Denial counts for each category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DenialCounts {
+    /// `denied-operations`
+    pub operations: u64,
+    /// `denied-data-writes`
+    pub data_writes: u64,
+    /// `denied-notifications`
+    pub notifications: u64,
+}
+
+impl DenialCounts {
+    fn increment(&mut self, category: DenialCategory) {
+        match category {
+            DenialCategory::Operation => self.operations += 1,
+            DenialCategory::DataWrite => self.data_writes += 1,
+            DenialCategory::Notification => self.notifications += 1,
+        }
+    }
+
+    /// (category label, count) pairs, in RFC 8341 order
+    fn labelled(&self) -> [(&'static str, u64); 3] {
+        [
+            ("operations", self.operations),
+            ("data_writes", self.data_writes),
+            ("notifications", self.notifications),
+        ]
+    }
+}
+
+/// Identifies a deciding rule in the per-rule breakdown
+///
+/// `None` stands for denials by a default policy.
+pub type RuleKey = Option<(String, String)>;
+
+/// Per-user and per-rule counts, kept only when requested
+#[derive(Debug, Default)]
+struct Breakdown {
+    by_user: BTreeMap<String, DenialCounts>,
+    by_rule: BTreeMap<RuleKey, DenialCounts>,
+}
+
+/// Thread-safe NACM denial counters
+///
+/// Totals are lock-free atomics. The optional breakdown takes a mutex, so
+/// enable it only where the extra detail is wanted.
+#[derive(Debug, Default)]
+pub struct NacmStats {
+    denied_operations: AtomicU64,
+    denied_data_writes: AtomicU64,
+    denied_notifications: AtomicU64,
+    breakdown: Option<Mutex<Breakdown>>,
+}
+
+impl NacmStats {
+    /// Counters with totals only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counters that also keep a breakdown by user and by deciding rule
+    pub fn with_breakdown() -> Self {
+        NacmStats {
+            breakdown: Some(Mutex::new(Breakdown::default())),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the per-user and per-rule breakdown is kept
+    pub fn has_breakdown(&self) -> bool {
+        self.breakdown.is_some()
+    }
+
+    /// Count one denial
+    ///
+    /// Called by the validator; exposed for callers that enforce decisions
+    /// made elsewhere.
+    ///
+    /// # Arguments
+    ///
+    /// * `category` - Which RFC 8341 counter to increment
+    /// * `user` - User whose request was denied
+    /// * `rule` - Rule that denied it, or `None` for a default policy
+    pub fn record_denial(&self, category: DenialCategory, user: &str, rule: Option<MatchedRule>) {
+        let counter = match category {
+            DenialCategory::Operation => &self.denied_operations,
+            DenialCategory::DataWrite => &self.denied_data_writes,
+            DenialCategory::Notification => &self.denied_notifications,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(breakdown) = &self.breakdown {
+            let mut breakdown = breakdown.lock().unwrap_or_else(|e| e.into_inner());
+            breakdown
+                .by_user
+                .entry(user.to_string())
+                .or_default()
+                .increment(category);
+            let key = rule.map(|m| (m.rule_list.to_string(), m.rule.to_string()));
+            breakdown.by_rule.entry(key).or_default().increment(category);
+        }
+    }
+
+    /// Current totals
+    pub fn totals(&self) -> DenialCounts {
+        DenialCounts {
+            operations: self.denied_operations.load(Ordering::Relaxed),
+            data_writes: self.denied_data_writes.load(Ordering::Relaxed),
+            notifications: self.denied_notifications.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Counts per user (empty without a breakdown)
+    pub fn by_user(&self) -> BTreeMap<String, DenialCounts> {
+        self.breakdown
+            .as_ref()
+            .map(|b| b.lock().unwrap_or_else(|e| e.into_inner()).by_user.clone())
+            .unwrap_or_default()
+    }
+
+    /// Counts per deciding rule (empty without a breakdown)
+    pub fn by_rule(&self) -> BTreeMap<RuleKey, DenialCounts> {
+        self.breakdown
+            .as_ref()
+            .map(|b| b.lock().unwrap_or_else(|e| e.into_inner()).by_rule.clone())
+            .unwrap_or_default()
+    }
+
+    /// RFC 8341 operational state as XML
+    ///
+    /// The counters are `zero-based-counter32` in the YANG model, so values
+    /// are reported modulo 2^32.
+    pub fn to_xml(&self) -> String {
+        let totals = self.totals();
+        format!(
+            "<nacm xmlns=\"urn:ietf:params:xml:ns:yang:ietf-netconf-acm\">\n\
+             \x20 <denied-operations>{}</denied-operations>\n\
+             \x20 <denied-data-writes>{}</denied-data-writes>\n\
+             \x20 <denied-notifications>{}</denied-notifications>\n\
+             </nacm>\n",
+            counter32(totals.operations),
+            counter32(totals.data_writes),
+            counter32(totals.notifications)
+        )
+    }
+
+    /// RFC 8341 operational state as RFC 7951 JSON
+    ///
+    /// Values are reported modulo 2^32, as for [`to_xml`](Self::to_xml).
+    pub fn to_json(&self) -> String {
+        let totals = self.totals();
+        format!(
+            "{{\"ietf-netconf-acm:nacm\":{{\"denied-operations\":{},\"denied-data-writes\":{},\"denied-notifications\":{}}}}}",
+            counter32(totals.operations),
+            counter32(totals.data_writes),
+            counter32(totals.notifications)
+        )
+    }
+
+    /// Prometheus text exposition format
+    ///
+    /// Emits `nacm_denied_operations_total`, `nacm_denied_data_writes_total`
+    /// and `nacm_denied_notifications_total`. With a breakdown, also emits
+    /// `nacm_denied_by_user_total` and `nacm_denied_by_rule_total`, labelled
+    /// by `category`; default-policy denials have empty `rule_list` and
+    /// `rule` labels.
+    pub fn to_prometheus(&self) -> String {
+        let totals = self.totals();
+        let mut out = String::new();
+
+        let totals_metrics = [
+            ("operations", "Protocol operation requests denied", totals.operations),
+            ("data_writes", "Data store write requests denied", totals.data_writes),
+            ("notifications", "Notifications dropped because read access was denied", totals.notifications),
+        ];
+        for (name, help, value) in totals_metrics {
+            let _ = writeln!(out, "# HELP nacm_denied_{}_total {}.", name, help);
+            let _ = writeln!(out, "# TYPE nacm_denied_{}_total counter", name);
+            let _ = writeln!(out, "nacm_denied_{}_total {}", name, value);
+        }
+
+        if !self.has_breakdown() {
+            return out;
+        }
+
+        let _ = writeln!(out, "# HELP nacm_denied_by_user_total Denials per user and category.");
+        let _ = writeln!(out, "# TYPE nacm_denied_by_user_total counter");
+        for (user, counts) in self.by_user() {
+            for (category, value) in counts.labelled() {
+                let _ = writeln!(
+                    out,
+                    "nacm_denied_by_user_total{{user=\"{}\",category=\"{}\"}} {}",
+                    escape_label(&user),
+                    category,
+                    value
+                );
+            }
+        }
+
+        let _ = writeln!(out, "# HELP nacm_denied_by_rule_total Denials per deciding rule and category.");
+        let _ = writeln!(out, "# TYPE nacm_denied_by_rule_total counter");
+        for (key, counts) in self.by_rule() {
+            let (rule_list, rule) = key.unwrap_or_default();
+            for (category, value) in counts.labelled() {
+                let _ = writeln!(
+                    out,
+                    "nacm_denied_by_rule_total{{rule_list=\"{}\",rule=\"{}\",category=\"{}\"}} {}",
+                    escape_label(&rule_list),
+                    escape_label(&rule),
+                    category,
+                    value
+                );
+            }
+        }
+
+        out
+    }
+}
+
+/// Truncate to a YANG `zero-based-counter32` value
+fn counter32(value: u64) -> u32 {
+    value as u32
+}
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NacmConfig, RequestContext, RuleEffect};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    /// The secure example policy with `stats` attached
+    fn config(stats: &Arc<NacmStats>) -> NacmConfig {
+        let xml_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("examples")
+            .join("data")
+            .join("aaa_ncm_init_secure.xml");
+        let xml = std::fs::read_to_string(&xml_path).unwrap();
+        NacmConfig {
+            stats: Some(Arc::clone(stats)),
+            ..NacmConfig::from_xml(&xml).unwrap()
+        }
+    }
+
+    fn request<'a>(user: &'a str, operation: Operation, rpc_name: Option<&'a str>) -> AccessRequest<'a> {
+        AccessRequest {
+            user,
+            module_name: None,
+            rpc_name,
+            operation,
+            path: None,
+            context: Some(&RequestContext::NETCONF),
+            command: None,
+        }
+    }
+
+    /// Stats after oper's edit-config (denied by rule), bill's edit-config
+    /// and update (denied by default), bill's read, admin's edit-config
+    /// (permitted) and a notification dropped for bill
+    fn populated() -> Arc<NacmStats> {
+        let stats = Arc::new(NacmStats::with_breakdown());
+        let config = config(&stats);
+        config.validate(&request("oper", Operation::Exec, Some("edit-config")));
+        config.validate(&request("bill", Operation::Exec, Some("edit-config")));
+        config.validate(&request("bill", Operation::Update, None));
+        config.validate(&request("bill", Operation::Read, None));
+        config.validate(&request("admin", Operation::Exec, Some("edit-config")));
+        config.validate_notification(&request("bill", Operation::Exec, None));
+        stats
+    }
+
+    #[test]
+    fn counts_denials_by_category() {
+        let totals = populated().totals();
+        assert_eq!((totals.operations, totals.data_writes, totals.notifications), (2, 1, 1));
+    }
+
+    #[test]
+    fn breakdown_by_user_and_rule() {
+        let stats = populated();
+        assert_eq!(stats.by_user()["bill"].operations, 1);
+        assert!(!stats.by_user().contains_key("admin"));
+        assert_eq!(
+            stats.by_rule()[&Some(("oper".to_string(), "edit-config".to_string()))].operations,
+            1
+        );
+        assert_eq!(stats.by_rule()[&None].data_writes, 1);
+    }
+
+    #[test]
+    fn totals_only_without_breakdown() {
+        let stats = Arc::new(NacmStats::new());
+        config(&stats).validate(&request("bill", Operation::Update, None));
+        assert_eq!(stats.totals().data_writes, 1);
+        assert!(stats.by_user().is_empty());
+        assert!(!stats.to_prometheus().contains("nacm_denied_by_user_total"));
+    }
+
+    #[test]
+    fn exports_rfc8341_state() {
+        let stats = populated();
+        assert_eq!(
+            stats.to_json(),
+            r#"{"ietf-netconf-acm:nacm":{"denied-operations":2,"denied-data-writes":1,"denied-notifications":1}}"#
+        );
+        assert!(stats.to_xml().contains("<denied-notifications>1</denied-notifications>"));
+    }
+
+    #[test]
+    fn exports_prometheus_series() {
+        let prometheus = populated().to_prometheus();
+        assert!(prometheus.contains("nacm_denied_operations_total 2\n"));
+        assert!(prometheus.contains("nacm_denied_by_user_total{user=\"bill\",category=\"data_writes\"} 1\n"));
+        assert!(prometheus.contains("nacm_denied_by_rule_total{rule_list=\"\",rule=\"\",category=\"operations\"} 1\n"));
+    }
+
+    #[test]
+    fn nothing_counted_while_disabled() {
+        let stats = Arc::new(NacmStats::new());
+        let disabled = NacmConfig { enable_nacm: false, ..config(&stats) };
+        disabled.validate(&request("bill", Operation::Delete, None));
+        assert_eq!(stats.totals(), DenialCounts::default());
+    }
+
+    #[test]
+    fn command_denials_are_not_counted() {
+        let stats = Arc::new(NacmStats::with_breakdown());
+        let config = NacmConfig { cmd_exec_default: RuleEffect::Deny, ..config(&stats) };
+        let command = AccessRequest {
+            context: Some(&RequestContext::WebUI),
+            command: Some("reboot"),
+            ..request("bill", Operation::Exec, None)
+        };
+        assert_eq!(config.validate(&command).effect, RuleEffect::Deny);
+        assert_eq!(stats.totals(), DenialCounts::default());
+        assert!(stats.by_user().is_empty());
+        assert_eq!(DenialCategory::for_request(&command), None);
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
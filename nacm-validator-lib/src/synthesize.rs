@@ -0,0 +1,343 @@
+//! # Least-Privilege Policy Synthesis
+//!
+//! Proposes a rule list that permits exactly what was observed: given the
+//! requests a user or group was permitted in the past (for example an audit
+//! log), [`synthesize_rule_list`] returns a [`NacmRuleList`] of permit
+//! rules that covers every one of them. Everything else falls through to the
+//! defaults, so the rule list is meant for a configuration with `deny`
+//! defaults. Use [`NacmRuleList::to_xml`] to write it out.
+//!
+//! ## How Requests Become Rules
+//!
+//! - **Protocol operations** get one rule per RPC name (and module), with
+//!   `exec` access.
+//! - **Data requests** get one rule per path (and module), with the union
+//!   of the observed operations. With
+//!   [`path_depth`](SynthesisOptions::path_depth), longer paths are cut to
+//!   a `prefix/*` rule, trading precision for fewer rules.
+//! - **Actions** get `exec` on the action path plus `read` on each
+//!   ancestor, which NACM checks before it runs an action.
+//! - **Commands** (Tail-f ACM) get one command rule per command, with the
+//!   observed `read`/`exec` access.
+//!
+//! Requests from different contexts get separate rules restricted to their
+//! context; requests without a context give rules without one. Finally, a
+//! rule is dropped when another rule for the same module, RPC and context
+//! already covers its path and operations.
+//!
+//! Rules are named `rule-N` and `cmdrule-N`, like in the
+//! [policy builder](crate::builder).
+//!
+//! # Examples
+//!
+//! ```
+//! use nacm_validator::{NacmConfig, OwnedAccessRequest, RuleEffect};
+//! use nacm_validator::synthesize::{synthesize_rule_list, SynthesisOptions};
+//!
+//! let observed: Vec<OwnedAccessRequest> = [
+//!     r#"{"user":"bob","operation":"read","module":"ietf-interfaces","path":"/interfaces"}"#,
+//!     r#"{"user":"bob","operation":"update","module":"ietf-interfaces","path":"/interfaces"}"#,
+//!     r#"{"user":"bob","operation":"exec","rpc":"get-config"}"#,
+//! ]
+//! .iter()
+//! .map(|json| serde_json::from_str(json).unwrap())
+//! .collect();
+//!
+//! let rule_list = synthesize_rule_list("bob-acl", ["oper"], &observed, &SynthesisOptions::default());
+//! assert_eq!(rule_list.rules.len(), 2);
+//! assert!(rule_list.to_xml().contains("<access-operations>read update</access-operations>"));
+//!
+//! // With deny defaults, the rule list permits the observed requests and nothing else
+//! let mut config = NacmConfig::builder()
+//!     .read_default(RuleEffect::Deny)
+//!     .exec_default(RuleEffect::Deny)
+//!     .group("oper", ["bob"])
+//!     .build()
+//!     .unwrap();
+//! config.insert_rule_list(rule_list, nacm_validator::mutation::Position::Last).unwrap();
+//! assert!(observed.iter().all(|r| config.validate(&r.as_request()).effect == RuleEffect::Permit));
+//!
+//! let other: OwnedAccessRequest =
+//!     serde_json::from_str(r#"{"user":"bob","operation":"delete","module":"ietf-interfaces","path":"/interfaces"}"#).unwrap();
+//! assert_eq!(config.validate(&other.as_request()).effect, RuleEffect::Deny);
+//! ```
+
+use crate::{path_steps, NacmCommandRule, NacmRule, NacmRuleList, Operation, OwnedAccessRequest, RequestKind, RuleEffect};
+use std::collections::{BTreeMap, HashSet};
+
+/// Tuning for [`synthesize_rule_list`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SynthesisOptions {
+    /// Cut data paths with more steps than this to a `prefix/*` rule
+    ///
+    /// `None` (the default) keeps every observed path exact. `Some(0)`
+    /// drops paths altogether, giving one rule per module.
+    pub path_depth: Option<usize>,
+}
+
+/// What a data or operation rule selects
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Target {
+    module: Option<String>,
+    rpc: Option<String>,
+    path: Option<String>,
+    context: Option<String>,
+}
+
+/// What a command rule selects
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct CommandTarget {
+    context: Option<String>,
+    command: String,
+}
+
+/// Propose permit rules covering a corpus of observed requests
+///
+/// # Arguments
+///
+/// * `name` - Name of the rule list
+/// * `groups` - Groups the rule list applies to
+/// * `requests` - Requests that were permitted and should stay permitted
+/// * `options` - How far to generalize paths
+///
+/// # Returns
+///
+/// A rule list of permit rules; data and operation rules come first, in a
+/// stable order, followed by command rules.
+pub fn synthesize_rule_list<I, S>(
+    name: &str,
+    groups: I,
+    requests: &[OwnedAccessRequest],
+    options: &SynthesisOptions,
+) -> NacmRuleList
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut targets: BTreeMap<Target, HashSet<Operation>> = BTreeMap::new();
+    let mut commands: BTreeMap<CommandTarget, HashSet<Operation>> = BTreeMap::new();
+
+    for request in requests {
+        let access = request.as_request();
+        let context = request.context.as_ref().map(|ctx| ctx.to_string());
+        let data = |path: Option<&str>| Target {
+            module: request.module_name.clone(),
+            rpc: None,
+            path: path.and_then(|path| generalize(path, options.path_depth)),
+            context: context.clone(),
+        };
+
+        match access.kind() {
+            RequestKind::Command(command) => {
+                let target = CommandTarget {
+                    context: context.clone(),
+                    command: command.to_string(),
+                };
+                commands.entry(target).or_default().insert(request.operation.clone());
+            }
+            RequestKind::Operation(rpc) => {
+                let target = Target {
+                    module: request.module_name.clone(),
+                    rpc: Some(rpc.to_string()),
+                    path: None,
+                    context: context.clone(),
+                };
+                targets.entry(target).or_default().insert(Operation::Exec);
+            }
+            RequestKind::Action { path, .. } => {
+                let steps = path_steps(path);
+                let mut ancestor = String::new();
+                for step in steps.iter().take(steps.len().saturating_sub(1)) {
+                    ancestor.push('/');
+                    ancestor.push_str(step);
+                    targets.entry(data(Some(&ancestor))).or_default().insert(Operation::Read);
+                }
+                targets.entry(data(Some(path))).or_default().insert(Operation::Exec);
+            }
+            RequestKind::Data => {
+                targets.entry(data(request.path.as_deref())).or_default().insert(request.operation.clone());
+            }
+        }
+    }
+
+    let kept: Vec<(&Target, &HashSet<Operation>)> = targets
+        .iter()
+        .filter(|(target, ops)| {
+            !targets
+                .iter()
+                .any(|(other, other_ops)| other != *target && covers(other, target) && ops.is_subset(other_ops))
+        })
+        .collect();
+
+    let rules = kept
+        .into_iter()
+        .enumerate()
+        .map(|(index, (target, ops))| NacmRule {
+            name: format!("rule-{}", index + 1),
+            module_name: target.module.clone(),
+            rpc_name: target.rpc.clone(),
+            path: target.path.clone(),
+            access_operations: ops.clone(),
+            effect: RuleEffect::Permit,
+            order: index as u32,
+            context: target.context.as_deref().and_then(|ctx| ctx.parse().ok()),
+            log_if_permit: false,
+            log_if_deny: false,
+            condition: None,
+        })
+        .collect();
+
+    let command_rules = commands
+        .into_iter()
+        .enumerate()
+        .map(|(index, (target, ops))| NacmCommandRule {
+            name: format!("cmdrule-{}", index + 1),
+            context: target.context.as_deref().and_then(|ctx| ctx.parse().ok()),
+            command: Some(target.command),
+            access_operations: ops,
+            effect: RuleEffect::Permit,
+            order: index as u32,
+            log_if_permit: false,
+            log_if_deny: false,
+            comment: None,
+            condition: None,
+        })
+        .collect();
+
+    NacmRuleList {
+        name: name.to_string(),
+        groups: groups.into_iter().map(Into::into).collect(),
+        rules,
+        command_rules,
+    }
+}
+
+/// Cut a path to `depth` steps, as a `prefix/*` rule path
+///
+/// Returns `None` for a depth of 0: the rule then applies to the whole module.
+fn generalize(path: &str, depth: Option<usize>) -> Option<String> {
+    let Some(depth) = depth else {
+        return Some(path.to_string());
+    };
+    if depth == 0 {
+        return None;
+    }
+    let steps = path_steps(path);
+    if steps.len() <= depth {
+        Some(path.to_string())
+    } else {
+        Some(format!("/{}/*", steps[..depth].join("/")))
+    }
+}
+
+/// Whether a rule for `outer` matches every request a rule for `inner` matches
+///
+/// Mirrors the path matching of rule evaluation: no path matches any path,
+/// `prefix/*` matches paths starting with the prefix, other paths match
+/// exactly.
+fn covers(outer: &Target, inner: &Target) -> bool {
+    if outer.module != inner.module || outer.rpc != inner.rpc || outer.context != inner.context {
+        return false;
+    }
+    match (&outer.path, &inner.path) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(outer), Some(inner)) => match outer.strip_suffix("/*") {
+            Some(prefix) => inner.starts_with(prefix),
+            None => outer == inner,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::Position;
+    use crate::NacmConfig;
+
+    fn requests(lines: &[&str]) -> Vec<OwnedAccessRequest> {
+        lines.iter().map(|json| serde_json::from_str(json).unwrap()).collect()
+    }
+
+    fn observed() -> Vec<OwnedAccessRequest> {
+        requests(&[
+            r#"{"user":"bob","operation":"read","module":"m","path":"/a/b/c"}"#,
+            r#"{"user":"bob","operation":"read","module":"m","path":"/a/b/d"}"#,
+            r#"{"user":"bob","operation":"update","module":"m","path":"/a/b/c","context":"netconf"}"#,
+            r#"{"user":"bob","operation":"exec","module":"m","path":"/a/reset"}"#,
+            r#"{"user":"bob","operation":"exec","rpc":"get","context":"netconf"}"#,
+            r#"{"user":"bob","operation":"exec","command":"show status","context":"cli"}"#,
+            r#"{"user":"bob","operation":"read","command":"show status","context":"cli"}"#,
+        ])
+    }
+
+    fn unobserved() -> Vec<OwnedAccessRequest> {
+        requests(&[
+            r#"{"user":"bob","operation":"update","module":"m","path":"/a/b/c","context":"cli"}"#,
+            r#"{"user":"bob","operation":"read","module":"m","path":"/a/x"}"#,
+            r#"{"user":"bob","operation":"exec","rpc":"kill-session","context":"netconf"}"#,
+            r#"{"user":"bob","operation":"exec","command":"reboot","context":"cli"}"#,
+        ])
+    }
+
+    /// A policy denying everything by default, with `rule_list` added last
+    fn deny_all_but(rule_list: NacmRuleList) -> NacmConfig {
+        let mut config = NacmConfig::builder().group("oper", ["bob"]).build().unwrap();
+        config.read_default = RuleEffect::Deny;
+        config.exec_default = RuleEffect::Deny;
+        config.cmd_read_default = RuleEffect::Deny;
+        config.cmd_exec_default = RuleEffect::Deny;
+        config.insert_rule_list(rule_list, Position::Last).unwrap();
+        config
+    }
+
+    fn effect(config: &NacmConfig, request: &OwnedAccessRequest) -> RuleEffect {
+        config.validate(&request.as_request()).effect
+    }
+
+    #[test]
+    fn exact_rules_permit_only_what_was_observed() {
+        let observed = observed();
+        let exact = synthesize_rule_list("bob", ["oper"], &observed, &SynthesisOptions::default());
+        // c, d, update c (netconf), the action and its ancestor /a, the RPC
+        assert_eq!(exact.rules.len(), 6);
+        assert_eq!(exact.command_rules.len(), 1);
+        assert_eq!(exact.command_rules[0].access_operations.len(), 2);
+
+        let config = deny_all_but(exact);
+        assert!(observed.iter().all(|r| effect(&config, r) == RuleEffect::Permit));
+        assert!(unobserved().iter().all(|r| effect(&config, r) == RuleEffect::Deny));
+    }
+
+    #[test]
+    fn written_xml_keeps_the_decisions() {
+        let observed = observed();
+        let exact = synthesize_rule_list("bob", ["oper"], &observed, &SynthesisOptions::default());
+        let config = deny_all_but(exact);
+
+        let reloaded = NacmConfig::from_xml(&config.to_xml()).unwrap();
+        for request in observed.iter().chain(&unobserved()) {
+            assert_eq!(effect(&reloaded, request), effect(&config, request));
+        }
+    }
+
+    #[test]
+    fn path_depth_generalizes_into_prefix_rules() {
+        // /a/b/c and /a/b/d merge into one prefix rule per operation
+        let observed = observed();
+        let options = SynthesisOptions { path_depth: Some(2) };
+        let general = synthesize_rule_list("bob", ["oper"], &observed, &options);
+        let paths: Vec<Option<&str>> = general.rules.iter().map(|r| r.path.as_deref()).collect();
+        assert_eq!(paths.iter().filter(|p| **p == Some("/a/b/*")).count(), 2);
+
+        let config = deny_all_but(general);
+        assert!(observed.iter().all(|r| effect(&config, r) == RuleEffect::Permit));
+    }
+
+    #[test]
+    fn zero_depth_makes_module_wide_rules() {
+        let module_wide = synthesize_rule_list("bob", ["oper"], &observed(), &SynthesisOptions { path_depth: Some(0) });
+        assert!(module_wide.rules.iter().all(|r| r.path.is_none()));
+        assert_eq!(module_wide.rules.len(), 3);
+    }
+}
@@ -0,0 +1,513 @@
+//! # Data Tree Filtering
+//!
+//! NACM does not reject a `<get>` or `<get-config>` outright when some of the
+//! requested data is off limits; it silently leaves out the nodes the user
+//! may not read (RFC 8341, section 3.4.5). [`NacmConfig::filter_tree`]
+//! applies the same pruning to an instance document offline, for example to
+//! check what a read-only dashboard account would actually see.
+//!
+//! ## Documents
+//!
+//! Both YANG instance encodings are supported through [`InstanceTree`]:
+//!
+//! - **XML**: an [`xmltree::Element`]. A root element named `data` or
+//!   `config` is treated as a wrapper and kept; its children are the
+//!   top-level data nodes. Any other root is itself a data node.
+//! - **JSON** (RFC 7951): a [`serde_json::Value`] object whose members are
+//!   the top-level data nodes, named `module:node`. A single
+//!   `ietf-restconf:data` member is treated as a wrapper.
+//!
+//! ## Evaluation
+//!
+//! Every data node is checked for read access with its schema path (e.g.
+//! `/interfaces/interface/name`, without prefixes or list keys) and its
+//! module. The module comes from the XML namespace, resolved by a
+//! [`ModuleResolver`], or from the `module:` prefix of a JSON member name,
+//! and is inherited by descendants in the same namespace.
+//!
+//! A rule applies to a node and all of its descendants, so a node that no
+//! rule matches inherits the decision of its nearest ancestor that was
+//! decided by a rule. Only nodes without such an ancestor fall back to the
+//! read default. An unreadable node is removed together with its subtree.
+//!
+//! Rules with a [condition](crate::condition) are checked at the current
+//! time and without request attributes, unless an [`Environment`] is passed
+//! to [`NacmConfig::filter_tree_with`].
+
+use crate::condition::Environment;
+use crate::{AccessRequest, NacmConfig, Operation, RequestContext, RuleEffect};
+use std::collections::HashMap;
+use xmltree::{Element, XMLNode};
+
+/// Namespace of the NETCONF base protocol, used for the empty reply wrapper
+pub(crate) const NETCONF_BASE_NS: &str = "urn:ietf:params:xml:ns:netconf:base:1.0";
+
+/// Prefix of the IETF URN namespaces, which end in the module name
+const IETF_YANG_NS_PREFIX: &str = "urn:ietf:params:xml:ns:yang:";
+
+/// An instance document to filter
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstanceTree {
+    /// XML encoding
+    Xml(Element),
+    /// JSON encoding (RFC 7951)
+    Json(serde_json::Value),
+}
+
+/// Maps XML namespaces to YANG module names
+///
+/// IETF namespaces of the form `urn:ietf:params:xml:ns:yang:<module>` are
+/// resolved automatically; any other namespace needs an explicit mapping.
+/// Nodes in an unknown namespace are checked without a module, so only
+/// rules without a `module-name` can match them.
+///
+/// # Examples
+///
+/// ```
+/// use nacm_validator::tree::ModuleResolver;
+///
+/// let resolver = ModuleResolver::new()
+///     .with_namespace("http://tail-f.com/ns/ncs", "tailf-ncs");
+///
+/// assert_eq!(resolver.module_for("http://tail-f.com/ns/ncs"), Some("tailf-ncs"));
+/// assert_eq!(
+///     resolver.module_for("urn:ietf:params:xml:ns:yang:ietf-interfaces"),
+///     Some("ietf-interfaces")
+/// );
+/// assert_eq!(resolver.module_for("http://example.com/unknown"), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ModuleResolver {
+    namespaces: HashMap<String, String>,
+}
+
+impl ModuleResolver {
+    /// A resolver that only knows the IETF namespace convention
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map `namespace` to `module`
+    pub fn with_namespace(mut self, namespace: impl Into<String>, module: impl Into<String>) -> Self {
+        self.insert(namespace, module);
+        self
+    }
+
+    /// Map `namespace` to `module`, in place
+    pub fn insert(&mut self, namespace: impl Into<String>, module: impl Into<String>) {
+        self.namespaces.insert(namespace.into(), module.into());
+    }
+
+    /// Module name for a namespace, if known
+    pub fn module_for<'a>(&'a self, namespace: &'a str) -> Option<&'a str> {
+        if let Some(module) = self.namespaces.get(namespace) {
+            return Some(module);
+        }
+        namespace
+            .strip_prefix(IETF_YANG_NS_PREFIX)
+            .filter(|module| !module.is_empty() && !module.contains(':'))
+    }
+}
+
+/// Read decision inherited from an ancestor
+#[derive(Clone, Copy)]
+struct Inherited {
+    /// Effect of the nearest ancestor decided by a rule, if any
+    rule_effect: Option<RuleEffect>,
+}
+
+/// Who is reading, shared by the whole walk
+struct Reader<'a> {
+    config: &'a NacmConfig,
+    user: &'a str,
+    context: Option<&'a RequestContext>,
+    env: &'a Environment,
+}
+
+impl Reader<'_> {
+    /// Decide read access to one node
+    ///
+    /// Returns whether the node is readable and what its descendants inherit.
+    fn check(&self, path: &str, module: Option<&str>, inherited: Inherited) -> (bool, Inherited) {
+        let request = AccessRequest {
+            user: self.user,
+            module_name: module,
+            rpc_name: None,
+            operation: Operation::Read,
+            path: Some(path),
+            context: self.context,
+            command: None,
+        };
+        let decision = self.config.evaluate_with(&request, self.env);
+
+        match (decision.matched_rule, inherited.rule_effect) {
+            // A rule matched this node: it decides, and covers the subtree
+            (Some(_), _) => {
+                let effect = decision.result.effect;
+                (effect == RuleEffect::Permit, Inherited { rule_effect: Some(effect) })
+            }
+            // No rule here: an ancestor's rule still applies
+            (None, Some(effect)) => (effect == RuleEffect::Permit, inherited),
+            // No rule anywhere above: the default policy decides
+            (None, None) => (decision.result.effect == RuleEffect::Permit, inherited),
+        }
+    }
+}
+
+impl NacmConfig {
+    /// Prune an instance document to what a user may read
+    ///
+    /// Uses a [`ModuleResolver`] that only knows IETF namespaces and checks
+    /// rule conditions at the current time; see
+    /// [`filter_tree_with`](Self::filter_tree_with) to supply a resolver and
+    /// an environment. See the [`tree`](crate::tree) module for the
+    /// evaluation rules.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - User reading the data
+    /// * `context` - Request context (Tail-f extension), if any
+    /// * `document` - The instance document to filter
+    ///
+    /// # Returns
+    ///
+    /// A copy of the document without the unreadable nodes. If an XML root
+    /// that is not a wrapper is unreadable, an empty NETCONF `<data/>`
+    /// element is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nacm_validator::{NacmConfig, RuleEffect};
+    /// use nacm_validator::tree::InstanceTree;
+    /// use std::collections::HashMap;
+    ///
+    /// let config = NacmConfig {
+    ///     enable_nacm: true,
+    ///     read_default: RuleEffect::Deny,
+    ///     write_default: RuleEffect::Deny,
+    ///     exec_default: RuleEffect::Deny,
+    ///     cmd_read_default: RuleEffect::Deny,
+    ///     cmd_exec_default: RuleEffect::Deny,
+    ///     log_if_default_permit: false,
+    ///     log_if_default_deny: false,
+    ///     groups: HashMap::new(),
+    ///     rule_lists: vec![],
+    ///     stats: None,
+    /// };
+    ///
+    /// let document = InstanceTree::Json(serde_json::json!({
+    ///     "ietf-interfaces:interfaces": { "interface": [{ "name": "eth0" }] }
+    /// }));
+    /// let filtered = config.filter_tree("guest", None, &document);
+    /// assert_eq!(filtered, InstanceTree::Json(serde_json::json!({})));
+    /// ```
+    pub fn filter_tree(&self, user: &str, context: Option<&RequestContext>, document: &InstanceTree) -> InstanceTree {
+        self.filter_tree_with(user, context, document, &ModuleResolver::new(), &Environment::new())
+    }
+
+    /// Prune an instance document, resolving XML namespaces with `resolver`
+    /// and checking rule conditions in `env`
+    ///
+    /// Same as [`filter_tree`](Self::filter_tree) with an explicit
+    /// namespace-to-module mapping and the time and request attributes for
+    /// [conditional rules](crate::condition). The resolver is not used for
+    /// JSON, whose member names carry the module name.
+    pub fn filter_tree_with(
+        &self,
+        user: &str,
+        context: Option<&RequestContext>,
+        document: &InstanceTree,
+        resolver: &ModuleResolver,
+        env: &Environment,
+    ) -> InstanceTree {
+        let reader = Reader { config: self, user, context, env };
+        let top = Inherited { rule_effect: None };
+
+        match document {
+            InstanceTree::Xml(root) => {
+                if root.name == "data" || root.name == "config" {
+                    let mut wrapper = root.clone();
+                    wrapper.children = filter_xml_children(&reader, resolver, &root.children, "", None, top);
+                    InstanceTree::Xml(wrapper)
+                } else {
+                    let filtered = filter_xml_element(&reader, resolver, root, "", None, top)
+                        .unwrap_or_else(|| {
+                            let mut data = Element::new("data");
+                            data.namespace = Some(NETCONF_BASE_NS.to_string());
+                            data
+                        });
+                    InstanceTree::Xml(filtered)
+                }
+            }
+            InstanceTree::Json(value) => {
+                let filtered = match value.as_object() {
+                    // RESTCONF replies wrap the data in a single "ietf-restconf:data" member
+                    Some(object) if object.len() == 1 && object.contains_key("ietf-restconf:data") => {
+                        let data = &object["ietf-restconf:data"];
+                        let inner = filter_json_members(&reader, data, "", None, top);
+                        serde_json::json!({ "ietf-restconf:data": inner })
+                    }
+                    Some(_) => filter_json_members(&reader, value, "", None, top),
+                    None => value.clone(),
+                };
+                InstanceTree::Json(filtered)
+            }
+        }
+    }
+}
+
+/// Filter the children of an XML element, keeping non-element content
+fn filter_xml_children(
+    reader: &Reader,
+    resolver: &ModuleResolver,
+    children: &[XMLNode],
+    parent_path: &str,
+    parent_module: Option<&str>,
+    inherited: Inherited,
+) -> Vec<XMLNode> {
+    children
+        .iter()
+        .filter_map(|child| match child {
+            XMLNode::Element(element) => {
+                filter_xml_element(reader, resolver, element, parent_path, parent_module, inherited)
+                    .map(XMLNode::Element)
+            }
+            other => Some(other.clone()),
+        })
+        .collect()
+}
+
+/// Filter one XML element, returning `None` if it is unreadable
+fn filter_xml_element(
+    reader: &Reader,
+    resolver: &ModuleResolver,
+    element: &Element,
+    parent_path: &str,
+    parent_module: Option<&str>,
+    inherited: Inherited,
+) -> Option<Element> {
+    let path = format!("{}/{}", parent_path, element.name);
+    // A namespace we cannot map means a module we do not know
+    let module = match &element.namespace {
+        Some(namespace) => resolver.module_for(namespace),
+        None => parent_module,
+    };
+
+    let (readable, inherited) = reader.check(&path, module, inherited);
+    if !readable {
+        return None;
+    }
+
+    let mut filtered = element.clone();
+    filtered.children = filter_xml_children(reader, resolver, &element.children, &path, module, inherited);
+    Some(filtered)
+}
+
+/// Filter the members of a JSON object
+fn filter_json_members(
+    reader: &Reader,
+    value: &serde_json::Value,
+    parent_path: &str,
+    parent_module: Option<&str>,
+    inherited: Inherited,
+) -> serde_json::Value {
+    let Some(object) = value.as_object() else {
+        return value.clone();
+    };
+
+    let mut filtered = serde_json::Map::new();
+    for (name, child) in object {
+        // RFC 7951: "module:node" starts a new module, plain names inherit it
+        let (module, node) = match name.split_once(':') {
+            Some((module, node)) => (Some(module), node),
+            None => (parent_module, name.as_str()),
+        };
+        let path = format!("{}/{}", parent_path, node);
+
+        let (readable, child_inherited) = reader.check(&path, module, inherited);
+        if !readable {
+            continue;
+        }
+
+        let child = match child {
+            // List entries share the list's path; filter each entry's members
+            serde_json::Value::Array(entries) => serde_json::Value::Array(
+                entries
+                    .iter()
+                    .map(|entry| filter_json_members(reader, entry, &path, module, child_inherited))
+                    .collect(),
+            ),
+            serde_json::Value::Object(_) => filter_json_members(reader, child, &path, module, child_inherited),
+            leaf => leaf.clone(),
+        };
+        filtered.insert(name.clone(), child);
+    }
+    serde_json::Value::Object(filtered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::{AttributeMatch, RuleCondition};
+
+    /// dash may read /interfaces except statistics, and tailf-ncs
+    fn config() -> NacmConfig {
+        NacmConfig::from_xml(
+            r#"
+        <config xmlns="http://tail-f.com/ns/config/1.0">
+            <nacm xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-acm">
+                <enable-nacm>true</enable-nacm>
+                <read-default>deny</read-default>
+                <write-default>deny</write-default>
+                <exec-default>deny</exec-default>
+                <groups>
+                    <group>
+                        <name>dashboards</name>
+                        <user-name>dash</user-name>
+                    </group>
+                </groups>
+                <rule-list>
+                    <name>dashboards</name>
+                    <group>dashboards</group>
+                    <rule>
+                        <name>hide-statistics</name>
+                        <module-name>ietf-interfaces</module-name>
+                        <path>/interfaces/interface/statistics</path>
+                        <access-operations>read</access-operations>
+                        <action>deny</action>
+                    </rule>
+                    <rule>
+                        <name>read-interfaces</name>
+                        <module-name>ietf-interfaces</module-name>
+                        <path>/interfaces</path>
+                        <access-operations>read</access-operations>
+                        <action>permit</action>
+                    </rule>
+                    <rule>
+                        <name>read-ncs</name>
+                        <module-name>tailf-ncs</module-name>
+                        <access-operations>read</access-operations>
+                        <action>permit</action>
+                    </rule>
+                </rule-list>
+            </nacm>
+        </config>"#,
+        )
+        .unwrap()
+    }
+
+    fn xml(document: &str) -> InstanceTree {
+        InstanceTree::Xml(Element::parse(document.as_bytes()).unwrap())
+    }
+
+    fn filter_xml(config: &NacmConfig, user: &str, document: &InstanceTree, resolver: &ModuleResolver) -> Element {
+        match config.filter_tree_with(user, None, document, resolver, &Environment::new()) {
+            InstanceTree::Xml(filtered) => filtered,
+            InstanceTree::Json(_) => panic!("XML in, XML out"),
+        }
+    }
+
+    const DATA: &str = r#"<data xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+            <interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces">
+                <interface><name>eth0</name><statistics><in-octets>42</in-octets></statistics></interface>
+            </interfaces>
+            <system xmlns="urn:ietf:params:xml:ns:yang:ietf-system"><hostname>r1</hostname></system>
+            <devices xmlns="http://tail-f.com/ns/ncs"><device><name>ce0</name></device></devices>
+        </data>"#;
+
+    #[test]
+    fn xml_prunes_denied_nodes_and_keeps_wrapper() {
+        let filtered = filter_xml(&config(), "dash", &xml(DATA), &ModuleResolver::new());
+        assert_eq!(filtered.name, "data");
+        let interface = filtered
+            .get_child("interfaces")
+            .and_then(|i| i.get_child("interface"))
+            .unwrap();
+        assert_eq!(interface.get_child("name").unwrap().get_text().unwrap(), "eth0");
+        assert!(interface.get_child("statistics").is_none());
+        assert!(filtered.get_child("system").is_none());
+    }
+
+    #[test]
+    fn xml_unknown_namespace_needs_resolver() {
+        let config = config();
+        let document = xml(DATA);
+        let filtered = filter_xml(&config, "dash", &document, &ModuleResolver::new());
+        assert!(filtered.get_child("devices").is_none());
+
+        let resolver = ModuleResolver::new().with_namespace("http://tail-f.com/ns/ncs", "tailf-ncs");
+        let filtered = filter_xml(&config, "dash", &document, &resolver);
+        assert!(filtered.get_child("devices").is_some());
+    }
+
+    #[test]
+    fn xml_bare_root_becomes_empty_data() {
+        let bare = xml(r#"<system xmlns="urn:ietf:params:xml:ns:yang:ietf-system"/>"#);
+        let empty = filter_xml(&config(), "dash", &bare, &ModuleResolver::new());
+        assert_eq!(empty.name, "data");
+        assert_eq!(empty.namespace.as_deref(), Some(NETCONF_BASE_NS));
+        assert!(empty.children.is_empty());
+    }
+
+    #[test]
+    fn conditional_rules_see_the_environment() {
+        // read-interfaces only holds for requests from the management network
+        let mut config = config();
+        config.rule_lists[0].rules[1].condition = Some(RuleCondition {
+            attributes: vec![AttributeMatch::new("source-ip", "192.0.2.0/24").unwrap()],
+            ..Default::default()
+        });
+        let document = xml(DATA);
+        let resolver = ModuleResolver::new();
+
+        let filtered = filter_xml(&config, "dash", &document, &resolver);
+        assert!(filtered.get_child("interfaces").is_none());
+
+        let env = Environment::new().with_attribute("source-ip", "192.0.2.7");
+        let filtered = match config.filter_tree_with("dash", None, &document, &resolver, &env) {
+            InstanceTree::Xml(filtered) => filtered,
+            InstanceTree::Json(_) => panic!("XML in, XML out"),
+        };
+        let interface = filtered.get_child("interfaces").and_then(|i| i.get_child("interface")).unwrap();
+        assert!(interface.get_child("statistics").is_none());
+    }
+
+    #[test]
+    fn unknown_user_sees_nothing() {
+        let filtered = filter_xml(&config(), "mallory", &xml(DATA), &ModuleResolver::new());
+        assert!(filtered.children.iter().all(|c| !matches!(c, XMLNode::Element(_))));
+    }
+
+    #[test]
+    fn json_filters_by_module_prefix_and_list_entry() {
+        let json = serde_json::json!({
+            "ietf-interfaces:interfaces": {
+                "interface": [
+                    { "name": "eth0", "statistics": { "in-octets": 42 } },
+                    { "name": "eth1" }
+                ]
+            },
+            "ietf-system:system": { "hostname": "r1" }
+        });
+        let filtered = config().filter_tree("dash", None, &InstanceTree::Json(json));
+        assert_eq!(
+            filtered,
+            InstanceTree::Json(serde_json::json!({
+                "ietf-interfaces:interfaces": {
+                    "interface": [{ "name": "eth0" }, { "name": "eth1" }]
+                }
+            }))
+        );
+    }
+
+    #[test]
+    fn ietf_namespaces_resolve_without_mapping() {
+        let resolver = ModuleResolver::new();
+        assert_eq!(
+            resolver.module_for("urn:ietf:params:xml:ns:yang:ietf-interfaces"),
+            Some("ietf-interfaces")
+        );
+        assert_eq!(resolver.module_for("http://tail-f.com/ns/ncs"), None);
+    }
+}
@@ -0,0 +1,423 @@
+//! # Property-Based Tests
+//!
+//! Generates random policies and requests over a small vocabulary, so that
+//! rules overlap often, and checks invariants of the engine:
+//!
+//! - decisions agree with the naive [reference evaluator](reference)
+//! - with NACM disabled every request is permitted
+//! - adding a deny rule never grants access; adding a permit rule never
+//!   revokes it
+//! - the order of rule lists only matters for users in more than one of them
+//! - a configuration written with `to_xml` reads back to the same decisions
+//! - rules that analysis reports as shadowed or redundant can be removed
+//!   without changing any decision, and conflict counterexamples are
+//!   decided by the first rule
+
+mod reference;
+
+use nacm_validator::analysis::FindingKind;
+use nacm_validator::builder::RuleListBuilder;
+use nacm_validator::{NacmConfig, Operation, OwnedAccessRequest, RequestContext, RuleEffect};
+use proptest::prelude::*;
+use proptest::sample::{select, subsequence};
+
+const USERS: [&str; 4] = ["alice", "bob", "carol", "dave"];
+const GROUPS: [&str; 3] = ["admin", "oper", "guest"];
+const MODULES: [&str; 2] = ["ietf-interfaces", "ietf-system"];
+const RULE_RPCS: [&str; 3] = ["*", "edit-config", "reboot"];
+const REQUEST_RPCS: [&str; 3] = ["edit-config", "reboot", "get"];
+const RULE_PATHS: [&str; 6] = ["/", "/interfaces", "/interfaces/*", "/interfaces/interface", "/system/*", "/system"];
+const REQUEST_PATHS: [&str; 5] = [
+    "/interfaces",
+    "/interfaces/interface",
+    "/interfaces/interface/reset",
+    "/system",
+    "/system/clock",
+];
+const CONTEXT_PATTERNS: [&str; 9] = ["*", "netconf", "cli", "webui", "cli webui", "net*", "?li", "?*", "c*"];
+const CONTEXTS: [&str; 4] = ["netconf", "cli", "webui", "restconf"];
+const COMMAND_PATTERNS: [&str; 5] = ["*", "show *", "show status", "reboot", "show*"];
+const COMMANDS: [&str; 5] = ["show status", "show", "show interfaces", "reboot", "configure"];
+
+/// A data or protocol operation rule
+#[derive(Debug, Clone)]
+struct RuleSpec {
+    effect: RuleEffect,
+    module: Option<&'static str>,
+    rpc: Option<&'static str>,
+    path: Option<&'static str>,
+    /// Empty for every operation
+    ops: Vec<Operation>,
+    context: Option<&'static str>,
+    log_if_permit: bool,
+    log_if_deny: bool,
+}
+
+/// A command rule
+#[derive(Debug, Clone)]
+struct CommandRuleSpec {
+    effect: RuleEffect,
+    command: &'static str,
+    /// Empty for every operation
+    ops: Vec<Operation>,
+    context: Option<&'static str>,
+}
+
+#[derive(Debug, Clone)]
+struct RuleListSpec {
+    name: String,
+    groups: Vec<&'static str>,
+    rules: Vec<RuleSpec>,
+    command_rules: Vec<CommandRuleSpec>,
+}
+
+/// A whole policy; `members[i]` are the users of `GROUPS[i]`
+#[derive(Debug, Clone)]
+struct ConfigSpec {
+    enable_nacm: bool,
+    defaults: [RuleEffect; 5],
+    log_if_default_permit: bool,
+    log_if_default_deny: bool,
+    members: Vec<Vec<&'static str>>,
+    rule_lists: Vec<RuleListSpec>,
+}
+
+fn effect() -> impl Strategy<Value = RuleEffect> {
+    prop_oneof![Just(RuleEffect::Permit), Just(RuleEffect::Deny)]
+}
+
+fn operation() -> impl Strategy<Value = Operation> {
+    select(vec![
+        Operation::Read,
+        Operation::Create,
+        Operation::Update,
+        Operation::Delete,
+        Operation::Exec,
+    ])
+}
+
+fn rule() -> impl Strategy<Value = RuleSpec> {
+    (
+        effect(),
+        proptest::option::of(select(&MODULES[..])),
+        proptest::option::weighted(0.3, select(&RULE_RPCS[..])),
+        proptest::option::of(select(&RULE_PATHS[..])),
+        proptest::collection::vec(operation(), 0..3),
+        proptest::option::weighted(0.3, select(&CONTEXT_PATTERNS[..])),
+        any::<bool>(),
+        any::<bool>(),
+    )
+        .prop_map(|(effect, module, rpc, path, ops, context, log_if_permit, log_if_deny)| RuleSpec {
+            effect,
+            module,
+            rpc,
+            path,
+            ops,
+            context,
+            log_if_permit,
+            log_if_deny,
+        })
+}
+
+fn command_rule() -> impl Strategy<Value = CommandRuleSpec> {
+    (
+        effect(),
+        select(&COMMAND_PATTERNS[..]),
+        proptest::collection::vec(prop_oneof![Just(Operation::Read), Just(Operation::Exec)], 0..2),
+        proptest::option::weighted(0.3, select(&CONTEXT_PATTERNS[..])),
+    )
+        .prop_map(|(effect, command, ops, context)| CommandRuleSpec {
+            effect,
+            command,
+            ops,
+            context,
+        })
+}
+
+fn rule_list() -> impl Strategy<Value = RuleListSpec> {
+    (
+        subsequence(vec!["admin", "oper", "guest", "*"], 1..=2),
+        proptest::collection::vec(rule(), 0..5),
+        proptest::collection::vec(command_rule(), 0..3),
+    )
+        .prop_map(|(groups, rules, command_rules)| RuleListSpec {
+            name: String::new(),
+            groups,
+            rules,
+            command_rules,
+        })
+}
+
+fn config_spec() -> impl Strategy<Value = ConfigSpec> {
+    (
+        prop::bool::weighted(0.9),
+        [effect(), effect(), effect(), effect(), effect()],
+        any::<bool>(),
+        any::<bool>(),
+        proptest::collection::vec(subsequence(USERS.to_vec(), 0..=USERS.len()), GROUPS.len()),
+        proptest::collection::vec(rule_list(), 0..4),
+    )
+        .prop_map(|(enable_nacm, defaults, log_if_default_permit, log_if_default_deny, members, rule_lists)| {
+            let rule_lists = rule_lists
+                .into_iter()
+                .enumerate()
+                .map(|(index, rule_list)| RuleListSpec {
+                    name: format!("list-{}", index + 1),
+                    ..rule_list
+                })
+                .collect();
+            ConfigSpec {
+                enable_nacm,
+                defaults,
+                log_if_default_permit,
+                log_if_default_deny,
+                members,
+                rule_lists,
+            }
+        })
+}
+
+fn request() -> impl Strategy<Value = OwnedAccessRequest> {
+    (
+        select(vec!["alice", "bob", "carol", "dave", "eve"]),
+        operation(),
+        proptest::option::of(select(&MODULES[..])),
+        proptest::option::weighted(0.3, select(&REQUEST_RPCS[..])),
+        proptest::option::of(select(&REQUEST_PATHS[..])),
+        proptest::option::of(select(&CONTEXTS[..])),
+        proptest::option::weighted(0.25, select(&COMMANDS[..])),
+    )
+        .prop_map(|(user, operation, module, rpc, path, context, command)| OwnedAccessRequest {
+            user: user.to_string(),
+            module_name: module.map(str::to_string),
+            rpc_name: rpc.map(str::to_string),
+            // Commands are only read or executed
+            operation: match operation {
+                Operation::Read => Operation::Read,
+                _ if command.is_some() => Operation::Exec,
+                other => other,
+            },
+            path: path.map(str::to_string),
+            context: context.map(|ctx| ctx.parse::<RequestContext>().unwrap()),
+            command: command.map(str::to_string),
+        })
+}
+
+fn requests() -> impl Strategy<Value = Vec<OwnedAccessRequest>> {
+    proptest::collection::vec(request(), 1..24)
+}
+
+fn add_rule(rl: RuleListBuilder, rule: &RuleSpec) -> RuleListBuilder {
+    let mut rl = match rule.effect {
+        RuleEffect::Permit => rl.permit(),
+        RuleEffect::Deny => rl.deny(),
+    };
+    if let Some(module) = rule.module {
+        rl = rl.module(module);
+    }
+    if let Some(rpc) = rule.rpc {
+        rl = rl.rpc(rpc);
+    }
+    if let Some(path) = rule.path {
+        rl = rl.path(path);
+    }
+    rl = if rule.ops.is_empty() {
+        rl.all_ops()
+    } else {
+        rl.ops(rule.ops.clone())
+    };
+    if let Some(context) = rule.context {
+        rl = rl.context(context);
+    }
+    if rule.log_if_permit {
+        rl = rl.log_if_permit();
+    }
+    if rule.log_if_deny {
+        rl = rl.log_if_deny();
+    }
+    rl
+}
+
+fn add_command_rule(rl: RuleListBuilder, rule: &CommandRuleSpec) -> RuleListBuilder {
+    let mut rl = match rule.effect {
+        RuleEffect::Permit => rl.permit_command(rule.command),
+        RuleEffect::Deny => rl.deny_command(rule.command),
+    };
+    rl = if rule.ops.is_empty() {
+        rl.all_ops()
+    } else {
+        rl.ops(rule.ops.clone())
+    };
+    if let Some(context) = rule.context {
+        rl = rl.context(context);
+    }
+    rl
+}
+
+impl ConfigSpec {
+    fn build(&self) -> NacmConfig {
+        let [read, write, exec, cmd_read, cmd_exec] = self.defaults;
+        let mut builder = NacmConfig::builder()
+            .enable_nacm(self.enable_nacm)
+            .read_default(read)
+            .write_default(write)
+            .exec_default(exec)
+            .cmd_read_default(cmd_read)
+            .cmd_exec_default(cmd_exec);
+        if self.log_if_default_permit {
+            builder = builder.log_if_default_permit();
+        }
+        if self.log_if_default_deny {
+            builder = builder.log_if_default_deny();
+        }
+        for (group, members) in GROUPS.iter().zip(&self.members) {
+            builder = builder.group(*group, members.iter().copied());
+        }
+        for rule_list in &self.rule_lists {
+            builder = builder.rule_list(&rule_list.name, rule_list.groups.iter().copied(), |rl| {
+                let rl = rule_list.rules.iter().fold(rl, add_rule);
+                rule_list.command_rules.iter().fold(rl, add_command_rule)
+            });
+        }
+        builder.build().expect("generated policies are valid")
+    }
+
+    /// Number of rule lists that apply to `user`
+    fn rule_lists_for(&self, user: &str) -> usize {
+        self.rule_lists
+            .iter()
+            .filter(|rule_list| {
+                rule_list.groups.iter().any(|group| {
+                    *group == "*"
+                        || GROUPS
+                            .iter()
+                            .zip(&self.members)
+                            .any(|(name, members)| name == group && members.contains(&user))
+                })
+            })
+            .count()
+    }
+}
+
+/// A spec with one more rule inserted at an arbitrary place
+fn with_rule(spec: &ConfigSpec, rule: RuleSpec, list: usize, position: usize) -> ConfigSpec {
+    let mut spec = spec.clone();
+    if spec.rule_lists.is_empty() {
+        spec.rule_lists.push(RuleListSpec {
+            name: "list-1".to_string(),
+            groups: vec!["*"],
+            rules: Vec::new(),
+            command_rules: Vec::new(),
+        });
+    }
+    let index = list % spec.rule_lists.len();
+    let rules = &mut spec.rule_lists[index].rules;
+    rules.insert(position % (rules.len() + 1), rule);
+    spec
+}
+
+proptest! {
+    #[test]
+    fn engine_matches_reference(spec in config_spec(), requests in requests()) {
+        let config = spec.build();
+        for request in &requests {
+            let access = request.as_request();
+            prop_assert_eq!(config.decide(&access), reference::evaluate(&config, &access), "{:?}", request);
+            prop_assert_eq!(config.validate(&access), config.decide(&access).result);
+        }
+    }
+
+    #[test]
+    fn disabled_nacm_permits_everything(spec in config_spec(), requests in requests()) {
+        let config = ConfigSpec { enable_nacm: false, ..spec }.build();
+        for request in &requests {
+            let result = config.validate(&request.as_request());
+            prop_assert_eq!(result.effect, RuleEffect::Permit);
+            prop_assert!(!result.should_log);
+        }
+    }
+
+    #[test]
+    fn deny_rule_never_grants_access(
+        spec in config_spec(),
+        rule in rule(),
+        list in any::<usize>(),
+        position in any::<usize>(),
+        requests in requests(),
+    ) {
+        let before = spec.build();
+        let after = with_rule(&spec, RuleSpec { effect: RuleEffect::Deny, ..rule }, list, position).build();
+        for request in &requests {
+            let access = request.as_request();
+            if after.validate(&access).effect == RuleEffect::Permit {
+                prop_assert_eq!(before.validate(&access).effect, RuleEffect::Permit, "{:?}", request);
+            }
+        }
+    }
+
+    #[test]
+    fn permit_rule_never_revokes_access(
+        spec in config_spec(),
+        rule in rule(),
+        list in any::<usize>(),
+        position in any::<usize>(),
+        requests in requests(),
+    ) {
+        let before = spec.build();
+        let after = with_rule(&spec, RuleSpec { effect: RuleEffect::Permit, ..rule }, list, position).build();
+        for request in &requests {
+            let access = request.as_request();
+            if before.validate(&access).effect == RuleEffect::Permit {
+                prop_assert_eq!(after.validate(&access).effect, RuleEffect::Permit, "{:?}", request);
+            }
+        }
+    }
+
+    #[test]
+    fn rule_list_order_matters_only_across_lists(spec in config_spec(), requests in requests()) {
+        let config = spec.build();
+        let mut reversed_spec = spec.clone();
+        reversed_spec.rule_lists.reverse();
+        let reversed = reversed_spec.build();
+        for request in requests.iter().filter(|r| spec.rule_lists_for(&r.user) <= 1) {
+            let access = request.as_request();
+            prop_assert_eq!(config.decide(&access), reversed.decide(&access), "{:?}", request);
+        }
+    }
+
+    #[test]
+    fn xml_round_trip_keeps_decisions(spec in config_spec(), requests in requests()) {
+        let config = spec.build();
+        let reloaded = NacmConfig::from_xml(&config.to_xml()).unwrap();
+        for request in &requests {
+            let access = request.as_request();
+            prop_assert_eq!(config.decide(&access), reloaded.decide(&access), "{:?}", request);
+        }
+    }
+
+    #[test]
+    fn analysis_findings_hold(spec in config_spec(), requests in requests()) {
+        let config = spec.build();
+        for finding in config.analyze() {
+            let counterexample = finding.counterexample.as_request();
+            prop_assert_eq!(config.decide(&counterexample), finding.decision);
+
+            if finding.kind == FindingKind::Conflict {
+                prop_assert_eq!(finding.decision.matched_rule, Some(finding.first));
+                prop_assert_eq!(finding.decision.result.effect, finding.first_effect);
+                continue;
+            }
+
+            // The second rule never decides, so removing it changes nothing
+            let mut pruned = config.clone();
+            for rule_list in pruned.rule_lists.iter_mut().filter(|rl| rl.name == finding.second.rule_list) {
+                rule_list.rules.retain(|rule| rule.name != finding.second.rule);
+                rule_list.command_rules.retain(|rule| rule.name != finding.second.rule);
+            }
+            for request in requests.iter().chain([&finding.counterexample]) {
+                let access = request.as_request();
+                prop_assert_eq!(config.decide(&access), pruned.decide(&access), "{} {:?}", finding.second, request);
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! # Reference Evaluator
+//!
+//! A deliberately naive implementation of NACM access decisions, used to
+//! test the engine differentially. It is written from RFC 8341 section 3.4
+//! and the matching rules documented in the crate, and shares no code with
+//! the engine:
+//!
+//! - rule lists and rules are scanned in the order they appear, so the
+//!   result does not depend on the `order` values the engine sorts by
+//! - nothing is precomputed; groups are looked up for every request
+//!
+//! Paths are split on `/` without regard to predicates, so requests used
+//! with it should not contain `[...]` predicates holding slashes.
+
+use nacm_validator::{
+    AccessRequest, ContextPattern, Decision, MatchedRule, NacmCommandRule, NacmConfig, NacmRule, NacmRuleList,
+    Operation, RuleEffect, ValidationResult,
+};
+
+/// Decide a request the slow, obvious way
+pub fn evaluate<'a>(config: &'a NacmConfig, req: &AccessRequest) -> Decision<'a> {
+    if !config.enable_nacm {
+        return Decision {
+            result: ValidationResult {
+                effect: RuleEffect::Permit,
+                should_log: false,
+            },
+            matched_rule: None,
+        };
+    }
+
+    let applicable: Vec<&NacmRuleList> = config
+        .rule_lists
+        .iter()
+        .filter(|rule_list| {
+            rule_list.groups.iter().any(|group| {
+                group == "*"
+                    || config
+                        .groups
+                        .get(group)
+                        .is_some_and(|g| g.users.iter().any(|user| user == req.user))
+            })
+        })
+        .collect();
+
+    if let Some(command) = req.command {
+        for rule_list in &applicable {
+            for rule in &rule_list.command_rules {
+                if command_rule_matches(rule, req, command) {
+                    return rule_decision(rule_list, &rule.name, rule.effect, rule.log_if_permit, rule.log_if_deny);
+                }
+            }
+        }
+        let effect = if req.operation == Operation::Read {
+            config.cmd_read_default
+        } else {
+            config.cmd_exec_default
+        };
+        return default_decision(config, effect);
+    }
+
+    // An action: read access is needed on every ancestor of the action node
+    if let (true, Some(path)) = (is_action(req), req.path) {
+        let steps: Vec<&str> = path.split('/').filter(|step| !step.is_empty()).collect();
+        for depth in 1..steps.len() {
+            let ancestor = format!("/{}", steps[..depth].join("/"));
+            let read = AccessRequest {
+                rpc_name: None,
+                operation: Operation::Read,
+                path: Some(&ancestor),
+                ..*req
+            };
+            let decision = data_decision(config, &applicable, &read);
+            if decision.result.effect == RuleEffect::Deny {
+                return decision;
+            }
+        }
+    }
+
+    data_decision(config, &applicable, req)
+}
+
+fn data_decision<'a>(config: &'a NacmConfig, applicable: &[&'a NacmRuleList], req: &AccessRequest) -> Decision<'a> {
+    for rule_list in applicable {
+        for rule in &rule_list.rules {
+            if rule_matches(rule, req) {
+                return rule_decision(rule_list, &rule.name, rule.effect, rule.log_if_permit, rule.log_if_deny);
+            }
+        }
+    }
+    let effect = match req.operation {
+        Operation::Read => config.read_default,
+        Operation::Create | Operation::Update | Operation::Delete => config.write_default,
+        Operation::Exec => config.exec_default,
+    };
+    default_decision(config, effect)
+}
+
+fn rule_decision<'a>(
+    rule_list: &'a NacmRuleList,
+    name: &'a str,
+    effect: RuleEffect,
+    log_if_permit: bool,
+    log_if_deny: bool,
+) -> Decision<'a> {
+    Decision {
+        result: ValidationResult {
+            effect,
+            should_log: if effect == RuleEffect::Permit { log_if_permit } else { log_if_deny },
+        },
+        matched_rule: Some(MatchedRule {
+            rule_list: &rule_list.name,
+            rule: name,
+        }),
+    }
+}
+
+fn default_decision(config: &NacmConfig, effect: RuleEffect) -> Decision<'_> {
+    Decision {
+        result: ValidationResult {
+            effect,
+            should_log: if effect == RuleEffect::Permit {
+                config.log_if_default_permit
+            } else {
+                config.log_if_default_deny
+            },
+        },
+        matched_rule: None,
+    }
+}
+
+/// Exec on a path, without an RPC name or named after the last path step
+fn is_action(req: &AccessRequest) -> bool {
+    match (&req.operation, req.path) {
+        (Operation::Exec, Some(path)) => req.rpc_name.map_or(true, |rpc| path.rsplit('/').next() == Some(rpc)),
+        _ => false,
+    }
+}
+
+fn rule_matches(rule: &NacmRule, req: &AccessRequest) -> bool {
+    let is_action = is_action(req);
+
+    let module = match &rule.module_name {
+        None => true,
+        Some(module) => req.module_name == Some(module.as_str()),
+    };
+    let rpc = match rule.rpc_name.as_deref() {
+        None | Some("*") => true,
+        Some(rpc) => !is_action && req.rpc_name == Some(rpc),
+    };
+    let path = match (rule.path.as_deref(), req.path) {
+        (None | Some("/"), _) => true,
+        (Some(_), None) => false,
+        (Some(pattern), Some(path)) => match pattern.strip_suffix("/*") {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == pattern,
+        },
+    };
+
+    operation_matches(rule.access_operations.iter(), &req.operation)
+        && context_matches(rule.context.as_ref(), req)
+        && module
+        && rpc
+        && path
+}
+
+fn command_rule_matches(rule: &NacmCommandRule, req: &AccessRequest, command: &str) -> bool {
+    let command = match rule.command.as_deref() {
+        None | Some("*") => true,
+        Some(pattern) if pattern == command => true,
+        Some(pattern) => pattern
+            .strip_suffix('*')
+            .is_some_and(|prefix| command.starts_with(prefix.trim())),
+    };
+
+    operation_matches(rule.access_operations.iter(), &req.operation) && context_matches(rule.context.as_ref(), req) && command
+}
+
+/// No listed operations means all of them
+fn operation_matches<'a>(mut operations: impl ExactSizeIterator<Item = &'a Operation>, operation: &Operation) -> bool {
+    operations.len() == 0 || operations.any(|op| op == operation)
+}
+
+fn context_matches(pattern: Option<&ContextPattern>, req: &AccessRequest) -> bool {
+    let Some(pattern) = pattern else {
+        return true;
+    };
+    let mut alternatives = pattern.as_str().split_whitespace().map(str::to_lowercase);
+    match req.context {
+        None => alternatives.any(|alt| alt == "*"),
+        Some(context) => {
+            let name = context.to_string().to_lowercase();
+            alternatives.any(|alt| glob(alt.as_bytes(), name.as_bytes()))
+        }
+    }
+}
+
+/// `*` matches any run of characters, `?` any single one
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match (pattern.first(), text.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => glob(&pattern[1..], text) || (!text.is_empty() && glob(pattern, &text[1..])),
+        (Some(b'?'), Some(_)) => glob(&pattern[1..], &text[1..]),
+        (Some(p), Some(t)) if p == t => glob(&pattern[1..], &text[1..]),
+        _ => false,
+    }
+}